@@ -0,0 +1,394 @@
+use iced::{
+    advanced::{
+        layout::{self, Layout},
+        mouse, overlay,
+        renderer::{self, Renderer as _},
+        widget::{self, tree, Tree, Widget},
+        Clipboard, Shell,
+    },
+    event, Color, Element, Event, Length, Point, Rectangle, Renderer, Size, Theme, Vector,
+};
+
+// Shows a floating element over the content, placed at an offset from its
+// top left corner. The floating element is moved back inside the window
+// when it would stick out, and above the anchor when there is no room
+// below it.
+pub struct Anchored<'a, Message> {
+    content: Element<'a, Message>,
+    floating: Option<Element<'a, Message>>,
+    offset: Vector,
+    clearance: f32,
+    on_hover: Option<Grid<'a, Message>>,
+    on_wrap: Option<(f32, Box<dyn Fn(bool) -> Message + 'a>)>,
+    rules: Vec<(Rectangle, Color)>,
+}
+
+// Cells of a fixed size laid out from an origin, which the pointer is
+// reported over by row and column.
+struct Grid<'a, Message> {
+    origin: Vector,
+    cell: Size,
+    on_hover: Box<dyn Fn(Option<(usize, usize)>) -> Message + 'a>,
+}
+
+#[derive(Default)]
+struct State {
+    hovered: Option<(usize, usize)>,
+    is_wrapped: Option<bool>,
+}
+
+impl<'a, Message> Anchored<'a, Message> {
+    pub fn new(content: impl Into<Element<'a, Message>>) -> Self {
+        Self {
+            content: content.into(),
+            floating: None,
+            offset: Vector::new(0.0, 0.0),
+            clearance: 0.0,
+            on_hover: None,
+            on_wrap: None,
+            rules: Vec::new(),
+        }
+    }
+
+    // The clearance is how far up the element moves when it has to go over
+    // the anchor instead, usually the height of the line it points at.
+    pub fn floating(
+        mut self,
+        floating: impl Into<Element<'a, Message>>,
+        offset: Vector,
+        clearance: f32,
+    ) -> Self {
+        self.floating = Some(floating.into());
+        self.offset = offset;
+        self.clearance = clearance;
+        self
+    }
+
+    // Only changes are reported, and None once the pointer leaves the
+    // content or goes over something floating above it.
+    pub fn on_hover(
+        mut self,
+        origin: Vector,
+        cell: Size,
+        on_hover: impl Fn(Option<(usize, usize)>) -> Message + 'a,
+    ) -> Self {
+        self.on_hover = Some(Grid {
+            origin,
+            cell,
+            on_hover: Box::new(on_hover),
+        });
+        self
+    }
+
+    // Reports whether the content is narrower than the width its longest
+    // line takes, so that lines wrap. Only changes are reported.
+    pub fn on_wrap(mut self, width: f32, on_wrap: impl Fn(bool) -> Message + 'a) -> Self {
+        self.on_wrap = Some((width, Box::new(on_wrap)));
+        self
+    }
+
+    // Thin rectangles drawn over the content, placed from its top left
+    // corner and cut off at its edges.
+    pub fn rules(mut self, rules: Vec<(Rectangle, Color)>) -> Self {
+        self.rules = rules;
+        self
+    }
+}
+
+impl<'a, Message> Widget<Message, Renderer> for Anchored<'a, Message> {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        let mut children = vec![Tree::new(&self.content)];
+        children.extend(self.floating.iter().map(Tree::new));
+        children
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        match &self.floating {
+            Some(floating) => tree.diff_children(&[&self.content, floating]),
+            None => tree.diff_children(&[&self.content]),
+        }
+    }
+
+    fn width(&self) -> Length {
+        self.content.as_widget().width()
+    }
+
+    fn height(&self) -> Length {
+        self.content.as_widget().height()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.content
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn widget::Operation<Message>,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        if let Some((width, on_wrap)) = &self.on_wrap {
+            let state = tree.state.downcast_mut::<State>();
+            let is_wrapped = layout.bounds().width < *width;
+
+            if state.is_wrapped != Some(is_wrapped) {
+                state.is_wrapped = Some(is_wrapped);
+                shell.publish(on_wrap(is_wrapped));
+            }
+        }
+
+        if let (
+            Some(grid),
+            Event::Mouse(moved @ (mouse::Event::CursorMoved { .. } | mouse::Event::CursorLeft)),
+        ) = (&self.on_hover, &event)
+        {
+            let position = match moved {
+                mouse::Event::CursorLeft => None,
+                _ => cursor.position_in(layout.bounds()),
+            };
+
+            let state = tree.state.downcast_mut::<State>();
+            let hovered = position.and_then(|point| {
+                let (x, y) = (point.x - grid.origin.x, point.y - grid.origin.y);
+
+                (x >= 0.0 && y >= 0.0).then(|| {
+                    (
+                        (y / grid.cell.height) as usize,
+                        (x / grid.cell.width) as usize,
+                    )
+                })
+            });
+
+            if hovered != state.hovered {
+                state.hovered = hovered;
+                shell.publish((grid.on_hover)(hovered));
+            }
+        }
+
+        self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+
+        let bounds = layout.bounds();
+
+        renderer.with_layer(bounds, |renderer| {
+            for &(rule, color) in &self.rules {
+                let rule = rule + Vector::new(bounds.x, bounds.y);
+
+                if rule.intersects(&bounds) {
+                    renderer.fill_quad(
+                        renderer::Quad {
+                            bounds: rule,
+                            border_radius: 0.0.into(),
+                            border_width: 0.0,
+                            border_color: Color::TRANSPARENT,
+                        },
+                        color,
+                    );
+                }
+            }
+        });
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+    ) -> Option<overlay::Element<'b, Message, Renderer>> {
+        let (content_tree, floating_tree) = match tree.children.as_mut_slice() {
+            [content, floating] => (content, Some(floating)),
+            [content] => (content, None),
+            _ => return None,
+        };
+
+        let content = self
+            .content
+            .as_widget_mut()
+            .overlay(content_tree, layout, renderer);
+
+        let floating = self
+            .floating
+            .as_mut()
+            .zip(floating_tree)
+            .map(|(floating, tree)| {
+                let anchor = layout.position() + self.offset;
+
+                overlay::Element::new(
+                    anchor,
+                    Box::new(Floating {
+                        element: floating,
+                        tree,
+                        anchor,
+                        clearance: self.clearance,
+                    }),
+                )
+            });
+
+        let overlays: Vec<_> = content.into_iter().chain(floating).collect();
+
+        (!overlays.is_empty()).then(|| overlay::Group::with_children(overlays).overlay())
+    }
+}
+
+impl<'a, Message: 'a> From<Anchored<'a, Message>> for Element<'a, Message> {
+    fn from(anchored: Anchored<'a, Message>) -> Self {
+        Element::new(anchored)
+    }
+}
+
+struct Floating<'a, 'b, Message> {
+    element: &'b mut Element<'a, Message>,
+    tree: &'b mut Tree,
+    anchor: Point,
+    clearance: f32,
+}
+
+impl<'a, 'b, Message> overlay::Overlay<Message, Renderer> for Floating<'a, 'b, Message> {
+    fn layout(&mut self, renderer: &Renderer, bounds: Size, _position: Point) -> layout::Node {
+        let limits = layout::Limits::new(Size::ZERO, bounds);
+        let node = self
+            .element
+            .as_widget()
+            .layout(self.tree, renderer, &limits);
+        let size = node.size();
+
+        let x = self.anchor.x.min(bounds.width - size.width).max(0.0);
+        let y = if self.anchor.y + size.height > bounds.height {
+            (self.anchor.y - self.clearance - size.height).max(0.0)
+        } else {
+            self.anchor.y
+        };
+
+        node.move_to(Point::new(x, y))
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+    ) {
+        self.element.as_widget().draw(
+            self.tree,
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            &layout.bounds(),
+        );
+    }
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        self.element.as_widget_mut().on_event(
+            self.tree,
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            &layout.bounds(),
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.element
+            .as_widget()
+            .mouse_interaction(self.tree, layout, cursor, viewport, renderer)
+    }
+}
@@ -0,0 +1,94 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Deserialize;
+
+use crate::paths;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backups {
+    #[default]
+    Off,
+    Sibling,
+    Directory(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Policy {
+    pub backups: Backups,
+    pub limit: usize,
+}
+
+// Copies what is on disk aside before it is overwritten. Next to the file
+// there is only ever the one `name~`, while a backup directory keeps
+// timestamped copies of each file, down to the newest `limit` of them.
+pub async fn write(path: &Path, policy: &Policy) -> io::Result<()> {
+    let directory = match &policy.backups {
+        Backups::Off => return Ok(()),
+        Backups::Sibling => None,
+        Backups::Directory(directory) => Some(paths::expand(directory)),
+    };
+
+    if !tokio::fs::try_exists(path).await.unwrap_or(false) {
+        return Ok(());
+    }
+
+    let Some(directory) = directory else {
+        let mut name = path.as_os_str().to_owned();
+        name.push("~");
+
+        return tokio::fs::copy(path, PathBuf::from(name)).await.map(drop);
+    };
+
+    tokio::fs::create_dir_all(&directory).await?;
+
+    let prefix = format!("{}.", flattened(path));
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis());
+
+    tokio::fs::copy(path, directory.join(format!("{prefix}{millis}~"))).await?;
+
+    prune(&directory, &prefix, policy.limit.max(1)).await
+}
+
+// Folds the whole path into one file name the way Vim does, so files with
+// the same name in different folders keep apart.
+fn flattened(path: &Path) -> String {
+    let path = match std::env::current_dir() {
+        Ok(directory) if path.is_relative() => directory.join(path),
+        _ => path.to_path_buf(),
+    };
+
+    path.to_string_lossy().replace(['/', '\\', ':'], "%")
+}
+
+async fn prune(directory: &Path, prefix: &str, limit: usize) -> io::Result<()> {
+    let mut entries = tokio::fs::read_dir(directory).await?;
+    let mut copies = Vec::new();
+
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        let millis = name
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix('~'))
+            .and_then(|millis| millis.parse::<u128>().ok());
+
+        if let Some(millis) = millis {
+            copies.push((millis, entry.path()));
+        }
+    }
+
+    copies.sort();
+
+    for (_, path) in copies.iter().rev().skip(limit) {
+        tokio::fs::remove_file(path).await?;
+    }
+
+    Ok(())
+}
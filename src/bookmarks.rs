@@ -0,0 +1,112 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use iced::widget::text_editor;
+
+// Marked lines of a buffer, in order. The text of every line is kept as a
+// hash, so after an edit of any kind the marks can follow the lines that
+// moved by comparing what stayed the same at either end.
+#[derive(Debug, Clone, Default)]
+pub struct Bookmarks {
+    lines: Vec<usize>,
+    hashes: Vec<u64>,
+    at: Option<(u64, usize)>,
+}
+
+impl Bookmarks {
+    pub fn lines(&self) -> &[usize] {
+        &self.lines
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn contains(&self, line: usize) -> bool {
+        self.lines.binary_search(&line).is_ok()
+    }
+
+    pub fn toggle(&mut self, line: usize, content: &text_editor::Content, revision: u64) {
+        self.follow(content, revision);
+
+        match self.lines.binary_search(&line) {
+            Ok(index) => {
+                self.lines.remove(index);
+            }
+            Err(index) => self.lines.insert(index, line),
+        }
+    }
+
+    // Wraps around at either end, like searching does.
+    pub fn next(&self, line: usize, is_forward: bool) -> Option<usize> {
+        if is_forward {
+            self.lines
+                .iter()
+                .find(|&&marked| marked > line)
+                .or(self.lines.first())
+                .copied()
+        } else {
+            self.lines
+                .iter()
+                .rev()
+                .find(|&&marked| marked < line)
+                .or(self.lines.last())
+                .copied()
+        }
+    }
+
+    // Marks inside the part that changed stay where they were as far as
+    // that part still goes, and those after it move with the lines.
+    pub fn follow(&mut self, content: &text_editor::Content, revision: u64) {
+        let at = (revision, content.line_count());
+
+        if self.at == Some(at) {
+            return;
+        }
+
+        let hashes = (0..content.line_count())
+            .map(|line| {
+                let mut hasher = DefaultHasher::new();
+
+                content.line(line).as_deref().hash(&mut hasher);
+                hasher.finish()
+            })
+            .collect::<Vec<_>>();
+
+        if self.at.is_some() && !self.lines.is_empty() {
+            let (old, new) = (self.hashes.len(), hashes.len());
+            let prefix = self
+                .hashes
+                .iter()
+                .zip(&hashes)
+                .take_while(|(old, new)| old == new)
+                .count();
+            let suffix = self
+                .hashes
+                .iter()
+                .rev()
+                .zip(hashes.iter().rev())
+                .take(old.min(new) - prefix)
+                .take_while(|(old, new)| old == new)
+                .count();
+            let changed_end = (new - suffix).max(prefix + 1).min(new.max(1)) - 1;
+
+            for line in &mut self.lines {
+                *line = if *line < prefix {
+                    *line
+                } else if *line >= old - suffix {
+                    *line + new - old
+                } else {
+                    (*line).min(changed_end)
+                };
+            }
+
+            self.lines.dedup();
+        }
+
+        self.hashes = hashes;
+        self.at = Some(at);
+    }
+}
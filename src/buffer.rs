@@ -0,0 +1,557 @@
+use std::{
+    borrow::Cow,
+    collections::hash_map::DefaultHasher,
+    fmt,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use encoding_rs::{Encoding, UTF_8};
+use iced::{highlighter, widget::text_editor};
+use ropey::Rope;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bookmarks::Bookmarks,
+    conflict::{self, Conflict},
+    cursors::Cursors,
+    editing::{self, Position},
+    git::{Blame, Gutter},
+    guides::{self, Guide},
+    history::{Change, History, Snapshot},
+    indentation::{self, Indentation},
+    minimap::Minimap,
+    outline::{self, Symbol},
+    spelling::{self, Dictionary},
+    stats::Stats,
+    text::Text,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Wrap {
+    #[default]
+    Window,
+    Column,
+    Off,
+}
+
+impl Wrap {
+    pub fn next(self) -> Self {
+        match self {
+            Wrap::Window => Wrap::Column,
+            Wrap::Column => Wrap::Off,
+            Wrap::Off => Wrap::Window,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub const ALL: [LineEnding; 2] = [LineEnding::Lf, LineEnding::Crlf];
+
+    // A file that mixes both is saved with whichever it uses more.
+    pub fn detect(text: &str) -> Self {
+        let crlf = text.matches("\r\n").count();
+        let lf = text.matches('\n').count() - crlf;
+
+        if crlf > lf {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    pub fn apply(self, text: String) -> String {
+        match self {
+            LineEnding::Lf => text,
+            LineEnding::Crlf => text.replace('\n', "\r\n"),
+        }
+    }
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineEnding::Lf => write!(f, "LF"),
+            LineEnding::Crlf => write!(f, "CRLF"),
+        }
+    }
+}
+
+// How the text is turned back into bytes on save.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Format {
+    pub encoding: &'static Encoding,
+    pub line_ending: LineEnding,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self {
+            encoding: UTF_8,
+            line_ending: LineEnding::default(),
+        }
+    }
+}
+
+// What the file held when it was last read or written. The time is checked
+// first and the hash settles it when only the time moved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stamp {
+    pub modified: Option<SystemTime>,
+    pub hash: u64,
+}
+
+impl Stamp {
+    pub fn of(bytes: &[u8], modified: Option<SystemTime>) -> Self {
+        let mut hasher = DefaultHasher::new();
+
+        bytes.hash(&mut hasher);
+
+        Self {
+            modified,
+            hash: hasher.finish(),
+        }
+    }
+}
+
+// The editor widget holds the text, and `text` keeps a rope of it that the
+// buffer is saved from.
+pub struct Buffer {
+    pub path: Option<PathBuf>,
+    pub url: Option<String>,
+    pub content: text_editor::Content,
+    pub text: Text,
+    pub history: History,
+    pub theme: highlighter::Theme,
+    pub scroll: usize,
+    pub wrap: Wrap,
+    pub language: Option<&'static str>,
+    pub format: Format,
+    pub indentation: Option<Indentation>,
+    pub cursors: Cursors,
+    pub bookmarks: Bookmarks,
+    pub brackets: Option<(Position, Position)>,
+    pub auto_closed: Option<(usize, usize)>,
+    pub stamp: Option<Stamp>,
+    pub swap: Option<(PathBuf, u64)>,
+    pub minimap: Minimap,
+    pub gutter: Gutter,
+    pub blame: Option<Blame>,
+    pub blamed: Option<u64>,
+    pub conflicts: Vec<Conflict>,
+    pub misspelled: Vec<(Position, Position)>,
+    pub guides: Vec<Guide>,
+    pub active_guide: Option<usize>,
+    pub symbols: Vec<Symbol>,
+    pub stats: Stats,
+    pub longest_line: usize,
+    pub synced: Option<u64>,
+    pub is_read_only: bool,
+    brackets_at: Option<(Position, u64)>,
+    conflicts_at: Option<(u64, usize)>,
+    spelling_at: Option<(u64, usize, String, usize)>,
+    guides_at: Option<(u64, usize, usize)>,
+    active_guide_at: Option<(u64, usize, usize, usize)>,
+    symbols_at: Option<(u64, usize, String)>,
+    longest_at: Option<(u64, usize)>,
+    saved_format: Format,
+}
+
+impl Buffer {
+    pub fn new(theme: highlighter::Theme) -> Self {
+        let content = text_editor::Content::new();
+
+        Self {
+            path: None,
+            url: None,
+            text: Text::new("", &content, 0),
+            content,
+            history: History::new(false),
+            theme,
+            scroll: 0,
+            wrap: Wrap::default(),
+            language: None,
+            format: Format::default(),
+            indentation: None,
+            cursors: Cursors::default(),
+            bookmarks: Bookmarks::default(),
+            brackets: None,
+            auto_closed: None,
+            stamp: None,
+            swap: None,
+            minimap: Minimap::default(),
+            gutter: Gutter::default(),
+            blame: None,
+            blamed: None,
+            conflicts: Vec::new(),
+            misspelled: Vec::new(),
+            guides: Vec::new(),
+            active_guide: None,
+            symbols: Vec::new(),
+            stats: Stats::default(),
+            longest_line: 0,
+            synced: None,
+            is_read_only: false,
+            brackets_at: None,
+            conflicts_at: None,
+            spelling_at: None,
+            guides_at: None,
+            active_guide_at: None,
+            symbols_at: None,
+            longest_at: None,
+            saved_format: Format::default(),
+        }
+    }
+
+    pub fn open(
+        path: PathBuf,
+        text: &str,
+        encoding: &'static Encoding,
+        stamp: Stamp,
+        theme: highlighter::Theme,
+    ) -> Self {
+        let format = Format {
+            encoding,
+            line_ending: LineEnding::detect(text),
+        };
+        let normalized = normalized(text);
+        let content = text_editor::Content::with(&normalized);
+
+        Self {
+            path: Some(path),
+            url: None,
+            text: Text::new(&normalized, &content, 0),
+            content,
+            history: History::new(true),
+            theme,
+            scroll: 0,
+            wrap: Wrap::default(),
+            language: None,
+            format,
+            indentation: indentation::detect(text),
+            cursors: Cursors::default(),
+            bookmarks: Bookmarks::default(),
+            brackets: None,
+            auto_closed: None,
+            stamp: Some(stamp),
+            swap: None,
+            minimap: Minimap::default(),
+            gutter: Gutter::default(),
+            blame: None,
+            blamed: None,
+            conflicts: Vec::new(),
+            misspelled: Vec::new(),
+            guides: Vec::new(),
+            active_guide: None,
+            symbols: Vec::new(),
+            stats: Stats::default(),
+            longest_line: 0,
+            synced: None,
+            is_read_only: false,
+            brackets_at: None,
+            conflicts_at: None,
+            spelling_at: None,
+            guides_at: None,
+            active_guide_at: None,
+            symbols_at: None,
+            longest_at: None,
+            saved_format: format,
+        }
+    }
+
+    // Takes on text read from disk again, which can still be undone.
+    pub fn reload(&mut self, text: &str, encoding: &'static Encoding, stamp: Stamp) {
+        let (line, column) = self.content.cursor_position();
+
+        self.record(Change::Other);
+        self.cursors.clear();
+        self.replace_content(text);
+        self.format = Format {
+            encoding,
+            line_ending: LineEnding::detect(text),
+        };
+        self.indentation = indentation::detect(text);
+        self.stamp = Some(stamp);
+        self.mark_saved();
+        crate::move_cursor(&mut self.content, line, column);
+    }
+
+    // The same bytes read another way. Undoing would bring back text that
+    // only makes sense the other way, so the history starts over.
+    pub fn reinterpret(&mut self, text: &str, encoding: &'static Encoding) {
+        self.history = History::new(false);
+        self.cursors.clear();
+        self.replace_content(text);
+        self.format = Format {
+            encoding,
+            line_ending: LineEnding::detect(text),
+        };
+        self.indentation = indentation::detect(text);
+    }
+
+    // Anything that puts new lines in the editor goes through here, so the
+    // rope is made anew with them. Left alone, it could pass for being in
+    // step whenever the revision and the number of lines happen to match.
+    pub fn replace_content(&mut self, text: &str) {
+        let normalized = normalized(text);
+
+        self.content = text_editor::Content::with(&normalized);
+        self.text = Text::new(&normalized, &self.content, self.history.revision());
+    }
+
+    // Lines shown in place of the text, as a filter does. The rope is read
+    // back from whatever the editor holds the next time it is asked for.
+    pub fn show(&mut self, content: text_editor::Content) {
+        self.content = content;
+        self.text = Text::default();
+    }
+
+    pub fn rope(&mut self) -> Rope {
+        self.text.rope(&self.content, self.history.revision())
+    }
+
+    // Looked up again only once the cursor or the text has moved on.
+    pub fn match_brackets(&mut self) {
+        let at = (self.content.cursor_position(), self.history.revision());
+
+        if self.brackets_at == Some(at) {
+            return;
+        }
+
+        self.brackets = editing::matching_bracket(&self.content.text(), at.0, &self.extension());
+        self.brackets_at = Some(at);
+    }
+
+    pub fn refresh_minimap(&mut self) {
+        let settings = highlighter::Settings {
+            theme: self.theme,
+            extension: self.extension(),
+        };
+
+        self.minimap
+            .refresh(self.history.revision(), &self.content, settings);
+    }
+
+    pub fn refresh_gutter(&mut self) {
+        self.gutter.refresh(&self.content, self.history.revision());
+    }
+
+    // A filter changes the lines without a new revision, hence the count.
+    pub fn refresh_conflicts(&mut self) {
+        let at = (self.history.revision(), self.content.line_count());
+
+        if self.conflicts_at == Some(at) {
+            return;
+        }
+
+        self.conflicts = conflict::find(&self.content.text());
+        self.conflicts_at = Some(at);
+    }
+
+    pub fn refresh_guides(&mut self, tab_width: usize) {
+        let at = (
+            self.history.revision(),
+            self.content.line_count(),
+            tab_width,
+        );
+
+        if self.guides_at == Some(at) {
+            return;
+        }
+
+        self.guides = guides::find(&self.content.text(), tab_width);
+        self.guides_at = Some(at);
+    }
+
+    // Follows the cursor, so it only looks at the line the cursor is on.
+    pub fn refresh_active_guide(&mut self, tab_width: usize) {
+        let (line, _) = self.content.cursor_position();
+        let at = (
+            self.history.revision(),
+            self.content.line_count(),
+            tab_width,
+            line,
+        );
+
+        if self.active_guide_at == Some(at) {
+            return;
+        }
+
+        let text = self.content.line(line);
+        self.active_guide = text
+            .as_deref()
+            .and_then(|text| guides::active(&self.guides, line, text, tab_width));
+        self.active_guide_at = Some(at);
+    }
+
+    pub fn refresh_symbols(&mut self) {
+        let at = (
+            self.history.revision(),
+            self.content.line_count(),
+            self.extension(),
+        );
+
+        if self.symbols_at.as_ref() == Some(&at) {
+            return;
+        }
+
+        self.symbols = outline::symbols(&self.content.text(), &at.2);
+        self.symbols_at = Some(at);
+    }
+
+    // Checked again whenever the text, the dictionary or the words added to
+    // it change, and cleared without a dictionary.
+    pub fn refresh_spelling(&mut self, dictionary: Option<&Dictionary>) {
+        let Some(dictionary) = dictionary else {
+            self.misspelled.clear();
+            self.spelling_at = None;
+
+            return;
+        };
+
+        let at = (
+            self.history.revision(),
+            self.content.line_count(),
+            dictionary.language.clone(),
+            dictionary.added(),
+        );
+
+        if self.spelling_at.as_ref() == Some(&at) {
+            return;
+        }
+
+        self.misspelled = spelling::check(dictionary, &self.content.text(), &self.extension());
+        self.spelling_at = Some(at);
+    }
+
+    pub fn refresh_stats(&mut self) {
+        self.stats.refresh(&self.content, self.history.revision());
+    }
+
+    pub fn is_minimap_stale(&self) -> bool {
+        self.minimap
+            .is_stale(self.history.revision(), &self.content)
+    }
+
+    pub fn refresh_longest_line(&mut self) {
+        let at = (self.history.revision(), self.content.line_count());
+
+        if self.longest_at == Some(at) {
+            return;
+        }
+
+        self.longest_line = (0..self.content.line_count())
+            .filter_map(|line| self.content.line(line))
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        self.longest_at = Some(at);
+    }
+
+    // Switching the encoding or line endings alone still leaves something
+    // to save.
+    pub fn is_dirty(&self) -> bool {
+        self.history.is_modified() || self.format != self.saved_format
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.mark_saved_at(self.history.revision(), self.format);
+    }
+
+    pub fn mark_saved_at(&mut self, revision: u64, format: Format) {
+        self.history.mark_saved_at(revision);
+        self.saved_format = format;
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::new(self.content.text(), self.content.cursor_position())
+    }
+
+    pub fn record(&mut self, change: Change) {
+        self.history.record(change, || {
+            Snapshot::new(self.content.text(), self.content.cursor_position())
+        });
+    }
+
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        let (line, column) = snapshot.cursor;
+
+        self.replace_content(&snapshot.text);
+        crate::move_cursor(&mut self.content, line, column);
+        self.scroll = self.scroll.min(line);
+        self.content.edit(text_editor::Action::Scroll {
+            lines: self.scroll as i32,
+        });
+    }
+
+    pub fn is_blank(&self) -> bool {
+        self.content.line_count() <= 1
+            && self
+                .content
+                .line(0)
+                .is_none_or(|line| line.trim().is_empty())
+    }
+
+    pub fn is_untouched(&self) -> bool {
+        self.path.is_none() && self.is_blank()
+    }
+
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.is_dirty() && (self.path.is_some() || !self.is_blank())
+    }
+
+    // A language picked by hand wins over the one the path suggests.
+    pub fn extension(&self) -> String {
+        if let Some(language) = self.language {
+            return language.to_string();
+        }
+
+        self.path
+            .as_ref()
+            .and_then(|path| path.extension()?.to_str())
+            .unwrap_or("rs")
+            .to_string()
+    }
+
+    // A page fetched from the web goes by the last part of its address
+    // until it is saved somewhere.
+    pub fn title(&self) -> String {
+        let name = match (self.path.as_deref().and_then(Path::file_name), &self.url) {
+            (Some(name), _) => name.to_string_lossy().into_owned(),
+            (None, Some(url)) => url
+                .split(['?', '#'])
+                .next()
+                .unwrap_or(url)
+                .trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .unwrap_or(url)
+                .to_string(),
+            (None, None) => String::from("Untitled"),
+        };
+
+        if self.has_unsaved_changes() {
+            format!("{name} •")
+        } else {
+            name
+        }
+    }
+}
+
+// Only files that actually use CRLF pay for a copy of their text.
+fn normalized(text: &str) -> Cow<'_, str> {
+    if text.contains("\r\n") {
+        Cow::Owned(text.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
@@ -0,0 +1,112 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    Upper,
+    Lower,
+    Title,
+    Snake,
+    Camel,
+    Kebab,
+}
+
+// Every line is converted on its own, so the selection keeps its shape.
+pub fn convert(text: &str, case: Case) -> String {
+    text.split('\n')
+        .map(|line| match case {
+            Case::Upper => line.to_uppercase(),
+            Case::Lower => line.to_lowercase(),
+            Case::Title => title(line),
+            Case::Snake => joined(line, "_", false),
+            Case::Camel => joined(line, "", true),
+            Case::Kebab => joined(line, "-", false),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Only letters at the start of a word are raised, and the rest lowered.
+fn title(line: &str) -> String {
+    let mut titled = String::with_capacity(line.len());
+    let mut is_start = true;
+
+    for c in line.chars() {
+        if is_start {
+            titled.extend(c.to_uppercase());
+        } else {
+            titled.extend(c.to_lowercase());
+        }
+
+        is_start = !c.is_alphanumeric() && c != '\'';
+    }
+
+    titled
+}
+
+// The indentation and trailing whitespace stay, while everything between
+// the words gives way to the one separator.
+fn joined(line: &str, separator: &str, is_camel: bool) -> String {
+    let start = line.len() - line.trim_start().len();
+    let end = line.trim_end().len();
+    let mut result = String::from(&line[..start]);
+
+    for (index, word) in words(&line[start..end.max(start)]).iter().enumerate() {
+        let word = word.to_lowercase();
+
+        if index > 0 && !is_camel {
+            result.push_str(separator);
+        }
+
+        let mut chars = word.chars();
+
+        match chars.next() {
+            Some(first) if is_camel && index > 0 => {
+                result.extend(first.to_uppercase());
+                result.extend(chars);
+            }
+            Some(_) => result.push_str(&word),
+            None => {}
+        }
+    }
+
+    result.push_str(&line[end.max(start)..]);
+    result
+}
+
+// Words end at separators and at humps, where a lower case letter or a
+// digit meets an upper case one, or an acronym meets the next word, as in
+// "HTTPServer".
+fn words(text: &str) -> Vec<String> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut words = Vec::new();
+    let mut word = String::new();
+
+    for (index, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+
+            continue;
+        }
+
+        let previous = index.checked_sub(1).map(|index| chars[index]);
+        let next = chars.get(index + 1);
+        let is_hump = c.is_uppercase()
+            && previous.is_some_and(|previous| {
+                previous.is_lowercase()
+                    || previous.is_ascii_digit()
+                    || (previous.is_uppercase() && next.is_some_and(|next| next.is_lowercase()))
+            });
+
+        if is_hump && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+
+        word.push(c);
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
@@ -0,0 +1,57 @@
+use std::fmt;
+
+use crate::config::Config;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cleanup {
+    pub trimmed: usize,
+    pub added_newline: bool,
+}
+
+impl Cleanup {
+    pub fn is_empty(&self) -> bool {
+        self.trimmed == 0 && !self.added_newline
+    }
+}
+
+impl fmt::Display for Cleanup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.trimmed, self.added_newline) {
+            (0, _) => write!(f, "Added final newline"),
+            (1, false) => write!(f, "Trimmed 1 line"),
+            (trimmed, false) => write!(f, "Trimmed {trimmed} lines"),
+            (1, true) => write!(f, "Trimmed 1 line, added final newline"),
+            (trimmed, true) => write!(f, "Trimmed {trimmed} lines, added final newline"),
+        }
+    }
+}
+
+// The cursor line can be spared, so saving halfway through typing does not
+// swallow the space just typed.
+pub fn apply(text: &str, cursor_line: usize, config: &Config) -> Option<(String, Cleanup)> {
+    let mut cleanup = Cleanup::default();
+    let mut cleaned = String::with_capacity(text.len() + 1);
+
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            cleaned.push('\n');
+        }
+
+        let is_spared = index == cursor_line && !config.trim_cursor_line;
+        let trimmed = line.trim_end();
+
+        if config.trim_trailing_whitespace && !is_spared && trimmed.len() != line.len() {
+            cleanup.trimmed += 1;
+            cleaned.push_str(trimmed);
+        } else {
+            cleaned.push_str(line);
+        }
+    }
+
+    if config.ensure_final_newline && !cleaned.is_empty() && !cleaned.ends_with('\n') {
+        cleanup.added_newline = true;
+        cleaned.push('\n');
+    }
+
+    (!cleanup.is_empty()).then_some((cleaned, cleanup))
+}
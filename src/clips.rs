@@ -0,0 +1,93 @@
+use std::collections::VecDeque;
+
+use iced::{
+    theme,
+    widget::{button, column, container, text},
+    Element, Length,
+};
+
+use crate::Messages;
+
+const LIMIT: usize = 20;
+const PREVIEW: usize = 60;
+
+// What was cut or copied during the session, the latest first, for every
+// buffer alike. Copying something again moves it to the front instead of
+// keeping it twice.
+#[derive(Debug, Clone, Default)]
+pub struct Clips {
+    entries: VecDeque<String>,
+}
+
+impl Clips {
+    pub fn push(&mut self, text: String) {
+        if text.is_empty() {
+            return;
+        }
+
+        self.entries.retain(|entry| *entry != text);
+        self.entries.push_front(text);
+        self.entries.truncate(LIMIT);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Pasting an entry counts as using it again.
+    pub fn take(&mut self, index: usize) -> Option<String> {
+        let text = self.entries.remove(index)?;
+
+        self.entries.push_front(text.clone());
+
+        Some(text)
+    }
+
+    pub fn view(&self) -> Element<'_, Messages> {
+        let items = self
+            .entries
+            .iter()
+            .enumerate()
+            .fold(column![], |items, (index, entry)| {
+                items.push(
+                    button(text(preview(entry)).size(14))
+                        .style(theme::Button::Text)
+                        .width(Length::Fill)
+                        .padding([2, 5])
+                        .on_press(Messages::PasteClip(index)),
+                )
+            });
+
+        container(items)
+            .width(360)
+            .padding(5)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
+
+// The first line that has something on it, with a count of the rest.
+fn preview(entry: &str) -> String {
+    let lines = entry.lines().count();
+    let first = entry
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+
+    let mut preview = first.chars().take(PREVIEW).collect::<String>();
+
+    if first.chars().count() > PREVIEW {
+        preview.push('…');
+    }
+
+    if preview.is_empty() {
+        preview.push_str("(whitespace)");
+    }
+
+    if lines > 1 {
+        preview.push_str(&format!("  (+{} lines)", lines - 1));
+    }
+
+    preview
+}
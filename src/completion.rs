@@ -0,0 +1,237 @@
+use iced::{
+    theme,
+    widget::{button, column, container, row, text},
+    Element, Length,
+};
+
+use crate::{editing::Position, lsp::Completion, Messages};
+
+const VISIBLE: usize = 8;
+
+// Offered completions, narrowed down as the word they complete grows.
+#[derive(Debug, Clone)]
+pub struct Popup {
+    pub start: Position,
+    items: Vec<Completion>,
+    matching: Vec<usize>,
+    selected: usize,
+}
+
+impl Popup {
+    pub fn new(start: Position, mut items: Vec<Completion>, typed: &str) -> Option<Popup> {
+        items.sort_by(|a, b| a.sort.cmp(&b.sort));
+
+        let mut popup = Popup {
+            start,
+            items,
+            matching: Vec::new(),
+            selected: 0,
+        };
+
+        popup.narrow(typed).then_some(popup)
+    }
+
+    // Items starting with what was typed come before those that only
+    // contain it. Tells whether anything is left.
+    pub fn narrow(&mut self, typed: &str) -> bool {
+        let typed = typed.to_lowercase();
+        let (mut prefixed, contained): (Vec<_>, Vec<_>) = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let filter = item.filter.to_lowercase();
+
+                filter
+                    .contains(&typed)
+                    .then(|| (index, filter.starts_with(&typed)))
+            })
+            .partition(|&(_, is_prefix)| is_prefix);
+
+        prefixed.extend(contained);
+
+        self.matching = prefixed.into_iter().map(|(index, _)| index).collect();
+        self.selected = 0;
+
+        !self.matching.is_empty()
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.matching.len().max(1);
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self
+            .selected
+            .checked_sub(1)
+            .unwrap_or(self.matching.len().saturating_sub(1));
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Completion> {
+        self.items.get(*self.matching.get(index)?)
+    }
+
+    pub fn selected(&self) -> Option<&Completion> {
+        self.get(self.selected)
+    }
+
+    pub fn view(&self) -> Element<'_, Messages> {
+        let first = self
+            .selected
+            .saturating_sub(VISIBLE - 1)
+            .min(self.matching.len().saturating_sub(VISIBLE));
+
+        let items = (first..self.matching.len())
+            .take(VISIBLE)
+            .filter_map(|index| Some((index, self.get(index)?)))
+            .fold(column![], |items, (index, item)| {
+                let style = if index == self.selected {
+                    theme::Button::Primary
+                } else {
+                    theme::Button::Text
+                };
+
+                items.push(
+                    button(text(&item.label).size(14))
+                        .style(style)
+                        .width(Length::Fill)
+                        .padding([2, 5])
+                        .on_press(Messages::AcceptCompletion(index)),
+                )
+            });
+
+        let items = container(items).width(240).style(theme::Container::Box);
+
+        let preview = self.selected().and_then(|item| {
+            let preview = [item.detail.as_deref(), item.documentation.as_deref()]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join("\n\n");
+
+            (!preview.is_empty()).then(|| {
+                container(text(preview).size(13))
+                    .max_width(360)
+                    .max_height(240)
+                    .padding(5)
+                    .style(theme::Container::Box)
+            })
+        });
+
+        match preview {
+            Some(preview) => row![items, preview].spacing(2).into(),
+            None => items.into(),
+        }
+    }
+}
+
+// Where the word that ends at the given column starts.
+pub fn word_start(line: &str, column: usize) -> usize {
+    let before = line.chars().take(column).collect::<Vec<_>>();
+    let word = before.iter().rev().take_while(|&&c| is_word(c)).count();
+
+    column.min(before.len()) - word
+}
+
+pub fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// Snippets lose their tab stops and placeholders keep only their default
+// text. The cursor goes to the first tab stop, or to the final one when
+// there is only that, with its placeholder selected.
+pub fn expand(snippet: &str) -> (String, Option<(usize, usize)>) {
+    let chars = snippet.chars().collect::<Vec<_>>();
+    let mut text = String::new();
+    let mut length = 0;
+    let mut stops: Vec<(usize, usize, usize)> = Vec::new();
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        match chars[index] {
+            '\\' if index + 1 < chars.len() => {
+                text.push(chars[index + 1]);
+                length += 1;
+                index += 2;
+                continue;
+            }
+            '$' => {
+                let braced = chars.get(index + 1) == Some(&'{');
+                let digits_at = index + 1 + usize::from(braced);
+                let digits = chars[digits_at..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit())
+                    .count();
+
+                if digits > 0 {
+                    let number = chars[digits_at..digits_at + digits]
+                        .iter()
+                        .collect::<String>()
+                        .parse()
+                        .unwrap_or(0);
+                    let after = digits_at + digits;
+
+                    if !braced {
+                        stops.push((number, length, length));
+                        index = after;
+                        continue;
+                    }
+
+                    match chars.get(after) {
+                        Some('}') => {
+                            stops.push((number, length, length));
+                            index = after + 1;
+                            continue;
+                        }
+                        Some(':') => {
+                            open.push((number, length));
+                            index = after + 1;
+                            continue;
+                        }
+                        // Choices keep the first option.
+                        Some('|') => {
+                            let option = chars[after + 1..]
+                                .iter()
+                                .take_while(|&&c| c != ',' && c != '|')
+                                .collect::<String>();
+                            let end = chars[after..]
+                                .iter()
+                                .position(|&c| c == '}')
+                                .map_or(chars.len(), |end| after + end + 1);
+
+                            stops.push((number, length, length + option.chars().count()));
+                            length += option.chars().count();
+                            text.push_str(&option);
+                            index = end;
+                            continue;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            '}' if !open.is_empty() => {
+                if let Some((number, start)) = open.pop() {
+                    stops.push((number, start, length));
+                }
+
+                index += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        text.push(chars[index]);
+        length += 1;
+        index += 1;
+    }
+
+    let first = stops
+        .iter()
+        .filter(|(number, ..)| *number > 0)
+        .min_by_key(|(number, ..)| *number)
+        .or_else(|| stops.iter().find(|(number, ..)| *number == 0))
+        .map(|&(_, start, end)| (start, end));
+
+    (text, first)
+}
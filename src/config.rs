@@ -0,0 +1,263 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::Deserialize;
+
+use crate::{backup::Backups, keymap::Action, remote::Profile, Error};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub tab_width: usize,
+    pub soft_tabs: bool,
+    pub detect_indentation: bool,
+    pub wrap_column: usize,
+    pub minimap: bool,
+    pub indent_guides: bool,
+    pub language_servers: bool,
+    pub word_completion_after: usize,
+    pub auto_indent: bool,
+    pub auto_close: bool,
+    pub trim_trailing_whitespace: bool,
+    pub trim_cursor_line: bool,
+    pub ensure_final_newline: bool,
+    pub format_on_save: bool,
+    pub formatters: BTreeMap<String, Vec<String>>,
+    pub backups: Backups,
+    pub backup_limit: usize,
+    pub swap_interval: u64,
+    pub autosave_interval: u64,
+    pub autosave_on_focus_loss: bool,
+    pub excluded: Vec<String>,
+    pub startup: Startup,
+    pub log_level: String,
+    pub theme: Option<String>,
+    pub print_line_numbers: bool,
+    pub print_header: bool,
+    pub spell_check: bool,
+    pub dictionary: String,
+    pub keys: BTreeMap<String, Action>,
+    pub remotes: BTreeMap<String, Profile>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Startup {
+    Empty,
+    #[default]
+    LastSession,
+    File(String),
+    Folder(String),
+    Welcome,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tab_width: 4,
+            soft_tabs: true,
+            detect_indentation: true,
+            wrap_column: 80,
+            minimap: true,
+            indent_guides: true,
+            language_servers: true,
+            word_completion_after: 3,
+            auto_indent: true,
+            auto_close: true,
+            trim_trailing_whitespace: false,
+            trim_cursor_line: false,
+            ensure_final_newline: false,
+            format_on_save: false,
+            formatters: BTreeMap::new(),
+            backups: Backups::default(),
+            backup_limit: 10,
+            swap_interval: 30,
+            autosave_interval: 0,
+            autosave_on_focus_loss: false,
+            excluded: vec![String::from("target"), String::from("node_modules")],
+            startup: Startup::default(),
+            log_level: String::from("info"),
+            theme: None,
+            print_line_numbers: true,
+            print_header: true,
+            spell_check: false,
+            dictionary: String::from("en_US"),
+            keys: BTreeMap::new(),
+            remotes: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Overrides {
+    pub tab_width: Option<usize>,
+    pub soft_tabs: Option<bool>,
+    pub detect_indentation: Option<bool>,
+    pub wrap_column: Option<usize>,
+    pub minimap: Option<bool>,
+    pub indent_guides: Option<bool>,
+    pub language_servers: Option<bool>,
+    pub word_completion_after: Option<usize>,
+    pub auto_indent: Option<bool>,
+    pub auto_close: Option<bool>,
+    pub trim_trailing_whitespace: Option<bool>,
+    pub trim_cursor_line: Option<bool>,
+    pub ensure_final_newline: Option<bool>,
+    pub format_on_save: Option<bool>,
+    pub formatters: Option<BTreeMap<String, Vec<String>>>,
+    pub backups: Option<Backups>,
+    pub backup_limit: Option<usize>,
+    pub swap_interval: Option<u64>,
+    pub autosave_interval: Option<u64>,
+    pub autosave_on_focus_loss: Option<bool>,
+    pub exclude: Option<Vec<String>>,
+    pub startup: Option<Startup>,
+    pub log_level: Option<String>,
+    pub theme: Option<String>,
+    pub print_line_numbers: Option<bool>,
+    pub print_header: Option<bool>,
+    pub spell_check: Option<bool>,
+    pub dictionary: Option<String>,
+    pub keys: Option<BTreeMap<String, Action>>,
+    pub remotes: Option<BTreeMap<String, Profile>>,
+    pub languages: Option<BTreeMap<String, Overrides>>,
+}
+
+impl Overrides {
+    // Settings given for a single language, by the extension it goes by.
+    pub fn language(&self, extension: &str) -> Overrides {
+        self.languages
+            .as_ref()
+            .and_then(|languages| languages.get(extension))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // A project's own settings come with whatever was cloned, so nothing in
+    // them gets to pick a program that runs on save, nor a host and key for
+    // ssh to sign in with.
+    fn untrusted(mut self) -> Overrides {
+        self.format_on_save = None;
+        self.formatters = None;
+        self.remotes = None;
+        self.languages = self.languages.map(|languages| {
+            languages
+                .into_iter()
+                .map(|(extension, overrides)| (extension, overrides.untrusted()))
+                .collect()
+        });
+
+        self
+    }
+}
+
+impl Config {
+    pub fn overridden(&self, overrides: &Overrides) -> Config {
+        Config {
+            tab_width: overrides.tab_width.unwrap_or(self.tab_width),
+            soft_tabs: overrides.soft_tabs.unwrap_or(self.soft_tabs),
+            detect_indentation: overrides
+                .detect_indentation
+                .unwrap_or(self.detect_indentation),
+            wrap_column: overrides.wrap_column.unwrap_or(self.wrap_column),
+            minimap: overrides.minimap.unwrap_or(self.minimap),
+            indent_guides: overrides.indent_guides.unwrap_or(self.indent_guides),
+            language_servers: overrides.language_servers.unwrap_or(self.language_servers),
+            word_completion_after: overrides
+                .word_completion_after
+                .unwrap_or(self.word_completion_after),
+            auto_indent: overrides.auto_indent.unwrap_or(self.auto_indent),
+            auto_close: overrides.auto_close.unwrap_or(self.auto_close),
+            trim_trailing_whitespace: overrides
+                .trim_trailing_whitespace
+                .unwrap_or(self.trim_trailing_whitespace),
+            trim_cursor_line: overrides.trim_cursor_line.unwrap_or(self.trim_cursor_line),
+            ensure_final_newline: overrides
+                .ensure_final_newline
+                .unwrap_or(self.ensure_final_newline),
+            format_on_save: overrides.format_on_save.unwrap_or(self.format_on_save),
+            formatters: overrides
+                .formatters
+                .clone()
+                .unwrap_or_else(|| self.formatters.clone()),
+            backups: overrides
+                .backups
+                .clone()
+                .unwrap_or_else(|| self.backups.clone()),
+            backup_limit: overrides.backup_limit.unwrap_or(self.backup_limit),
+            swap_interval: overrides.swap_interval.unwrap_or(self.swap_interval),
+            autosave_interval: overrides
+                .autosave_interval
+                .unwrap_or(self.autosave_interval),
+            autosave_on_focus_loss: overrides
+                .autosave_on_focus_loss
+                .unwrap_or(self.autosave_on_focus_loss),
+            excluded: overrides
+                .exclude
+                .clone()
+                .unwrap_or_else(|| self.excluded.clone()),
+            startup: overrides
+                .startup
+                .clone()
+                .unwrap_or_else(|| self.startup.clone()),
+            log_level: overrides
+                .log_level
+                .clone()
+                .unwrap_or_else(|| self.log_level.clone()),
+            theme: overrides.theme.clone().or_else(|| self.theme.clone()),
+            print_line_numbers: overrides
+                .print_line_numbers
+                .unwrap_or(self.print_line_numbers),
+            print_header: overrides.print_header.unwrap_or(self.print_header),
+            spell_check: overrides.spell_check.unwrap_or(self.spell_check),
+            dictionary: overrides
+                .dictionary
+                .clone()
+                .unwrap_or_else(|| self.dictionary.clone()),
+            keys: overrides.keys.clone().unwrap_or_else(|| self.keys.clone()),
+            remotes: overrides
+                .remotes
+                .clone()
+                .unwrap_or_else(|| self.remotes.clone()),
+        }
+    }
+}
+
+pub fn project_file(root: &Path) -> PathBuf {
+    root.join(".editor").join("config.toml")
+}
+
+pub fn user_file() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("editor").join("settings.toml"))
+}
+
+pub async fn load_project(root: PathBuf) -> Result<Overrides, Error> {
+    load(project_file(&root)).await.map(Overrides::untrusted)
+}
+
+pub async fn load_user() -> Result<Overrides, Error> {
+    match user_file() {
+        Some(path) => load(path).await,
+        None => Ok(Overrides::default()),
+    }
+}
+
+pub async fn user_modified() -> Option<SystemTime> {
+    let metadata = tokio::fs::metadata(user_file()?).await.ok()?;
+
+    metadata.modified().ok()
+}
+
+async fn load(path: PathBuf) -> Result<Overrides, Error> {
+    match tokio::fs::read_to_string(&path).await {
+        Ok(content) => {
+            toml::from_str(&content).map_err(|err| Error::InvalidConfig(path, err.to_string()))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Overrides::default()),
+        Err(err) => Err(Error::from(err)),
+    }
+}
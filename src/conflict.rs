@@ -0,0 +1,142 @@
+use iced::{
+    widget::{button, horizontal_space, row, text},
+    Element, Length,
+};
+
+use crate::Messages;
+
+const OURS: &str = "<<<<<<<";
+const BASE: &str = "|||||||";
+const SEPARATOR: &str = "=======";
+const THEIRS: &str = ">>>>>>>";
+
+// A region git left in a file, by the lines of its markers. The common
+// ancestor only shows up when git was asked to write it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Conflict {
+    pub start: usize,
+    pub base: Option<usize>,
+    pub separator: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Marker,
+    Ours,
+    Base,
+    Theirs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Ours,
+    Theirs,
+    Both,
+}
+
+impl Conflict {
+    pub fn contains(&self, line: usize) -> bool {
+        (self.start..=self.end).contains(&line)
+    }
+
+    pub fn section(&self, line: usize) -> Option<Section> {
+        if !self.contains(line) {
+            return None;
+        }
+
+        let ours_end = self.base.unwrap_or(self.separator);
+
+        Some(match line {
+            line if line == self.start || line == self.separator || line == self.end => {
+                Section::Marker
+            }
+            line if Some(line) == self.base => Section::Marker,
+            line if line < ours_end => Section::Ours,
+            line if line < self.separator => Section::Base,
+            _ => Section::Theirs,
+        })
+    }
+
+    // The lines that take the place of the whole region, markers and all.
+    pub fn resolved<'a>(&self, lines: &[&'a str], resolution: Resolution) -> Vec<&'a str> {
+        let ours = &lines[self.start + 1..self.base.unwrap_or(self.separator)];
+        let theirs = &lines[self.separator + 1..self.end];
+
+        match resolution {
+            Resolution::Ours => ours.to_vec(),
+            Resolution::Theirs => theirs.to_vec(),
+            Resolution::Both => ours.iter().chain(theirs).copied().collect(),
+        }
+    }
+}
+
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.strip_prefix(marker)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+}
+
+// Markers are only taken as a conflict when they come in order, so a file
+// that merely mentions one is left alone.
+pub fn find(text: &str) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let mut start = None;
+    let mut base = None;
+    let mut separator = None;
+
+    for (index, line) in text.split('\n').enumerate() {
+        let line = line.trim_end_matches('\r');
+
+        if is_marker(line, OURS) {
+            start = Some(index);
+            base = None;
+            separator = None;
+        } else if start.is_some() && separator.is_none() && is_marker(line, BASE) {
+            base = Some(index);
+        } else if start.is_some() && separator.is_none() && line == SEPARATOR {
+            separator = Some(index);
+        } else if is_marker(line, THEIRS) {
+            if let (Some(start), Some(separator)) = (start, separator) {
+                conflicts.push(Conflict {
+                    start,
+                    base,
+                    separator,
+                    end: index,
+                });
+            }
+
+            start = None;
+            base = None;
+            separator = None;
+        }
+    }
+
+    conflicts
+}
+
+// Acts on the conflict the cursor is in, if any, with a way to the others
+// either way.
+pub fn view(conflicts: &[Conflict], line: usize) -> Element<'_, Messages> {
+    let current = conflicts
+        .iter()
+        .position(|conflict| conflict.contains(line));
+
+    let status = match current {
+        Some(index) => format!("Merge conflict {} of {}", index + 1, conflicts.len()),
+        None => format!("{} merge conflict(s)", conflicts.len()),
+    };
+
+    let bar = row![text(status), horizontal_space(Length::Fill)].spacing(10);
+
+    let bar = if current.is_some() {
+        bar.push(button("Accept ours").on_press(Messages::ResolveConflict(Resolution::Ours)))
+            .push(button("Accept theirs").on_press(Messages::ResolveConflict(Resolution::Theirs)))
+            .push(button("Accept both").on_press(Messages::ResolveConflict(Resolution::Both)))
+    } else {
+        bar
+    };
+
+    bar.push(button("Previous").on_press(Messages::PreviousConflict))
+        .push(button("Next").on_press(Messages::NextConflict))
+        .into()
+}
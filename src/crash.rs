@@ -0,0 +1,170 @@
+use std::{
+    backtrace::Backtrace,
+    collections::VecDeque,
+    fmt::Write,
+    io, panic,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+const HISTORY: usize = 20;
+
+static MESSAGES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static RESCUED: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rescued {
+    pub path: Option<PathBuf>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Recovery {
+    pub file: PathBuf,
+    pub rescued: Rescued,
+}
+
+fn directory() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("editor").join("recovery"))
+}
+
+fn stamp() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis())
+}
+
+pub fn install() {
+    let default = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        let _ = write_report(&info.to_string(), Backtrace::force_capture());
+
+        default(info);
+    }));
+}
+
+pub fn record_message(kind: &str) {
+    let Ok(mut messages) = MESSAGES.lock() else {
+        return;
+    };
+
+    messages.push_back(kind.to_owned());
+
+    if messages.len() > HISTORY {
+        messages.pop_front();
+    }
+}
+
+fn write_report(panic: &str, backtrace: Backtrace) -> Option<()> {
+    let directory = directory()?;
+    let mut report = String::new();
+
+    let _ = writeln!(report, "editor {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(report, "{panic}\n");
+    let _ = writeln!(report, "Last messages:");
+
+    if let Ok(messages) = MESSAGES.lock() {
+        for message in messages.iter() {
+            let _ = writeln!(report, "    {message}");
+        }
+    }
+
+    let _ = writeln!(report, "\nBacktrace:\n{backtrace}");
+
+    std::fs::create_dir_all(&directory).ok()?;
+    std::fs::write(directory.join(format!("crash-{}.txt", stamp())), report).ok()?;
+
+    tracing::error!("{panic}");
+
+    Some(())
+}
+
+pub fn rescue(rescued: &Rescued) -> Option<PathBuf> {
+    let directory = directory()?;
+    let file = directory.join(format!(
+        "buffer-{}-{}.json",
+        stamp(),
+        RESCUED.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    std::fs::create_dir_all(&directory).ok()?;
+    std::fs::write(&file, serde_json::to_string(rescued).ok()?).ok()?;
+
+    Some(file)
+}
+
+// Each buffer has one swap file that every snapshot writes over, until the
+// buffer is saved or closed. A crash or power loss leaves them behind to be
+// offered again on the next start, just like rescued buffers.
+pub fn swap_file() -> Option<PathBuf> {
+    let directory = directory()?;
+
+    Some(directory.join(format!(
+        "swap-{}-{}.json",
+        stamp(),
+        RESCUED.fetch_add(1, Ordering::Relaxed)
+    )))
+}
+
+// Written aside first and then moved into place, so being cut off halfway
+// never leaves a broken snapshot.
+pub async fn write_swap(file: PathBuf, rescued: Rescued) -> Result<(), Error> {
+    let json = serde_json::to_vec(&rescued).map_err(io::Error::from)?;
+    let partial = file.with_extension("tmp");
+
+    if let Some(directory) = file.parent() {
+        tokio::fs::create_dir_all(directory).await?;
+    }
+
+    tokio::fs::write(&partial, json).await?;
+    tokio::fs::rename(&partial, &file).await?;
+
+    Ok(())
+}
+
+pub fn forget(file: &Path) {
+    let _ = std::fs::remove_file(file);
+}
+
+pub async fn pending() -> Vec<Recovery> {
+    let Some(directory) = directory() else {
+        return Vec::new();
+    };
+    let Ok(mut entries) = tokio::fs::read_dir(directory).await else {
+        return Vec::new();
+    };
+
+    let mut recoveries = Vec::new();
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let file = entry.path();
+
+        if file.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+
+        let Ok(content) = tokio::fs::read_to_string(&file).await else {
+            continue;
+        };
+
+        if let Ok(rescued) = serde_json::from_str(&content) {
+            recoveries.push(Recovery { file, rescued });
+        }
+    }
+
+    recoveries.sort_by(|a, b| a.file.cmp(&b.file));
+    recoveries
+}
+
+pub async fn discard(file: PathBuf) -> Result<(), Error> {
+    tokio::fs::remove_file(file).await.map_err(Error::from)
+}
@@ -0,0 +1,262 @@
+use iced::widget::text_editor::{Edit, Motion};
+
+use crate::editing::{self, Position};
+
+// Runs from start to end, and a lone caret starts and ends in one place.
+pub type Selection = (Position, Position);
+
+// The editor widget only knows about one cursor, so the others are kept
+// here and every edit is carried out at all of them over the plain text.
+#[derive(Debug, Clone, Default)]
+pub struct Cursors {
+    extra: Vec<Selection>,
+    block: Option<(Position, Position)>,
+}
+
+impl Cursors {
+    pub fn is_empty(&self) -> bool {
+        self.extra.is_empty() && self.block.is_none()
+    }
+
+    pub fn count(&self) -> usize {
+        self.extra.len() + 1
+    }
+
+    pub fn clear(&mut self) {
+        self.extra.clear();
+        self.block = None;
+    }
+
+    // Where the rectangle was started and the corner across from it.
+    pub fn block(&self) -> Option<(Position, Position)> {
+        self.block
+    }
+
+    // Every line the rectangle spans gets a selection of its columns, as
+    // far as the line goes. The one on the corner's line is handed back
+    // for the editor's own cursor.
+    pub fn select_block(&mut self, text: &str, anchor: Position, corner: Position) -> Selection {
+        let widths = text
+            .split('\n')
+            .map(|line| line.chars().count())
+            .collect::<Vec<_>>();
+        let left = anchor.1.min(corner.1);
+        let right = anchor.1.max(corner.1);
+        let columns = |line: usize| {
+            let width = widths.get(line).copied().unwrap_or_default();
+
+            ((line, left.min(width)), (line, right.min(width)))
+        };
+
+        self.extra = (anchor.0.min(corner.0)..=anchor.0.max(corner.0))
+            .filter(|&line| line != corner.0)
+            .map(columns)
+            .collect();
+        self.block = Some((anchor, corner));
+
+        columns(corner.0)
+    }
+
+    // The columns of every line, top to bottom, as they would be pasted
+    // back into a block.
+    pub fn block_text(&self, text: &str, primary: Selection) -> Option<String> {
+        self.block?;
+
+        let lines = text.split('\n').collect::<Vec<_>>();
+        let mut selections = std::iter::once(primary)
+            .chain(self.extra.iter().copied())
+            .collect::<Vec<_>>();
+
+        selections.sort();
+
+        let columns = selections
+            .into_iter()
+            .map(|((line, start), (_, end))| {
+                lines
+                    .get(line)
+                    .map(|line| line.chars().skip(start).take(end - start).collect())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<String>>();
+
+        Some(columns.join("\n"))
+    }
+
+    pub fn add(&mut self, selection: Selection) {
+        if !self.extra.contains(&selection) {
+            self.extra.push(selection);
+        }
+    }
+
+    // Returns the edited text and where the editor's own cursor ends up.
+    // Cursors whose selections run into each other are merged first, so
+    // no part of the text is edited twice. Pasting as many lines as there
+    // are cursors gives each of them one, the way a block is copied.
+    pub fn edit(&mut self, text: &str, primary: Selection, edit: &Edit) -> (String, Position) {
+        let chars = text.chars().collect::<Vec<_>>();
+        let starts = editing::line_starts(&chars);
+        let offset = |(line, column): Position| {
+            starts
+                .get(line)
+                .map_or(chars.len(), |start| (start + column).min(chars.len()))
+        };
+
+        let inserted = match edit {
+            Edit::Insert(c) => vec![*c],
+            Edit::Paste(text) => text.chars().collect(),
+            Edit::Enter => vec!['\n'],
+            _ => Vec::new(),
+        };
+
+        let mut ranges = std::iter::once((primary, true))
+            .chain(self.extra.iter().map(|&selection| (selection, false)))
+            .map(|((start, end), is_primary)| (offset(start), offset(end), is_primary))
+            .collect::<Vec<_>>();
+
+        ranges.sort();
+
+        let mut merged: Vec<(usize, usize, bool)> = Vec::new();
+
+        for (start, end, is_primary) in ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => {
+                    last.1 = last.1.max(end);
+                    last.2 |= is_primary;
+                }
+                _ => merged.push((start, end, is_primary)),
+            }
+        }
+
+        let pasted: Vec<Vec<char>> = match edit {
+            Edit::Paste(text) if text.split('\n').count() == merged.len() && merged.len() > 1 => {
+                text.split('\n')
+                    .map(|line| line.chars().collect())
+                    .collect()
+            }
+            _ => vec![inserted; merged.len()],
+        };
+
+        let mut result = Vec::with_capacity(chars.len() + text.len());
+        let mut carets = Vec::with_capacity(merged.len());
+        let mut copied = 0;
+
+        for ((start, end, is_primary), inserted) in merged.into_iter().zip(pasted) {
+            let (start, end) = match edit {
+                Edit::Backspace if start == end => (start.saturating_sub(1), end),
+                Edit::Delete if start == end => (start, (end + 1).min(chars.len())),
+                _ => (start, end),
+            };
+            let start = start.max(copied);
+
+            result.extend_from_slice(&chars[copied..start]);
+            result.extend(inserted);
+            carets.push((result.len(), is_primary));
+            copied = end.max(start);
+        }
+
+        result.extend_from_slice(&chars[copied..]);
+
+        let starts = editing::line_starts(&result);
+        let mut primary = (0, 0);
+
+        self.clear();
+
+        for (caret, is_primary) in carets {
+            let position = editing::position(&starts, caret);
+
+            if is_primary {
+                primary = position;
+            } else {
+                self.extra.push((position, position));
+            }
+        }
+
+        (result.into_iter().collect(), primary)
+    }
+
+    // Only moves that make sense for every caret on its own are followed.
+    // Anything else is left to the editor's cursor alone.
+    pub fn shift(&mut self, text: &str, motion: Motion, primary: Position) -> bool {
+        let lines = text
+            .split('\n')
+            .map(|line| line.chars().count())
+            .collect::<Vec<_>>();
+        let last = lines.len() - 1;
+
+        self.block = None;
+
+        for selection in &mut self.extra {
+            let (start, end) = *selection;
+            let line = end.0.min(last);
+            let end = (line, end.1.min(lines[line]));
+            let (line, column) = match motion {
+                Motion::Left if start != end => start,
+                Motion::Right if start != end => end,
+                Motion::Left if end.1 > 0 => (end.0, end.1 - 1),
+                Motion::Left if end.0 > 0 => (end.0 - 1, lines[end.0 - 1]),
+                Motion::Right if end.1 < lines[end.0] => (end.0, end.1 + 1),
+                Motion::Right if end.0 < last => (end.0 + 1, 0),
+                Motion::Left | Motion::Right => end,
+                Motion::Up => (end.0.saturating_sub(1), end.1),
+                Motion::Down => ((end.0 + 1).min(last), end.1),
+                Motion::Home => (end.0, 0),
+                Motion::End => (end.0, lines[end.0]),
+                _ => return false,
+            };
+            let line = line.min(last);
+            let position = (line, column.min(lines[line]));
+
+            *selection = (position, position);
+        }
+
+        let mut seen = vec![primary];
+
+        self.extra.retain(|&(caret, _)| {
+            let is_new = !seen.contains(&caret);
+            seen.push(caret);
+
+            is_new
+        });
+
+        true
+    }
+
+    // Looks past the last cursor first and wraps around to the top,
+    // passing over text that a cursor already covers.
+    pub fn next_occurrence(&self, text: &str, primary: Selection) -> Option<Selection> {
+        let chars = text.chars().collect::<Vec<_>>();
+        let starts = editing::line_starts(&chars);
+        let offset = |(line, column): Position| {
+            starts
+                .get(line)
+                .map_or(chars.len(), |start| (start + column).min(chars.len()))
+        };
+
+        let needle = &chars[offset(primary.0)..offset(primary.1)];
+
+        if needle.is_empty() {
+            return None;
+        }
+
+        let taken = std::iter::once(primary)
+            .chain(self.extra.iter().copied())
+            .map(|(start, end)| (offset(start), offset(end)))
+            .collect::<Vec<_>>();
+        let after = taken.iter().map(|&(_, end)| end).max().unwrap_or(0);
+
+        let found = (after..chars.len())
+            .chain(0..after)
+            .filter(|start| chars[*start..].starts_with(needle))
+            .map(|start| (start, start + needle.len()))
+            .find(|&(start, end)| {
+                taken
+                    .iter()
+                    .all(|&(taken_start, taken_end)| end <= taken_start || start >= taken_end)
+            })?;
+
+        Some((
+            editing::position(&starts, found.0),
+            editing::position(&starts, found.1),
+        ))
+    }
+}
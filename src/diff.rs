@@ -0,0 +1,431 @@
+use iced::{
+    theme::{self, Palette},
+    widget::{button, column, horizontal_space, row, scrollable, text},
+    Color, Element, Length,
+};
+
+use crate::Messages;
+
+// Changes too large to match up line by line within this many comparisons
+// are taken as all of the old lines replaced by all of the new ones.
+const MAX_CELLS: usize = 1_000_000;
+
+// Lines shown around each change.
+pub const CONTEXT: usize = 3;
+
+// One step through both texts, by the index of the line it is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Same(usize, usize),
+    Removed(usize),
+    Added(usize),
+}
+
+// Lines both sides start and end with are set aside before the rest is
+// matched up, which keeps the usual edit of a few lines cheap.
+pub fn lines<A: AsRef<str>, B: AsRef<str>>(old: &[A], new: &[B]) -> Vec<Step> {
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(old, new)| old.as_ref() == new.as_ref())
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old.as_ref() == new.as_ref())
+        .count();
+
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+    let middle_old = &old[prefix..old_end];
+    let middle_new = &new[prefix..new_end];
+
+    let mut steps = (0..prefix)
+        .map(|line| Step::Same(line, line))
+        .collect::<Vec<_>>();
+
+    if middle_old.len().saturating_mul(middle_new.len()) > MAX_CELLS {
+        steps.extend((prefix..old_end).map(Step::Removed));
+        steps.extend((prefix..new_end).map(Step::Added));
+    } else {
+        steps.extend(
+            matched(middle_old, middle_new)
+                .into_iter()
+                .map(|step| match step {
+                    Step::Same(old, new) => Step::Same(prefix + old, prefix + new),
+                    Step::Removed(old) => Step::Removed(prefix + old),
+                    Step::Added(new) => Step::Added(prefix + new),
+                }),
+        );
+    }
+
+    steps.extend((0..suffix).map(|line| Step::Same(old_end + line, new_end + line)));
+
+    steps
+}
+
+// Walks the longest common subsequence, found from the end backwards, with
+// removals put before the additions that replace them.
+fn matched<A: AsRef<str>, B: AsRef<str>>(old: &[A], new: &[B]) -> Vec<Step> {
+    let width = new.len() + 1;
+    let mut common = vec![0u32; (old.len() + 1) * width];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i * width + j] = if old[i].as_ref() == new[j].as_ref() {
+                common[(i + 1) * width + j + 1] + 1
+            } else {
+                common[(i + 1) * width + j].max(common[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut steps = Vec::with_capacity(old.len().max(new.len()));
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i].as_ref() == new[j].as_ref() {
+            steps.push(Step::Same(i, j));
+            i += 1;
+            j += 1;
+        } else if j == new.len()
+            || (i < old.len() && common[(i + 1) * width + j] >= common[i * width + j + 1])
+        {
+            steps.push(Step::Removed(i));
+            i += 1;
+        } else {
+            steps.push(Step::Added(j));
+            j += 1;
+        }
+    }
+
+    steps
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Same,
+    Removed,
+    Added,
+}
+
+#[derive(Debug, Clone)]
+pub struct Row {
+    pub kind: Kind,
+    pub old: Option<usize>,
+    pub new: Option<usize>,
+    pub text: String,
+}
+
+// A run of changes with the unchanged lines around them.
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    pub rows: Vec<Row>,
+}
+
+fn rows(old: &str, new: &str) -> Vec<Row> {
+    let old = old.split('\n').collect::<Vec<_>>();
+    let new = new.split('\n').collect::<Vec<_>>();
+
+    lines(&old, &new)
+        .into_iter()
+        .map(|step| match step {
+            Step::Same(before, after) => Row {
+                kind: Kind::Same,
+                old: Some(before),
+                new: Some(after),
+                text: old[before].to_owned(),
+            },
+            Step::Removed(before) => Row {
+                kind: Kind::Removed,
+                old: Some(before),
+                new: None,
+                text: old[before].to_owned(),
+            },
+            Step::Added(after) => Row {
+                kind: Kind::Added,
+                old: None,
+                new: Some(after),
+                text: new[after].to_owned(),
+            },
+        })
+        .collect()
+}
+
+// Changes closer together than twice the context share a hunk.
+pub fn hunks(old: &str, new: &str, context: usize) -> Vec<Hunk> {
+    let rows = rows(old, new);
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+
+    for (index, row) in rows.iter().enumerate() {
+        if row.kind == Kind::Same {
+            continue;
+        }
+
+        let start = index.saturating_sub(context);
+        let end = index.saturating_add(context + 1).min(rows.len());
+
+        match ranges.last_mut() {
+            Some((_, last)) if start <= *last => *last = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| Hunk {
+            rows: rows[start..end].to_vec(),
+        })
+        .collect()
+}
+
+// Both texts in full as a single hunk, or none at all when they are the
+// same.
+pub fn whole(old: &str, new: &str) -> Vec<Hunk> {
+    let rows = rows(old, new);
+
+    if rows.iter().all(|row| row.kind == Kind::Same) {
+        return Vec::new();
+    }
+
+    vec![Hunk { rows }]
+}
+
+// Removed and added lines facing each other, the way they replace one
+// another within each hunk.
+fn pairs(hunk: &Hunk) -> Vec<(Option<&Row>, Option<&Row>)> {
+    fn flush<'a>(
+        removed: &mut Vec<&'a Row>,
+        added: &mut Vec<&'a Row>,
+        pairs: &mut Vec<(Option<&'a Row>, Option<&'a Row>)>,
+    ) {
+        for index in 0..removed.len().max(added.len()) {
+            pairs.push((removed.get(index).copied(), added.get(index).copied()));
+        }
+
+        removed.clear();
+        added.clear();
+    }
+
+    let mut pairs = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+
+    for row in &hunk.rows {
+        match row.kind {
+            Kind::Removed => removed.push(row),
+            Kind::Added => added.push(row),
+            Kind::Same => {
+                flush(&mut removed, &mut added, &mut pairs);
+                pairs.push((Some(row), Some(row)));
+            }
+        }
+    }
+
+    flush(&mut removed, &mut added, &mut pairs);
+
+    pairs
+}
+
+pub fn scroll_id() -> scrollable::Id {
+    scrollable::Id::new("comparison")
+}
+
+// What takes up one line of the comparison as it is laid out.
+enum Shown<'a> {
+    Gap,
+    Unified(&'a Row),
+    Paired(Option<&'a Row>, Option<&'a Row>),
+}
+
+impl Shown<'_> {
+    fn is_change(&self) -> bool {
+        let is_changed = |row: &Row| row.kind != Kind::Same;
+
+        match self {
+            Shown::Gap => false,
+            Shown::Unified(row) => is_changed(row),
+            Shown::Paired(left, right) => {
+                left.is_none_or(is_changed) || right.is_none_or(is_changed)
+            }
+        }
+    }
+}
+
+// Two versions of a text, shown around each change or in full. Changes
+// are stepped through in the order they are shown.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub title: String,
+    pub hunks: Vec<Hunk>,
+    pub is_side_by_side: bool,
+    current: Option<usize>,
+}
+
+impl Comparison {
+    pub fn new(title: String, hunks: Vec<Hunk>) -> Self {
+        Self {
+            title,
+            hunks,
+            is_side_by_side: false,
+            current: None,
+        }
+    }
+
+    pub fn side_by_side(title: String, hunks: Vec<Hunk>) -> Self {
+        Self {
+            is_side_by_side: true,
+            ..Self::new(title, hunks)
+        }
+    }
+
+    pub fn toggle_layout(&mut self) {
+        self.is_side_by_side = !self.is_side_by_side;
+        self.current = None;
+    }
+
+    // Wraps around at either end, and returns where the change is within
+    // everything shown.
+    pub fn step(&mut self, is_forward: bool) -> Option<scrollable::RelativeOffset> {
+        let (changes, total) = {
+            let shown = self.shown();
+
+            (changes(&shown), shown.len())
+        };
+
+        let count = changes.len();
+
+        if count == 0 {
+            return None;
+        }
+
+        let current = match (self.current, is_forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(current), true) => (current + 1) % count,
+            (Some(current), false) => (current + count - 1) % count,
+        };
+
+        self.current = Some(current);
+
+        Some(scrollable::RelativeOffset {
+            x: 0.0,
+            y: changes[current] as f32 / total.saturating_sub(1).max(1) as f32,
+        })
+    }
+
+    fn shown(&self) -> Vec<Shown<'_>> {
+        let mut shown = Vec::new();
+
+        for (index, hunk) in self.hunks.iter().enumerate() {
+            if index > 0 {
+                shown.push(Shown::Gap);
+            }
+
+            if self.is_side_by_side {
+                shown.extend(
+                    pairs(hunk)
+                        .into_iter()
+                        .map(|(left, right)| Shown::Paired(left, right)),
+                );
+            } else {
+                shown.extend(hunk.rows.iter().map(Shown::Unified));
+            }
+        }
+
+        shown
+    }
+
+    pub fn view(&self, palette: Palette) -> Element<'_, Messages> {
+        let color = |kind: Kind| match kind {
+            Kind::Same => palette.text,
+            Kind::Removed => palette.danger,
+            Kind::Added => palette.success,
+        };
+        let faded = Color {
+            a: 0.5,
+            ..palette.text
+        };
+
+        let number =
+            |line: Option<usize>| line.map_or(String::new(), |line| (line + 1).to_string());
+
+        let shown = self.shown();
+        let changes = changes(&shown);
+
+        let lines = shown
+            .into_iter()
+            .fold(column![].spacing(2), |lines, shown| match shown {
+                Shown::Gap => lines.push(text("⋯").size(13).style(theme::Text::Color(faded))),
+                Shown::Unified(row) => {
+                    let sign = match row.kind {
+                        Kind::Same => ' ',
+                        Kind::Removed => '-',
+                        Kind::Added => '+',
+                    };
+
+                    lines.push(
+                        text(format!(
+                            "{:>5} {:>5} {sign} {}",
+                            number(row.old),
+                            number(row.new),
+                            row.text
+                        ))
+                        .size(13)
+                        .style(theme::Text::Color(color(row.kind))),
+                    )
+                }
+                Shown::Paired(left, right) => {
+                    let side = |row: Option<&Row>, line: Option<usize>| {
+                        let (content, kind) = row.map_or((String::new(), Kind::Same), |row| {
+                            (format!("{:>5}  {}", number(line), row.text), row.kind)
+                        });
+
+                        text(content)
+                            .size(13)
+                            .width(Length::Fill)
+                            .style(theme::Text::Color(color(kind)))
+                    };
+
+                    lines.push(row![
+                        side(left, left.and_then(|row| row.old)),
+                        side(right, right.and_then(|row| row.new)),
+                    ])
+                }
+            });
+
+        let position = match self.current {
+            Some(current) => format!("Change {} of {}", current + 1, changes.len()),
+            None => format!("{} change(s)", changes.len()),
+        };
+
+        let layout = if self.is_side_by_side {
+            "Unified"
+        } else {
+            "Side by side"
+        };
+
+        column![
+            row![
+                text(&self.title),
+                horizontal_space(Length::Fill),
+                text(position),
+                button("Previous").on_press(Messages::PreviousChange),
+                button("Next").on_press(Messages::NextChange),
+                button(layout).on_press(Messages::ToggleDiffLayout),
+                button("Close").on_press(Messages::CloseDiff),
+            ]
+            .spacing(10),
+            scrollable(lines).id(scroll_id()).height(300),
+        ]
+        .spacing(5)
+        .into()
+    }
+}
+
+// Where each run of changed lines starts.
+fn changes(shown: &[Shown<'_>]) -> Vec<usize> {
+    (0..shown.len())
+        .filter(|&index| shown[index].is_change() && (index == 0 || !shown[index - 1].is_change()))
+        .collect()
+}
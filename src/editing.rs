@@ -0,0 +1,993 @@
+use iced::widget::text_editor::{Action, Content, Edit, Motion};
+
+use crate::{
+    filter::Filter,
+    language::{self, Comment},
+    move_cursor,
+    text::Text,
+};
+
+pub type Position = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Backward,
+    Forward,
+}
+
+impl Direction {
+    fn motion(self) -> Motion {
+        match self {
+            Direction::Backward => Motion::Left,
+            Direction::Forward => Motion::Right,
+        }
+    }
+}
+
+// Edits made through here are not spliced into the rope, which is read
+// back in full the next time it is asked for.
+pub struct Document<'a> {
+    content: &'a mut Content,
+    text: &'a mut Text,
+    filter: Option<&'a mut Filter>,
+}
+
+impl<'a> Document<'a> {
+    pub fn new(
+        content: &'a mut Content,
+        text: &'a mut Text,
+        filter: Option<&'a mut Filter>,
+    ) -> Self {
+        Self {
+            content,
+            text,
+            filter,
+        }
+    }
+
+    pub fn apply(&mut self, action: Action) {
+        if action.is_edit() {
+            *self.text = Text::default();
+        }
+
+        match self.filter.as_deref_mut() {
+            Some(filter) => filter.edit(self.content, action),
+            None => self.content.edit(action),
+        }
+    }
+
+    pub fn line(&self, line: usize) -> String {
+        self.content
+            .line(line)
+            .map_or(String::new(), |text| String::from(&*text))
+    }
+
+    pub fn cursor(&self) -> Position {
+        self.content.cursor_position()
+    }
+
+    pub fn move_to(&mut self, (line, column): Position) {
+        move_cursor(self.content, line, column);
+    }
+
+    pub fn insert(&mut self, text: &str) {
+        for c in text.chars() {
+            self.apply(Action::Edit(Edit::Insert(c)));
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.content.line_count()
+    }
+
+    pub fn text(&self) -> String {
+        self.content.text()
+    }
+
+    pub fn select(&mut self, ((start, column), (end, end_column)): (Position, Position)) {
+        self.move_to((start, column));
+
+        for _ in start..end {
+            self.apply(Action::Select(Motion::Down));
+        }
+
+        let from = if end > start {
+            self.apply(Action::Select(Motion::Home));
+            0
+        } else {
+            column
+        };
+
+        for _ in from..end_column {
+            self.apply(Action::Select(Motion::Right));
+        }
+    }
+
+    pub fn select_lines(&mut self, start: usize, end: usize) {
+        self.move_to((start, 0));
+
+        for _ in start..end {
+            self.apply(Action::Select(Motion::Down));
+        }
+
+        self.apply(Action::Select(Motion::End));
+    }
+
+    pub fn selection(&self) -> Option<(Position, Position)> {
+        selection(self.content)
+    }
+
+    pub fn has_selection(&self) -> bool {
+        self.content
+            .selection()
+            .is_some_and(|selected| !selected.is_empty())
+    }
+
+    pub fn selected_lines(&self) -> Option<(usize, usize)> {
+        let ((start, _), (end, column)) = self.selection()?;
+
+        match end {
+            end if end > start && column == 0 => Some((start, end - 1)),
+            end => Some((start, end)),
+        }
+    }
+}
+
+// The editor only exposes the selected text and the cursor, so the anchor
+// is found by checking which side of the cursor the text fits.
+pub fn selection(content: &Content) -> Option<(Position, Position)> {
+    let selected = content.selection()?;
+    let cursor = content.cursor_position();
+    let segments = selected.split('\n').collect::<Vec<_>>();
+    let breaks = segments.len() - 1;
+    let width = |text: &str| text.chars().count();
+
+    if let Some(line) = cursor.0.checked_sub(breaks) {
+        let first = content
+            .line(line)
+            .map_or(String::new(), |text| String::from(&*text));
+        let start = match breaks {
+            0 => cursor.1.checked_sub(width(&selected)),
+            _ => width(&first).checked_sub(width(segments[0])),
+        };
+
+        if let Some(start) = start.filter(|&start| slice(&first, start).starts_with(segments[0])) {
+            return Some(((line, start), cursor));
+        }
+    }
+
+    let end = match breaks {
+        0 => cursor.1 + width(&selected),
+        _ => width(segments[breaks]),
+    };
+
+    Some((cursor, (cursor.0 + breaks, end)))
+}
+
+fn slice(line: &str, column: usize) -> &str {
+    line.char_indices()
+        .nth(column)
+        .map_or("", |(index, _)| &line[index..])
+}
+
+pub fn indent(document: &mut Document<'_>, start: usize, end: usize, unit: &str) {
+    for line in start..=end {
+        if document.line(line).is_empty() {
+            continue;
+        }
+
+        document.move_to((line, 0));
+        document.insert(unit);
+    }
+}
+
+pub fn dedent(document: &mut Document<'_>, start: usize, end: usize, unit: usize) {
+    for line in start..=end {
+        let width = dedent_width(&document.line(line), unit);
+
+        if width == 0 {
+            continue;
+        }
+
+        document.move_to((line, 0));
+
+        for _ in 0..width {
+            document.apply(Action::Select(Motion::Right));
+        }
+
+        document.apply(Action::Edit(Edit::Delete));
+    }
+}
+
+pub fn dedent_width(line: &str, unit: usize) -> usize {
+    if line.starts_with('\t') {
+        1
+    } else {
+        line.chars().take(unit).take_while(|&c| c == ' ').count()
+    }
+}
+
+pub fn smart_home(document: &mut Document<'_>, is_selecting: bool) {
+    let (line, column) = document.cursor();
+    let text = document.line(line);
+    let indentation = text.chars().take_while(|c| c.is_whitespace()).count();
+    let target = if column == indentation {
+        0
+    } else {
+        indentation
+    };
+
+    if !is_selecting {
+        document.move_to((line, target));
+        return;
+    }
+
+    document.apply(Action::Select(Motion::Home));
+
+    for _ in 0..target {
+        document.apply(Action::Select(Motion::Right));
+    }
+}
+
+type Range = (usize, usize);
+
+pub fn expanded(text: &str, (start, end): (Position, Position)) -> Option<(Position, Position)> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let starts = line_starts(&chars);
+    let offset = |(line, column): Position| starts.get(line).map_or(chars.len(), |s| s + column);
+    let range = (offset(start), offset(end));
+
+    let candidates = [
+        word(&chars, range),
+        quoted(&chars, &starts, range, false),
+        quoted(&chars, &starts, range, true),
+        bracketed(&chars, range, false),
+        bracketed(&chars, range, true),
+        lines(&chars, &starts, range, true),
+        lines(&chars, &starts, range, false),
+        block(&chars, &starts, range, false),
+        block(&chars, &starts, range, true),
+        Some((0, chars.len())),
+    ];
+
+    let (start, end) = candidates
+        .into_iter()
+        .flatten()
+        .filter(|&(start, end)| start <= range.0 && end >= range.1 && (start, end) != range)
+        .min_by_key(|&(start, end)| end - start)?;
+
+    Some((position(&starts, start), position(&starts, end)))
+}
+
+// The innermost brackets or quotes around the range, as where the opening
+// and the closing one are.
+pub fn surrounding(text: &str, (start, end): (Position, Position)) -> Option<(Position, Position)> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let starts = line_starts(&chars);
+    let offset = |(line, column): Position| starts.get(line).map_or(chars.len(), |s| s + column);
+    let range = (offset(start), offset(end));
+
+    let (opened, closed) = [
+        quoted(&chars, &starts, range, false),
+        bracketed(&chars, range, false),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|&(start, end)| start <= range.0 && end >= range.1)
+    .min_by_key(|&(start, end)| end - start)?;
+
+    Some((position(&starts, opened - 1), position(&starts, closed)))
+}
+
+pub fn line_starts(chars: &[char]) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(
+            chars
+                .iter()
+                .enumerate()
+                .filter(|(_, &c)| c == '\n')
+                .map(|(index, _)| index + 1),
+        )
+        .collect()
+}
+
+pub fn position(starts: &[usize], offset: usize) -> Position {
+    let line = starts.partition_point(|&start| start <= offset) - 1;
+
+    (line, offset - starts[line])
+}
+
+fn line_end(chars: &[char], starts: &[usize], line: usize) -> usize {
+    starts.get(line + 1).map_or(chars.len(), |next| next - 1)
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn word(chars: &[char], (start, end): Range) -> Option<Range> {
+    let start = start
+        - chars[..start]
+            .iter()
+            .rev()
+            .take_while(|&&c| is_word(c))
+            .count();
+    let end = end + chars[end..].iter().take_while(|&&c| is_word(c)).count();
+
+    (start < end).then_some((start, end))
+}
+
+fn quoted(chars: &[char], starts: &[usize], (start, end): Range, is_outer: bool) -> Option<Range> {
+    let line = position(starts, start).0;
+    let (first, last) = (starts[line], line_end(chars, starts, line));
+
+    if end > last {
+        return None;
+    }
+
+    let mut open: Option<(char, usize)> = None;
+    let mut index = first;
+
+    while index < last {
+        let c = chars[index];
+
+        match open {
+            Some(_) if c == '\\' => index += 1,
+            Some((quote, opened)) if c == quote => {
+                if opened < start && index >= end {
+                    return Some(if is_outer {
+                        (opened, index + 1)
+                    } else {
+                        (opened + 1, index)
+                    });
+                }
+
+                open = None;
+            }
+            None if matches!(c, '"' | '\'' | '`') => open = Some((c, index)),
+            _ => {}
+        }
+
+        index += 1;
+    }
+
+    None
+}
+
+fn bracketed(chars: &[char], (start, end): Range, is_outer: bool) -> Option<Range> {
+    let mut depth = 0;
+    let opened = (0..start).rev().find(|&index| match chars[index] {
+        ')' | ']' | '}' => {
+            depth += 1;
+            false
+        }
+        '(' | '[' | '{' if depth == 0 => true,
+        '(' | '[' | '{' => {
+            depth -= 1;
+            false
+        }
+        _ => false,
+    })?;
+
+    let closer = match chars[opened] {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    };
+
+    let mut depth = 0;
+    let closed = (end..chars.len()).find(|&index| match chars[index] {
+        c if c == closer && depth == 0 => true,
+        '(' | '[' | '{' => {
+            depth += 1;
+            false
+        }
+        ')' | ']' | '}' => {
+            depth -= 1;
+            false
+        }
+        _ => false,
+    })?;
+
+    Some(if is_outer {
+        (opened, closed + 1)
+    } else {
+        (opened + 1, closed)
+    })
+}
+
+fn lines(chars: &[char], starts: &[usize], (start, end): Range, is_trimmed: bool) -> Option<Range> {
+    let first = position(starts, start).0;
+    let last = position(starts, end).0;
+    let (mut start, mut end) = (starts[first], line_end(chars, starts, last));
+
+    if is_trimmed {
+        while start < end && chars[start].is_whitespace() {
+            start += 1;
+        }
+
+        while end > start && chars[end - 1].is_whitespace() {
+            end -= 1;
+        }
+    }
+
+    Some((start, end))
+}
+
+fn block(chars: &[char], starts: &[usize], (start, end): Range, has_header: bool) -> Option<Range> {
+    let text = |line: usize| &chars[starts[line]..line_end(chars, starts, line)];
+    let is_blank = |line: usize| text(line).iter().all(|c| c.is_whitespace());
+    let indentation = |line: usize| text(line).iter().take_while(|c| c.is_whitespace()).count();
+
+    let (mut first, mut last) = (position(starts, start).0, position(starts, end).0);
+    let level = (first..=last)
+        .filter(|&line| !is_blank(line))
+        .map(indentation)
+        .min()?;
+
+    while first > 0 && (is_blank(first - 1) || indentation(first - 1) >= level) {
+        first -= 1;
+    }
+
+    while last + 1 < starts.len() && (is_blank(last + 1) || indentation(last + 1) >= level) {
+        last += 1;
+    }
+
+    if has_header && first > 0 {
+        first -= 1;
+
+        let header = indentation(first);
+        let closes = last + 1 < starts.len()
+            && indentation(last + 1) == header
+            && matches!(text(last + 1).get(header), Some(')' | ']' | '}'));
+
+        if closes {
+            last += 1;
+        }
+    }
+
+    Some((starts[first], line_end(chars, starts, last)))
+}
+
+pub fn move_subword(document: &mut Document<'_>, direction: Direction, is_selecting: bool) {
+    let (line, column) = document.cursor();
+    let chars = document.line(line).chars().collect::<Vec<_>>();
+    let target = subword_boundary(&chars, column, direction);
+    let motion = direction.motion();
+
+    match target {
+        Some(target) if !is_selecting => document.move_to((line, target)),
+        Some(target) => {
+            for _ in 0..target.abs_diff(column) {
+                document.apply(Action::Select(motion));
+            }
+        }
+        None if is_selecting => document.apply(Action::Select(motion)),
+        None => document.apply(Action::Move(motion)),
+    }
+}
+
+pub fn delete_subword(document: &mut Document<'_>, direction: Direction) {
+    if !document.has_selection() {
+        move_subword(document, direction, true);
+    }
+
+    document.apply(Action::Edit(Edit::Delete));
+}
+
+pub fn delete_word(document: &mut Document<'_>, direction: Direction) {
+    if !document.has_selection() {
+        document.apply(Action::Select(match direction {
+            Direction::Backward => Motion::WordLeft,
+            Direction::Forward => Motion::WordRight,
+        }));
+    }
+
+    document.apply(Action::Edit(Edit::Delete));
+}
+
+// At the end of a line there is nothing left to delete, so the line break
+// goes instead and the next line is pulled up.
+pub fn delete_to_line_end(document: &mut Document<'_>) {
+    let (line, column) = document.cursor();
+    let is_at_end = column >= document.line(line).chars().count();
+
+    document.apply(Action::Select(if is_at_end {
+        Motion::Right
+    } else {
+        Motion::End
+    }));
+    document.apply(Action::Edit(Edit::Delete));
+}
+
+fn subword_boundary(chars: &[char], column: usize, direction: Direction) -> Option<usize> {
+    let is_gap = |c: char| c.is_whitespace() || c == '_';
+    let boundaries = subword_boundaries(chars);
+
+    if direction == Direction::Forward {
+        let start = column
+            + chars[column.min(chars.len())..]
+                .iter()
+                .take_while(|&&c| is_gap(c))
+                .count();
+
+        (column < chars.len()).then(|| {
+            boundaries
+                .into_iter()
+                .find(|&boundary| boundary > start)
+                .unwrap_or(chars.len())
+        })
+    } else {
+        let start = column
+            - chars[..column.min(chars.len())]
+                .iter()
+                .rev()
+                .take_while(|&&c| is_gap(c))
+                .count();
+
+        (column > 0).then(|| {
+            boundaries
+                .into_iter()
+                .rev()
+                .find(|&boundary| boundary < start)
+                .unwrap_or(0)
+        })
+    }
+}
+
+fn subword_boundaries(chars: &[char]) -> Vec<usize> {
+    #[derive(PartialEq)]
+    enum Class {
+        Upper,
+        Lower,
+        Digit,
+        Gap,
+        Other,
+    }
+
+    let class = |c: char| match c {
+        c if c.is_uppercase() => Class::Upper,
+        c if c.is_alphabetic() => Class::Lower,
+        c if c.is_numeric() => Class::Digit,
+        c if c.is_whitespace() || c == '_' => Class::Gap,
+        _ => Class::Other,
+    };
+
+    (1..chars.len())
+        .filter(|&index| {
+            let (previous, current) = (class(chars[index - 1]), class(chars[index]));
+            let next = chars.get(index + 1).copied().map(class);
+
+            match (previous, current) {
+                (Class::Upper, Class::Lower) => false,
+                (Class::Upper, Class::Upper) => next == Some(Class::Lower),
+                (previous, current) => previous != current,
+            }
+        })
+        .collect()
+}
+
+pub fn join_lines(document: &mut Document<'_>, comment: Option<&str>) -> bool {
+    let (start, end) = match document.selected_lines() {
+        Some((start, end)) if start != end => (start, end),
+        _ => (document.cursor().0, document.cursor().0 + 1),
+    };
+
+    if end >= document.line_count() {
+        return false;
+    }
+
+    let mut joined = document.line(start);
+    let mut column = 0;
+
+    for line in start + 1..=end {
+        let next = document.line(line);
+        let mut next = next.trim_start();
+
+        // Joining two line comments would leave a stray marker mid-line.
+        if let Some(marker) = comment.filter(|marker| joined.contains(marker)) {
+            if let Some(rest) = next.strip_prefix(marker) {
+                next = rest.trim_start();
+            }
+        }
+
+        joined.truncate(joined.trim_end().len());
+        column = joined.chars().count();
+
+        if !joined.is_empty() && !next.is_empty() {
+            joined.push(' ');
+        }
+
+        joined.push_str(next);
+    }
+
+    let width = document.line(end).chars().count();
+
+    document.select(((start, 0), (end, width)));
+    document.insert(&joined);
+    document.move_to((start, column));
+
+    true
+}
+
+// The lines the cursor or the selection touches, even in part.
+fn touched_lines(document: &Document<'_>) -> (usize, usize) {
+    document
+        .selected_lines()
+        .unwrap_or((document.cursor().0, document.cursor().0))
+}
+
+// The line sizes stay as they were, so the selection or the cursor can go
+// along with the lines that moved.
+fn follow_lines(
+    document: &mut Document<'_>,
+    selection: Option<(Position, Position)>,
+    (line, column): Position,
+    offset: isize,
+) {
+    let shifted = |(line, column): Position| (line.saturating_add_signed(offset), column);
+
+    match selection.filter(|(start, end)| start != end) {
+        Some((start, end)) => document.select((shifted(start), shifted(end))),
+        None => document.move_to(shifted((line, column))),
+    }
+}
+
+pub fn duplicate_lines(document: &mut Document<'_>) {
+    let (start, end) = touched_lines(document);
+    let (selection, cursor) = (document.selection(), document.cursor());
+    let block = (start..=end)
+        .map(|line| document.line(line))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    document.move_to((end, document.line(end).chars().count()));
+    document.insert(&format!("\n{block}"));
+    follow_lines(document, selection, cursor, (end - start + 1) as isize);
+}
+
+// The line on the other side of the block trades places with it.
+pub fn move_lines(document: &mut Document<'_>, direction: Direction) -> bool {
+    let (start, end) = touched_lines(document);
+    let (selection, cursor) = (document.selection(), document.cursor());
+
+    let (from, to, offset) = match direction {
+        Direction::Backward if start > 0 => (start - 1, end, -1),
+        Direction::Forward if end + 1 < document.line_count() => (start, end + 1, 1),
+        _ => return false,
+    };
+
+    let mut lines = (from..=to)
+        .map(|line| document.line(line))
+        .collect::<Vec<_>>();
+
+    match direction {
+        Direction::Backward => lines.rotate_left(1),
+        Direction::Forward => lines.rotate_right(1),
+    }
+
+    let width = document.line(to).chars().count();
+
+    document.select(((from, 0), (to, width)));
+    document.insert(&lines.join("\n"));
+    follow_lines(document, selection, cursor, offset);
+
+    true
+}
+
+// The line break goes with the lines, from after them or, at the end of the
+// buffer, from before them.
+pub fn delete_lines(document: &mut Document<'_>) {
+    let (start, end) = touched_lines(document);
+    let column = document.cursor().1;
+    let last = document.line_count() - 1;
+
+    let range = if end < last {
+        ((start, 0), (end + 1, 0))
+    } else if start > 0 {
+        (
+            (start - 1, document.line(start - 1).chars().count()),
+            (end, document.line(end).chars().count()),
+        )
+    } else {
+        ((0, 0), (end, document.line(end).chars().count()))
+    };
+
+    document.select(range);
+    document.apply(Action::Edit(Edit::Delete));
+
+    let line = start.min(document.line_count() - 1);
+
+    document.move_to((line, column.min(document.line(line).chars().count())));
+}
+
+// Lines are commented at the indentation of the least indented of them, and
+// only uncommented when all of them already are. Blank lines are left alone.
+pub fn toggle_comment(
+    document: &mut Document<'_>,
+    start: usize,
+    end: usize,
+    comment: Comment,
+) -> bool {
+    let (open, close) = match comment {
+        Comment::Line(open) => (open, ""),
+        Comment::Block(open, close) => (open, close),
+    };
+
+    let lines = (start..=end)
+        .map(|line| (line, document.line(line)))
+        .filter(|(_, text)| !text.trim().is_empty())
+        .collect::<Vec<_>>();
+
+    let Some(column) = lines
+        .iter()
+        .map(|(_, text)| text.chars().take_while(|c| c.is_whitespace()).count())
+        .min()
+    else {
+        return false;
+    };
+
+    let is_commented = lines.iter().all(|(_, text)| {
+        let text = text.trim();
+
+        text.starts_with(open) && text.ends_with(close)
+    });
+
+    for (line, text) in &lines {
+        let changed = if is_commented {
+            uncommented(text, open, close)
+        } else {
+            commented(text, column, open, close)
+        };
+
+        document.select(((*line, 0), (*line, text.chars().count())));
+        document.insert(&changed);
+    }
+
+    true
+}
+
+fn commented(text: &str, column: usize, open: &str, close: &str) -> String {
+    let split = text
+        .char_indices()
+        .nth(column)
+        .map_or(text.len(), |(index, _)| index);
+    let (indentation, body) = text.split_at(split);
+
+    if close.is_empty() {
+        format!("{indentation}{open} {body}")
+    } else {
+        format!("{indentation}{open} {body} {close}")
+    }
+}
+
+fn uncommented(text: &str, open: &str, close: &str) -> String {
+    let body = text.trim_start();
+    let indentation = &text[..text.len() - body.len()];
+    let body = body.strip_prefix(open).unwrap_or(body);
+    let body = body.strip_prefix(' ').unwrap_or(body);
+
+    let body = if close.is_empty() {
+        body
+    } else {
+        let body = body.trim_end();
+        let body = body.strip_suffix(close).unwrap_or(body);
+
+        body.strip_suffix(' ').unwrap_or(body)
+    };
+
+    format!("{indentation}{body}")
+}
+
+// A new line keeps the indentation of the one it was split from, with a
+// level more after an opening bracket.
+pub fn indentation(before: &str, unit: &str) -> String {
+    let mut indentation = before
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect::<String>();
+
+    if before.trim_end().ends_with(['{', '(', '[']) {
+        indentation.push_str(unit);
+    }
+
+    indentation
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pairing {
+    Overtype,
+    Wrap(char),
+    Close(char),
+}
+
+// A closer the editor put in itself is typed over rather than doubled. Nothing
+// is closed right before a word, and quotes not right after one either, so an
+// apostrophe stays on its own.
+pub fn pairing(
+    c: char,
+    line: &[char],
+    column: usize,
+    is_selected: bool,
+    is_pending: bool,
+) -> Option<Pairing> {
+    let next = line.get(column).copied();
+    let previous = column
+        .checked_sub(1)
+        .and_then(|index| line.get(index))
+        .copied();
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+
+    if is_pending && !is_selected && next == Some(c) && matches!(c, ')' | ']' | '}' | '"' | '\'') {
+        return Some(Pairing::Overtype);
+    }
+
+    let closing = match c {
+        '(' => ')',
+        '[' => ']',
+        '{' => '}',
+        '"' | '\'' => c,
+        _ => return None,
+    };
+
+    if is_selected {
+        return Some(Pairing::Wrap(closing));
+    }
+
+    if is_word(next) || (closing == c && is_word(previous)) {
+        return None;
+    }
+
+    Some(Pairing::Close(closing))
+}
+
+// The bracket right at the cursor is tried before the one just behind it.
+pub fn matching_bracket(
+    text: &str,
+    (line, column): Position,
+    extension: &str,
+) -> Option<(Position, Position)> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let starts = line_starts(&chars);
+    let offset = starts.get(line)? + column;
+    let is_code = code_mask(&chars, extension);
+
+    let (bracket, partner) = std::iter::once(offset)
+        .chain(offset.checked_sub(1))
+        .filter(|&index| index < chars.len() && is_code[index])
+        .find_map(|index| Some((index, partner(&chars, &is_code, index)?)))?;
+
+    Some((position(&starts, bracket), position(&starts, partner)))
+}
+
+fn partner(chars: &[char], is_code: &[bool], index: usize) -> Option<usize> {
+    let (same, other, is_forward) = match chars[index] {
+        '(' => ('(', ')', true),
+        '[' => ('[', ']', true),
+        '{' => ('{', '}', true),
+        ')' => (')', '(', false),
+        ']' => (']', '[', false),
+        '}' => ('}', '{', false),
+        _ => return None,
+    };
+
+    let mut depth = 0;
+    let mut is_partner = |&index: &usize| match chars[index] {
+        _ if !is_code[index] => false,
+        c if c == same => {
+            depth += 1;
+            false
+        }
+        c if c == other && depth == 0 => true,
+        c if c == other => {
+            depth -= 1;
+            false
+        }
+        _ => false,
+    };
+
+    if is_forward {
+        (index + 1..chars.len()).find(&mut is_partner)
+    } else {
+        (0..index).rev().find(&mut is_partner)
+    }
+}
+
+// Marks what lies outside strings and comments. Languages without known
+// comment syntax are taken to be code throughout.
+fn code_mask(chars: &[char], extension: &str) -> Vec<bool> {
+    let mut mask = vec![true; chars.len()];
+    let line_comment: Option<Vec<char>> =
+        language::line_comment(extension).map(|open| open.chars().collect());
+    let block_comment: Option<(Vec<char>, Vec<char>)> = language::block_comment(extension)
+        .map(|(open, close)| (open.chars().collect(), close.chars().collect()));
+
+    if line_comment.is_none() && block_comment.is_none() {
+        return mask;
+    }
+
+    // Rust uses single quotes for lifetimes too, so only a character
+    // literal counts there. In markup, quotes are mostly just prose.
+    let is_rust = extension == "rs";
+    let quotes: &[char] = match extension {
+        "rs" => &['"'],
+        "md" | "html" | "htm" | "xml" => &[],
+        _ => &['"', '\'', '`'],
+    };
+
+    let mut index = 0;
+
+    while index < chars.len() {
+        let rest = &chars[index..];
+
+        let skipped = match (&line_comment, &block_comment) {
+            (Some(open), _) if rest.starts_with(open) => {
+                rest.iter().position(|&c| c == '\n').unwrap_or(rest.len())
+            }
+            (_, Some((open, close))) if rest.starts_with(open) => rest[open.len()..]
+                .windows(close.len())
+                .position(|window| window == &close[..])
+                .map_or(rest.len(), |end| open.len() + end + close.len()),
+            _ if quotes.contains(&rest[0]) => quoted_length(rest),
+            _ if is_rust && rest[0] == '\'' => character_length(rest),
+            _ => 0,
+        };
+
+        mask[index..index + skipped].fill(false);
+        index += skipped.max(1);
+    }
+
+    mask
+}
+
+fn quoted_length(rest: &[char]) -> usize {
+    let mut index = 1;
+
+    while index < rest.len() && rest[index] != rest[0] {
+        if rest[index] == '\\' {
+            index += 1;
+        }
+
+        index += 1;
+    }
+
+    (index + 1).min(rest.len())
+}
+
+fn character_length(rest: &[char]) -> usize {
+    match rest.get(1) {
+        Some('\\') => rest
+            .iter()
+            .skip(2)
+            .take(10)
+            .position(|&c| c == '\'')
+            .map_or(0, |end| end + 3),
+        Some(_) if rest.get(2) == Some(&'\'') => 3,
+        _ => 0,
+    }
+}
+
+// Accepts the one-based `line[:column]` shown in the status bar.
+pub fn parse_position(input: &str) -> Option<Position> {
+    let (line, column) = match input.trim().split_once(':') {
+        Some((line, column)) => (line, Some(column)),
+        None => (input.trim(), None),
+    };
+
+    let line = line.trim().parse::<usize>().ok()?.checked_sub(1)?;
+    let column = match column {
+        Some(column) => column.trim().parse::<usize>().ok()?.checked_sub(1)?,
+        None => 0,
+    };
+
+    Some((line, column))
+}
+
+pub fn go_to(document: &mut Document<'_>, (line, column): Position) -> Position {
+    let line = line.min(document.line_count().saturating_sub(1));
+    let column = column.min(document.line(line).chars().count());
+
+    document.move_to((line, column));
+
+    (line, column)
+}
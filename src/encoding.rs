@@ -0,0 +1,147 @@
+use std::fmt;
+
+use chardetng::EncodingDetector;
+use encoding_rs::{
+    Encoding, BIG5, EUC_JP, EUC_KR, GBK, ISO_8859_2, KOI8_R, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8,
+    WINDOWS_1251, WINDOWS_1252, X_USER_DEFINED,
+};
+
+use crate::hex;
+
+// Binary files are shown as a hex dump, which takes the place of their
+// encoding. x-user-defined stands for it, being the one encoding that maps
+// every byte on its own as well.
+pub const HEX: &Encoding = X_USER_DEFINED;
+
+pub const ALL: [Choice; 13] = [
+    Choice(UTF_8),
+    Choice(UTF_16LE),
+    Choice(UTF_16BE),
+    Choice(WINDOWS_1252),
+    Choice(ISO_8859_2),
+    Choice(WINDOWS_1251),
+    Choice(KOI8_R),
+    Choice(SHIFT_JIS),
+    Choice(EUC_JP),
+    Choice(GBK),
+    Choice(BIG5),
+    Choice(EUC_KR),
+    Choice(HEX),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Choice(pub &'static Encoding);
+
+impl fmt::Display for Choice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", name(self.0))
+    }
+}
+
+pub fn name(encoding: &'static Encoding) -> &'static str {
+    match encoding {
+        encoding if encoding == WINDOWS_1252 => "Latin-1",
+        encoding if encoding == HEX => "Hex",
+        encoding => encoding.name(),
+    }
+}
+
+// A byte order mark settles it, valid UTF-8 is taken as such, and anything
+// else that does not look binary is left to the detector.
+pub fn detect(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+
+    if let Some(encoding) = sniff_utf16(bytes) {
+        return encoding;
+    }
+
+    if hex::is_binary(bytes) {
+        return HEX;
+    }
+
+    let mut detector = EncodingDetector::new();
+
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+// Plain UTF-8 is by far the common case and takes over the bytes as they
+// are instead of copying them, which matters for very large files.
+pub fn decode(bytes: Vec<u8>, encoding: &'static Encoding) -> String {
+    if encoding == HEX {
+        return hex::dump(&bytes);
+    }
+
+    if encoding == UTF_8 && Encoding::for_bom(&bytes).is_none() {
+        return String::from_utf8(bytes)
+            .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned());
+    }
+
+    let (text, _) = encoding.decode_with_bom_removal(&bytes);
+
+    text.into_owned()
+}
+
+// UTF-16 is written with a byte order mark, since that is the only way to
+// recognise it again. Nothing is returned when the text has characters the
+// encoding cannot represent.
+pub fn encode(text: &str, encoding: &'static Encoding) -> Option<Vec<u8>> {
+    if encoding == HEX {
+        return hex::parse(text);
+    }
+
+    let units = || std::iter::once('\u{feff}').chain(text.chars());
+
+    if encoding == UTF_16LE {
+        return Some(
+            units()
+                .flat_map(|c| c.encode_utf16(&mut [0; 2]).to_vec())
+                .flat_map(u16::to_le_bytes)
+                .collect(),
+        );
+    }
+
+    if encoding == UTF_16BE {
+        return Some(
+            units()
+                .flat_map(|c| c.encode_utf16(&mut [0; 2]).to_vec())
+                .flat_map(u16::to_be_bytes)
+                .collect(),
+        );
+    }
+
+    let (bytes, _, had_errors) = encoding.encode(text);
+
+    (!had_errors).then(|| bytes.into_owned())
+}
+
+// Mostly ASCII text without a byte order mark still gives UTF-16 away by
+// the zero byte in every other position.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let pairs = bytes.len() / 2;
+
+    if pairs == 0 {
+        return None;
+    }
+
+    let (mut even, mut odd) = (0, 0);
+
+    for pair in bytes.chunks_exact(2) {
+        even += usize::from(pair[0] == 0);
+        odd += usize::from(pair[1] == 0);
+    }
+
+    if odd * 2 > pairs && even == 0 {
+        Some(UTF_16LE)
+    } else if even * 2 > pairs && odd == 0 {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    collections::VecDeque,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use iced::{
+    widget::{button, column, horizontal_space, row, scrollable, text},
+    Element, Length,
+};
+
+use crate::{Error, Messages};
+
+#[derive(Debug, Clone)]
+struct Entry {
+    time: SystemTime,
+    error: Error,
+}
+
+impl Entry {
+    fn describe(&self) -> String {
+        format!("[{}] {}", timestamp(self.time), self.error)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ErrorLog {
+    entries: VecDeque<Entry>,
+}
+
+impl ErrorLog {
+    const LIMIT: usize = 200;
+
+    pub fn record(&mut self, error: &Error) {
+        tracing::warn!(%error);
+
+        self.entries.push_back(Entry {
+            time: SystemTime::now(),
+            error: error.clone(),
+        });
+
+        if self.entries.len() > Self::LIMIT {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn export(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| format!("{}\n    {:?}\n", entry.describe(), entry.error))
+            .collect()
+    }
+
+    pub fn view(&self) -> Element<'_, Messages> {
+        let entries = self
+            .entries
+            .iter()
+            .rev()
+            .fold(column![].spacing(5), |entries, entry| {
+                entries.push(column![
+                    text(entry.describe()).size(14),
+                    text(format!("{:?}", entry.error)).size(12),
+                ])
+            });
+
+        column![
+            row![
+                text(format!("{} error(s) logged", self.len())),
+                horizontal_space(Length::Fill),
+                button("Open log file").on_press(Messages::OpenLogFile),
+                button("Copy log").on_press(Messages::CopyErrorLog),
+                button("Clear").on_press(Messages::ClearErrorLog),
+                button("Close").on_press(Messages::ToggleErrorLog),
+            ]
+            .spacing(10),
+            scrollable(entries).height(160),
+        ]
+        .spacing(5)
+        .into()
+    }
+}
+
+fn timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let of_day = seconds % 86_400;
+
+    format!(
+        "{:02}:{:02}:{:02} UTC",
+        of_day / 3600,
+        of_day % 3600 / 60,
+        of_day % 60
+    )
+}
@@ -0,0 +1,111 @@
+use std::ops::Range;
+
+use iced::{
+    advanced::text::Highlighter as _,
+    highlighter::{self, Highlighter},
+    Color,
+};
+
+// A page that stands on its own, with every color written out on the span
+// it belongs to rather than in a stylesheet, so a piece of it still looks
+// the same when pasted somewhere else.
+pub fn html(
+    title: &str,
+    text: &str,
+    settings: highlighter::Settings,
+    (background, foreground): (Color, Color),
+    tab_width: usize,
+) -> String {
+    let mut highlighter = Highlighter::new(&settings);
+    let mut body = String::new();
+
+    for line in text.split('\n') {
+        let spans: Vec<_> = highlighter
+            .highlight_line(line)
+            .map(|(range, highlight)| (range, highlight.to_format().color))
+            .collect();
+
+        push_line(&mut body, line, &spans);
+        body.push('\n');
+    }
+
+    format!(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>{}</title>\n\
+         </head>\n\
+         <body style=\"margin: 0; background: {background}\">\n\
+         <pre style=\"margin: 0; padding: 1em; color: {foreground}; background: {background}; \
+         font-family: monospace; font-size: 14px; line-height: 1.5; tab-size: {tab_width}\">\
+         {body}</pre>\n\
+         </body>\n\
+         </html>\n",
+        escape(title),
+        background = hex(background),
+        foreground = hex(foreground),
+    )
+}
+
+// Characters in a row with the same color share a span, and those without
+// one take the color of the page.
+fn push_line(html: &mut String, line: &str, spans: &[(Range<usize>, Option<Color>)]) {
+    let mut run = String::new();
+    let mut color = None;
+
+    for (index, c) in line.char_indices() {
+        let current = spans
+            .iter()
+            .rev()
+            .find(|(range, _)| range.contains(&index))
+            .and_then(|(_, color)| *color);
+
+        if current != color && !run.is_empty() {
+            push_run(html, &run, color);
+            run.clear();
+        }
+
+        color = current;
+        run.push(c);
+    }
+
+    if !run.is_empty() {
+        push_run(html, &run, color);
+    }
+}
+
+fn push_run(html: &mut String, text: &str, color: Option<Color>) {
+    match color {
+        Some(color) => {
+            html.push_str(&format!(
+                "<span style=\"color: {}\">{}</span>",
+                hex(color),
+                escape(text)
+            ));
+        }
+        None => html.push_str(&escape(text)),
+    }
+}
+
+fn hex(color: Color) -> String {
+    let [red, green, blue, _] = color.into_rgba8();
+
+    format!("#{red:02x}{green:02x}{blue:02x}")
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use encoding_rs::Encoding;
+use iced::{
+    widget::{button, row, text, text_input},
+    Element,
+};
+use reqwest::{header::CONTENT_TYPE, Url};
+
+use crate::{encoding, language::Language, Error, Messages};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn input_id() -> text_input::Id {
+    text_input::Id::new("open-url")
+}
+
+pub fn view(input: &str) -> Element<'_, Messages> {
+    row![
+        text("Open URL"),
+        text_input("https://…", input)
+            .id(input_id())
+            .on_input(Messages::OpenUrlChanged)
+            .on_submit(Messages::SubmitOpenUrl),
+        button("Open").on_press(Messages::SubmitOpenUrl),
+        button("Close").on_press(Messages::CloseOpenUrl),
+    ]
+    .spacing(10)
+    .into()
+}
+
+#[derive(Debug, Clone)]
+pub struct Fetched {
+    pub url: String,
+    pub text: String,
+    pub encoding: &'static Encoding,
+    pub language: Option<&'static str>,
+}
+
+// The charset the server names wins over guessing from the bytes, which
+// matters most for short bodies the detector has little to go on with.
+pub async fn fetch(url: String) -> Result<Fetched, Error> {
+    let failed = |reason: String| Error::FetchFailed(url.clone(), reason);
+
+    let parsed = Url::parse(&url).map_err(|err| failed(err.to_string()))?;
+
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(failed(String::from("only http and https can be opened")));
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .user_agent(concat!("editor/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|err| failed(err.to_string()))?;
+
+    let response = client
+        .get(parsed.clone())
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|err| failed(err.to_string()))?;
+
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|err| failed(err.to_string()))?
+        .to_vec();
+
+    let encoding = charset(&content_type).unwrap_or_else(|| encoding::detect(&bytes));
+
+    Ok(Fetched {
+        url: parsed.to_string(),
+        text: encoding::decode(bytes, encoding),
+        encoding,
+        language: language(&content_type, &parsed),
+    })
+}
+
+fn charset(content_type: &str) -> Option<&'static Encoding> {
+    content_type
+        .split(';')
+        .filter_map(|parameter| parameter.trim().strip_prefix("charset="))
+        .find_map(|label| Encoding::for_label(label.trim_matches('"').as_bytes()))
+}
+
+// Raw files are mostly served as plain text whatever they hold, so a type
+// that says nothing more leaves it to the extension at the end of the path.
+fn language(content_type: &str, url: &Url) -> Option<&'static str> {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+
+    let named = match essence {
+        "text/x-rust" | "text/rust" => Some("rs"),
+        "text/x-python" | "application/x-python" | "text/x-script.python" => Some("py"),
+        "text/javascript" | "application/javascript" | "application/x-javascript" => Some("js"),
+        "text/typescript" | "application/typescript" => Some("ts"),
+        "text/x-c" | "text/x-csrc" | "text/x-chdr" => Some("c"),
+        "text/x-c++" | "text/x-c++src" | "text/x-c++hdr" => Some("cpp"),
+        "text/x-go" => Some("go"),
+        "text/x-java" | "text/x-java-source" => Some("java"),
+        "application/x-sh" | "text/x-sh" | "text/x-shellscript" => Some("sh"),
+        "text/markdown" | "text/x-markdown" => Some("md"),
+        "text/html" | "application/xhtml+xml" => Some("html"),
+        "text/css" => Some("css"),
+        "application/toml" | "text/x-toml" => Some("toml"),
+        "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => Some("yaml"),
+        essence if essence == "application/json" || essence.ends_with("+json") => Some("json"),
+        _ => None,
+    };
+
+    named
+        .or_else(|| {
+            let name = url.path_segments()?.last()?;
+            let (_, extension) = name.rsplit_once('.')?;
+
+            Language::of(&extension.to_lowercase()).map(|language| language.0)
+        })
+        .or_else(|| essence.starts_with("text/").then_some("txt"))
+}
@@ -0,0 +1,128 @@
+use std::iter;
+
+use iced::widget::text_editor::{Action, Content, Edit};
+
+#[derive(Debug)]
+pub struct Filter {
+    pub pattern: String,
+    lines: Vec<String>,
+    visible: Vec<usize>,
+}
+
+impl Filter {
+    pub fn new(text: &str, pattern: String) -> (Self, Content) {
+        let lines = text.split('\n').map(str::to_owned).collect::<Vec<_>>();
+
+        let visible = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.contains(&pattern))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+
+        let filtered = visible
+            .iter()
+            .map(|&index| lines[index].as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        (
+            Self {
+                pattern,
+                lines,
+                visible,
+            },
+            Content::with(&filtered),
+        )
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn visible_lines(&self) -> usize {
+        self.visible.len()
+    }
+
+    pub fn total_lines(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn real_line(&self, line: usize) -> usize {
+        self.visible.get(line).copied().unwrap_or_default()
+    }
+
+    pub fn edit(&mut self, content: &mut Content, action: Action) {
+        if !action.is_edit() {
+            content.edit(action);
+            return;
+        }
+
+        if self.visible.is_empty() {
+            return;
+        }
+
+        let inserted = match &action {
+            Action::Edit(Edit::Paste(text)) => text.matches('\n').count(),
+            Action::Edit(Edit::Enter) => 1,
+            _ => 0,
+        };
+        let before = content.line_count();
+
+        content.edit(action);
+
+        let after = content.line_count();
+        let start = content.cursor_position().0.saturating_sub(inserted);
+        let removed = (before + inserted).saturating_sub(after);
+
+        self.remap(start, removed, inserted);
+
+        for line in start..=start + inserted {
+            if let (Some(&real), Some(text)) = (self.visible.get(line), content.line(line)) {
+                self.lines[real] = String::from(&*text);
+            }
+        }
+    }
+
+    // The edited region spanned visible lines `start..=start + removed` and now
+    // spans `start..=start + inserted`. Real lines are reused in order; extra
+    // ones are created right after the region and surplus ones are dropped,
+    // leaving hidden lines in between untouched.
+    fn remap(&mut self, start: usize, removed: usize, inserted: usize) {
+        let end = start + removed;
+
+        if end >= self.visible.len() {
+            return;
+        }
+
+        if inserted > removed {
+            let count = inserted - removed;
+            let anchor = self.visible[end];
+
+            self.lines.splice(
+                anchor + 1..anchor + 1,
+                iter::repeat(String::new()).take(count),
+            );
+
+            for real in &mut self.visible[end + 1..] {
+                *real += count;
+            }
+
+            self.visible
+                .splice(end + 1..end + 1, anchor + 1..=anchor + count);
+        } else if removed > inserted {
+            let gone = self
+                .visible
+                .drain(start + inserted + 1..=end)
+                .collect::<Vec<_>>();
+
+            for &real in gone.iter().rev() {
+                self.lines.remove(real);
+            }
+
+            for real in &mut self.visible[start + inserted + 1..] {
+                *real -= gone.len();
+            }
+        }
+    }
+}
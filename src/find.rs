@@ -0,0 +1,448 @@
+use std::ops::Range;
+
+use iced::{
+    advanced::text::{self, highlighter::Format},
+    widget::{button, checkbox, row, text as label, text_input},
+    Color, Element, Font,
+};
+
+use crate::{
+    conflict::{Conflict, Section},
+    editing::{self, Direction, Document, Position},
+    lsp::Severity,
+    Messages,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Find {
+    pub query: String,
+    pub replacement: String,
+    pub is_replacing: bool,
+    pub count: usize,
+    pub current: Option<usize>,
+    pub scope: Option<(Position, Position)>,
+}
+
+pub fn input_id() -> text_input::Id {
+    text_input::Id::new("find")
+}
+
+impl Find {
+    pub fn view(&self) -> Element<'_, Messages> {
+        let matches = match (self.count, self.current) {
+            _ if self.query.is_empty() => String::new(),
+            (0, _) => String::from("No matches"),
+            (count, Some(current)) => format!("{current} of {count}"),
+            (count, None) => format!("{count} match(es)"),
+        };
+
+        let find = row![
+            text_input("Find", &self.query)
+                .id(input_id())
+                .on_input(Messages::FindChanged)
+                .on_submit(Messages::FindNext),
+            label(matches).size(14),
+            button("Previous").on_press(Messages::FindPrevious),
+            button("Next").on_press(Messages::FindNext),
+        ]
+        .spacing(10);
+
+        let find = if self.is_replacing {
+            find.push(
+                text_input("Replace with", &self.replacement)
+                    .on_input(Messages::FindReplacementChanged)
+                    .on_submit(Messages::ReplaceOne),
+            )
+            .push(button("Replace").on_press(Messages::ReplaceOne))
+            .push(button("Replace all").on_press(Messages::ReplaceAll))
+            .push(checkbox(
+                "In selection",
+                self.scope.is_some(),
+                Messages::ToggleFindInSelection,
+            ))
+        } else {
+            find
+        };
+
+        find.push(button("Close").on_press(Messages::CloseFind))
+            .into()
+    }
+}
+
+// Matches never span lines, which keeps them in step with the per-line
+// highlighter below.
+pub fn matches(text: &str, query: &str) -> Vec<(Position, Position)> {
+    if query.is_empty() || query.contains('\n') {
+        return Vec::new();
+    }
+
+    let width = query.chars().count();
+
+    text.split('\n')
+        .enumerate()
+        .flat_map(|(line, content)| {
+            content.match_indices(query).map(move |(index, _)| {
+                let column = content[..index].chars().count();
+
+                ((line, column), (line, column + width))
+            })
+        })
+        .collect()
+}
+
+pub fn is_within((start, end): (Position, Position), scope: Option<(Position, Position)>) -> bool {
+    scope.is_none_or(|(from, to)| from <= start && end <= to)
+}
+
+// Only matches wholly inside the scope are replaced. The scope comes back
+// covering the same text with the replacements in it.
+pub fn replace_all(
+    text: &str,
+    query: &str,
+    replacement: &str,
+    scope: Option<(Position, Position)>,
+) -> (String, usize, Option<(Position, Position)>) {
+    let found = matches(text, query)
+        .into_iter()
+        .filter(|&range| is_within(range, scope))
+        .collect::<Vec<_>>();
+    let count = found.len();
+
+    let mut replaced = String::with_capacity(text.len());
+    let mut found = found.iter().peekable();
+
+    for (line, content) in text.split('\n').enumerate() {
+        if line > 0 {
+            replaced.push('\n');
+        }
+
+        let mut copied = 0;
+
+        for (index, _) in content.match_indices(query) {
+            let column = content[..index].chars().count();
+
+            if found.peek().map(|(start, _)| *start) == Some((line, column)) {
+                found.next();
+                replaced.push_str(&content[copied..index]);
+                replaced.push_str(replacement);
+                copied = index + query.len();
+            }
+        }
+
+        replaced.push_str(&content[copied..]);
+    }
+
+    let scope = scope.map(|(start, end)| {
+        let chars = text.chars().collect::<Vec<_>>();
+        let starts = editing::line_starts(&chars);
+        let offset = starts
+            .get(end.0)
+            .map_or(chars.len(), |start| (start + end.1).min(chars.len()));
+        let grown = replacement.chars().count() as isize - query.chars().count() as isize;
+        let offset = offset.saturating_add_signed(grown * count as isize);
+
+        let chars = replaced.chars().collect::<Vec<_>>();
+        let starts = editing::line_starts(&chars);
+
+        (start, editing::position(&starts, offset.min(chars.len())))
+    });
+
+    (replaced, count, scope)
+}
+
+// While typing, the match the selection starts at is kept as long as it
+// still matches, so the view does not jump ahead with every letter. Only
+// matches inside the scope are taken, as they are the ones counted.
+pub fn select_nearest(
+    document: &mut Document<'_>,
+    query: &str,
+    scope: Option<(Position, Position)>,
+) -> bool {
+    let matches = matches(&document.text(), query)
+        .into_iter()
+        .filter(|&range| is_within(range, scope))
+        .collect::<Vec<_>>();
+    let cursor = document.cursor();
+    let (start, _) = document.selection().unwrap_or((cursor, cursor));
+
+    match matches
+        .iter()
+        .find(|(found, _)| *found >= start)
+        .or(matches.first())
+    {
+        Some(&range) => {
+            document.select(range);
+            true
+        }
+        None => false,
+    }
+}
+
+pub fn select_match(document: &mut Document<'_>, query: &str, direction: Direction) -> bool {
+    let matches = matches(&document.text(), query);
+    let cursor = document.cursor();
+    let (start, end) = document.selection().unwrap_or((cursor, cursor));
+
+    let target = match direction {
+        Direction::Forward => matches
+            .iter()
+            .find(|(found, _)| *found >= end)
+            .or(matches.first()),
+        Direction::Backward => matches
+            .iter()
+            .rev()
+            .find(|(found, _)| *found < start)
+            .or(matches.last()),
+    };
+
+    match target {
+        Some(&range) => {
+            document.select(range);
+            true
+        }
+        None => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings<S> {
+    pub inner: S,
+    pub query: String,
+    pub brackets: Vec<Position>,
+    pub problems: Vec<(Position, Position, Severity)>,
+    pub conflicts: Vec<Conflict>,
+    pub misspelled: Vec<(Position, Position)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Highlight<H> {
+    Syntax(H),
+    Match,
+    Bracket,
+    Problem(Severity),
+    Conflict(Section),
+    Misspelled,
+}
+
+impl<H> Highlight<H> {
+    pub fn to_format(&self, syntax: impl Fn(&H) -> Format<Font>) -> Format<Font> {
+        match self {
+            Highlight::Syntax(highlight) => syntax(highlight),
+            Highlight::Match => Format {
+                color: Some(Color::from_rgb(1.0, 0.6, 0.0)),
+                font: None,
+            },
+            Highlight::Bracket => Format {
+                color: Some(Color::from_rgb(0.3, 0.8, 1.0)),
+                font: None,
+            },
+            Highlight::Problem(severity) => Format {
+                color: Some(match severity {
+                    Severity::Error => Color::from_rgb(1.0, 0.35, 0.35),
+                    Severity::Warning => Color::from_rgb(0.95, 0.8, 0.2),
+                    Severity::Information | Severity::Hint => Color::from_rgb(0.55, 0.7, 1.0),
+                }),
+                font: None,
+            },
+            Highlight::Conflict(section) => Format {
+                color: Some(match section {
+                    Section::Marker | Section::Base => Color::from_rgb(0.9, 0.4, 0.9),
+                    Section::Ours => Color::from_rgb(0.45, 0.8, 0.45),
+                    Section::Theirs => Color::from_rgb(0.45, 0.65, 1.0),
+                }),
+                font: None,
+            },
+            Highlight::Misspelled => Format {
+                color: Some(Color::from_rgb(0.95, 0.45, 0.75)),
+                font: None,
+            },
+        }
+    }
+}
+
+// Conflict, spelling, problem, match and bracket spans come after the syntax
+// spans of a line, and each later kind wins over the ones before it where
+// they overlap.
+pub struct Matches<H> {
+    inner: H,
+    query: String,
+    brackets: Vec<Position>,
+    problems: Vec<(Position, Position, Severity)>,
+    conflicts: Vec<Conflict>,
+    misspelled: Vec<(Position, Position)>,
+}
+
+impl<H: text::Highlighter> text::Highlighter for Matches<H> {
+    type Settings = Settings<H::Settings>;
+    type Highlight = Highlight<H::Highlight>;
+    type Iterator<'a>
+        = std::vec::IntoIter<(Range<usize>, Self::Highlight)>
+    where
+        Self: 'a;
+
+    fn new(settings: &Self::Settings) -> Self {
+        Matches {
+            inner: H::new(&settings.inner),
+            query: settings.query.clone(),
+            brackets: settings.brackets.clone(),
+            problems: settings.problems.clone(),
+            conflicts: settings.conflicts.clone(),
+            misspelled: settings.misspelled.clone(),
+        }
+    }
+
+    fn update(&mut self, new_settings: &Self::Settings) {
+        self.inner.update(&new_settings.inner);
+
+        if self.query != new_settings.query {
+            self.query = new_settings.query.clone();
+            self.inner.change_line(0);
+        }
+
+        // Only the lines from the first bracket that appeared or went away
+        // need highlighting again.
+        if self.brackets != new_settings.brackets {
+            let first = self
+                .brackets
+                .iter()
+                .chain(&new_settings.brackets)
+                .map(|&(line, _)| line)
+                .min();
+
+            self.brackets = new_settings.brackets.clone();
+
+            if let Some(first) = first {
+                self.inner.change_line(first);
+            }
+        }
+
+        if self.problems != new_settings.problems {
+            let first = self
+                .problems
+                .iter()
+                .chain(&new_settings.problems)
+                .map(|&((line, _), ..)| line)
+                .min();
+
+            self.problems = new_settings.problems.clone();
+
+            if let Some(first) = first {
+                self.inner.change_line(first);
+            }
+        }
+
+        if self.conflicts != new_settings.conflicts {
+            let first = self
+                .conflicts
+                .iter()
+                .chain(&new_settings.conflicts)
+                .map(|conflict| conflict.start)
+                .min();
+
+            self.conflicts = new_settings.conflicts.clone();
+
+            if let Some(first) = first {
+                self.inner.change_line(first);
+            }
+        }
+
+        if self.misspelled != new_settings.misspelled {
+            let first = self
+                .misspelled
+                .iter()
+                .chain(&new_settings.misspelled)
+                .map(|&((line, _), _)| line)
+                .min();
+
+            self.misspelled = new_settings.misspelled.clone();
+
+            if let Some(first) = first {
+                self.inner.change_line(first);
+            }
+        }
+    }
+
+    fn change_line(&mut self, line: usize) {
+        self.inner.change_line(line);
+    }
+
+    fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+        let number = self.inner.current_line();
+        let mut highlights = self
+            .inner
+            .highlight_line(line)
+            .map(|(range, highlight)| (range, Highlight::Syntax(highlight)))
+            .collect::<Vec<_>>();
+
+        // Lines of a conflict are colored whole, by the side they belong to.
+        let section = self
+            .conflicts
+            .iter()
+            .find_map(|conflict| conflict.section(number));
+
+        if let Some(section) = section.filter(|_| !line.is_empty()) {
+            highlights.push((0..line.len(), Highlight::Conflict(section)));
+        }
+
+        for &((_, start), (_, end)) in self.misspelled.iter().filter(|((at, _), _)| *at == number) {
+            let mut columns = line.char_indices().skip(start).take(end - start);
+
+            if let Some((from, c)) = columns.next() {
+                let to = columns
+                    .last()
+                    .map_or(from + c.len_utf8(), |(index, c)| index + c.len_utf8());
+
+                highlights.push((from..to, Highlight::Misspelled));
+            }
+        }
+
+        // A problem spanning lines covers the whole of the lines in between.
+        // An empty one still marks the character it starts at, or the last
+        // one on its line when it points past the end.
+        for &(start, end, severity) in &self.problems {
+            if number < start.0 || number > end.0 {
+                continue;
+            }
+
+            let first = if number == start.0 {
+                start.1.min(line.chars().count().saturating_sub(1))
+            } else {
+                0
+            };
+            let last = if number == end.0 {
+                end.1.max(first + 1)
+            } else {
+                usize::MAX
+            };
+
+            let mut columns = line.char_indices().skip(first).take(last - first);
+
+            if let Some((from, c)) = columns.next() {
+                let to = columns
+                    .last()
+                    .map_or(from + c.len_utf8(), |(index, c)| index + c.len_utf8());
+
+                highlights.push((from..to, Highlight::Problem(severity)));
+            }
+        }
+
+        if !self.query.is_empty() {
+            highlights.extend(
+                line.match_indices(&self.query)
+                    .map(|(index, found)| (index..index + found.len(), Highlight::Match)),
+            );
+        }
+
+        for &(_, column) in self.brackets.iter().filter(|(at, _)| *at == number) {
+            if let Some((index, c)) = line.char_indices().nth(column) {
+                highlights.push((index..index + c.len_utf8(), Highlight::Bracket));
+            }
+        }
+
+        highlights.into_iter()
+    }
+
+    fn current_line(&self) -> usize {
+        self.inner.current_line()
+    }
+}
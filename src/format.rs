@@ -0,0 +1,153 @@
+use std::{collections::BTreeMap, path::PathBuf, process::Stdio, time::Duration};
+
+use iced::futures::future;
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::{editing::Position, Error};
+
+// Formatters read the text on stdin and write it formatted to stdout.
+// `{path}` stands for the file, for those that pick their rules by its name.
+const FORMATTERS: [(&[&str], &[&str]); 3] = [
+    (&["rs"], &["rustfmt", "--edition", "2021"]),
+    (&["py"], &["black", "--quiet", "-"]),
+    (
+        &[
+            "js", "mjs", "ts", "json", "css", "html", "htm", "md", "yaml", "yml",
+        ],
+        &["prettier", "--stdin-filepath", "{path}"],
+    ),
+];
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formatter {
+    pub program: String,
+    args: Vec<String>,
+}
+
+// A configured command takes the place of the built-in one, and an empty
+// one turns formatting off for the extension.
+pub fn formatter(extension: &str, configured: &BTreeMap<String, Vec<String>>) -> Option<Formatter> {
+    let command = match configured.get(extension) {
+        Some(command) => command.clone(),
+        None => FORMATTERS
+            .iter()
+            .find(|(extensions, _)| extensions.contains(&extension))?
+            .1
+            .iter()
+            .map(|&part| part.to_owned())
+            .collect(),
+    };
+
+    let (program, args) = command.split_first()?;
+
+    Some(Formatter {
+        program: program.clone(),
+        args: args.to_vec(),
+    })
+}
+
+// Runs next to the file, so that formatters find the project's settings.
+#[tracing::instrument(skip(formatter, text), fields(program = %formatter.program), err)]
+pub async fn run(formatter: Formatter, path: PathBuf, text: String) -> Result<String, Error> {
+    let failed = |reason: String| Error::FormatFailed(formatter.program.clone(), reason);
+
+    let mut command = Command::new(&formatter.program);
+
+    command
+        .args(
+            formatter
+                .args
+                .iter()
+                .map(|arg| arg.replace("{path}", &path.to_string_lossy())),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    if let Some(directory) = path.parent().filter(|directory| directory.is_dir()) {
+        command.current_dir(directory);
+    }
+
+    let mut child = command.spawn().map_err(|err| failed(err.to_string()))?;
+
+    let Some(mut stdin) = child.stdin.take() else {
+        return Err(failed(String::from("no stdin")));
+    };
+
+    // Written alongside reading the output, since a formatter may start
+    // writing before it has read everything.
+    let write = async move {
+        let written = stdin.write_all(text.as_bytes()).await;
+        drop(stdin);
+
+        written
+    };
+
+    let (written, output) =
+        tokio::time::timeout(TIMEOUT, future::join(write, child.wait_with_output()))
+            .await
+            .map_err(|_| failed(String::from("timed out")))?;
+    let output = output.map_err(|err| failed(err.to_string()))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map_or_else(|| output.status.to_string(), str::to_owned);
+
+        return Err(failed(reason));
+    }
+
+    written.map_err(|err| failed(err.to_string()))?;
+
+    String::from_utf8(output.stdout).map_err(|_| failed(String::from("wrote invalid UTF-8")))
+}
+
+// The cursor keeps its place among the characters that are not whitespace,
+// which formatters mostly leave alone. It stays before the text following
+// it on its line, or else right after the text before it.
+pub fn follow(old: &str, new: &str, (line, column): Position) -> Position {
+    let is_text = |c: &char| !c.is_whitespace();
+    let lines = old.split('\n').collect::<Vec<_>>();
+    let current = lines.get(line).copied().unwrap_or_default();
+    let is_before_text = current.chars().skip(column).any(|c| is_text(&c));
+    let before = lines
+        .iter()
+        .take(line)
+        .map(|line| line.chars().filter(is_text).count())
+        .sum::<usize>()
+        + current.chars().take(column).filter(is_text).count();
+
+    if before == 0 && !is_before_text {
+        return (0, 0);
+    }
+
+    let mut seen = 0;
+
+    for (index, text) in new.split('\n').enumerate() {
+        for (column, c) in text.chars().enumerate() {
+            if !is_text(&c) {
+                continue;
+            }
+
+            if is_before_text && seen == before {
+                return (index, column);
+            }
+
+            seen += 1;
+
+            if !is_before_text && seen == before {
+                return (index, column + 1);
+            }
+        }
+    }
+
+    let last = new.split('\n').last().unwrap_or_default();
+
+    (new.matches('\n').count(), last.chars().count())
+}
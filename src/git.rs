@@ -0,0 +1,521 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use encoding_rs::Encoding;
+use iced::{
+    futures::future,
+    mouse,
+    widget::{
+        canvas::{self, Canvas, Frame, Geometry},
+        text_editor,
+    },
+    Color, Element, Font, Length, Point, Rectangle, Renderer, Size, Theme,
+};
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::{
+    diff::{self, Step},
+    encoding, Error, Messages,
+};
+
+const WIDTH: f32 = 4.0;
+const BLAME_WIDTH: f32 = 300.0;
+const AUTHOR_LENGTH: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Modified,
+    // Lines were removed just above this one.
+    Deleted,
+}
+
+// Runs git in the given directory and returns what it printed. The input,
+// if any, is written to it alongside reading the output.
+pub async fn git(directory: &Path, args: &[&str], input: Option<&str>) -> Result<Vec<u8>, Error> {
+    let failed = |err: std::io::Error| Error::GitFailed(err.to_string());
+
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(directory)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(failed)?;
+
+    let stdin = child.stdin.take();
+    let write = async move {
+        if let (Some(mut stdin), Some(input)) = (stdin, input) {
+            stdin.write_all(input.as_bytes()).await?;
+        }
+
+        Ok(())
+    };
+
+    let (written, output) = future::join(write, child.wait_with_output()).await;
+    let output = output.map_err(failed)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map_or_else(|| output.status.to_string(), str::to_owned);
+
+        return Err(Error::GitFailed(reason));
+    }
+
+    written.map_err(failed)?;
+
+    Ok(output.stdout)
+}
+
+// The file as it was last committed. Files outside of a repository, and
+// those it does not track yet, have nothing to compare with.
+#[tracing::instrument(skip(encoding))]
+pub async fn head(path: PathBuf, encoding: &'static Encoding) -> Option<String> {
+    let directory = path.parent()?;
+    let name = path.file_name()?.to_string_lossy();
+
+    match git(directory, &["show", &format!("HEAD:./{name}")], None).await {
+        Ok(bytes) => Some(encoding::decode(bytes, encoding).replace("\r\n", "\n")),
+        Err(err) => {
+            tracing::debug!(%err, "no committed version");
+
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Status {
+    pub branch: String,
+    pub staged: usize,
+    pub changed: usize,
+}
+
+// Untracked files count as changed, since they are there to be staged too.
+#[tracing::instrument]
+pub async fn status(directory: PathBuf) -> Result<Status, Error> {
+    let output = git(&directory, &["status", "--porcelain", "--branch"], None).await?;
+    let mut status = Status::default();
+
+    for line in String::from_utf8_lossy(&output).lines() {
+        if let Some(branch) = line.strip_prefix("## ") {
+            status.branch = branch_name(branch);
+        } else if !line.is_empty() {
+            status.changed += 1;
+
+            if !line.starts_with([' ', '?']) {
+                status.staged += 1;
+            }
+        }
+    }
+
+    Ok(status)
+}
+
+// The branch line also names what it tracks and how far apart they are.
+fn branch_name(line: &str) -> String {
+    if let Some(branch) = line.strip_prefix("No commits yet on ") {
+        return branch.to_owned();
+    }
+
+    if line.starts_with("HEAD (no branch)") {
+        return String::from("detached HEAD");
+    }
+
+    line.split("...")
+        .next()
+        .and_then(|branch| branch.split(' ').next())
+        .unwrap_or(line)
+        .to_owned()
+}
+
+#[tracing::instrument]
+pub async fn stage(path: PathBuf) -> Result<(), Error> {
+    let (Some(directory), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(Error::GitFailed(String::from("not a file")));
+    };
+
+    git(directory, &["add", "--", &name.to_string_lossy()], None).await?;
+
+    Ok(())
+}
+
+// What git says about the new commit, which starts with its branch and
+// short hash.
+#[tracing::instrument(skip(message))]
+pub async fn commit(directory: PathBuf, message: String) -> Result<String, Error> {
+    let output = git(&directory, &["commit", "--file", "-"], Some(&message)).await?;
+
+    Ok(String::from_utf8_lossy(&output)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_owned())
+}
+
+// The committed lines of a buffer and how its text differs from them, found
+// again whenever the text changes.
+#[derive(Debug, Default)]
+pub struct Gutter {
+    head: Option<Vec<String>>,
+    markers: BTreeMap<usize, Change>,
+    at: Option<(u64, usize)>,
+}
+
+impl Gutter {
+    pub fn set_head(&mut self, head: Option<String>) {
+        self.head = head.map(|text| text.split('\n').map(str::to_owned).collect());
+        self.markers.clear();
+        self.at = None;
+    }
+
+    pub fn is_tracked(&self) -> bool {
+        self.head.is_some()
+    }
+
+    pub fn refresh(&mut self, content: &text_editor::Content, revision: u64) {
+        let at = (revision, content.line_count());
+
+        if self.at == Some(at) {
+            return;
+        }
+
+        self.at = Some(at);
+        self.markers = match self.head.as_ref() {
+            Some(head) => {
+                let text = content.text();
+                let lines = text.split('\n').collect::<Vec<_>>();
+
+                changes(head, &lines)
+            }
+            None => BTreeMap::new(),
+        };
+    }
+}
+
+// Each run of changed lines is marked by whether it replaced lines, only
+// added some or only took some away.
+fn changes(old: &[String], new: &[&str]) -> BTreeMap<usize, Change> {
+    let last = new.len() - 1;
+    let mut markers = BTreeMap::new();
+    let mut removed = 0;
+    let mut added = Vec::new();
+
+    let end = Step::Same(old.len(), new.len());
+
+    for step in diff::lines(old, new).into_iter().chain(Some(end)) {
+        match step {
+            Step::Removed(_) => removed += 1,
+            Step::Added(line) => added.push(line),
+            Step::Same(_, line) => {
+                let change = if removed == 0 {
+                    Change::Added
+                } else {
+                    Change::Modified
+                };
+
+                if added.is_empty() && removed > 0 {
+                    markers.insert(line.min(last), Change::Deleted);
+                }
+
+                for line in added.drain(..) {
+                    markers.insert(line, change);
+                }
+
+                removed = 0;
+            }
+        }
+    }
+
+    markers
+}
+
+// A strip next to the editor lining up with its lines, which start `top`
+// pixels down from it.
+pub fn view(gutter: &Gutter, scroll: usize, line_height: f32, top: f32) -> Element<'_, Messages> {
+    Canvas::new(Markers {
+        gutter,
+        scroll,
+        line_height,
+        top,
+    })
+    .width(Length::Fixed(WIDTH))
+    .height(Length::Fill)
+    .into()
+}
+
+struct Markers<'a> {
+    gutter: &'a Gutter,
+    scroll: usize,
+    line_height: f32,
+    top: f32,
+}
+
+impl canvas::Program<Messages> for Markers<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let palette = theme.palette();
+        let visible = (bounds.height / self.line_height) as usize + 1;
+        let mut frame = Frame::new(renderer, bounds.size());
+
+        for (&line, &change) in self
+            .gutter
+            .markers
+            .range(self.scroll..self.scroll + visible)
+        {
+            let y = self.top + (line - self.scroll) as f32 * self.line_height;
+
+            let (y, height, color) = match change {
+                Change::Added => (y, self.line_height, palette.success),
+                Change::Modified => (y, self.line_height, palette.primary),
+                Change::Deleted => (y - 2.0, 4.0, palette.danger),
+            };
+
+            frame.fill_rectangle(
+                Point::new(0.0, y.max(0.0)),
+                Size::new(bounds.width, height),
+                Color { a: 0.8, ..color },
+            );
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Commit {
+    pub hash: String,
+    pub author: String,
+    pub time: u64,
+    pub summary: String,
+}
+
+impl Commit {
+    // Lines that differ from the last commit come back from git under a
+    // hash of zeros.
+    pub fn is_committed(&self) -> bool {
+        self.hash.bytes().any(|b| b != b'0')
+    }
+
+    pub fn describe(&self) -> String {
+        if !self.is_committed() {
+            return String::from("Not committed yet");
+        }
+
+        format!("{}, {} ago • {}", self.author, ago(self.time), self.summary)
+    }
+
+    fn short(&self) -> String {
+        if !self.is_committed() {
+            return String::from("Not committed yet");
+        }
+
+        let author = self.author.chars().take(AUTHOR_LENGTH).collect::<String>();
+
+        format!(
+            "{} {author:AUTHOR_LENGTH$} {}",
+            &self.hash[..7],
+            ago(self.time)
+        )
+    }
+}
+
+// The commit behind every line of a buffer as it read at a revision.
+#[derive(Debug, Clone)]
+pub struct Blame {
+    pub revision: u64,
+    lines: Vec<Arc<Commit>>,
+}
+
+impl Blame {
+    pub fn line(&self, line: usize) -> Option<&Commit> {
+        self.lines.get(line).map(|commit| &**commit)
+    }
+}
+
+// The text is blamed as it is in the buffer rather than as it was saved,
+// so the lines match up while it is being edited.
+#[tracing::instrument(skip(text))]
+pub async fn blame(path: PathBuf, revision: u64, text: String) -> Result<Blame, Error> {
+    let (Some(directory), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(Error::GitFailed(String::from("not a file")));
+    };
+
+    let name = name.to_string_lossy();
+    let output = git(
+        directory,
+        &["blame", "--porcelain", "--contents", "-", "--", &name],
+        Some(&text),
+    )
+    .await?;
+
+    Ok(Blame {
+        revision,
+        lines: parse_blame(&String::from_utf8_lossy(&output)),
+    })
+}
+
+// Each line starts with a header naming its commit, followed by details of
+// the commit the first time it comes up, and then the line itself after a
+// tab.
+fn parse_blame(output: &str) -> Vec<Arc<Commit>> {
+    let mut commits: HashMap<String, Commit> = HashMap::new();
+    let mut hashes = Vec::new();
+    let mut current = String::new();
+
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            hashes.push(current.clone());
+            continue;
+        }
+
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+
+        if matches!(key.len(), 40 | 64) && key.bytes().all(|b| b.is_ascii_hexdigit()) {
+            current = key.to_owned();
+            commits.entry(current.clone()).or_insert_with(|| Commit {
+                hash: current.clone(),
+                ..Commit::default()
+            });
+            continue;
+        }
+
+        let Some(commit) = commits.get_mut(&current) else {
+            continue;
+        };
+
+        match key {
+            "author" => commit.author = value.to_owned(),
+            "author-time" => commit.time = value.parse().unwrap_or_default(),
+            "summary" => commit.summary = value.to_owned(),
+            _ => {}
+        }
+    }
+
+    let commits = commits
+        .into_iter()
+        .map(|(hash, commit)| (hash, Arc::new(commit)))
+        .collect::<HashMap<_, _>>();
+
+    hashes
+        .iter()
+        .filter_map(|hash| commits.get(hash).cloned())
+        .collect()
+}
+
+fn ago(time: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
+    let seconds = now.saturating_sub(time);
+
+    let (count, unit) = match seconds {
+        0..=59 => (seconds, "second"),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86_399 => (seconds / 3600, "hour"),
+        86_400..=2_591_999 => (seconds / 86_400, "day"),
+        2_592_000..=31_535_999 => (seconds / 2_592_000, "month"),
+        _ => (seconds / 31_536_000, "year"),
+    };
+
+    match count {
+        1 => format!("1 {unit}"),
+        count => format!("{count} {unit}s"),
+    }
+}
+
+// Who last changed each line, written next to the first of the lines a
+// commit changed in a row.
+pub fn blame_view(
+    blame: &Blame,
+    scroll: usize,
+    line_height: f32,
+    top: f32,
+) -> Element<'_, Messages> {
+    Canvas::new(Annotations {
+        blame,
+        scroll,
+        line_height,
+        top,
+    })
+    .width(Length::Fixed(BLAME_WIDTH))
+    .height(Length::Fill)
+    .into()
+}
+
+struct Annotations<'a> {
+    blame: &'a Blame,
+    scroll: usize,
+    line_height: f32,
+    top: f32,
+}
+
+impl canvas::Program<Messages> for Annotations<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let text = theme.palette().text;
+        let visible = (bounds.height / self.line_height) as usize + 1;
+        let mut frame = Frame::new(renderer, bounds.size());
+
+        for line in self.scroll..self.scroll + visible {
+            let Some(commit) = self.blame.lines.get(line) else {
+                break;
+            };
+
+            let is_continued = line > self.scroll
+                && self
+                    .blame
+                    .lines
+                    .get(line - 1)
+                    .is_some_and(|previous| previous.hash == commit.hash);
+
+            if is_continued {
+                continue;
+            }
+
+            frame.fill_text(canvas::Text {
+                content: commit.short(),
+                position: Point::new(
+                    0.0,
+                    self.top + (line - self.scroll) as f32 * self.line_height,
+                ),
+                color: Color { a: 0.6, ..text },
+                font: Font::MONOSPACE,
+                ..canvas::Text::default()
+            });
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
@@ -0,0 +1,113 @@
+// A line down the lines start..end at one level of indentation, by the
+// column it is drawn at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Guide {
+    pub column: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+// The width of the leading whitespace, with tabs going on to the next stop.
+// Blank lines have none of their own.
+fn indentation(line: &str, tab_width: usize) -> Option<usize> {
+    let mut width = 0;
+
+    for c in line.chars() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += tab_width - width % tab_width,
+            c if c.is_whitespace() => {}
+            _ => return Some(width),
+        }
+    }
+
+    None
+}
+
+// Blank lines take the lesser indentation of the lines around them, so a
+// guide goes on through the gaps inside a block but not past its end.
+fn levels(text: &str, tab_width: usize) -> Vec<usize> {
+    let own = text
+        .split('\n')
+        .map(|line| indentation(line, tab_width))
+        .collect::<Vec<_>>();
+
+    let mut before = own.clone();
+    let mut after = own.clone();
+
+    for line in 1..own.len() {
+        before[line] = before[line].or(before[line - 1]);
+    }
+
+    for line in (0..own.len().saturating_sub(1)).rev() {
+        after[line] = after[line].or(after[line + 1]);
+    }
+
+    own.iter()
+        .zip(before.iter().zip(&after))
+        .map(|(own, (before, after))| match (own, before, after) {
+            (Some(width), ..) => *width,
+            (None, Some(before), Some(after)) => *before.min(after),
+            _ => 0,
+        })
+        .collect()
+}
+
+pub fn find(text: &str, tab_width: usize) -> Vec<Guide> {
+    let tab_width = tab_width.max(1);
+    let mut guides = Vec::new();
+
+    // Where the guide at each level started, innermost last.
+    let mut open: Vec<usize> = Vec::new();
+
+    for (line, width) in levels(text, tab_width).into_iter().enumerate() {
+        let depth = (width + tab_width - 1) / tab_width;
+
+        while open.len() > depth {
+            let level = open.len() - 1;
+            let start = open.pop().unwrap_or_default();
+
+            guides.push(Guide {
+                column: level * tab_width,
+                start,
+                end: line,
+            });
+        }
+
+        while open.len() < depth {
+            open.push(line);
+        }
+    }
+
+    let end = text.split('\n').count();
+
+    while let Some(start) = open.pop() {
+        guides.push(Guide {
+            column: open.len() * tab_width,
+            start,
+            end,
+        });
+    }
+
+    guides
+}
+
+// The innermost block the cursor is in, which counts the lines that open
+// and close it. Only the line the cursor is on is needed.
+pub fn active(guides: &[Guide], line: usize, text: &str, tab_width: usize) -> Option<usize> {
+    let width = indentation(text, tab_width.max(1));
+
+    guides
+        .iter()
+        .enumerate()
+        .filter(|(_, guide)| {
+            let is_inside = (guide.start..guide.end).contains(&line)
+                && width.is_none_or(|width| guide.column < width);
+            let is_edge =
+                (guide.start == line + 1 || guide.end == line) && width == Some(guide.column);
+
+            is_inside || is_edge
+        })
+        .max_by_key(|(_, guide)| guide.column)
+        .map(|(index, _)| index)
+}
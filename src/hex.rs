@@ -0,0 +1,65 @@
+use std::fmt::Write;
+
+const WIDTH: usize = 16;
+const SNIFFED: usize = 8000;
+
+// Text runs through so many encodings that a zero byte near the start is
+// the surest sign there is none, the way git tells binary files apart.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(SNIFFED)].contains(&0)
+}
+
+// The offset, the bytes in two groups of eight, and those of them that can
+// be printed, laid out like `hexdump -C`.
+pub fn dump(bytes: &[u8]) -> String {
+    let mut dump = String::with_capacity(bytes.len() / WIDTH * 80 + 80);
+
+    for (row, chunk) in bytes.chunks(WIDTH).enumerate() {
+        let _ = write!(dump, "{:08x} ", row * WIDTH);
+
+        for index in 0..WIDTH {
+            if index % 8 == 0 {
+                dump.push(' ');
+            }
+
+            match chunk.get(index) {
+                Some(byte) => {
+                    let _ = write!(dump, "{byte:02x} ");
+                }
+                None => dump.push_str("   "),
+            }
+        }
+
+        dump.push('|');
+        dump.extend(chunk.iter().map(|&byte| match byte {
+            0x20..=0x7e => byte as char,
+            _ => '.',
+        }));
+        dump.push_str("|\n");
+    }
+
+    dump
+}
+
+// Only the bytes count: the offsets are skipped and the printable column
+// is ignored, so bytes can be added or taken out anywhere. Nothing comes
+// back if any of them is not two hex digits.
+pub fn parse(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    for line in text.lines() {
+        for token in line
+            .split_whitespace()
+            .skip(1)
+            .take_while(|token| !token.starts_with('|'))
+        {
+            if token.len() != 2 || !token.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+                return None;
+            }
+
+            bytes.push(u8::from_str_radix(token, 16).ok()?);
+        }
+    }
+
+    Some(bytes)
+}
@@ -0,0 +1,135 @@
+use std::time::{Duration, Instant};
+
+use crate::editing::Position;
+
+const MAX_SNAPSHOTS: usize = 500;
+const GROUP_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Insert,
+    Delete,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub text: String,
+    pub cursor: Position,
+    revision: u64,
+}
+
+impl Snapshot {
+    pub fn new(text: String, cursor: Position) -> Self {
+        Self {
+            text,
+            cursor,
+            revision: 0,
+        }
+    }
+}
+
+// Every edit moves the buffer to a fresh revision, and the buffer is clean
+// whenever its current revision is the one that was last saved, even after
+// undoing back to it.
+#[derive(Debug, Clone)]
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    revision: u64,
+    latest: u64,
+    saved: Option<u64>,
+    group: Option<(Change, Instant)>,
+}
+
+impl History {
+    pub fn new(is_saved: bool) -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            revision: 0,
+            latest: 0,
+            saved: is_saved.then_some(0),
+            group: None,
+        }
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.saved != Some(self.revision)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // Called before an edit. Keystrokes of the same kind that follow each
+    // other closely share the snapshot taken before the first of them.
+    pub fn record(&mut self, change: Change, snapshot: impl FnOnce() -> Snapshot) {
+        let now = Instant::now();
+        let is_grouped = change != Change::Other
+            && self.group.is_some_and(|(previous, at)| {
+                previous == change && now.duration_since(at) < GROUP_TIMEOUT
+            });
+
+        if !is_grouped {
+            self.undo.push(Snapshot {
+                revision: self.revision,
+                ..snapshot()
+            });
+
+            if self.undo.len() > MAX_SNAPSHOTS {
+                self.undo.remove(0);
+            }
+        }
+
+        self.redo.clear();
+        self.latest += 1;
+        self.revision = self.latest;
+        self.group = Some((change, now));
+    }
+
+    pub fn seal(&mut self) {
+        self.group = None;
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    // Saves finish asynchronously, so they hold on to the revision that was
+    // written rather than whatever the buffer has reached since.
+    pub fn mark_saved_at(&mut self, revision: u64) {
+        self.saved = Some(revision);
+        self.seal();
+    }
+
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let previous = self.undo.pop()?;
+
+        self.redo.push(Snapshot {
+            revision: self.revision,
+            ..current
+        });
+        self.revision = previous.revision;
+        self.seal();
+
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let next = self.redo.pop()?;
+
+        self.undo.push(Snapshot {
+            revision: self.revision,
+            ..current
+        });
+        self.revision = next.revision;
+        self.seal();
+
+        Some(next)
+    }
+}
@@ -0,0 +1,194 @@
+use iced::{
+    theme,
+    widget::{column, container, horizontal_rule, pane_grid, row, scrollable, text},
+    Element, Length,
+};
+use regex::Regex;
+
+use crate::{editing::Position, Messages};
+
+// Where a hover was asked for, in UTF-16 columns as it was sent. Answers
+// to anything but the latest request are dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    pub pane: pane_grid::Pane,
+    pub sent: Position,
+    pub is_explicit: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Block {
+    Heading(String),
+    Paragraph(String),
+    Item(String),
+    Code(String),
+    Rule,
+}
+
+// Information about the word from start up to end, a character column on
+// the same line.
+#[derive(Debug, Clone)]
+pub struct Hover {
+    pub pane: pane_grid::Pane,
+    pub start: Position,
+    end: usize,
+    blocks: Vec<Block>,
+}
+
+impl Hover {
+    pub fn new(pane: pane_grid::Pane, start: Position, end: usize, markdown: &str) -> Hover {
+        Hover {
+            pane,
+            start,
+            end,
+            blocks: parse(markdown),
+        }
+    }
+
+    pub fn contains(&self, pane: pane_grid::Pane, (line, column): Position) -> bool {
+        pane == self.pane && line == self.start.0 && (self.start.1..self.end).contains(&column)
+    }
+
+    pub fn view(&self) -> Element<'_, Messages> {
+        let blocks = self
+            .blocks
+            .iter()
+            .fold(column![].spacing(6), |blocks, block| {
+                let block: Element<'_, Messages> = match block {
+                    Block::Heading(heading) => text(heading).size(16).into(),
+                    Block::Paragraph(paragraph) => text(paragraph).size(13).into(),
+                    Block::Item(item) => row![text("•").size(13), text(item).size(13)]
+                        .spacing(5)
+                        .into(),
+                    Block::Code(code) => container(text(code).size(13))
+                        .width(Length::Fill)
+                        .padding(5)
+                        .style(theme::Container::Box)
+                        .into(),
+                    Block::Rule => horizontal_rule(1).into(),
+                };
+
+                blocks.push(block)
+            });
+
+        container(scrollable(blocks))
+            .max_width(480)
+            .max_height(300)
+            .padding(8)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
+
+// Only what servers use in hovers is understood: fenced code, headings,
+// bullets, rules and paragraphs. Inline markup is dropped rather than
+// styled.
+fn parse(markdown: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph = Vec::new();
+    let mut code: Option<Vec<&str>> = None;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+
+        if let Some(lines) = code.as_mut() {
+            if trimmed.starts_with("```") {
+                blocks.push(Block::Code(lines.join("\n")));
+                code = None;
+            } else {
+                lines.push(line);
+            }
+
+            continue;
+        }
+
+        if trimmed.starts_with("```") {
+            flush(&mut paragraph, &mut blocks);
+            code = Some(Vec::new());
+        } else if trimmed.is_empty() {
+            flush(&mut paragraph, &mut blocks);
+        } else if is_rule(trimmed) {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Rule);
+        } else if let Some(heading) = heading(trimmed) {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Heading(inline(heading)));
+        } else if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Item(inline(item)));
+        } else {
+            paragraph.push(trimmed);
+        }
+    }
+
+    flush(&mut paragraph, &mut blocks);
+
+    if let Some(lines) = code {
+        blocks.push(Block::Code(lines.join("\n")));
+    }
+
+    // Servers like to start or end on a rule.
+    while matches!(blocks.last(), Some(Block::Rule)) {
+        blocks.pop();
+    }
+
+    if matches!(blocks.first(), Some(Block::Rule)) {
+        blocks.remove(0);
+    }
+
+    blocks
+}
+
+fn flush(paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>) {
+    if !paragraph.is_empty() {
+        blocks.push(Block::Paragraph(inline(&paragraph.join(" "))));
+        paragraph.clear();
+    }
+}
+
+fn is_rule(line: &str) -> bool {
+    line.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&c| line.chars().all(|other| other == c))
+}
+
+fn heading(line: &str) -> Option<&str> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+
+    line[level..].strip_prefix(' ').map(str::trim)
+}
+
+// Links keep their label, code spans their text and bold its words.
+// Backslashes escape punctuation outside of code.
+fn inline(text: &str) -> String {
+    let links = Regex::new(r"\[([^\]]*)\]\([^)]*\)").expect("valid pattern");
+    let text = links.replace_all(text, "$1");
+
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut is_code = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '`' => is_code = !is_code,
+            _ if is_code => result.push(c),
+            '\\' if chars.peek().is_some_and(char::is_ascii_punctuation) => {
+                result.extend(chars.next());
+            }
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
@@ -0,0 +1,110 @@
+use std::{cmp::Reverse, collections::BTreeMap, fmt};
+
+use crate::config::Overrides;
+
+// Fewer indented lines than this say too little about a file, which is
+// then left to the settings.
+const MIN_LINES: usize = 3;
+const MAX_STEP: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indentation {
+    Spaces(usize),
+    Tabs,
+}
+
+impl Indentation {
+    // Tabs keep whatever width they are shown at.
+    pub fn overrides(self) -> Overrides {
+        match self {
+            Indentation::Spaces(width) => Overrides {
+                tab_width: Some(width),
+                soft_tabs: Some(true),
+                ..Overrides::default()
+            },
+            Indentation::Tabs => Overrides {
+                soft_tabs: Some(false),
+                ..Overrides::default()
+            },
+        }
+    }
+}
+
+impl fmt::Display for Indentation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Indentation::Spaces(_) => write!(f, "spaces"),
+            Indentation::Tabs => write!(f, "tabs"),
+        }
+    }
+}
+
+// Goes by whichever most indented lines start with, and for spaces by the
+// step most often taken from one line's indentation to the next.
+pub fn detect(text: &str) -> Option<Indentation> {
+    let mut tabs = 0;
+    let mut spaces = 0;
+    let mut steps = BTreeMap::<usize, usize>::new();
+    let mut previous = 0;
+
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        if line.starts_with('\t') {
+            tabs += 1;
+            continue;
+        }
+
+        let width = line.chars().take_while(|&c| c == ' ').count();
+        let step = width.abs_diff(previous);
+
+        if width > 0 {
+            spaces += 1;
+        }
+
+        // Single spaces are usually alignment, like the stars of a block
+        // comment, rather than a level of indentation.
+        if (2..=MAX_STEP).contains(&step) {
+            *steps.entry(step).or_default() += 1;
+        }
+
+        previous = width;
+    }
+
+    if tabs + spaces < MIN_LINES {
+        return None;
+    }
+
+    if tabs > spaces {
+        return Some(Indentation::Tabs);
+    }
+
+    steps
+        .into_iter()
+        .max_by_key(|&(step, count)| (count, Reverse(step)))
+        .map(|(step, _)| Indentation::Spaces(step))
+}
+
+// Only the leading whitespace of each line changes. Going to tabs, what
+// does not fill a whole tab is kept as spaces.
+pub fn convert(text: &str, to: Indentation, tab_width: usize) -> String {
+    let tab_width = tab_width.max(1);
+
+    text.split('\n')
+        .map(|line| {
+            let leading = line.len() - line.trim_start_matches([' ', '\t']).len();
+            let width = line[..leading].chars().fold(0, |width, c| match c {
+                '\t' => width + tab_width - width % tab_width,
+                _ => width + 1,
+            });
+
+            let indentation = match to {
+                Indentation::Spaces(_) => " ".repeat(width),
+                Indentation::Tabs => {
+                    "\t".repeat(width / tab_width) + &" ".repeat(width % tab_width)
+                }
+            };
+
+            indentation + &line[leading..]
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
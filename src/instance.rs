@@ -0,0 +1,157 @@
+use std::{
+    env,
+    io::{self, Write},
+    time::Duration,
+};
+
+use iced::{
+    futures::{channel::mpsc, SinkExt},
+    subscription, Subscription,
+};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::Target;
+
+// A launch that connects and then says nothing is given up on, so the next
+// one is not kept waiting behind it.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// The runtime directory belongs to the user alone. Where there is none the
+// socket goes in a directory of its own under the shared temporary one.
+#[cfg(unix)]
+fn directory() -> std::path::PathBuf {
+    dirs::runtime_dir().unwrap_or_else(|| {
+        env::temp_dir().join(format!("editor-{}", env::var("USER").unwrap_or_default()))
+    })
+}
+
+#[cfg(unix)]
+fn address() -> std::path::PathBuf {
+    directory().join("editor.sock")
+}
+
+#[cfg(windows)]
+fn address() -> String {
+    format!(
+        r"\\.\pipe\editor-{}",
+        env::var("USERNAME").unwrap_or_default()
+    )
+}
+
+// Launching the editor while it already runs passes the files on to that
+// one instead. Paths are made whole first, since it was started somewhere
+// else. Nothing is sent when none answers, and the caller starts up itself.
+pub fn hand_over(targets: &[Target]) -> bool {
+    let Ok(directory) = env::current_dir() else {
+        return false;
+    };
+
+    let targets = targets
+        .iter()
+        .map(|target| Target {
+            path: directory.join(&target.path),
+            ..target.clone()
+        })
+        .collect::<Vec<_>>();
+
+    let Ok(message) = serde_json::to_vec(&targets) else {
+        return false;
+    };
+
+    match connect() {
+        Ok(mut stream) => stream.write_all(&message).is_ok(),
+        Err(_) => false,
+    }
+}
+
+#[cfg(unix)]
+fn connect() -> io::Result<std::os::unix::net::UnixStream> {
+    std::os::unix::net::UnixStream::connect(address())
+}
+
+#[cfg(windows)]
+fn connect() -> io::Result<std::fs::File> {
+    std::fs::OpenOptions::new().write(true).open(address())
+}
+
+// Every connection brings the files of one launch, and closes once they
+// are written. Only the first editor to start gets to listen.
+pub fn listen() -> Subscription<Vec<Target>> {
+    subscription::channel("instance", 10, |mut output| async move {
+        if let Err(err) = serve(&mut output).await {
+            tracing::warn!(%err, "cannot take files from other launches");
+        }
+
+        std::future::pending().await
+    })
+}
+
+async fn receive(stream: &mut (impl AsyncRead + Unpin), output: &mut mpsc::Sender<Vec<Target>>) {
+    let mut message = Vec::new();
+
+    let read = tokio::time::timeout(TIMEOUT, stream.read_to_end(&mut message)).await;
+
+    if !matches!(read, Ok(Ok(_))) {
+        return;
+    }
+
+    if let Ok(targets) = serde_json::from_slice(&message) {
+        let _ = output.send(targets).await;
+    }
+}
+
+// A socket left behind by an editor that crashed answers nobody, and is
+// taken over. Only the user may open it, since whoever can gets to open
+// files in the editor.
+#[cfg(unix)]
+async fn serve(output: &mut mpsc::Sender<Vec<Target>>) -> io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let address = address();
+
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(directory())?;
+
+    if std::fs::metadata(directory())?.permissions().mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "the socket directory is open to other users",
+        ));
+    }
+
+    if connect().is_err() {
+        let _ = std::fs::remove_file(&address);
+    }
+
+    let listener = tokio::net::UnixListener::bind(&address)?;
+
+    std::fs::set_permissions(&address, std::fs::Permissions::from_mode(0o600))?;
+
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+
+        receive(&mut stream, output).await;
+    }
+}
+
+// A pipe takes a single client, so the next one is made ready before the
+// files come in.
+#[cfg(windows)]
+async fn serve(output: &mut mpsc::Sender<Vec<Target>>) -> io::Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let address = address();
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&address)?;
+
+    loop {
+        server.connect().await?;
+
+        let mut client = std::mem::replace(&mut server, ServerOptions::new().create(&address)?);
+
+        receive(&mut client, output).await;
+    }
+}
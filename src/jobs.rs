@@ -0,0 +1,181 @@
+use std::{
+    sync::{
+        atomic::{AtomicU8, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use iced::{
+    theme,
+    widget::{button, column, container, horizontal_space, row, text},
+    Element, Length,
+};
+
+use crate::Messages;
+
+const SPINNER: [char; 8] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠇'];
+
+const RUNNING: u8 = 0;
+const CANCELLED: u8 = 1;
+const COMMITTED: u8 = 2;
+
+#[derive(Debug, Default)]
+struct Progress {
+    done: AtomicUsize,
+    total: AtomicUsize,
+    state: AtomicU8,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Handle(Arc<Progress>);
+
+impl Handle {
+    pub fn set_total(&self, total: usize) {
+        self.0.total.store(total, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self) {
+        self.0.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn set_done(&self, done: usize) {
+        self.0.done.store(done, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.state.load(Ordering::Relaxed) == CANCELLED
+    }
+
+    // Past this point the job can no longer be cancelled, since stopping it
+    // would leave its work half done. False if a cancel came first.
+    pub fn commit(&self) -> bool {
+        self.0
+            .state
+            .compare_exchange(RUNNING, COMMITTED, Ordering::AcqRel, Ordering::Acquire)
+            .map_or_else(|state| state == COMMITTED, |_| true)
+    }
+
+    fn is_committed(&self) -> bool {
+        self.0.state.load(Ordering::Relaxed) == COMMITTED
+    }
+
+    fn describe(&self) -> Option<String> {
+        let total = self.0.total.load(Ordering::Relaxed);
+
+        (total > 0).then(|| format!("{}/{total}", self.0.done.load(Ordering::Relaxed)))
+    }
+}
+
+#[derive(Debug)]
+struct Job {
+    id: u64,
+    label: String,
+    started: Instant,
+    handle: Handle,
+}
+
+impl Job {
+    fn describe(&self) -> String {
+        match self.handle.describe() {
+            Some(progress) => format!("{} ({progress})", self.label),
+            None => self.label.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Jobs {
+    jobs: Vec<Job>,
+    next_id: u64,
+    pub is_open: bool,
+}
+
+impl Jobs {
+    pub fn start(&mut self, label: impl Into<String>) -> (u64, Handle) {
+        let id = self.next_id;
+        let handle = Handle::default();
+
+        self.jobs.push(Job {
+            id,
+            label: label.into(),
+            started: Instant::now(),
+            handle: handle.clone(),
+        });
+        self.next_id += 1;
+
+        (id, handle)
+    }
+
+    pub fn finish(&mut self, id: u64) -> bool {
+        let Some(index) = self.jobs.iter().position(|job| job.id == id) else {
+            return false;
+        };
+
+        self.jobs.remove(index);
+
+        if self.jobs.is_empty() {
+            self.is_open = false;
+        }
+
+        true
+    }
+
+    // A job that has committed runs to the end, and its result still comes
+    // back like any other.
+    pub fn cancel(&mut self, id: u64) {
+        let Some(job) = self.jobs.iter().find(|job| job.id == id) else {
+            return;
+        };
+
+        let state = &job.handle.0.state;
+
+        if state
+            .compare_exchange(RUNNING, CANCELLED, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+        {
+            self.finish(id);
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        !self.jobs.is_empty()
+    }
+
+    pub fn status(&self) -> Option<String> {
+        let job = self.jobs.first()?;
+        let frame = job.started.elapsed().as_millis() / 100;
+        let spinner = SPINNER[frame as usize % SPINNER.len()];
+
+        Some(match self.jobs.len() {
+            1 => format!("{spinner} {}", job.describe()),
+            count => format!("{spinner} {} (+{} more)", job.describe(), count - 1),
+        })
+    }
+
+    pub fn view(&self) -> Element<'_, Messages> {
+        let jobs = self.jobs.iter().fold(column![].spacing(5), |jobs, job| {
+            let cancel = button("Cancel").style(theme::Button::Secondary);
+
+            jobs.push(
+                row![
+                    text(job.describe()).size(14),
+                    text(format!("{:.0?}", job.started.elapsed())).size(14),
+                    horizontal_space(Length::Fill),
+                    if job.handle.is_committed() {
+                        cancel
+                    } else {
+                        cancel.on_press(Messages::CancelJob(job.id))
+                    },
+                ]
+                .spacing(10),
+            )
+        });
+
+        container(jobs)
+            .padding(10)
+            .width(Length::Fill)
+            .style(theme::Container::Box)
+            .into()
+    }
+}
@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use crate::editing::Position;
+
+const LIMIT: usize = 100;
+
+pub type Location = (PathBuf, Position);
+
+// Where the cursor was before each jump, like the history of a browser:
+// going back keeps the way forward until a new jump is made.
+#[derive(Debug, Clone, Default)]
+pub struct Jumps {
+    back: Vec<Location>,
+    forward: Vec<Location>,
+}
+
+impl Jumps {
+    pub fn push(&mut self, from: Location) {
+        self.forward.clear();
+
+        if self.back.last() == Some(&from) {
+            return;
+        }
+
+        self.back.push(from);
+
+        if self.back.len() > LIMIT {
+            self.back.remove(0);
+        }
+    }
+
+    pub fn back(&mut self, current: Option<Location>) -> Option<Location> {
+        step(&mut self.back, &mut self.forward, current)
+    }
+
+    pub fn forward(&mut self, current: Option<Location>) -> Option<Location> {
+        step(&mut self.forward, &mut self.back, current)
+    }
+}
+
+// Entries for where the cursor already is are passed over, so every step
+// goes somewhere.
+fn step(
+    from: &mut Vec<Location>,
+    to: &mut Vec<Location>,
+    current: Option<Location>,
+) -> Option<Location> {
+    let mut target = from.pop()?;
+
+    while Some(&target) == current.as_ref() {
+        target = from.pop()?;
+    }
+
+    to.extend(current);
+
+    Some(target)
+}
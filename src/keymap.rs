@@ -0,0 +1,1099 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    time::{Duration, Instant},
+};
+
+use iced::{
+    keyboard::{KeyCode, Modifiers},
+    theme,
+    widget::{
+        button, column, container, horizontal_space, pane_grid, row, scrollable, text, text_editor,
+    },
+    Element, Length,
+};
+use serde::Deserialize;
+
+use crate::{case::Case, editing::Direction, lines::Transform, Messages};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    key: KeyCode,
+    command: bool,
+    shift: bool,
+    alt: bool,
+}
+
+impl Chord {
+    pub fn new(key: KeyCode, modifiers: Modifiers) -> Self {
+        Self {
+            key,
+            command: modifiers.command(),
+            shift: modifiers.shift(),
+            alt: modifiers.alt(),
+        }
+    }
+
+    pub fn is_modifier(&self) -> bool {
+        matches!(
+            self.key,
+            KeyCode::LControl
+                | KeyCode::RControl
+                | KeyCode::LShift
+                | KeyCode::RShift
+                | KeyCode::LAlt
+                | KeyCode::RAlt
+                | KeyCode::LWin
+                | KeyCode::RWin
+        )
+    }
+
+    fn key(key: KeyCode) -> Self {
+        Self {
+            key,
+            command: false,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    fn command(key: KeyCode) -> Self {
+        Self {
+            command: true,
+            ..Self::key(key)
+        }
+    }
+
+    fn shift(self) -> Self {
+        Self {
+            shift: true,
+            ..self
+        }
+    }
+
+    fn alt(self) -> Self {
+        Self { alt: true, ..self }
+    }
+
+    // Written like "ctrl+shift+p", with the key last.
+    fn parse(input: &str) -> Option<Self> {
+        let mut parts = input.split('+').collect::<Vec<_>>();
+        let mut chord = Self::key(parse_key(parts.pop()?)?);
+
+        for modifier in parts {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "cmd" => chord.command = true,
+                "shift" => chord.shift = true,
+                "alt" => chord.alt = true,
+                _ => return None,
+            }
+        }
+
+        Some(chord)
+    }
+}
+
+const NAMED_KEYS: [KeyCode; 67] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Tab,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Backspace,
+    KeyCode::Delete,
+    KeyCode::Space,
+    KeyCode::Backslash,
+    KeyCode::Slash,
+    KeyCode::Minus,
+    KeyCode::Period,
+    KeyCode::Grave,
+];
+
+// Keys go by the names the cheat sheet shows, and a bare digit stands for
+// that digit key.
+fn parse_key(name: &str) -> Option<KeyCode> {
+    let name = if name.len() == 1 && name.chars().all(|c| c.is_ascii_digit()) {
+        format!("Key{name}")
+    } else {
+        name.to_owned()
+    };
+
+    NAMED_KEYS
+        .into_iter()
+        .find(|key| format!("{key:?}").eq_ignore_ascii_case(&name))
+}
+
+fn parse_sequence(input: &str) -> Option<Vec<Chord>> {
+    let chords = input
+        .split_whitespace()
+        .map(Chord::parse)
+        .collect::<Option<Vec<_>>>()?;
+
+    (!chords.is_empty()).then_some(chords)
+}
+
+// The editor widget consumes navigation and deletion keys itself, so their
+// chords only reach the keymap by mapping its actions back to keys.
+pub fn key_of(action: &text_editor::Action) -> Option<KeyCode> {
+    use text_editor::{Action, Edit, Motion};
+
+    let motion = match action {
+        Action::Move(motion) | Action::Select(motion) => motion,
+        Action::Edit(Edit::Backspace) => return Some(KeyCode::Backspace),
+        Action::Edit(Edit::Delete) => return Some(KeyCode::Delete),
+        _ => return None,
+    };
+
+    Some(match motion {
+        Motion::Left | Motion::WordLeft => KeyCode::Left,
+        Motion::Right | Motion::WordRight => KeyCode::Right,
+        Motion::Up => KeyCode::Up,
+        Motion::Down => KeyCode::Down,
+        Motion::Home | Motion::DocumentStart => KeyCode::Home,
+        Motion::End | Motion::DocumentEnd => KeyCode::End,
+        Motion::PageUp => KeyCode::PageUp,
+        Motion::PageDown => KeyCode::PageDown,
+    })
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.command {
+            write!(f, "Ctrl+")?;
+        }
+
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+
+        write!(f, "{:?}", self.key)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    New,
+    Open,
+    OpenRemote,
+    OpenUrl,
+    Save,
+    SaveAs,
+    ExportHtml,
+    Print,
+    Close,
+    Quit,
+    ToggleHud,
+    ResetHud,
+    ToggleErrorLog,
+    ToggleProblems,
+    ToggleBookmark,
+    NextBookmark,
+    PreviousBookmark,
+    ToggleBookmarks,
+    ShowShortcuts,
+    Indent,
+    Dedent,
+    ExpandSelection,
+    ShrinkSelection,
+    GoBack,
+    GoForward,
+    SubwordLeft,
+    SubwordRight,
+    SelectSubwordLeft,
+    SelectSubwordRight,
+    DeleteSubwordLeft,
+    DeleteSubwordRight,
+    DeleteWordLeft,
+    DeleteWordRight,
+    DeleteToLineEnd,
+    JoinLines,
+    DuplicateLines,
+    MoveLinesUp,
+    MoveLinesDown,
+    DeleteLines,
+    SplitRight,
+    SplitDown,
+    ClosePane,
+    Find,
+    Replace,
+    FindNext,
+    FindPrevious,
+    ToggleSidebar,
+    ToggleOutline,
+    QuickOpen,
+    GoToLine,
+    Undo,
+    Redo,
+    CycleWrap,
+    CycleWhitespace,
+    IndentWithSpaces,
+    IndentWithTabs,
+    Uppercase,
+    Lowercase,
+    TitleCase,
+    SnakeCase,
+    CamelCase,
+    KebabCase,
+    Surround,
+    ChangeSurrounding,
+    RemoveSurrounding,
+    SortLines,
+    SortLinesDescending,
+    SortLinesIgnoringCase,
+    UniqueLines,
+    ReverseLines,
+    ShuffleLines,
+    AddNextOccurrence,
+    BlockUp,
+    BlockDown,
+    BlockLeft,
+    BlockRight,
+    JumpToBracket,
+    ToggleComment,
+    Complete,
+    GoToDefinition,
+    FindReferences,
+    Rename,
+    ShowHover,
+    ToggleSpellCheck,
+    ShowSpelling,
+    ShowClipboardHistory,
+    FormatDocument,
+    ToggleReadOnly,
+    ToggleTerminal,
+    ToggleInlineBlame,
+    ToggleBlameGutter,
+    ToggleSourceControl,
+    ShowUnsavedChanges,
+    CompareFiles,
+    NextConflict,
+    PreviousConflict,
+}
+
+impl Action {
+    pub fn message(self) -> Messages {
+        match self {
+            Action::New => Messages::New,
+            Action::Open => Messages::Open,
+            Action::OpenRemote => Messages::ShowOpenRemote,
+            Action::OpenUrl => Messages::ShowOpenUrl,
+            Action::Save => Messages::Save,
+            Action::SaveAs => Messages::SaveAs,
+            Action::ExportHtml => Messages::ExportHtml,
+            Action::Print => Messages::Print,
+            Action::Close => Messages::Close,
+            Action::Quit => Messages::Quit,
+            Action::ToggleHud => Messages::ToggleHud,
+            Action::ResetHud => Messages::ResetHud,
+            Action::ToggleErrorLog => Messages::ToggleErrorLog,
+            Action::ToggleProblems => Messages::ToggleProblems,
+            Action::ToggleBookmark => Messages::ToggleBookmark,
+            Action::NextBookmark => Messages::NextBookmark,
+            Action::PreviousBookmark => Messages::PreviousBookmark,
+            Action::ToggleBookmarks => Messages::ToggleBookmarks,
+            Action::Complete => Messages::Complete,
+            Action::GoToDefinition => Messages::GoToDefinition,
+            Action::FindReferences => Messages::FindReferences,
+            Action::Rename => Messages::ShowRename,
+            Action::ShowHover => Messages::ShowHover,
+            Action::ToggleSpellCheck => Messages::ToggleSpellCheck,
+            Action::ShowSpelling => Messages::ShowSpelling,
+            Action::ShowClipboardHistory => Messages::ShowClipboardHistory,
+            Action::FormatDocument => Messages::Format,
+            Action::ToggleReadOnly => Messages::ToggleReadOnly,
+            Action::ToggleTerminal => Messages::ToggleTerminal,
+            Action::ToggleInlineBlame => Messages::ToggleInlineBlame,
+            Action::ToggleBlameGutter => Messages::ToggleBlameGutter,
+            Action::ToggleSourceControl => Messages::ToggleSourceControl,
+            Action::ShowUnsavedChanges => Messages::ShowUnsavedChanges,
+            Action::CompareFiles => Messages::ToggleCompare,
+            Action::NextConflict => Messages::NextConflict,
+            Action::PreviousConflict => Messages::PreviousConflict,
+            Action::ShowShortcuts => Messages::ToggleShortcuts,
+            Action::Indent => Messages::Indent,
+            Action::Dedent => Messages::Dedent,
+            Action::ExpandSelection => Messages::ExpandSelection,
+            Action::ShrinkSelection => Messages::ShrinkSelection,
+            Action::GoBack => Messages::GoBack,
+            Action::GoForward => Messages::GoForward,
+            Action::SubwordLeft => Messages::MoveSubword(Direction::Backward),
+            Action::SubwordRight => Messages::MoveSubword(Direction::Forward),
+            Action::SelectSubwordLeft => Messages::SelectSubword(Direction::Backward),
+            Action::SelectSubwordRight => Messages::SelectSubword(Direction::Forward),
+            Action::DeleteSubwordLeft => Messages::DeleteSubword(Direction::Backward),
+            Action::DeleteSubwordRight => Messages::DeleteSubword(Direction::Forward),
+            Action::DeleteWordLeft => Messages::DeleteWord(Direction::Backward),
+            Action::DeleteWordRight => Messages::DeleteWord(Direction::Forward),
+            Action::DeleteToLineEnd => Messages::DeleteToLineEnd,
+            Action::JoinLines => Messages::JoinLines,
+            Action::DuplicateLines => Messages::DuplicateLines,
+            Action::MoveLinesUp => Messages::MoveLines(Direction::Backward),
+            Action::MoveLinesDown => Messages::MoveLines(Direction::Forward),
+            Action::DeleteLines => Messages::DeleteLines,
+            Action::SplitRight => Messages::SplitPane(pane_grid::Axis::Vertical),
+            Action::SplitDown => Messages::SplitPane(pane_grid::Axis::Horizontal),
+            Action::ClosePane => Messages::ClosePane,
+            Action::Find => Messages::ShowFind(false),
+            Action::Replace => Messages::ShowFind(true),
+            Action::FindNext => Messages::FindNext,
+            Action::FindPrevious => Messages::FindPrevious,
+            Action::ToggleSidebar => Messages::ToggleSidebar,
+            Action::ToggleOutline => Messages::ToggleOutline,
+            Action::QuickOpen => Messages::ShowQuickOpen,
+            Action::GoToLine => Messages::ShowGoToLine,
+            Action::Undo => Messages::Undo,
+            Action::Redo => Messages::Redo,
+            Action::CycleWrap => Messages::CycleWrap,
+            Action::CycleWhitespace => Messages::CycleWhitespace,
+            Action::IndentWithSpaces => Messages::IndentWithSpaces,
+            Action::IndentWithTabs => Messages::IndentWithTabs,
+            Action::Uppercase => Messages::ConvertCase(Case::Upper),
+            Action::Lowercase => Messages::ConvertCase(Case::Lower),
+            Action::TitleCase => Messages::ConvertCase(Case::Title),
+            Action::SnakeCase => Messages::ConvertCase(Case::Snake),
+            Action::CamelCase => Messages::ConvertCase(Case::Camel),
+            Action::KebabCase => Messages::ConvertCase(Case::Kebab),
+            Action::Surround => Messages::ShowSurround,
+            Action::ChangeSurrounding => Messages::ShowChangeSurrounding,
+            Action::RemoveSurrounding => Messages::RemoveSurrounding,
+            Action::SortLines => Messages::TransformLines(Transform::SortAscending),
+            Action::SortLinesDescending => Messages::TransformLines(Transform::SortDescending),
+            Action::SortLinesIgnoringCase => Messages::TransformLines(Transform::SortIgnoringCase),
+            Action::UniqueLines => Messages::TransformLines(Transform::Unique),
+            Action::ReverseLines => Messages::TransformLines(Transform::Reverse),
+            Action::ShuffleLines => Messages::TransformLines(Transform::Shuffle),
+            Action::AddNextOccurrence => Messages::AddNextOccurrence,
+            Action::BlockUp => Messages::SelectBlock(text_editor::Motion::Up),
+            Action::BlockDown => Messages::SelectBlock(text_editor::Motion::Down),
+            Action::BlockLeft => Messages::SelectBlock(text_editor::Motion::Left),
+            Action::BlockRight => Messages::SelectBlock(text_editor::Motion::Right),
+            Action::JumpToBracket => Messages::JumpToBracket,
+            Action::ToggleComment => Messages::ToggleComment,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Action::New => "New file",
+            Action::Open => "Open file",
+            Action::OpenRemote => "Open remote file…",
+            Action::OpenUrl => "Open URL…",
+            Action::Save => "Save",
+            Action::SaveAs => "Save as",
+            Action::ExportHtml => "Export as HTML",
+            Action::Print => "Print",
+            Action::Close => "Close file",
+            Action::Quit => "Quit",
+            Action::ToggleHud => "Toggle performance HUD",
+            Action::ResetHud => "Reset performance HUD",
+            Action::ToggleErrorLog => "Toggle error log",
+            Action::ToggleProblems => "Toggle problems",
+            Action::ToggleBookmark => "Toggle bookmark",
+            Action::NextBookmark => "Next bookmark",
+            Action::PreviousBookmark => "Previous bookmark",
+            Action::ToggleBookmarks => "Toggle bookmark list",
+            Action::ShowShortcuts => "Show keyboard shortcuts",
+            Action::Indent => "Indent selected lines",
+            Action::Dedent => "Dedent selected lines",
+            Action::ExpandSelection => "Expand selection",
+            Action::ShrinkSelection => "Shrink selection",
+            Action::GoBack => "Go back",
+            Action::GoForward => "Go forward",
+            Action::SubwordLeft => "Previous sub-word",
+            Action::SubwordRight => "Next sub-word",
+            Action::SelectSubwordLeft => "Select previous sub-word",
+            Action::SelectSubwordRight => "Select next sub-word",
+            Action::DeleteSubwordLeft => "Delete previous sub-word",
+            Action::DeleteSubwordRight => "Delete next sub-word",
+            Action::DeleteWordLeft => "Delete previous word",
+            Action::DeleteWordRight => "Delete next word",
+            Action::DeleteToLineEnd => "Delete to end of line",
+            Action::JoinLines => "Join lines",
+            Action::DuplicateLines => "Duplicate lines",
+            Action::MoveLinesUp => "Move lines up",
+            Action::MoveLinesDown => "Move lines down",
+            Action::DeleteLines => "Delete lines",
+            Action::SplitRight => "Split pane right",
+            Action::SplitDown => "Split pane down",
+            Action::ClosePane => "Close pane",
+            Action::Find => "Find in file",
+            Action::Replace => "Replace in file",
+            Action::FindNext => "Find next",
+            Action::FindPrevious => "Find previous",
+            Action::ToggleSidebar => "Toggle file tree",
+            Action::ToggleOutline => "Toggle symbol outline",
+            Action::QuickOpen => "Go to file",
+            Action::GoToLine => "Go to line",
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::CycleWrap => "Cycle word wrap",
+            Action::CycleWhitespace => "Cycle whitespace rendering",
+            Action::IndentWithSpaces => "Convert indentation to spaces",
+            Action::IndentWithTabs => "Convert indentation to tabs",
+            Action::Uppercase => "Convert to UPPERCASE",
+            Action::Lowercase => "Convert to lowercase",
+            Action::TitleCase => "Convert to Title Case",
+            Action::SnakeCase => "Convert to snake_case",
+            Action::CamelCase => "Convert to camelCase",
+            Action::KebabCase => "Convert to kebab-case",
+            Action::Surround => "Surround with…",
+            Action::ChangeSurrounding => "Change surrounding pair",
+            Action::RemoveSurrounding => "Remove surrounding pair",
+            Action::SortLines => "Sort lines",
+            Action::SortLinesDescending => "Sort lines in descending order",
+            Action::SortLinesIgnoringCase => "Sort lines ignoring case",
+            Action::UniqueLines => "Remove duplicate lines",
+            Action::ReverseLines => "Reverse lines",
+            Action::ShuffleLines => "Shuffle lines",
+            Action::AddNextOccurrence => "Add cursor at next occurrence",
+            Action::BlockUp => "Extend block selection up",
+            Action::BlockDown => "Extend block selection down",
+            Action::BlockLeft => "Extend block selection left",
+            Action::BlockRight => "Extend block selection right",
+            Action::JumpToBracket => "Jump to matching bracket",
+            Action::ToggleComment => "Toggle comment",
+            Action::Complete => "Show completions",
+            Action::GoToDefinition => "Go to definition",
+            Action::FindReferences => "Find references",
+            Action::Rename => "Rename symbol",
+            Action::ShowHover => "Show hover information",
+            Action::ToggleSpellCheck => "Toggle spell checking",
+            Action::ShowSpelling => "Show spelling suggestions",
+            Action::ShowClipboardHistory => "Paste from clipboard history",
+            Action::FormatDocument => "Format document",
+            Action::ToggleReadOnly => "Toggle read-only",
+            Action::ToggleTerminal => "Toggle terminal",
+            Action::ToggleInlineBlame => "Toggle blame for the current line",
+            Action::ToggleBlameGutter => "Toggle blame for every line",
+            Action::ToggleSourceControl => "Toggle source control",
+            Action::ShowUnsavedChanges => "Show unsaved changes",
+            Action::CompareFiles => "Compare with another file",
+            Action::NextConflict => "Next merge conflict",
+            Action::PreviousConflict => "Previous merge conflict",
+        }
+    }
+
+    fn category(self) -> &'static str {
+        match self {
+            Action::New
+            | Action::Open
+            | Action::OpenRemote
+            | Action::OpenUrl
+            | Action::Save
+            | Action::SaveAs
+            | Action::ExportHtml
+            | Action::Print
+            | Action::Close
+            | Action::Quit
+            | Action::QuickOpen => "File",
+            Action::ToggleHud | Action::ResetHud | Action::ToggleErrorLog => "Debugging",
+            Action::ShowShortcuts => "Help",
+            Action::SplitRight
+            | Action::SplitDown
+            | Action::ClosePane
+            | Action::ToggleSidebar
+            | Action::ToggleOutline
+            | Action::ToggleProblems
+            | Action::ToggleBookmarks
+            | Action::ToggleTerminal
+            | Action::ToggleInlineBlame
+            | Action::ToggleBlameGutter
+            | Action::ToggleSourceControl
+            | Action::ShowUnsavedChanges
+            | Action::CompareFiles
+            | Action::ToggleSpellCheck
+            | Action::CycleWrap
+            | Action::CycleWhitespace => "View",
+            Action::Find | Action::Replace | Action::FindNext | Action::FindPrevious => "Search",
+            Action::Undo
+            | Action::Redo
+            | Action::IndentWithSpaces
+            | Action::IndentWithTabs
+            | Action::Uppercase
+            | Action::Lowercase
+            | Action::TitleCase
+            | Action::SnakeCase
+            | Action::CamelCase
+            | Action::KebabCase
+            | Action::Surround
+            | Action::ChangeSurrounding
+            | Action::RemoveSurrounding
+            | Action::SortLines
+            | Action::SortLinesDescending
+            | Action::SortLinesIgnoringCase
+            | Action::UniqueLines
+            | Action::ReverseLines
+            | Action::ShuffleLines
+            | Action::Indent
+            | Action::Dedent
+            | Action::DeleteSubwordLeft
+            | Action::DeleteSubwordRight
+            | Action::DeleteWordLeft
+            | Action::DeleteWordRight
+            | Action::DeleteToLineEnd
+            | Action::JoinLines
+            | Action::DuplicateLines
+            | Action::MoveLinesUp
+            | Action::MoveLinesDown
+            | Action::DeleteLines
+            | Action::ToggleComment
+            | Action::Complete
+            | Action::Rename
+            | Action::ShowSpelling
+            | Action::ShowClipboardHistory
+            | Action::FormatDocument
+            | Action::ToggleReadOnly => "Editing",
+            Action::ExpandSelection
+            | Action::ShrinkSelection
+            | Action::SelectSubwordLeft
+            | Action::SelectSubwordRight
+            | Action::AddNextOccurrence
+            | Action::BlockUp
+            | Action::BlockDown
+            | Action::BlockLeft
+            | Action::BlockRight => "Selection",
+            Action::SubwordLeft
+            | Action::SubwordRight
+            | Action::GoToLine
+            | Action::JumpToBracket
+            | Action::GoToDefinition
+            | Action::FindReferences
+            | Action::NextConflict
+            | Action::GoBack
+            | Action::GoForward
+            | Action::ToggleBookmark
+            | Action::NextBookmark
+            | Action::PreviousBookmark
+            | Action::PreviousConflict
+            | Action::ShowHover => "Navigation",
+        }
+    }
+}
+
+pub enum Resolution {
+    Action(Action),
+    Prefix,
+    Unbound,
+}
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<Vec<Chord>, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: HashMap::from([
+                (vec![Chord::command(KeyCode::N)], Action::New),
+                (vec![Chord::command(KeyCode::O)], Action::Open),
+                (vec![Chord::command(KeyCode::P)], Action::QuickOpen),
+                (vec![Chord::command(KeyCode::S)], Action::Save),
+                (vec![Chord::command(KeyCode::S).shift()], Action::SaveAs),
+                (vec![Chord::command(KeyCode::P).alt()], Action::Print),
+                (vec![Chord::command(KeyCode::W)], Action::Close),
+                (vec![Chord::command(KeyCode::Q)], Action::Quit),
+                (vec![Chord::command(KeyCode::J)], Action::JoinLines),
+                (
+                    vec![Chord::command(KeyCode::D).shift()],
+                    Action::DuplicateLines,
+                ),
+                (vec![Chord::key(KeyCode::Up).alt()], Action::MoveLinesUp),
+                (vec![Chord::key(KeyCode::Down).alt()], Action::MoveLinesDown),
+                (
+                    vec![Chord::command(KeyCode::K).shift()],
+                    Action::DeleteLines,
+                ),
+                (vec![Chord::command(KeyCode::Z)], Action::Undo),
+                (vec![Chord::command(KeyCode::Z).shift()], Action::Redo),
+                (vec![Chord::command(KeyCode::F)], Action::Find),
+                (vec![Chord::command(KeyCode::G)], Action::GoToLine),
+                (vec![Chord::command(KeyCode::M)], Action::JumpToBracket),
+                (
+                    vec![Chord::command(KeyCode::M).shift()],
+                    Action::ToggleProblems,
+                ),
+                (vec![Chord::command(KeyCode::Slash)], Action::ToggleComment),
+                (vec![Chord::command(KeyCode::Space)], Action::Complete),
+                (vec![Chord::command(KeyCode::H)], Action::Replace),
+                (vec![Chord::key(KeyCode::F3)], Action::FindNext),
+                (vec![Chord::key(KeyCode::F3).shift()], Action::FindPrevious),
+                (vec![Chord::command(KeyCode::B)], Action::ToggleSidebar),
+                (
+                    vec![Chord::command(KeyCode::O).shift()],
+                    Action::ToggleOutline,
+                ),
+                (vec![Chord::command(KeyCode::Grave)], Action::ToggleTerminal),
+                (
+                    vec![Chord::command(KeyCode::G).shift()],
+                    Action::ToggleSourceControl,
+                ),
+                (vec![Chord::command(KeyCode::D)], Action::AddNextOccurrence),
+                (
+                    vec![Chord::command(KeyCode::K).alt()],
+                    Action::ToggleBookmark,
+                ),
+                (vec![Chord::command(KeyCode::L).alt()], Action::NextBookmark),
+                (
+                    vec![Chord::command(KeyCode::J).alt()],
+                    Action::PreviousBookmark,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::L)],
+                    Action::ToggleBookmarks,
+                ),
+                (
+                    vec![Chord::command(KeyCode::Up).alt().shift()],
+                    Action::BlockUp,
+                ),
+                (
+                    vec![Chord::command(KeyCode::Down).alt().shift()],
+                    Action::BlockDown,
+                ),
+                (
+                    vec![Chord::command(KeyCode::Left).alt().shift()],
+                    Action::BlockLeft,
+                ),
+                (
+                    vec![Chord::command(KeyCode::Right).alt().shift()],
+                    Action::BlockRight,
+                ),
+                (vec![Chord::key(KeyCode::Z).alt()], Action::CycleWrap),
+                (vec![Chord::command(KeyCode::Backslash)], Action::SplitRight),
+                (
+                    vec![Chord::command(KeyCode::Backslash).shift()],
+                    Action::SplitDown,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::W)],
+                    Action::ClosePane,
+                ),
+                (vec![Chord::key(KeyCode::Tab)], Action::Indent),
+                (vec![Chord::key(KeyCode::Tab).shift()], Action::Dedent),
+                (
+                    vec![Chord::key(KeyCode::Up).alt().shift()],
+                    Action::ExpandSelection,
+                ),
+                (
+                    vec![Chord::key(KeyCode::Down).alt().shift()],
+                    Action::ShrinkSelection,
+                ),
+                (vec![Chord::key(KeyCode::Left).alt()], Action::GoBack),
+                (vec![Chord::key(KeyCode::Right).alt()], Action::GoForward),
+                (
+                    vec![Chord::command(KeyCode::Left).alt()],
+                    Action::SubwordLeft,
+                ),
+                (
+                    vec![Chord::command(KeyCode::Right).alt()],
+                    Action::SubwordRight,
+                ),
+                (
+                    vec![Chord::key(KeyCode::Left).alt().shift()],
+                    Action::SelectSubwordLeft,
+                ),
+                (
+                    vec![Chord::key(KeyCode::Right).alt().shift()],
+                    Action::SelectSubwordRight,
+                ),
+                (
+                    vec![Chord::key(KeyCode::Backspace).alt()],
+                    Action::DeleteSubwordLeft,
+                ),
+                (
+                    vec![Chord::key(KeyCode::Delete).alt()],
+                    Action::DeleteSubwordRight,
+                ),
+                (
+                    vec![Chord::command(KeyCode::Backspace)],
+                    Action::DeleteWordLeft,
+                ),
+                (
+                    vec![Chord::command(KeyCode::Delete)],
+                    Action::DeleteWordRight,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::K)],
+                    Action::DeleteToLineEnd,
+                ),
+                (vec![Chord::key(KeyCode::F12)], Action::GoToDefinition),
+                (
+                    vec![Chord::key(KeyCode::F12).shift()],
+                    Action::FindReferences,
+                ),
+                (vec![Chord::key(KeyCode::F2)], Action::Rename),
+                (vec![Chord::key(KeyCode::F7)], Action::ToggleSpellCheck),
+                (vec![Chord::key(KeyCode::F9)], Action::SortLines),
+                (
+                    vec![Chord::key(KeyCode::F9).shift()],
+                    Action::SortLinesDescending,
+                ),
+                (
+                    vec![Chord::command(KeyCode::F9)],
+                    Action::SortLinesIgnoringCase,
+                ),
+                (vec![Chord::key(KeyCode::F9).alt()], Action::UniqueLines),
+                (
+                    vec![Chord::command(KeyCode::F9).shift()],
+                    Action::ReverseLines,
+                ),
+                (
+                    vec![Chord::command(KeyCode::F9).alt()],
+                    Action::ShuffleLines,
+                ),
+                (vec![Chord::command(KeyCode::Period)], Action::ShowSpelling),
+                (
+                    vec![Chord::command(KeyCode::H).shift()],
+                    Action::ShowClipboardHistory,
+                ),
+                (
+                    vec![Chord::command(KeyCode::I).shift()],
+                    Action::FormatDocument,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::I)],
+                    Action::ShowHover,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::B)],
+                    Action::ToggleInlineBlame,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::G)],
+                    Action::ToggleBlameGutter,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::D)],
+                    Action::ShowUnsavedChanges,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::C)],
+                    Action::CompareFiles,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::N)],
+                    Action::NextConflict,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::P)],
+                    Action::PreviousConflict,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::H)],
+                    Action::ExportHtml,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::R)],
+                    Action::CycleWhitespace,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::Space)],
+                    Action::IndentWithSpaces,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::T)],
+                    Action::IndentWithTabs,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::U)],
+                    Action::Uppercase,
+                ),
+                (
+                    vec![
+                        Chord::command(KeyCode::K),
+                        Chord::command(KeyCode::U).shift(),
+                    ],
+                    Action::Lowercase,
+                ),
+                (
+                    vec![
+                        Chord::command(KeyCode::K),
+                        Chord::command(KeyCode::T).shift(),
+                    ],
+                    Action::TitleCase,
+                ),
+                (
+                    vec![
+                        Chord::command(KeyCode::K),
+                        Chord::command(KeyCode::S).shift(),
+                    ],
+                    Action::SnakeCase,
+                ),
+                (
+                    vec![
+                        Chord::command(KeyCode::K),
+                        Chord::command(KeyCode::C).shift(),
+                    ],
+                    Action::CamelCase,
+                ),
+                (
+                    vec![
+                        Chord::command(KeyCode::K),
+                        Chord::command(KeyCode::K).shift(),
+                    ],
+                    Action::KebabCase,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::O)],
+                    Action::OpenRemote,
+                ),
+                (
+                    vec![
+                        Chord::command(KeyCode::K),
+                        Chord::command(KeyCode::O).shift(),
+                    ],
+                    Action::OpenUrl,
+                ),
+                (
+                    vec![
+                        Chord::command(KeyCode::K),
+                        Chord::command(KeyCode::R).shift(),
+                    ],
+                    Action::ToggleReadOnly,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::Q)],
+                    Action::Surround,
+                ),
+                (
+                    vec![
+                        Chord::command(KeyCode::K),
+                        Chord::command(KeyCode::Q).shift(),
+                    ],
+                    Action::ChangeSurrounding,
+                ),
+                (
+                    vec![
+                        Chord::command(KeyCode::K),
+                        Chord::command(KeyCode::Backspace),
+                    ],
+                    Action::RemoveSurrounding,
+                ),
+                (vec![Chord::command(KeyCode::F12)], Action::ToggleHud),
+                (vec![Chord::command(KeyCode::F12).shift()], Action::ResetHud),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::E)],
+                    Action::ToggleErrorLog,
+                ),
+                (
+                    vec![Chord::command(KeyCode::K), Chord::command(KeyCode::S)],
+                    Action::ShowShortcuts,
+                ),
+            ]),
+        }
+    }
+}
+
+impl Keymap {
+    // Configured bindings are laid over the defaults, which cover whatever
+    // the settings leave out. Anything that cannot work as written is
+    // reported back rather than failing the whole keymap.
+    pub fn configured(keys: &BTreeMap<String, Action>) -> (Self, Vec<String>) {
+        let mut keymap = Self::default();
+        let mut configured = HashMap::new();
+        let mut problems = Vec::new();
+
+        for (input, &action) in keys {
+            let Some(chords) = parse_sequence(input) else {
+                problems.push(format!("Unknown key binding \"{input}\""));
+                continue;
+            };
+
+            if let Some(previous) = configured.insert(chords.clone(), action) {
+                if previous != action {
+                    problems.push(format!(
+                        "{} is bound to both \"{}\" and \"{}\"",
+                        describe(&chords),
+                        previous.describe(),
+                        action.describe()
+                    ));
+                }
+            }
+
+            keymap.bindings.insert(chords, action);
+        }
+
+        // A binding that starts another one is matched first, so the longer
+        // one could never be reached.
+        for shorter in keymap.bindings.keys() {
+            for longer in keymap.bindings.keys() {
+                let is_configured =
+                    configured.contains_key(shorter) || configured.contains_key(longer);
+
+                if is_configured && longer.len() > shorter.len() && longer.starts_with(shorter) {
+                    problems.push(format!("{} hides {}", describe(shorter), describe(longer)));
+                }
+            }
+        }
+
+        problems.sort();
+
+        (keymap, problems)
+    }
+
+    pub fn resolve(&self, sequence: &[Chord]) -> Resolution {
+        if let Some(action) = self.bindings.get(sequence) {
+            return Resolution::Action(*action);
+        }
+
+        let is_prefix = self
+            .bindings
+            .keys()
+            .any(|chords| chords.len() > sequence.len() && chords.starts_with(sequence));
+
+        if is_prefix {
+            Resolution::Prefix
+        } else {
+            Resolution::Unbound
+        }
+    }
+
+    pub fn cheat_sheet(&self) -> Element<'_, Messages> {
+        let mut bindings = self
+            .bindings
+            .iter()
+            .map(|(chords, action)| (action.category(), action.describe(), describe(chords)))
+            .collect::<Vec<_>>();
+
+        bindings.sort();
+
+        let mut sheet = column![].spacing(5);
+        let mut category = "";
+
+        for (group, description, keys) in bindings {
+            if group != category {
+                sheet = sheet.push(text(group).size(18));
+                category = group;
+            }
+
+            sheet = sheet.push(
+                row![
+                    text(description).size(14),
+                    horizontal_space(Length::Fill),
+                    text(keys).size(14),
+                ]
+                .spacing(10),
+            );
+        }
+
+        container(column![
+            row![
+                text("Keyboard shortcuts").size(20),
+                horizontal_space(Length::Fill),
+                button("Close").on_press(Messages::ToggleShortcuts),
+            ],
+            scrollable(sheet).height(240),
+        ])
+        .padding(10)
+        .width(Length::Fill)
+        .style(theme::Container::Box)
+        .into()
+    }
+}
+
+fn describe(chords: &[Chord]) -> String {
+    chords
+        .iter()
+        .map(|chord| chord.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Prefix {
+    chords: Vec<Chord>,
+    started: Option<Instant>,
+}
+
+impl Prefix {
+    const TIMEOUT: Duration = Duration::from_millis(1500);
+
+    pub fn extend(&self, chord: Chord) -> Vec<Chord> {
+        let mut sequence = self.chords.clone();
+        sequence.push(chord);
+
+        sequence
+    }
+
+    pub fn set(&mut self, chords: Vec<Chord>) {
+        self.chords = chords;
+        self.started = Some(Instant::now());
+    }
+
+    pub fn clear(&mut self) {
+        self.chords.clear();
+        self.started = None;
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.chords.is_empty()
+    }
+
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.started
+            .is_some_and(|started| now - started > Self::TIMEOUT)
+    }
+
+    pub fn describe(&self) -> String {
+        describe(&self.chords)
+    }
+}
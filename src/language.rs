@@ -0,0 +1,139 @@
+use std::fmt;
+
+use crate::config::Overrides;
+
+pub const ALL: [Language; 16] = [
+    Language("txt"),
+    Language("md"),
+    Language("rs"),
+    Language("py"),
+    Language("js"),
+    Language("ts"),
+    Language("c"),
+    Language("cpp"),
+    Language("go"),
+    Language("java"),
+    Language("sh"),
+    Language("html"),
+    Language("css"),
+    Language("json"),
+    Language("toml"),
+    Language("yaml"),
+];
+
+// Stands for a language by the extension the highlighter knows it under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Language(pub &'static str);
+
+impl Language {
+    pub fn of(extension: &str) -> Option<Language> {
+        let wanted = name(extension);
+
+        ALL.into_iter().find(|language| name(language.0) == wanted)
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", name(self.0))
+    }
+}
+
+pub fn name(extension: &str) -> String {
+    let name = match extension {
+        "rs" => "Rust",
+        "py" => "Python",
+        "js" | "mjs" => "JavaScript",
+        "ts" => "TypeScript",
+        "c" | "h" => "C",
+        "cpp" | "cc" | "hpp" => "C++",
+        "go" => "Go",
+        "java" => "Java",
+        "sh" | "bash" => "Shell",
+        "md" => "Markdown",
+        "html" | "htm" => "HTML",
+        "css" => "CSS",
+        "json" => "JSON",
+        "toml" => "TOML",
+        "yaml" | "yml" => "YAML",
+        "txt" => "Plain text",
+        _ => return extension.to_uppercase(),
+    };
+
+    name.to_owned()
+}
+
+type Comments = (
+    &'static [&'static str],
+    Option<&'static str>,
+    Option<(&'static str, &'static str)>,
+);
+
+// Extensions with their line comment and block comment markers. Adding a
+// language only takes another row here.
+const COMMENTS: [Comments; 6] = [
+    (
+        &[
+            "rs", "js", "mjs", "ts", "c", "h", "cpp", "cc", "hpp", "go", "java",
+        ],
+        Some("//"),
+        Some(("/*", "*/")),
+    ),
+    (
+        &["py", "sh", "bash", "toml", "yaml", "yml"],
+        Some("#"),
+        None,
+    ),
+    (&["sql", "lua", "hs"], Some("--"), None),
+    (&["css"], None, Some(("/*", "*/"))),
+    (&["html", "htm", "xml", "md"], None, Some(("<!--", "-->"))),
+    (&["txt"], None, None),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comment {
+    Line(&'static str),
+    Block(&'static str, &'static str),
+}
+
+fn comments(extension: &str) -> Option<&'static Comments> {
+    COMMENTS
+        .iter()
+        .find(|(extensions, ..)| extensions.contains(&extension))
+}
+
+pub fn line_comment(extension: &str) -> Option<&'static str> {
+    comments(extension)?.1
+}
+
+pub fn block_comment(extension: &str) -> Option<(&'static str, &'static str)> {
+    comments(extension)?.2
+}
+
+// Line comments are preferred, since they toggle cleanly one line at a time.
+pub fn comment(extension: &str) -> Option<Comment> {
+    match *comments(extension)? {
+        (_, Some(line), _) => Some(Comment::Line(line)),
+        (_, None, Some((open, close))) => Some(Comment::Block(open, close)),
+        _ => None,
+    }
+}
+
+// Applied beneath the user and project settings, so either can still
+// override them.
+pub fn defaults(extension: &str) -> Overrides {
+    let tab_width = match extension {
+        "js" | "mjs" | "ts" | "json" | "yaml" | "yml" | "html" | "htm" | "css" => Some(2),
+        _ => None,
+    };
+    let soft_tabs = match extension {
+        "go" | "mk" => Some(false),
+        _ => None,
+    };
+
+    Overrides {
+        tab_width,
+        soft_tabs,
+        ..Overrides::default()
+    }
+}
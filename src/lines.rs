@@ -0,0 +1,50 @@
+use std::{
+    collections::HashSet,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    SortAscending,
+    SortDescending,
+    SortIgnoringCase,
+    Unique,
+    Reverse,
+    Shuffle,
+}
+
+// Sorts are stable, so lines that compare the same keep their order, and
+// of duplicate lines the first one stays.
+pub fn transform(mut lines: Vec<String>, transform: Transform) -> Vec<String> {
+    match transform {
+        Transform::SortAscending => lines.sort(),
+        Transform::SortDescending => lines.sort_by(|a, b| b.cmp(a)),
+        Transform::SortIgnoringCase => lines.sort_by_cached_key(|line| line.to_lowercase()),
+        Transform::Unique => {
+            let mut seen = HashSet::new();
+
+            lines.retain(|line| seen.insert(line.clone()));
+        }
+        Transform::Reverse => lines.reverse(),
+        Transform::Shuffle => shuffle(&mut lines),
+    }
+
+    lines
+}
+
+// A shuffle has no need for more than the clock as a seed, which saves
+// pulling in a source of randomness.
+fn shuffle(lines: &mut [String]) {
+    let mut state = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(1, |elapsed| elapsed.as_nanos() as u64)
+        | 1;
+
+    for index in (1..lines.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+
+        lines.swap(index, (state % (index as u64 + 1)) as usize);
+    }
+}
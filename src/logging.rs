@@ -0,0 +1,76 @@
+use std::{path::PathBuf, str::FromStr, sync::OnceLock};
+
+use tracing::level_filters::LevelFilter;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{
+    fmt::{self, format::FmtSpan},
+    layer::SubscriberExt,
+    reload,
+    util::SubscriberInitExt,
+    Registry,
+};
+
+const PREFIX: &str = "editor.log";
+
+static LEVEL: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+
+pub fn directory() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("editor").join("logs"))
+}
+
+pub fn init() -> Option<WorkerGuard> {
+    let appender = tracing_appender::rolling::Builder::new()
+        .rotation(tracing_appender::rolling::Rotation::DAILY)
+        .filename_prefix(PREFIX)
+        .max_log_files(7)
+        .build(directory()?)
+        .ok()?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    let (level, handle) = reload::Layer::new(LevelFilter::INFO);
+
+    tracing_subscriber::registry()
+        .with(level)
+        .with(
+            fmt::layer()
+                .with_writer(writer)
+                .with_ansi(false)
+                .with_span_events(FmtSpan::CLOSE),
+        )
+        .try_init()
+        .ok()?;
+
+    let _ = LEVEL.set(handle);
+
+    Some(guard)
+}
+
+pub fn set_level(level: &str) -> bool {
+    let Ok(level) = LevelFilter::from_str(level) else {
+        return false;
+    };
+
+    if let Some(handle) = LEVEL.get() {
+        let _ = handle.modify(|filter| *filter = level);
+    }
+
+    true
+}
+
+pub async fn latest() -> Option<PathBuf> {
+    let mut entries = tokio::fs::read_dir(directory()?).await.ok()?;
+    let mut latest = None;
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let is_log = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(PREFIX));
+
+        if is_log && latest.as_ref().is_none_or(|latest| path > *latest) {
+            latest = Some(path);
+        }
+    }
+
+    latest
+}
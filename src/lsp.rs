@@ -0,0 +1,869 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use iced::{
+    futures::{channel::mpsc, stream, SinkExt, StreamExt},
+    subscription, Subscription,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    process::{ChildStdin, ChildStdout, Command},
+};
+
+use crate::editing::Position;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Server {
+    pub name: &'static str,
+    command: &'static str,
+    args: &'static [&'static str],
+    extensions: &'static [&'static str],
+    language: &'static str,
+}
+
+// Adding a language server only takes another entry here, as long as it
+// speaks over stdio.
+const SERVERS: [Server; 2] = [
+    Server {
+        name: "rust-analyzer",
+        command: "rust-analyzer",
+        args: &[],
+        extensions: &["rs"],
+        language: "rust",
+    },
+    Server {
+        name: "pyright",
+        command: "pyright-langserver",
+        args: &["--stdio"],
+        extensions: &["py"],
+        language: "python",
+    },
+];
+
+pub fn server(extension: &str) -> Option<&'static Server> {
+    SERVERS
+        .iter()
+        .find(|server| server.extensions.contains(&extension))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+impl Severity {
+    fn from_code(code: Option<u8>) -> Severity {
+        match code {
+            Some(2) => Severity::Warning,
+            Some(3) => Severity::Information,
+            Some(4) => Severity::Hint,
+            _ => Severity::Error,
+        }
+    }
+
+    pub fn icon(self) -> char {
+        match self {
+            Severity::Error => '✖',
+            Severity::Warning => '▲',
+            Severity::Information | Severity::Hint => 'ℹ',
+        }
+    }
+}
+
+// Columns are counted in UTF-16 code units, the way the server sends them,
+// since the file may not be open to convert them against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub start: Position,
+    pub end: Position,
+    pub severity: Severity,
+    pub message: String,
+    pub source: Option<String>,
+}
+
+// The character column on a line that a UTF-16 column falls on.
+pub fn column(line: &str, units: usize) -> usize {
+    let mut counted = 0;
+
+    line.chars()
+        .take_while(|c| {
+            counted += c.len_utf16();
+            counted <= units
+        })
+        .count()
+}
+
+// The UTF-16 column a character column on a line stands for.
+pub fn units(line: &str, column: usize) -> usize {
+    line.chars().take(column).map(char::len_utf16).sum()
+}
+
+// Positions in UTF-16 columns, like those of a diagnostic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub start: Position,
+    pub end: Position,
+    pub text: String,
+}
+
+// Without a range of its own, a completion replaces the word before the
+// cursor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub label: String,
+    pub detail: Option<String>,
+    pub documentation: Option<String>,
+    pub filter: String,
+    pub sort: String,
+    pub text: String,
+    pub range: Option<(Position, Position)>,
+    pub is_snippet: bool,
+    pub additional: Vec<TextEdit>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub start: Position,
+    pub end: Position,
+}
+
+#[derive(Debug, Clone)]
+pub enum Request {
+    Completion(PathBuf, Position),
+    Definition(PathBuf, Position),
+    References(PathBuf, Position),
+    Rename(PathBuf, Position, String),
+    Hover(PathBuf, Position),
+}
+
+impl Request {
+    fn method(&self) -> &'static str {
+        match self {
+            Request::Completion(..) => "textDocument/completion",
+            Request::Definition(..) => "textDocument/definition",
+            Request::References(..) => "textDocument/references",
+            Request::Rename(..) => "textDocument/rename",
+            Request::Hover(..) => "textDocument/hover",
+        }
+    }
+
+    fn params(&self) -> Value {
+        match self {
+            Request::Completion(path, position)
+            | Request::Definition(path, position)
+            | Request::Hover(path, position) => at(path, *position),
+            Request::References(path, position) => {
+                let mut params = at(path, *position);
+                params["context"] = json!({ "includeDeclaration": true });
+
+                params
+            }
+            Request::Rename(path, position, name) => {
+                let mut params = at(path, *position);
+                params["newName"] = json!(name);
+
+                params
+            }
+        }
+    }
+
+    fn parse(&self, result: Value) -> Option<Response> {
+        match self {
+            Request::Completion(..) => {
+                let items = match serde_json::from_value::<Option<CompletionList>>(result).ok()? {
+                    Some(CompletionList::Items(items) | CompletionList::List { items }) => items,
+                    None => Vec::new(),
+                };
+
+                Some(Response::Completion(
+                    items
+                        .into_iter()
+                        .map(CompletionItem::into_completion)
+                        .collect(),
+                ))
+            }
+            Request::Definition(..) | Request::References(..) => {
+                let locations = match serde_json::from_value::<Option<Locations>>(result).ok()? {
+                    Some(Locations::One(location)) => vec![location],
+                    Some(Locations::Many(locations)) => locations,
+                    None => Vec::new(),
+                };
+
+                Some(Response::Locations(
+                    locations
+                        .into_iter()
+                        .filter_map(LinkOrLocation::into_location)
+                        .collect(),
+                ))
+            }
+            Request::Rename(..) => {
+                let edit = serde_json::from_value::<Option<WorkspaceEdit>>(result).ok()?;
+
+                Some(Response::WorkspaceEdit(
+                    edit.map(WorkspaceEdit::into_files).unwrap_or_default(),
+                ))
+            }
+            Request::Hover(..) => {
+                let hover = serde_json::from_value::<Option<HoverResult>>(result).ok()?;
+
+                Some(Response::Hover(
+                    hover.map(|hover| hover.contents.into_markdown()),
+                ))
+            }
+        }
+    }
+}
+
+fn at(path: &Path, (line, character): Position) -> Value {
+    json!({
+        "textDocument": { "uri": uri(path) },
+        "position": { "line": line, "character": character },
+    })
+}
+
+#[derive(Debug, Clone)]
+pub enum Response {
+    Completion(Vec<Completion>),
+    Locations(Vec<Location>),
+    WorkspaceEdit(Vec<(PathBuf, Vec<TextEdit>)>),
+    // Markdown, which plain text also passes for.
+    Hover(Option<String>),
+    // The server answered with an error, such as a rename it cannot do.
+    Refused(String),
+}
+
+// A server by name, along with the root it was started for.
+pub type Id = (&'static str, PathBuf);
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Ready(Id, Client),
+    Diagnostics(PathBuf, Vec<Diagnostic>),
+    Responded(Request, Response),
+    Failed(Id, String),
+}
+
+#[derive(Debug)]
+enum Notice {
+    Open(PathBuf, String),
+    Change(PathBuf, String),
+    Close(PathBuf),
+    Request(Request),
+}
+
+// Documents are always sent whole, which every server accepts.
+#[derive(Debug, Clone)]
+pub struct Client(mpsc::UnboundedSender<Notice>);
+
+impl Client {
+    pub fn open(&self, path: &Path, text: String) {
+        let _ = self.0.unbounded_send(Notice::Open(path.to_owned(), text));
+    }
+
+    pub fn change(&self, path: &Path, text: String) {
+        let _ = self.0.unbounded_send(Notice::Change(path.to_owned(), text));
+    }
+
+    pub fn close(&self, path: &Path) {
+        let _ = self.0.unbounded_send(Notice::Close(path.to_owned()));
+    }
+
+    // Answered with an event once the server replies. Requests go out after
+    // every document change sent before them.
+    pub fn request(&self, request: Request) {
+        let _ = self.0.unbounded_send(Notice::Request(request));
+    }
+}
+
+// One server runs per project root. It is killed once the subscription
+// goes away, which happens when the last file it handles is closed.
+pub fn connect(server: &'static Server, root: PathBuf) -> Subscription<Event> {
+    subscription::channel(
+        (server.name, root.clone()),
+        100,
+        move |mut output| async move {
+            if let Err(err) = run(server, &root, &mut output).await {
+                tracing::warn!(server = server.name, %err, "language server stopped");
+
+                let _ = output
+                    .send(Event::Failed((server.name, root.clone()), err.to_string()))
+                    .await;
+            }
+
+            std::future::pending().await
+        },
+    )
+}
+
+enum Input {
+    Notice(Notice),
+    Message(Value),
+}
+
+async fn run(
+    server: &'static Server,
+    root: &Path,
+    output: &mut mpsc::Sender<Event>,
+) -> io::Result<()> {
+    let mut child = Command::new(server.command)
+        .args(server.args)
+        .current_dir(root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let (Some(mut stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+        return Err(io::Error::new(io::ErrorKind::BrokenPipe, "no stdio"));
+    };
+
+    let mut incoming = Box::pin(stream::unfold(
+        BufReader::new(stdout),
+        |mut reader| async move {
+            let message = read(&mut reader).await.ok()?;
+
+            Some((message, reader))
+        },
+    ));
+
+    write(
+        &mut stdin,
+        &json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "initialize",
+            "params": {
+                "processId": std::process::id(),
+                "rootUri": uri(root),
+                "workspaceFolders": [{ "uri": uri(root), "name": "root" }],
+                "capabilities": {
+                    "textDocument": {
+                        "publishDiagnostics": {},
+                        "synchronization": { "didSave": false },
+                        "completion": {
+                            "completionItem": {
+                                "snippetSupport": true,
+                                "documentationFormat": ["plaintext", "markdown"],
+                            },
+                        },
+                        "definition": { "linkSupport": true },
+                        "references": {},
+                        "rename": {},
+                        "hover": { "contentFormat": ["markdown", "plaintext"] },
+                    },
+                },
+            },
+        }),
+    )
+    .await?;
+
+    loop {
+        let Some(message) = incoming.next().await else {
+            return Err(exited());
+        };
+
+        if message["id"] == 0 && message.get("method").is_none() {
+            break;
+        }
+    }
+
+    write(&mut stdin, &notification("initialized", json!({}))).await?;
+
+    let (sender, notices) = mpsc::unbounded();
+    let _ = output
+        .send(Event::Ready((server.name, root.to_owned()), Client(sender)))
+        .await;
+
+    let mut inputs = stream::select(notices.map(Input::Notice), incoming.map(Input::Message));
+    let mut versions = HashMap::new();
+    let mut requests = HashMap::new();
+    let mut next_id = 0;
+
+    while let Some(input) = inputs.next().await {
+        match input {
+            Input::Notice(Notice::Request(request)) => {
+                next_id += 1;
+
+                let message = json!({
+                    "jsonrpc": "2.0",
+                    "id": next_id,
+                    "method": request.method(),
+                    "params": request.params(),
+                });
+
+                requests.insert(next_id, request);
+                write(&mut stdin, &message).await?;
+            }
+            Input::Notice(notice) => {
+                let message = document(server, notice, &mut versions);
+
+                write(&mut stdin, &message).await?;
+            }
+            Input::Message(message) => {
+                if let Some(reply) = receive(message, &mut requests, output).await {
+                    write(&mut stdin, &reply).await?;
+                }
+            }
+        }
+    }
+
+    Err(exited())
+}
+
+fn exited() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "the server exited")
+}
+
+fn document(server: &Server, notice: Notice, versions: &mut HashMap<PathBuf, u64>) -> Value {
+    match notice {
+        Notice::Open(path, text) => {
+            versions.insert(path.clone(), 1);
+
+            notification(
+                "textDocument/didOpen",
+                json!({
+                    "textDocument": {
+                        "uri": uri(&path),
+                        "languageId": server.language,
+                        "version": 1,
+                        "text": text,
+                    },
+                }),
+            )
+        }
+        Notice::Change(path, text) => {
+            let version = versions.entry(path.clone()).or_insert(1);
+            *version += 1;
+
+            notification(
+                "textDocument/didChange",
+                json!({
+                    "textDocument": { "uri": uri(&path), "version": *version },
+                    "contentChanges": [{ "text": text }],
+                }),
+            )
+        }
+        Notice::Close(path) => {
+            versions.remove(&path);
+
+            notification(
+                "textDocument/didClose",
+                json!({ "textDocument": { "uri": uri(&path) } }),
+            )
+        }
+        Notice::Request(request) => unreachable!("{request:?} is sent as a request"),
+    }
+}
+
+#[derive(Deserialize)]
+struct Published {
+    uri: String,
+    diagnostics: Vec<PublishedDiagnostic>,
+}
+
+#[derive(Deserialize)]
+struct PublishedDiagnostic {
+    range: Range,
+    severity: Option<u8>,
+    message: String,
+    source: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Range {
+    start: Point,
+    end: Point,
+}
+
+impl Range {
+    fn positions(&self) -> (Position, Position) {
+        (
+            (self.start.line, self.start.character),
+            (self.end.line, self.end.character),
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct Point {
+    line: usize,
+    character: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Locations {
+    One(LinkOrLocation),
+    Many(Vec<LinkOrLocation>),
+}
+
+// Links point at the whole of the target, and at the name within it with
+// their selection range.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LinkOrLocation {
+    Location {
+        uri: String,
+        range: Range,
+    },
+    Link {
+        #[serde(rename = "targetUri")]
+        target_uri: String,
+        #[serde(rename = "targetSelectionRange")]
+        target_selection_range: Range,
+    },
+}
+
+impl LinkOrLocation {
+    fn into_location(self) -> Option<Location> {
+        let (uri, range) = match self {
+            LinkOrLocation::Location { uri, range } => (uri, range),
+            LinkOrLocation::Link {
+                target_uri,
+                target_selection_range,
+            } => (target_uri, target_selection_range),
+        };
+        let (start, end) = range.positions();
+
+        Some(Location {
+            path: path(&uri)?,
+            start,
+            end,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CompletionList {
+    Items(Vec<CompletionItem>),
+    List { items: Vec<CompletionItem> },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletionItem {
+    label: String,
+    detail: Option<String>,
+    documentation: Option<Documentation>,
+    filter_text: Option<String>,
+    sort_text: Option<String>,
+    insert_text: Option<String>,
+    insert_text_format: Option<u8>,
+    text_edit: Option<Edit>,
+    #[serde(default)]
+    additional_text_edits: Vec<Edit>,
+}
+
+impl CompletionItem {
+    fn into_completion(self) -> Completion {
+        let (text, range) = match self.text_edit {
+            Some(edit) => {
+                let range = edit.range().map(Range::positions);
+
+                (edit.new_text, range)
+            }
+            None => (self.insert_text.unwrap_or_else(|| self.label.clone()), None),
+        };
+
+        Completion {
+            filter: self.filter_text.unwrap_or_else(|| self.label.clone()),
+            sort: self.sort_text.unwrap_or_else(|| self.label.clone()),
+            label: self.label,
+            detail: self.detail,
+            documentation: self.documentation.map(|documentation| match documentation {
+                Documentation::Plain(text) | Documentation::Markup { value: text } => text,
+            }),
+            text,
+            range,
+            is_snippet: self.insert_text_format == Some(2),
+            additional: self
+                .additional_text_edits
+                .into_iter()
+                .filter_map(Edit::into_text_edit)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Documentation {
+    Plain(String),
+    Markup { value: String },
+}
+
+// Servers send either a plain edit or one with separate insert and
+// replace ranges, of which the insert range is used.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Edit {
+    range: Option<Range>,
+    insert: Option<Range>,
+    new_text: String,
+}
+
+impl Edit {
+    fn range(&self) -> Option<&Range> {
+        self.range.as_ref().or(self.insert.as_ref())
+    }
+
+    fn into_text_edit(self) -> Option<TextEdit> {
+        let (start, end) = self.range()?.positions();
+
+        Some(TextEdit {
+            start,
+            end,
+            text: self.new_text,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct HoverResult {
+    contents: Contents,
+}
+
+// Older servers send marked strings, which are either markdown or code in
+// a language.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Contents {
+    Markup { value: String },
+    One(Marked),
+    Many(Vec<Marked>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Marked {
+    Markdown(String),
+    Code { language: String, value: String },
+}
+
+impl Contents {
+    fn into_markdown(self) -> String {
+        match self {
+            Contents::Markup { value } => value,
+            Contents::One(marked) => marked.into_markdown(),
+            Contents::Many(marked) => marked
+                .into_iter()
+                .map(Marked::into_markdown)
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        }
+    }
+}
+
+impl Marked {
+    fn into_markdown(self) -> String {
+        match self {
+            Marked::Markdown(markdown) => markdown,
+            Marked::Code { language, value } => format!("```{language}\n{value}\n```"),
+        }
+    }
+}
+
+// Edits come either keyed by document, or as a list of document changes
+// that may also create, rename or delete files. Those are left out.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkspaceEdit {
+    #[serde(default)]
+    changes: HashMap<String, Vec<Edit>>,
+    #[serde(default)]
+    document_changes: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DocumentEdit {
+    text_document: Document,
+    edits: Vec<Edit>,
+}
+
+#[derive(Deserialize)]
+struct Document {
+    uri: String,
+}
+
+impl WorkspaceEdit {
+    fn into_files(self) -> Vec<(PathBuf, Vec<TextEdit>)> {
+        let documents = self
+            .document_changes
+            .into_iter()
+            .filter_map(|change| serde_json::from_value::<DocumentEdit>(change).ok())
+            .map(|change| (change.text_document.uri, change.edits));
+
+        let mut files: Vec<(PathBuf, Vec<TextEdit>)> = Vec::new();
+
+        for (uri, edits) in self.changes.into_iter().chain(documents) {
+            let Some(path) = path(&uri) else {
+                continue;
+            };
+
+            let edits = edits.into_iter().filter_map(Edit::into_text_edit);
+
+            match files.iter_mut().find(|(other, _)| *other == path) {
+                Some((_, existing)) => existing.extend(edits),
+                None => files.push((path, edits.collect())),
+            }
+        }
+
+        files
+    }
+}
+
+// Requests from the server still need an answer, or some servers wait on
+// it forever. None of them are acted on.
+async fn receive(
+    message: Value,
+    requests: &mut HashMap<u64, Request>,
+    output: &mut mpsc::Sender<Event>,
+) -> Option<Value> {
+    let Some(method) = message["method"].as_str() else {
+        let request = requests.remove(&message["id"].as_u64()?)?;
+        let response = match message.get("error") {
+            Some(error) => Response::Refused(
+                error["message"]
+                    .as_str()
+                    .unwrap_or("the request failed")
+                    .to_owned(),
+            ),
+            None => request.parse(message["result"].clone())?,
+        };
+
+        let _ = output.send(Event::Responded(request, response)).await;
+
+        return None;
+    };
+
+    if let Some(id) = message.get("id") {
+        let result = match method {
+            "workspace/configuration" => {
+                let items = message["params"]["items"].as_array().map_or(0, Vec::len);
+
+                Value::Array(vec![Value::Null; items])
+            }
+            _ => Value::Null,
+        };
+
+        return Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }));
+    }
+
+    if method == "textDocument/publishDiagnostics" {
+        let published = serde_json::from_value::<Published>(message["params"].clone()).ok()?;
+        let diagnostics = published
+            .diagnostics
+            .into_iter()
+            .map(|diagnostic| Diagnostic {
+                start: (
+                    diagnostic.range.start.line,
+                    diagnostic.range.start.character,
+                ),
+                end: (diagnostic.range.end.line, diagnostic.range.end.character),
+                severity: Severity::from_code(diagnostic.severity),
+                message: diagnostic.message,
+                source: diagnostic.source,
+            })
+            .collect();
+
+        let _ = output
+            .send(Event::Diagnostics(path(&published.uri)?, diagnostics))
+            .await;
+    }
+
+    None
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+async fn write(stdin: &mut ChildStdin, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+
+    stdin
+        .write_all(format!("Content-Length: {}\r\n\r\n{body}", body.len()).as_bytes())
+        .await?;
+    stdin.flush().await
+}
+
+async fn read(reader: &mut BufReader<ChildStdout>) -> io::Result<Value> {
+    let mut length = None;
+
+    loop {
+        let mut header = String::new();
+
+        if reader.read_line(&mut header).await? == 0 {
+            return Err(exited());
+        }
+
+        let header = header.trim_end();
+
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse().ok();
+        }
+    }
+
+    let length = length
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length"))?;
+    let mut body = vec![0; length];
+
+    reader.read_exact(&mut body).await?;
+
+    serde_json::from_slice(&body).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(byte as char);
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    uri
+}
+
+fn path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut index = 0;
+
+    while index < encoded.len() {
+        let byte = match encoded[index] {
+            b'%' => {
+                let hex = std::str::from_utf8(encoded.get(index + 1..index + 3)?).ok()?;
+                index += 2;
+
+                u8::from_str_radix(hex, 16).ok()?
+            }
+            byte => byte,
+        };
+
+        decoded.push(byte);
+        index += 1;
+    }
+
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
@@ -1,70 +1,879 @@
+mod anchored;
+mod backup;
+mod bookmarks;
+mod buffer;
+mod case;
+mod cleanup;
+mod clips;
+mod completion;
+mod config;
+mod conflict;
+mod crash;
+mod cursors;
+mod diff;
+mod editing;
+mod encoding;
+mod error_log;
+mod export;
+mod fetch;
+mod filter;
+mod find;
+mod format;
+mod git;
+mod guides;
+mod hex;
+mod history;
+mod hover;
+mod indentation;
+mod instance;
+mod jobs;
+mod jumps;
+mod keymap;
+mod language;
+mod lines;
+mod logging;
+mod lsp;
+mod minimap;
+mod open_bar;
+mod outline;
+mod paths;
+mod perf;
+mod print;
+mod quick_open;
+mod recent;
+mod references;
+mod remote;
+mod rename;
+mod search;
+mod session;
+mod spelling;
+mod stats;
+mod store;
+mod surround;
+mod terminal;
+mod text;
+mod themes;
+mod toast;
+mod tree;
+mod watcher;
+mod whitespace;
+mod words;
+
 use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    future::Future,
     io,
+    panic::{self, AssertUnwindSafe},
     path::{Path, PathBuf},
     sync::Arc,
+    time::{Duration, Instant, SystemTime},
 };
 
 use iced::{
-    executor,
+    clipboard, event, executor,
     highlighter::{self, Highlighter},
-    keyboard, theme,
+    keyboard, subscription, theme,
     widget::{
-        button, column, container, horizontal_space, pick_list, row, text, text_editor, tooltip,
+        button, checkbox, column, container, horizontal_space,
+        pane_grid::{self, PaneGrid},
+        pick_list, row, scrollable, text, text_editor, text_input, tooltip,
     },
-    Application, Command, Element, Font, Length, Settings, Subscription, Theme,
+    window, Application, Color, Command, Element, Event, Font, Length, Point, Rectangle, Settings,
+    Size, Subscription, Theme, Vector,
 };
 
+use anchored::Anchored;
+use buffer::{Buffer, Format, LineEnding, Stamp, Wrap};
+use case::Case;
+use clap::Parser;
+use cleanup::Cleanup;
+use clips::Clips;
+use completion::Popup;
+use config::{Config, Overrides, Startup};
+use crash::{Recovery, Rescued};
+use diff::Comparison;
+use editing::{Direction, Document, Pairing, Position};
+use encoding_rs::Encoding;
+use error_log::ErrorLog;
+use filter::Filter;
+use find::{Find, Matches};
+use history::{Change, Snapshot};
+use hover::Hover;
+use indentation::Indentation;
+use jobs::{Handle, Jobs};
+use jumps::{Jumps, Location};
+use keymap::{Chord, Keymap, Prefix, Resolution};
+use lines::Transform;
+use lsp::Diagnostic;
+use open_bar::{Candidate, OpenBar};
+use perf::{Hud, Timed};
+use quick_open::QuickOpen;
+use recent::RecentFiles;
+use references::References;
+use rename::Rename;
+use ropey::Rope;
+use search::{ReplaceReport, SearchResults};
+use serde::{Deserialize, Serialize};
+use session::{Session, ViewState};
+use stats::Span;
+use surround::Surround;
+use terminal::Terminal;
+use themes::CustomTheme;
+use toast::{Severity, Toasts};
+use tree::Tree;
+use whitespace::Whitespace;
+
 fn main() -> iced::Result {
+    let _log = logging::init();
+    crash::install();
+
+    let mut flags = Flags::parse();
+
+    for target in &mut flags.files {
+        target.is_read_only = flags.is_read_only;
+    }
+
+    // A running editor keeps its own theme, settings and session, so asking
+    // for any of those starts another one too.
+    let is_own_window = flags.is_new_window
+        || flags.theme.is_some()
+        || flags.is_safe_mode
+        || flags.is_session_disabled;
+
+    if !is_own_window && instance::hand_over(&flags.files) {
+        return Ok(());
+    }
+
     Editor::run(Settings {
+        flags,
         default_font: Font::MONOSPACE,
         fonts: vec![include_bytes!("../fonts/editor-icons.ttf")
             .as_slice()
             .into()],
+        // Closing the window goes through the same unsaved changes prompt
+        // as quitting from the menu.
+        window: window::Settings {
+            exit_on_close_request: false,
+            ..window::Settings::default()
+        },
         ..Settings::default()
     })
 }
 
+#[derive(Debug, Default, Parser)]
+#[command(version, about = "An iced out editor")]
+struct Flags {
+    #[arg(
+        value_name = "FILE[:LINE[:COLUMN]]",
+        value_parser = target,
+        help = "Files to open, each at a line and column if given"
+    )]
+    files: Vec<Target>,
+    #[arg(long = "readonly", help = "Open the given files read-only")]
+    is_read_only: bool,
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Use this theme over the one in the settings"
+    )]
+    theme: Option<String>,
+    #[arg(
+        long = "safe-mode",
+        help = "Start without settings, themes or language servers"
+    )]
+    is_safe_mode: bool,
+    #[arg(long = "no-session", help = "Neither restore nor save the open files")]
+    is_session_disabled: bool,
+    #[arg(
+        long = "new-window",
+        help = "Start another editor even if one is running"
+    )]
+    is_new_window: bool,
+}
+
+// A file asked for on the command line, and where to put the cursor in it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Target {
+    path: PathBuf,
+    position: Option<Position>,
+    is_read_only: bool,
+}
+
+// A path that exists is taken as it is, colons and all. Otherwise up to two
+// numbers are taken off its end as the line and column, the way compilers
+// and grep point at a spot.
+fn target(arg: &str) -> Result<Target, String> {
+    let whole = paths::expand(arg);
+
+    if whole.exists() {
+        return Ok(Target {
+            path: whole,
+            position: None,
+            is_read_only: false,
+        });
+    }
+
+    let mut path = arg;
+    let mut numbers = Vec::new();
+
+    while numbers.len() < 2 {
+        match path.rsplit_once(':') {
+            Some((rest, number)) if !rest.is_empty() && number.parse::<usize>().is_ok() => {
+                numbers.push(number);
+                path = rest;
+            }
+            _ => break,
+        }
+    }
+
+    numbers.reverse();
+
+    Ok(Target {
+        path: paths::expand(path),
+        position: editing::parse_position(&numbers.join(":")),
+        is_read_only: false,
+    })
+}
+
 #[derive(Debug, Clone)]
 enum Error {
     DialogClosed,
-    IOFailed(io::ErrorKind),
+    IOFailed(Arc<io::Error>),
+    FileFailed(PathBuf, Arc<io::Error>),
+    InvalidConfig(PathBuf, String),
+    NotWritable(PathBuf),
+    InvalidPattern(String, String),
+    Unencodable(PathBuf, &'static str),
+    ChangedOnDisk(PathBuf),
+    BackupFailed(PathBuf, Arc<io::Error>),
+    FormatFailed(String, String),
+    GitFailed(String),
+    RemoteFailed(String, String),
+    FetchFailed(String, String),
+    PrintFailed(String),
+    NoDictionary(String),
+    Cancelled,
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::IOFailed(Arc::new(err))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DialogClosed => write!(f, "Dialog closed"),
+            Error::IOFailed(err) => write!(f, "{err}"),
+            Error::FileFailed(path, err) => write!(f, "{}: {err}", path.display()),
+            Error::InvalidConfig(path, err) => {
+                write!(f, "Invalid config {}: {err}", path.display())
+            }
+            Error::NotWritable(directory) => write!(f, "Cannot write to {}", directory.display()),
+            Error::InvalidPattern(pattern, err) => write!(f, "Invalid pattern {pattern}: {err}"),
+            Error::Unencodable(path, encoding) => {
+                write!(f, "{} cannot be written as {encoding}", path.display())
+            }
+            Error::ChangedOnDisk(path) => {
+                write!(f, "{} was changed by another program", path.display())
+            }
+            Error::BackupFailed(path, err) => {
+                write!(f, "Could not back up {}: {err}", path.display())
+            }
+            Error::FormatFailed(program, reason) => write!(f, "{program} failed: {reason}"),
+            Error::GitFailed(reason) => write!(f, "git failed: {reason}"),
+            Error::RemoteFailed(host, reason) => write!(f, "{host}: {reason}"),
+            Error::FetchFailed(url, reason) => write!(f, "Could not fetch {url}: {reason}"),
+            Error::PrintFailed(reason) => write!(f, "Could not print: {reason}"),
+            Error::NoDictionary(language) => write!(f, "No dictionary for {language}"),
+            Error::Cancelled => write!(f, "Cancelled"),
+        }
+    }
 }
 
+type Loaded = (PathBuf, Arc<String>, &'static Encoding, Stamp);
+
 #[derive(Debug, Clone)]
 enum Messages {
     New,
     Open,
     Save,
+    SaveAs,
     Edit(text_editor::Action),
-    FileOpened(Result<(PathBuf, Arc<String>), Error>),
-    FileSaved(Result<PathBuf, Error>),
-    ThemeSelected(highlighter::Theme),
+    FileOpened(Result<Loaded, Error>),
+    FileSaved(Result<(PathBuf, Stamp), Error>),
+    OverwriteConflict,
+    ReloadConflict,
+    SaveConflictElsewhere,
+    CancelConflict,
+    ThemeSelected(themes::Choice),
+    ThemesLoaded(Vec<CustomTheme>, Vec<Error>),
+    CheckSettings,
+    SettingsChecked(Option<SystemTime>),
+    SettingsReloaded(Result<Overrides, Error>),
+    ToggleProjectSearch,
+    ProjectSearchChanged(String),
+    ProjectReplacementChanged(String),
+    ToggleProjectRegex(bool),
+    ProjectSearch,
+    ProjectSearched(Result<SearchResults, Error>),
+    ToggleSearchFile(usize),
+    OpenSearchHit(PathBuf, usize, usize),
+    CopySearchResults,
+    DismissSearchResults,
+    ProjectReplace,
+    ProjectReplaced(Result<Arc<ReplaceReport>, Error>),
+    RevertReplace,
+    ReplaceReverted(Result<(Arc<ReplaceReport>, Vec<PathBuf>), Error>),
+    DismissReplaceReport,
+    ToggleFilter,
+    FilterChanged(String),
+    StateLoaded(Session, RecentFiles, Result<Overrides, Error>),
+    OpenRecent(PathBuf),
+    FileDropped(PathBuf),
+    ClearRecent,
+    StateSaved(Result<(), Error>),
+    OpenFolder,
+    FolderPicked(Result<PathBuf, Error>),
+    SwitchProject(PathBuf),
+    PromptSave,
+    PromptDiscard,
+    PromptCancel,
+    ProjectConfigLoaded(Result<Overrides, Error>),
+    ShowOpenBar,
+    CloseOpenBar,
+    OpenPathChanged(String),
+    OpenCandidates(String, Vec<Candidate>),
+    OpenBarKey(keyboard::KeyCode),
+    ToggleHiddenFiles(bool),
+    OpenCandidate(usize),
+    SubmitOpenPath,
+    SaveTargetPicked(Result<(PathBuf, bool), Error>),
+    SaveTargetChecked(Result<SaveTarget, Error>),
+    ConfirmOverwrite,
+    SaveAsSuggestion,
+    CancelOverwrite,
+    ExportHtml,
+    ExportTargetPicked(Result<PathBuf, Error>),
+    Exported(Result<PathBuf, Error>),
+    Print,
+    Printed(Result<PathBuf, Error>),
+    DismissToast(u64),
+    ToastAction(u64),
+    Tick(Instant),
+    Autosave,
+    Autosaved(u64, Format, Result<(PathBuf, Stamp), Error>),
+    ToggleErrorLog,
+    ToggleProblems,
+    ToggleBookmark,
+    NextBookmark,
+    PreviousBookmark,
+    ToggleBookmarks,
+    JumpToBookmark(usize, usize),
+    ToggleOutline,
+    OutlineFilterChanged(String),
+    JumpToSymbol(usize),
+    ShowSurround,
+    ShowChangeSurrounding,
+    SurroundChanged(String),
+    SubmitSurround,
+    SurroundWith(String),
+    CloseSurround,
+    RemoveSurrounding,
+    GoBack,
+    GoForward,
+    OpenLocation(PathBuf, Position),
+    LanguageServer(lsp::Event),
+    Complete,
+    AcceptCompletion(usize),
+    GoToDefinition,
+    FindReferences,
+    ReferencesFound(References),
+    DismissReferences,
+    ShowRename,
+    RenameChanged(String),
+    SubmitRename,
+    CloseRename,
+    RenamePreviewed(Result<Rename, Error>),
+    ApplyRename,
+    CancelRename,
+    RenameApplied(Result<usize, Error>),
+    ShowHover,
+    Hovered(pane_grid::Pane, Option<(usize, usize)>),
+    PaneWrapped(pane_grid::Pane, bool),
+    ToggleSpellCheck,
+    DictionariesListed(Vec<String>),
+    DictionarySelected(String),
+    DictionaryLoaded(Result<Arc<spelling::Dictionary>, Error>),
+    ShowSpelling,
+    ReplaceMisspelled(String),
+    AddToDictionary,
+    AddedToDictionary(Result<(), Error>),
+    Copied,
+    ClipboardRead(Option<String>),
+    ShowClipboardHistory,
+    PasteClip(usize),
+    ToggleTerminal,
+    FocusTerminal,
+    Terminal(terminal::Event),
+    TerminalTyped(char),
+    Format,
+    Formatted(Option<PathBuf>, u64, Option<PathBuf>, Result<String, Error>),
+    WindowFocused,
+    HeadRead(PathBuf, Option<String>),
+    ToggleInlineBlame,
+    ToggleBlameGutter,
+    Blamed(PathBuf, Result<git::Blame, Error>),
+    StatusRead(Option<git::Status>),
+    ToggleSourceControl,
+    CommitMessageChanged(String),
+    StageFile,
+    Staged(Result<(), Error>),
+    Commit,
+    Committed(Result<String, Error>),
+    ShowUnsavedChanges,
+    SavedTextRead(Result<Loaded, Error>),
+    ToggleDiffLayout,
+    CloseDiff,
+    ToggleCompare,
+    CompareWithBuffer(CompareChoice),
+    CompareWithFile,
+    CompareFilePicked(Result<Loaded, Error>),
+    NextChange,
+    PreviousChange,
+    NextConflict,
+    PreviousConflict,
+    ResolveConflict(conflict::Resolution),
+    SyncDocuments,
+    CopyErrorLog,
+    ClearErrorLog,
+    ToggleHud,
+    ResetHud,
+    OpenLogFile,
+    ShowOpenUrl,
+    OpenUrlChanged(String),
+    SubmitOpenUrl,
+    CloseOpenUrl,
+    FetchUrl(String),
+    UrlFetched(Result<fetch::Fetched, Error>),
+    ToggleReadOnly,
+    ShowOpenRemote,
+    CloseRemote,
+    RemoteProfileSelected(String),
+    RemoteListed(String, Result<(String, Vec<remote::Entry>), Error>),
+    OpenRemoteEntry(remote::Entry),
+    RemoteLinkFollowed(
+        String,
+        remote::Entry,
+        Result<(String, Vec<remote::Entry>), Error>,
+    ),
+    RemoteDownloaded(remote::Location, Result<PathBuf, Error>),
+    UploadRemote(PathBuf),
+    RemoteUploaded(PathBuf, Result<String, Error>),
+    LogFileFound(Option<PathBuf>),
+    RecoveryFound(Vec<Recovery>),
+    RestoreRecovery,
+    DiscardRecovery,
+    RecoveryDiscarded(Result<(), Error>),
+    WriteSwaps,
+    SwapWritten(Result<(), Error>),
+    JobFinished(u64, Box<Messages>),
+    CancelJob(u64),
+    ToggleJobs,
+    KeyPressed(keyboard::KeyCode, keyboard::Modifiers),
+    Close,
+    Quit,
+    Exit,
+    ToggleShortcuts,
+    Indent,
+    Dedent,
+    ModifiersChanged(keyboard::Modifiers),
+    ExpandSelection,
+    ShrinkSelection,
+    MoveSubword(Direction),
+    SelectSubword(Direction),
+    DeleteSubword(Direction),
+    DeleteWord(Direction),
+    DeleteToLineEnd,
+    JoinLines,
+    DuplicateLines,
+    MoveLines(Direction),
+    DeleteLines,
+    SelectTab(usize),
+    CloseTab(usize),
+    FileReloaded(Result<Loaded, Error>),
+    FileTouched(PathBuf),
+    DiskChecked(Result<Loaded, Error>),
+    ReloadFromDisk,
+    KeepMine,
+    CompareWithDisk,
+    ConvertEncoding(encoding::Choice),
+    ReopenWithEncoding(encoding::Choice),
+    ConvertLineEnding(LineEnding),
+    LanguageSelected(language::Language),
+    FileReinterpreted(Result<Loaded, Error>),
+    SessionRestored(Vec<Result<Loaded, Error>>),
+    SplitPane(pane_grid::Axis),
+    ClosePane,
+    FocusPane(pane_grid::Pane),
+    ResizePane(pane_grid::ResizeEvent),
+    EditPane(pane_grid::Pane, text_editor::Action),
+    MinimapScrolled(pane_grid::Pane, usize),
+    ShowFind(bool),
+    CloseFind,
+    Undo,
+    Redo,
+    CycleWrap,
+    CycleWhitespace,
+    IndentWithSpaces,
+    IndentWithTabs,
+    ConvertCase(Case),
+    TransformLines(Transform),
+    WhitespaceSelected(Whitespace),
+    AddNextOccurrence,
+    SelectBlock(text_editor::Motion),
+    JumpToBracket,
+    ToggleComment,
+    ShowGoToLine,
+    GoToLineChanged(String),
+    SubmitGoToLine,
+    CloseGoToLine,
+    FindChanged(String),
+    FindReplacementChanged(String),
+    FindNext,
+    FindPrevious,
+    ReplaceOne,
+    ReplaceAll,
+    ToggleFindInSelection(bool),
+    ToggleSidebar,
+    RefreshTree,
+    TreeListed(PathBuf, Result<Vec<tree::Entry>, Error>),
+    ToggleTreeDirectory(PathBuf),
+    OpenTreeFile(PathBuf),
+    ShowQuickOpen,
+    CloseQuickOpen,
+    QuickOpenChanged(String),
+    QuickOpenKey(keyboard::KeyCode),
+    OpenQuickMatch(usize),
+    SubmitQuickOpen,
+    FilesIndexed(PathBuf, Result<Vec<PathBuf>, Error>),
+    InstanceRequested(Vec<Target>),
 }
 
 struct Editor {
-    theme: highlighter::Theme,
-    path: Option<PathBuf>,
-    content: text_editor::Content,
-    error: Option<Error>,
-    is_dirty: bool,
+    buffers: Vec<Buffer>,
+    active: usize,
+    panes: pane_grid::State<usize>,
+    focus: pane_grid::Pane,
+    toasts: Toasts,
+    error_log: ErrorLog,
+    is_error_log_open: bool,
+    is_welcome_open: bool,
+    hud: Hud,
+    recoveries: Vec<Recovery>,
+    jobs: Jobs,
+    keymap: Keymap,
+    key_prefix: Prefix,
+    is_shortcuts_open: bool,
+    swallow_tab: bool,
+    modifiers: keyboard::Modifiers,
+    selection_history: Vec<(Position, Position)>,
+    cleanup: Option<Cleanup>,
+    find: Option<Find>,
+    go_to_line: Option<String>,
+    project_search: Option<ProjectSearch>,
+    search_results: Option<SearchResults>,
+    replace_report: Option<Arc<ReplaceReport>>,
+    pending_jump: Option<(usize, usize)>,
+    jumps: Jumps,
+    is_navigating: bool,
+    filter_pattern: Option<String>,
+    filter: Option<Filter>,
+    session: Session,
+    recent: RecentFiles,
+    workspace: Option<PathBuf>,
+    tree: Option<Tree>,
+    is_sidebar_open: bool,
+    themes: Vec<CustomTheme>,
+    custom_theme: Option<String>,
+    prompt: Option<Pending>,
+    after_save: Option<Pending>,
+    settings: Overrides,
+    settings_modified: Option<SystemTime>,
+    project_config: Overrides,
+    startup_files: Vec<Target>,
+    is_new_window: bool,
+    flag_overrides: Overrides,
+    is_safe_mode: bool,
+    is_session_disabled: bool,
+    open_bar: Option<OpenBar>,
+    quick_open: Option<QuickOpen>,
+    file_index: Vec<PathBuf>,
+    is_indexing: bool,
+    overwrite: Option<SaveTarget>,
+    conflict: Option<PathBuf>,
+    disk_changes: Vec<DiskChange>,
+    language_servers: HashMap<lsp::Id, lsp::Client>,
+    problems: BTreeMap<PathBuf, Vec<Diagnostic>>,
+    is_problems_open: bool,
+    is_bookmarks_open: bool,
+    outline: Option<String>,
+    completion: Option<Popup>,
+    references: Option<References>,
+    rename: Option<String>,
+    surround: Option<Surround>,
+    open_url: Option<String>,
+    remote: Option<remote::Browser>,
+    remote_files: HashMap<PathBuf, remote::Location>,
+    rename_preview: Option<Rename>,
+    hover: Option<Hover>,
+    hover_target: Option<hover::Target>,
+    hover_pending: Option<(pane_grid::Pane, Position, Instant)>,
+    wrapped_panes: BTreeSet<pane_grid::Pane>,
+    terminal: Option<Terminal>,
+    terminals_started: u64,
+    is_blame_inline: bool,
+    whitespace: Whitespace,
+    is_blame_gutter: bool,
+    blame_pending: Option<(PathBuf, u64)>,
+    git_status: Option<git::Status>,
+    commit_message: Option<String>,
+    comparison: Option<Comparison>,
+    is_compare_open: bool,
+    is_spell_checking: bool,
+    dictionary: Option<Arc<spelling::Dictionary>>,
+    dictionaries: Vec<String>,
+    spelling: Option<spelling::Suggestions>,
+    clips: Clips,
+    clip_picker: Option<(pane_grid::Pane, Position)>,
+}
+
+#[derive(Debug, Clone)]
+struct SaveTarget {
+    path: PathBuf,
+    is_asked: bool,
+    exists: bool,
+    suggestion: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+struct DiskChange {
+    path: PathBuf,
+    content: Arc<String>,
+    encoding: &'static Encoding,
+    stamp: Stamp,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ProjectSearch {
+    search: String,
+    replacement: String,
+    is_regex: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct DialogDefaults {
+    directory: Option<PathBuf>,
+    file_name: Option<String>,
+    extension: String,
+}
+
+impl DialogDefaults {
+    fn apply(self, mut dialog: rfd::AsyncFileDialog) -> rfd::AsyncFileDialog {
+        if let Some(directory) = self.directory {
+            dialog = dialog.set_directory(directory);
+        }
+
+        if let Some(file_name) = self.file_name {
+            dialog = dialog.set_file_name(file_name);
+        }
+
+        dialog
+            .add_filter(language::name(&self.extension), &[self.extension.as_str()])
+            .add_filter("All files", &["*"])
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Pending {
+    SwitchProject(PathBuf),
+    Close,
+    Quit,
+}
+
+impl Pending {
+    fn describe(&self) -> String {
+        match self {
+            Pending::SwitchProject(root) => {
+                format!("Save changes before switching to {}?", root.display())
+            }
+            Pending::Close => String::from("Save changes before closing?"),
+            Pending::Quit => String::from("Save changes before quitting?"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Project(PathBuf);
+
+impl fmt::Display for Project {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.display())
+    }
+}
+
+// Another open buffer to compare the current one with, by its index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CompareChoice(usize, String);
+
+impl fmt::Display for CompareChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RecentChoice {
+    File(PathBuf),
+    Clear,
+}
+
+impl RecentChoice {
+    const SHOWN: usize = 10;
+
+    fn message(self) -> Messages {
+        match self {
+            RecentChoice::File(path) => Messages::OpenRecent(path),
+            RecentChoice::Clear => Messages::ClearRecent,
+        }
+    }
+}
+
+impl fmt::Display for RecentChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecentChoice::File(path) => write!(f, "{}", path.display()),
+            RecentChoice::Clear => write!(f, "Clear recent files"),
+        }
+    }
 }
 
 impl Application for Editor {
     type Message = Messages;
     type Theme = Theme;
     type Executor = executor::Default;
-    type Flags = ();
+    type Flags = Flags;
+
+    fn new(flags: Self::Flags) -> (Self, Command<Messages>) {
+        let (panes, focus) = pane_grid::State::new(0);
 
-    fn new(_flags: Self::Flags) -> (Self, Command<Messages>) {
         (
             Self {
-                theme: highlighter::Theme::SolarizedDark,
-                path: None,
-                content: text_editor::Content::new(),
-                error: None,
-                is_dirty: true,
+                buffers: vec![Buffer::new(highlighter::Theme::SolarizedDark)],
+                active: 0,
+                panes,
+                focus,
+                toasts: Toasts::default(),
+                find: None,
+                go_to_line: None,
+                project_search: None,
+                search_results: None,
+                replace_report: None,
+                pending_jump: None,
+                jumps: Jumps::default(),
+                is_navigating: false,
+                filter_pattern: None,
+                filter: None,
+                session: Session::default(),
+                recent: RecentFiles::default(),
+                workspace: None,
+                tree: None,
+                is_sidebar_open: true,
+                themes: Vec::new(),
+                custom_theme: None,
+                prompt: None,
+                after_save: None,
+                settings: Overrides::default(),
+                settings_modified: None,
+                project_config: Overrides::default(),
+                startup_files: flags.files,
+                is_new_window: flags.is_new_window,
+                flag_overrides: Overrides {
+                    theme: flags.theme,
+                    ..Overrides::default()
+                },
+                is_safe_mode: flags.is_safe_mode,
+                is_session_disabled: flags.is_session_disabled,
+                open_bar: None,
+                quick_open: None,
+                file_index: Vec::new(),
+                is_indexing: false,
+                overwrite: None,
+                conflict: None,
+                disk_changes: Vec::new(),
+                language_servers: HashMap::new(),
+                problems: BTreeMap::new(),
+                is_problems_open: false,
+                is_bookmarks_open: false,
+                outline: None,
+                completion: None,
+                references: None,
+                rename: None,
+                surround: None,
+                open_url: None,
+                remote: None,
+                remote_files: HashMap::new(),
+                rename_preview: None,
+                hover: None,
+                hover_target: None,
+                hover_pending: None,
+                wrapped_panes: BTreeSet::new(),
+                terminal: None,
+                terminals_started: 0,
+                is_blame_inline: false,
+                whitespace: Whitespace::default(),
+                is_blame_gutter: false,
+                blame_pending: None,
+                git_status: None,
+                commit_message: None,
+                comparison: None,
+                is_compare_open: false,
+                is_spell_checking: false,
+                dictionary: None,
+                dictionaries: Vec::new(),
+                spelling: None,
+                clips: Clips::default(),
+                clip_picker: None,
+                error_log: ErrorLog::default(),
+                is_error_log_open: false,
+                is_welcome_open: false,
+                hud: Hud::default(),
+                recoveries: Vec::new(),
+                jobs: Jobs::default(),
+                keymap: Keymap::default(),
+                key_prefix: Prefix::default(),
+                is_shortcuts_open: false,
+                swallow_tab: false,
+                modifiers: keyboard::Modifiers::default(),
+                selection_history: Vec::new(),
+                cleanup: None,
             },
-            Command::perform(load_file(default_file()), Messages::FileOpened),
+            Command::batch([
+                Command::perform(
+                    load_state(flags.is_safe_mode),
+                    |(session, recent, settings)| Messages::StateLoaded(session, recent, settings),
+                ),
+                Command::perform(crash::pending(), Messages::RecoveryFound),
+                if flags.is_safe_mode {
+                    Command::none()
+                } else {
+                    Command::perform(themes::load_all(), |(themes, errors)| {
+                        Messages::ThemesLoaded(themes, errors)
+                    })
+                },
+            ]),
         )
     }
 
@@ -73,122 +882,268 @@ impl Application for Editor {
     }
 
     fn update(&mut self, message: Self::Message) -> Command<Messages> {
-        match message {
-            Messages::Open => Command::perform(pick_file(), Messages::FileOpened),
-            Messages::New => {
-                self.is_dirty = true;
-                self.path = None;
-                self.content = text_editor::Content::new();
+        let started = Instant::now();
+        let kind = perf::kind(&message);
 
-                Command::none()
-            }
-            Messages::Edit(action) => {
-                self.is_dirty = self.is_dirty || action.is_edit();
-                self.error = None;
-                self.content.edit(action);
+        crash::record_message(&kind);
 
-                Command::none()
+        let command = match panic::catch_unwind(AssertUnwindSafe(|| self.handle(message))) {
+            Ok(command) => command,
+            Err(panic) => {
+                self.rescue();
+                panic::resume_unwind(panic)
             }
-            Messages::Save => {
-                let text = self.content.text();
+        };
 
-                Command::perform(save_file(self.path.clone(), text), Messages::FileSaved)
-            }
-            Messages::FileOpened(Ok((path, content))) => {
-                self.path = Some(path);
-                self.content = text_editor::Content::with(&content);
-                self.is_dirty = false;
+        self.buffer_mut().match_brackets();
+        self.buffer_mut().refresh_stats();
 
-                Command::none()
-            }
-            Messages::FileOpened(Err(err)) => {
-                self.error = Some(err);
+        // A filter shows other lines under the same numbers, so the marks
+        // wait until it is cleared.
+        let filtered = self.filter.as_ref().map(|_| self.active);
 
-                Command::none()
+        for (index, buffer) in self.buffers.iter_mut().enumerate() {
+            if !buffer.bookmarks.is_empty() && filtered != Some(index) {
+                buffer
+                    .bookmarks
+                    .follow(&buffer.content, buffer.history.revision());
             }
-            Messages::FileSaved(Ok(path)) => {
-                self.path = Some(path);
-                self.is_dirty = false;
+        }
 
-                Command::none()
+        let dictionary = self.dictionary.clone().filter(|_| self.is_spell_checking);
+
+        for &index in self.panes.iter().map(|(_, index)| index) {
+            self.buffers[index].refresh_gutter();
+            self.buffers[index].refresh_longest_line();
+            self.buffers[index].refresh_conflicts();
+            self.buffers[index].refresh_spelling(dictionary.as_deref());
+        }
+
+        // Symbols are only looked for while they are shown, and like the
+        // marks not in a filtered buffer.
+        if self.outline.is_some() && filtered.is_none() {
+            self.buffer_mut().refresh_symbols();
+        }
+
+        if self.config().indent_guides {
+            let tab_width = self.config().tab_width;
+
+            for &index in self.panes.iter().map(|(_, index)| index) {
+                self.buffers[index].refresh_guides(tab_width);
             }
-            Messages::FileSaved(Err(err)) => {
-                self.error = Some(err);
 
-                Command::none()
+            // Only the focused pane shows the block its cursor is in.
+            if let Some(&index) = self.panes.get(&self.focus) {
+                self.buffers[index].refresh_active_guide(tab_width);
             }
-            Messages::ThemeSelected(theme) => {
-                self.theme = theme;
+        }
 
-                Command::none()
+        if self.config().minimap {
+            for &index in self.panes.iter().map(|(_, index)| index) {
+                self.buffers[index].refresh_minimap();
             }
         }
+
+        let blame = self.request_blame();
+
+        // Typing keeps the rope in step, so its length is at hand without
+        // putting the text together.
+        if self.hud.is_open {
+            let buffer = self.buffer_mut();
+            let lines = buffer.content.line_count();
+            let bytes = buffer.rope().len_bytes();
+
+            self.hud.measure(lines, bytes);
+        }
+
+        self.hud.record_update(kind, started.elapsed());
+
+        Command::batch([command, blame])
+    }
+
+    fn view(&self) -> iced::Element<'_, Self::Message> {
+        let started = self.hud.frame();
+        let view = match panic::catch_unwind(AssertUnwindSafe(|| self.layout())) {
+            Ok(view) => view,
+            Err(panic) => {
+                self.rescue();
+                panic::resume_unwind(panic)
+            }
+        };
+
+        self.hud.finish_frame(started);
+
+        view
     }
 
     fn subscription(&self) -> Subscription<Self::Message> {
-        keyboard::on_key_press(|key_code, modifiers| match key_code {
-            keyboard::KeyCode::S if modifiers.command() => Some(Messages::Save),
+        let keys = keyboard::on_key_press(|key_code, modifiers| {
+            Some(Messages::KeyPressed(key_code, modifiers))
+        });
+
+        let modifiers = subscription::events_with(|event, _status| match event {
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                Some(Messages::ModifiersChanged(modifiers))
+            }
             _ => None,
-        })
-    }
+        });
 
-    fn view(&self) -> iced::Element<'_, Self::Message> {
-        let controls = row![
-            action(new_icon(), "Create a new file", Some(Messages::New)),
-            action(open_icon(), "Open file", Some(Messages::Open)),
-            action(
-                save_icon(),
-                "Save file",
-                self.is_dirty.then_some(Messages::Save)
-            ),
-            horizontal_space(Length::Fill),
-            pick_list(
-                highlighter::Theme::ALL,
-                Some(self.theme),
-                Messages::ThemeSelected
+        // The editor copies and cuts by itself, so what it put on the
+        // clipboard is read back once it has taken the keys.
+        let copies = subscription::events_with(|event, status| match event {
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::C | keyboard::KeyCode::X,
+                modifiers,
+            }) if modifiers.command() && status == event::Status::Captured => {
+                Some(Messages::Copied)
+            }
+            _ => None,
+        });
+
+        let window_events = subscription::events_with(|event, _status| match event {
+            Event::Window(window::Event::FileDropped(path)) => Some(Messages::FileDropped(path)),
+            Event::Window(window::Event::CloseRequested) => Some(Messages::Quit),
+            Event::Window(window::Event::Focused) => Some(Messages::WindowFocused),
+            _ => None,
+        });
+
+        let tick = if self.jobs.is_busy()
+            || self.key_prefix.is_pending()
+            || self.is_minimap_stale()
+            || self.hover_pending.is_some()
+        {
+            iced::time::every(Duration::from_millis(100)).map(Messages::Tick)
+        } else if self.toasts.is_expiring() {
+            iced::time::every(Duration::from_millis(500)).map(Messages::Tick)
+        } else {
+            Subscription::none()
+        };
+
+        let refresh_tree = if self.tree.is_some() && self.is_sidebar_open {
+            iced::time::every(Duration::from_secs(2)).map(|_| Messages::RefreshTree)
+        } else {
+            Subscription::none()
+        };
+
+        let config = self.config();
+
+        // An interval of zero seconds turns the timer off.
+        let autosave = match config.autosave_interval {
+            0 => Subscription::none(),
+            seconds => iced::time::every(Duration::from_secs(seconds)).map(|_| Messages::Autosave),
+        };
+
+        let swaps = match config.swap_interval {
+            0 => Subscription::none(),
+            seconds => {
+                iced::time::every(Duration::from_secs(seconds)).map(|_| Messages::WriteSwaps)
+            }
+        };
+
+        // The settings file is polled, which keeps working when an editor
+        // replaces the file on save instead of writing to it.
+        let settings = if self.is_safe_mode {
+            Subscription::none()
+        } else {
+            iced::time::every(Duration::from_secs(2)).map(|_| Messages::CheckSettings)
+        };
+
+        let focus_loss = if config.autosave_on_focus_loss {
+            subscription::events_with(|event, _status| match event {
+                Event::Window(window::Event::Unfocused) => Some(Messages::Autosave),
+                _ => None,
+            })
+        } else {
+            Subscription::none()
+        };
+
+        let open_files = self
+            .buffers
+            .iter()
+            .filter_map(|buffer| buffer.path.clone())
+            .collect::<BTreeSet<_>>();
+
+        let files = if open_files.is_empty() {
+            Subscription::none()
+        } else {
+            watcher::watch(open_files).map(Messages::FileTouched)
+        };
+
+        let language_servers = if config.language_servers && !self.is_safe_mode {
+            Subscription::batch(
+                self.buffers
+                    .iter()
+                    .filter_map(|buffer| self.language_server(buffer))
+                    .collect::<BTreeMap<_, _>>()
+                    .into_iter()
+                    .map(|((_, root), server)| lsp::connect(server, root))
+                    .map(|subscription| subscription.map(Messages::LanguageServer)),
             )
-        ]
-        .spacing(10);
+        } else {
+            Subscription::none()
+        };
 
-        let input = text_editor(&self.content)
-            .on_edit(Messages::Edit)
-            .highlight::<Highlighter>(
-                highlighter::Settings {
-                    theme: self.theme,
-                    extension: self
-                        .path
-                        .as_ref()
-                        .and_then(|path| path.extension()?.to_str())
-                        .unwrap_or("rs")
-                        .to_string(),
+        // Typed characters are only listened for while the shell takes them.
+        let terminal = match self.terminal.as_ref() {
+            Some(terminal) => Subscription::batch([
+                terminal::connect(terminal.id, terminal.directory.clone()).map(Messages::Terminal),
+                if terminal.is_focused {
+                    subscription::events_with(|event, _status| match event {
+                        Event::Keyboard(keyboard::Event::CharacterReceived(c)) => {
+                            Some(Messages::TerminalTyped(c))
+                        }
+                        _ => None,
+                    })
+                } else {
+                    Subscription::none()
                 },
-                |highlight, _theme| highlight.to_format(),
-            );
-        let status_bar = {
-            let status = if let Some(Error::IOFailed(err)) = self.error.as_ref() {
-                text(err.to_string())
-            } else {
-                match self.path.as_deref().and_then(Path::to_str) {
-                    Some(path) => text(path).size(14),
-                    None => text("New file"),
-                }
-            };
+            ]),
+            None => Subscription::none(),
+        };
 
-            let position = {
-                let (line, column) = self.content.cursor_position();
-                text(format!("{}:{}", line + 1, column + 1))
-            };
+        let documents = if self.has_unsynced_documents() {
+            iced::time::every(Duration::from_millis(500)).map(|_| Messages::SyncDocuments)
+        } else {
+            Subscription::none()
+        };
 
-            row![status, horizontal_space(Length::Fill), position]
+        // An editor started with --new-window leaves the files of later
+        // launches to the one that was there first.
+        let instance = if self.is_new_window {
+            Subscription::none()
+        } else {
+            instance::listen().map(Messages::InstanceRequested)
         };
 
-        container(column![controls, input, status_bar].spacing(10))
-            .padding(10)
-            .into()
+        Subscription::batch([
+            keys,
+            modifiers,
+            copies,
+            window_events,
+            files,
+            language_servers,
+            terminal,
+            documents,
+            tick,
+            refresh_tree,
+            autosave,
+            swaps,
+            focus_loss,
+            settings,
+            instance,
+        ])
     }
 
     fn theme(&self) -> iced::Theme {
-        if self.theme.is_dark() {
+        if let Some(theme) = self
+            .themes
+            .iter()
+            .find(|theme| Some(&theme.name) == self.custom_theme.as_ref())
+        {
+            return iced::Theme::custom(theme.palette);
+        }
+
+        if self.buffer().theme.is_dark() {
             iced::Theme::Dark
         } else {
             iced::Theme::Light
@@ -196,86 +1151,6907 @@ impl Application for Editor {
     }
 }
 
-fn action<'a>(
-    content: Element<'a, Messages>,
-    label: &str,
-    on_press: Option<Messages>,
-) -> Element<'a, Messages> {
-    let is_disabled = on_press.is_none();
+// Advance and line height of the editor's monospace font at the default 16px
+// text size, and the padding the editor adds on either side of its text.
+const CHAR_WIDTH: f32 = 9.6;
+const LINE_HEIGHT: f32 = 20.8;
+const EDITOR_PADDING: f32 = 10.0;
 
-    tooltip(
-        button(container(content).width(30).center_x())
-            .on_press_maybe(on_press)
-            .padding([5, 10])
-            .style(if is_disabled {
-                theme::Button::Secondary
-            } else {
-                theme::Button::Primary
-            }),
-        label,
-        tooltip::Position::FollowCursor,
-    )
-    .style(theme::Container::Box)
+// Characters that start a member or path, after which completions are
+// asked for before any of the member's name is typed.
+const COMPLETION_TRIGGERS: [char; 2] = ['.', ':'];
+
+// How long the pointer rests on a word before its hover is asked for.
+const HOVER_DELAY: Duration = Duration::from_millis(500);
+
+// Lines kept visible above the target of a jump.
+const GO_TO_CONTEXT: usize = 5;
+
+fn go_to_line_id() -> text_input::Id {
+    text_input::Id::new("go-to-line")
+}
+
+fn go_to_line(input: &str) -> Element<'_, Messages> {
+    row![
+        text_input("Line[:column]", input)
+            .id(go_to_line_id())
+            .on_input(Messages::GoToLineChanged)
+            .on_submit(Messages::SubmitGoToLine),
+        button("Go").on_press(Messages::SubmitGoToLine),
+        button("Close").on_press(Messages::CloseGoToLine),
+    ]
+    .spacing(10)
     .into()
 }
 
-fn new_icon<'a>() -> Element<'a, Messages> {
-    icon('\u{E800}')
+fn rename_id() -> text_input::Id {
+    text_input::Id::new("rename")
 }
 
-fn open_icon<'a>() -> Element<'a, Messages> {
-    icon('\u{E802}')
+fn rename_bar(name: &str) -> Element<'_, Messages> {
+    row![
+        text("Rename to"),
+        text_input("New name", name)
+            .id(rename_id())
+            .on_input(Messages::RenameChanged)
+            .on_submit(Messages::SubmitRename),
+        button("Preview").on_press(Messages::SubmitRename),
+        button("Close").on_press(Messages::CloseRename),
+    ]
+    .spacing(10)
+    .into()
 }
 
-fn save_icon<'a>() -> Element<'a, Messages> {
-    icon('\u{E801}')
+fn compare_bar(title: &str, choices: Vec<CompareChoice>) -> Element<'static, Messages> {
+    row![
+        text(format!("Compare {title} with")),
+        pick_list(choices, None, Messages::CompareWithBuffer).placeholder("An open file"),
+        button("Pick a file…").on_press(Messages::CompareWithFile),
+        horizontal_space(Length::Fill),
+        button("Close").on_press(Messages::ToggleCompare),
+    ]
+    .spacing(10)
+    .into()
 }
 
-fn icon<'a>(codepoint: char) -> Element<'a, Messages> {
-    const ICON_FONT: Font = Font::with_name("editor-icons");
+fn commit_message_id() -> text_input::Id {
+    text_input::Id::new("commit-message")
+}
 
-    text(codepoint).font(ICON_FONT).into()
+fn source_control<'a>(
+    status: Option<&git::Status>,
+    message: &'a str,
+    can_stage: bool,
+) -> Element<'a, Messages> {
+    let summary = match status {
+        Some(status) => format!(
+            "On {}: {} staged, {} changed",
+            status.branch, status.staged, status.changed
+        ),
+        None => String::from("Not in a git repository"),
+    };
+
+    let stage = button("Stage current file");
+    let stage = if can_stage && status.is_some() {
+        stage.on_press(Messages::StageFile)
+    } else {
+        stage
+    };
+
+    column![
+        row![
+            text(summary),
+            horizontal_space(Length::Fill),
+            stage,
+            button("Close").on_press(Messages::ToggleSourceControl),
+        ]
+        .spacing(10),
+        row![
+            text_input("Commit message", message)
+                .id(commit_message_id())
+                .on_input(Messages::CommitMessageChanged)
+                .on_submit(Messages::Commit),
+            button("Commit").on_press(Messages::Commit),
+        ]
+        .spacing(10),
+    ]
+    .spacing(5)
+    .into()
 }
 
-fn default_file() -> PathBuf {
-    format!("{}/src/main.rs", env!("CARGO_MANIFEST_DIR")).into()
+fn open_bar_id() -> text_input::Id {
+    text_input::Id::new("open-bar")
 }
 
-async fn pick_file() -> Result<(PathBuf, Arc<String>), Error> {
-    let handle = rfd::AsyncFileDialog::new()
-        .set_title("Choose a text file")
-        .pick_file()
-        .await
-        .ok_or(Error::DialogClosed)?;
+fn open_bar(bar: &OpenBar) -> Element<'_, Messages> {
+    const MAX_CANDIDATES: usize = 10;
 
-    load_file(handle.path().to_path_buf()).await
+    let candidates = bar.candidates.iter().enumerate().take(MAX_CANDIDATES).fold(
+        column![].spacing(2),
+        |candidates, (index, candidate)| {
+            candidates.push(
+                button(text(candidate.label()).size(14))
+                    .style(if index == bar.selected {
+                        theme::Button::Primary
+                    } else {
+                        theme::Button::Text
+                    })
+                    .on_press(Messages::OpenCandidate(index)),
+            )
+        },
+    );
+
+    column![
+        row![
+            text_input("Path to open", &bar.input)
+                .id(open_bar_id())
+                .on_input(Messages::OpenPathChanged)
+                .on_submit(Messages::SubmitOpenPath),
+            checkbox("Hidden files", bar.show_hidden, Messages::ToggleHiddenFiles),
+            button("Close").on_press(Messages::CloseOpenBar),
+        ]
+        .spacing(10),
+        candidates,
+    ]
+    .spacing(5)
+    .into()
 }
 
-async fn load_file(path: PathBuf) -> Result<(PathBuf, Arc<String>), Error> {
-    let content = tokio::fs::read_to_string(&path)
-        .await
-        .map(Arc::new)
-        .map_err(|err| err.kind())
-        .map_err(Error::IOFailed)?;
+fn welcome(recent: &RecentFiles) -> Element<'_, Messages> {
+    const SHOWN: usize = 10;
+
+    let files = recent.files().iter().take(SHOWN).fold(
+        column![text("Recent files")].spacing(5),
+        |files, file| {
+            files.push(
+                button(text(file.path.display()).size(14))
+                    .style(theme::Button::Text)
+                    .on_press(Messages::OpenRecent(file.path.clone())),
+            )
+        },
+    );
+
+    let folders = recent.folders().iter().take(SHOWN).fold(
+        column![text("Recent folders")].spacing(5),
+        |folders, root| {
+            folders.push(
+                button(text(root.display()).size(14))
+                    .style(theme::Button::Text)
+                    .on_press(Messages::SwitchProject(root.clone())),
+            )
+        },
+    );
 
-    Ok((path, content))
+    container(
+        column![
+            text("Welcome").size(24),
+            row![
+                button("New file").on_press(Messages::New),
+                button("Open file…").on_press(Messages::Open),
+                button("Open folder…").on_press(Messages::OpenFolder),
+            ]
+            .spacing(10),
+            scrollable(row![files, folders].spacing(40)),
+        ]
+        .spacing(20),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .padding(20)
+    .into()
 }
 
-async fn save_file(path: Option<PathBuf>, text: String) -> Result<PathBuf, Error> {
-    let path = if let Some(path) = path {
-        path
-    } else {
-        rfd::AsyncFileDialog::new()
-            .set_title("Choose a file name.")
-            .save_file()
-            .await
-            .ok_or(Error::DialogClosed)
-            .map(|handle| handle.path().to_path_buf())?
-    };
+fn tabs(buffers: &[Buffer], active: usize) -> Element<'_, Messages> {
+    buffers
+        .iter()
+        .enumerate()
+        .fold(row![].spacing(5), |tabs, (index, buffer)| {
+            tabs.push(row![
+                button(text(buffer.title()).size(14))
+                    .style(if index == active {
+                        theme::Button::Primary
+                    } else {
+                        theme::Button::Secondary
+                    })
+                    .on_press(Messages::SelectTab(index)),
+                button(text("×").size(14))
+                    .style(theme::Button::Text)
+                    .on_press(Messages::CloseTab(index)),
+            ])
+        })
+        .into()
+}
 
-    tokio::fs::write(&path, text)
-        .await
-        .map_err(|err| Error::IOFailed(err.kind()))?;
+fn search_results(results: &SearchResults) -> Element<'_, Messages> {
+    let files =
+        results
+            .files
+            .iter()
+            .enumerate()
+            .fold(column![].spacing(5), |files, (index, file)| {
+                let header = button(
+                    text(format!(
+                        "{} {} ({})",
+                        if file.is_collapsed { '▸' } else { '▾' },
+                        file.path.display(),
+                        file.hits.len()
+                    ))
+                    .size(14),
+                )
+                .style(theme::Button::Text)
+                .on_press(Messages::ToggleSearchFile(index));
+
+                if file.is_collapsed {
+                    return files.push(header);
+                }
+
+                let hits = file.hits.iter().fold(column![].spacing(2), |hits, hit| {
+                    let context = hit
+                        .context
+                        .iter()
+                        .fold(column![], |context, (line, content)| {
+                            let marker = if *line == hit.line { '>' } else { ' ' };
+
+                            context
+                                .push(text(format!("{marker}{:>6}: {content}", line + 1)).size(13))
+                        });
+
+                    hits.push(button(context).style(theme::Button::Text).on_press(
+                        Messages::OpenSearchHit(file.path.clone(), hit.line, hit.column),
+                    ))
+                });
 
-    Ok(path)
+                files.push(header).push(hits)
+            });
+
+    column![
+        row![
+            text(results.summary()),
+            horizontal_space(Length::Fill),
+            button("Copy results").on_press(Messages::CopySearchResults),
+            button("Dismiss").on_press(Messages::DismissSearchResults),
+        ]
+        .spacing(10),
+        scrollable(files).height(200),
+    ]
+    .spacing(5)
+    .into()
+}
+
+fn problems(problems: &BTreeMap<PathBuf, Vec<Diagnostic>>) -> Element<'_, Messages> {
+    let count = problems.values().map(Vec::len).sum::<usize>();
+
+    let entries = problems
+        .iter()
+        .flat_map(|(path, diagnostics)| {
+            diagnostics.iter().map(move |diagnostic| (path, diagnostic))
+        })
+        .fold(column![].spacing(2), |entries, (path, diagnostic)| {
+            let (line, column) = diagnostic.start;
+            let source = diagnostic
+                .source
+                .as_ref()
+                .map_or(String::new(), |source| format!(" [{source}]"));
+
+            entries.push(
+                button(
+                    text(format!(
+                        "{} {}:{}:{} {}{source}",
+                        diagnostic.severity.icon(),
+                        path.display(),
+                        line + 1,
+                        column + 1,
+                        diagnostic.message
+                    ))
+                    .size(13),
+                )
+                .style(theme::Button::Text)
+                .on_press(Messages::OpenLocation(path.clone(), diagnostic.start)),
+            )
+        });
+
+    column![
+        row![
+            text(format!("{count} problem(s)")),
+            horizontal_space(Length::Fill),
+            button("Close").on_press(Messages::ToggleProblems),
+        ]
+        .spacing(10),
+        scrollable(entries).height(160),
+    ]
+    .spacing(5)
+    .into()
+}
+
+fn bookmarks(buffers: &[Buffer]) -> Element<'_, Messages> {
+    let count = buffers
+        .iter()
+        .map(|buffer| buffer.bookmarks.lines().len())
+        .sum::<usize>();
+
+    let entries = buffers
+        .iter()
+        .enumerate()
+        .flat_map(|(index, buffer)| {
+            buffer
+                .bookmarks
+                .lines()
+                .iter()
+                .map(move |&line| (index, buffer, line))
+        })
+        .fold(column![].spacing(2), |entries, (index, buffer, line)| {
+            let preview = buffer
+                .content
+                .line(line)
+                .map_or(String::new(), |text| text.trim().to_string());
+
+            entries.push(
+                button(text(format!("{}:{} {preview}", buffer.title(), line + 1)).size(13))
+                    .style(theme::Button::Text)
+                    .on_press(Messages::JumpToBookmark(index, line)),
+            )
+        });
+
+    column![
+        row![
+            text(format!("{count} bookmark(s)")),
+            horizontal_space(Length::Fill),
+            button("Close").on_press(Messages::ToggleBookmarks),
+        ]
+        .spacing(10),
+        scrollable(entries).height(160),
+    ]
+    .spacing(5)
+    .into()
+}
+
+// Scrolling a fresh content sets its offset exactly, and the cursor placed
+// before it stays put as long as it remains within the restored viewport.
+fn restore_view(content: &mut text_editor::Content, view: ViewState) {
+    let (line, column) = view.cursor;
+
+    move_cursor(content, line, column);
+    content.edit(text_editor::Action::Scroll {
+        lines: view.scroll as i32,
+    });
+}
+
+fn move_cursor(content: &mut text_editor::Content, line: usize, column: usize) {
+    content.edit(text_editor::Action::Move(
+        text_editor::Motion::DocumentStart,
+    ));
+
+    for _ in 0..line {
+        content.edit(text_editor::Action::Move(text_editor::Motion::Down));
+    }
+
+    for _ in 0..column {
+        content.edit(text_editor::Action::Move(text_editor::Motion::Right));
+    }
+}
+
+impl Editor {
+    fn handle(&mut self, message: Messages) -> Command<Messages> {
+        match message {
+            Messages::Open => Command::perform(
+                pick_file(DialogDefaults {
+                    file_name: None,
+                    ..self.dialog_defaults()
+                }),
+                Messages::FileOpened,
+            ),
+            Messages::Close => self.guard(Pending::Close),
+            Messages::Quit => self.guard(Pending::Quit),
+            Messages::Exit => window::close(),
+            Messages::Indent if self.completion.is_some() => {
+                self.accept_completion(None);
+                self.swallow_tab = true;
+
+                Command::none()
+            }
+            Messages::Indent => {
+                self.swallow_tab = self.indent_lines(false);
+
+                Command::none()
+            }
+            Messages::Dedent => {
+                self.swallow_tab = self.indent_lines(true);
+
+                Command::none()
+            }
+            Messages::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+
+                Command::none()
+            }
+            Messages::ExpandSelection => {
+                let mut document = self.document();
+                let cursor = document.cursor();
+                let current = document.selection().unwrap_or((cursor, cursor));
+
+                if let Some(expanded) = editing::expanded(&document.text(), current) {
+                    document.select(expanded);
+                    self.selection_history.push(current);
+                }
+
+                Command::none()
+            }
+            Messages::ShrinkSelection => {
+                if let Some(previous) = self.selection_history.pop() {
+                    self.document().select(previous);
+                }
+
+                Command::none()
+            }
+            Messages::MoveSubword(direction) | Messages::SelectSubword(direction) => {
+                let is_selecting = matches!(message, Messages::SelectSubword(_));
+
+                editing::move_subword(&mut self.document(), direction, is_selecting);
+
+                Command::none()
+            }
+            Messages::DeleteSubword(direction) => {
+                self.record(Change::Delete);
+                editing::delete_subword(&mut self.document(), direction);
+
+                Command::none()
+            }
+            Messages::DeleteWord(direction) => {
+                self.record(Change::Delete);
+                editing::delete_word(&mut self.document(), direction);
+
+                Command::none()
+            }
+            Messages::DeleteToLineEnd => {
+                self.record(Change::Other);
+                editing::delete_to_line_end(&mut self.document());
+
+                Command::none()
+            }
+            Messages::JoinLines => {
+                let comment = language::line_comment(&self.extension());
+
+                let before = self.snapshot();
+
+                if editing::join_lines(&mut self.document(), comment) {
+                    self.buffer_mut().history.record(Change::Other, || before);
+                }
+
+                Command::none()
+            }
+            Messages::DuplicateLines => {
+                let before = self.snapshot();
+
+                editing::duplicate_lines(&mut self.document());
+                self.buffer_mut().history.record(Change::Other, || before);
+
+                Command::none()
+            }
+            Messages::MoveLines(direction) => {
+                let before = self.snapshot();
+
+                if editing::move_lines(&mut self.document(), direction) {
+                    self.buffer_mut().history.record(Change::Other, || before);
+                }
+
+                Command::none()
+            }
+            Messages::DeleteLines => {
+                let before = self.snapshot();
+
+                editing::delete_lines(&mut self.document());
+                self.buffer_mut().history.record(Change::Other, || before);
+
+                Command::none()
+            }
+            Messages::ToggleShortcuts => {
+                self.is_shortcuts_open = !self.is_shortcuts_open;
+
+                Command::none()
+            }
+            Messages::KeyPressed(key_code, modifiers) => {
+                let chord = Chord::new(key_code, modifiers);
+
+                if chord.is_modifier() {
+                    return Command::none();
+                }
+
+                if let Some(terminal) = self
+                    .terminal
+                    .as_ref()
+                    .filter(|terminal| terminal.is_focused)
+                {
+                    if let Some(bytes) = terminal::key(key_code, modifiers) {
+                        terminal.send(bytes);
+
+                        return Command::none();
+                    }
+                }
+
+                let is_open_bar_key = matches!(
+                    key_code,
+                    keyboard::KeyCode::Tab
+                        | keyboard::KeyCode::Up
+                        | keyboard::KeyCode::Down
+                        | keyboard::KeyCode::Escape
+                );
+
+                if self.quick_open.is_some() && is_open_bar_key && !self.key_prefix.is_pending() {
+                    return self.update(Messages::QuickOpenKey(key_code));
+                }
+
+                if self.open_bar.is_some() && is_open_bar_key && !self.key_prefix.is_pending() {
+                    return self.update(Messages::OpenBarKey(key_code));
+                }
+
+                let was_pending = self.key_prefix.is_pending();
+                let sequence = self.key_prefix.extend(chord);
+
+                self.key_prefix.clear();
+
+                match self.keymap.resolve(&sequence) {
+                    Resolution::Action(action) => return self.update(action.message()),
+                    Resolution::Prefix => {
+                        self.key_prefix.set(sequence);
+
+                        return Command::none();
+                    }
+                    Resolution::Unbound if was_pending => return Command::none(),
+                    Resolution::Unbound => {}
+                }
+
+                if key_code == keyboard::KeyCode::Escape {
+                    self.completion = None;
+                    self.buffer_mut().cursors.clear();
+                    self.is_shortcuts_open = false;
+                    self.find = None;
+                    self.go_to_line = None;
+                    self.rename = None;
+                    self.surround = None;
+                    self.remote = None;
+                    self.open_url = None;
+                    self.hover = None;
+                    self.spelling = None;
+                    self.clip_picker = None;
+                    self.commit_message = None;
+                }
+
+                Command::none()
+            }
+            Messages::New => {
+                self.push_buffer(Buffer::new(self.buffer().theme));
+
+                self.save_state()
+            }
+            Messages::Edit(action) => {
+                self.hover = None;
+                self.spelling = None;
+                self.clip_picker = None;
+
+                let is_tab = matches!(
+                    action,
+                    text_editor::Action::Edit(text_editor::Edit::Insert('\t'))
+                );
+                let is_space = matches!(
+                    action,
+                    text_editor::Action::Edit(text_editor::Edit::Insert(' '))
+                );
+
+                if std::mem::take(&mut self.swallow_tab) && is_tab {
+                    return Command::none();
+                }
+
+                // Ctrl+Space still reaches the editor as a space.
+                if is_space && self.modifiers.command() {
+                    return Command::none();
+                }
+
+                if self.navigate_completion(&action) {
+                    return Command::none();
+                }
+
+                let has_selection = self.document().selection().is_some();
+
+                // Cutting a block takes the columns of every line along, not
+                // only what the editor put on the clipboard itself. The cut
+                // arrives as Ctrl+Delete, so it is picked out before keys.
+                let is_cut = matches!(action, text_editor::Action::Edit(text_editor::Edit::Delete))
+                    && self.modifiers.command()
+                    && has_selection;
+                let cut = is_cut.then(|| self.copy_block()).flatten();
+
+                // Deleting with a selection takes only the selection away,
+                // which is the editor's to do.
+                let is_deleting_selection = matches!(
+                    action,
+                    text_editor::Action::Edit(
+                        text_editor::Edit::Backspace | text_editor::Edit::Delete
+                    )
+                ) && has_selection;
+
+                if let Some(key_code) = keymap::key_of(&action).filter(|_| !is_deleting_selection) {
+                    let chord = Chord::new(key_code, self.modifiers);
+
+                    if let Resolution::Action(action) = self.keymap.resolve(&[chord]) {
+                        return self.update(action.message());
+                    }
+                }
+
+                if action.is_edit() && self.buffer().is_read_only {
+                    self.toasts.push_with_action(
+                        Severity::Info,
+                        "This buffer is read-only",
+                        Some((String::from("Make editable"), Messages::ToggleReadOnly)),
+                    );
+
+                    return cut.unwrap_or_else(Command::none);
+                }
+
+                self.selection_history.clear();
+
+                if self.edit_at_cursors(&action) {
+                    return cut.unwrap_or_else(Command::none);
+                }
+
+                if let text_editor::Action::Edit(text_editor::Edit::Insert(c)) = action {
+                    if self.auto_close(c) {
+                        self.count_matches();
+
+                        return Command::none();
+                    }
+                }
+
+                if let text_editor::Action::Move(text_editor::Motion::Home)
+                | text_editor::Action::Select(text_editor::Motion::Home) = action
+                {
+                    let is_selecting = matches!(action, text_editor::Action::Select(_));
+
+                    editing::smart_home(&mut self.document(), is_selecting);
+
+                    return Command::none();
+                }
+
+                let span = (action.is_edit() && self.filter.is_none()).then(|| {
+                    let buffer = self.buffer();
+
+                    Span::before(&buffer.content, buffer.history.revision())
+                });
+
+                match &action {
+                    text_editor::Action::Edit(edit) => self.record(match edit {
+                        text_editor::Edit::Insert(_) => Change::Insert,
+                        text_editor::Edit::Backspace | text_editor::Edit::Delete => Change::Delete,
+                        _ => Change::Other,
+                    }),
+                    text_editor::Action::Scroll { .. } => {}
+                    _ => self.buffer_mut().history.seal(),
+                }
+
+                let indentation = match action {
+                    text_editor::Action::Edit(text_editor::Edit::Enter)
+                        if self.config().auto_indent =>
+                    {
+                        Some(self.new_line_indentation())
+                    }
+                    _ => None,
+                };
+
+                let buffer = &mut self.buffers[self.active];
+
+                if let text_editor::Action::Scroll { lines } = action {
+                    buffer.scroll = buffer.scroll.saturating_add_signed(lines as isize);
+                }
+
+                let is_edit = action.is_edit();
+
+                match self.filter.as_mut() {
+                    Some(filter) => filter.edit(&mut buffer.content, action),
+                    None => buffer.content.edit(action),
+                }
+
+                if let Some(indentation) = indentation {
+                    self.document().insert(&indentation);
+                }
+
+                if let Some(span) = span {
+                    let buffer = self.buffer_mut();
+                    let revision = buffer.history.revision();
+
+                    buffer.stats.splice(&buffer.content, span, revision);
+                    buffer.text.splice(&buffer.content, span, revision);
+                }
+
+                if is_edit {
+                    self.cleanup = None;
+                    self.count_matches();
+                }
+
+                self.follow_completion(&action);
+
+                let line = self.cursor().0;
+                let buffer = self.buffer_mut();
+
+                buffer.scroll = buffer.scroll.min(line);
+
+                Command::none()
+            }
+            Messages::Save => match self.buffer().path.clone() {
+                Some(path) => self.write_to(path),
+                None => self.update(Messages::SaveAs),
+            },
+            Messages::SaveAs => Command::perform(
+                pick_save_target(self.dialog_defaults()),
+                Messages::SaveTargetPicked,
+            ),
+            Messages::SaveTargetPicked(Ok((path, is_asked))) => Command::perform(
+                check_save_target(path, is_asked),
+                Messages::SaveTargetChecked,
+            ),
+            // The dialog asks before picking a file that exists, so only one
+            // that came to be since then is asked about here.
+            Messages::SaveTargetChecked(Ok(target)) => {
+                if target.exists
+                    && !target.is_asked
+                    && self.buffer().path.as_ref() != Some(&target.path)
+                {
+                    self.overwrite = Some(target);
+
+                    Command::none()
+                } else {
+                    self.write_to(target.path)
+                }
+            }
+            Messages::SaveTargetPicked(Err(err)) | Messages::SaveTargetChecked(Err(err)) => {
+                self.update(Messages::FileSaved(Err(err)))
+            }
+            Messages::ConfirmOverwrite => match self.overwrite.take() {
+                Some(target) => self.write_to(target.path),
+                None => Command::none(),
+            },
+            Messages::SaveAsSuggestion => match self.overwrite.take() {
+                Some(target) => self.write_to(target.suggestion),
+                None => Command::none(),
+            },
+            Messages::CancelOverwrite => {
+                self.overwrite = None;
+                self.after_save = None;
+
+                Command::none()
+            }
+            Messages::ExportHtml => {
+                let defaults = DialogDefaults {
+                    file_name: Some(format!("{}.html", self.buffer().title())),
+                    extension: String::from("html"),
+                    ..self.dialog_defaults()
+                };
+
+                Command::perform(pick_save_path(defaults), Messages::ExportTargetPicked)
+            }
+            Messages::ExportTargetPicked(Ok(path)) => {
+                let buffer = self.buffer();
+                let palette = self.theme().palette();
+                let html = export::html(
+                    &buffer.title(),
+                    &self.text(),
+                    highlighter::Settings {
+                        theme: buffer.theme,
+                        extension: buffer.extension(),
+                    },
+                    (palette.background, palette.text),
+                    self.config().tab_width,
+                );
+
+                Command::perform(write_export(path, html), Messages::Exported)
+            }
+            Messages::Exported(Ok(path)) => {
+                self.toasts
+                    .push(Severity::Success, format!("Exported to {}", path.display()));
+
+                Command::none()
+            }
+            Messages::Print => {
+                let config = self.config();
+                let title = match self.buffer().path.as_deref() {
+                    Some(path) => path.display().to_string(),
+                    None => self.buffer().title(),
+                };
+                let pdf = print::pdf(
+                    &title,
+                    &self.text(),
+                    print::Options {
+                        line_numbers: config.print_line_numbers,
+                        header: config.print_header,
+                        tab_width: config.tab_width,
+                    },
+                );
+
+                Command::perform(print::open(self.buffer().title(), pdf), Messages::Printed)
+            }
+            Messages::Printed(Ok(path)) => {
+                self.toasts.push(
+                    Severity::Info,
+                    format!("Opened {} for printing", path.display()),
+                );
+
+                Command::none()
+            }
+            Messages::Printed(Err(err)) => {
+                self.notify_error(err, Some(Messages::Print));
+
+                Command::none()
+            }
+            Messages::ExportTargetPicked(Err(err)) | Messages::Exported(Err(err)) => {
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::DismissToast(id) => {
+                self.toasts.dismiss(id);
+
+                Command::none()
+            }
+            Messages::ToastAction(id) => match self.toasts.take_action(id) {
+                Some(message) => self.update(message),
+                None => Command::none(),
+            },
+            Messages::Tick(now) => {
+                self.toasts.expire(now);
+
+                if self.key_prefix.is_expired(now) {
+                    self.key_prefix.clear();
+                }
+
+                if let Some((pane, position, since)) = self.hover_pending {
+                    if now.duration_since(since) >= HOVER_DELAY {
+                        self.hover_pending = None;
+                        self.request_hover(pane, position, false);
+                    }
+                }
+
+                Command::none()
+            }
+            Messages::Autosave => self.autosave(),
+            Messages::Autosaved(revision, format, Ok((path, stamp))) => {
+                if let Some(index) = self.position(&path) {
+                    self.buffers[index].stamp = Some(stamp);
+                    self.buffers[index].mark_saved_at(revision, format);
+                }
+
+                Command::none()
+            }
+            Messages::Autosaved(_, _, Err(Error::ChangedOnDisk(path))) => {
+                self.conflict = Some(path);
+
+                Command::none()
+            }
+            Messages::Autosaved(_, _, Err(err)) => {
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::ToggleErrorLog => {
+                self.is_error_log_open = !self.is_error_log_open;
+
+                Command::none()
+            }
+            Messages::ToggleProblems => {
+                self.is_problems_open = !self.is_problems_open;
+
+                Command::none()
+            }
+            Messages::ToggleBookmark => {
+                self.clear_filter();
+                self.filter_pattern = None;
+
+                let (line, _) = self.cursor();
+                let buffer = self.buffer_mut();
+
+                buffer
+                    .bookmarks
+                    .toggle(line, &buffer.content, buffer.history.revision());
+
+                Command::none()
+            }
+            Messages::NextBookmark => {
+                self.step_bookmark(true);
+
+                Command::none()
+            }
+            Messages::PreviousBookmark => {
+                self.step_bookmark(false);
+
+                Command::none()
+            }
+            Messages::ToggleBookmarks => {
+                self.is_bookmarks_open = !self.is_bookmarks_open;
+
+                Command::none()
+            }
+            Messages::JumpToBookmark(index, line) => {
+                self.remember_jump();
+                self.activate(index);
+                self.reveal_line(line);
+
+                Command::none()
+            }
+            Messages::ToggleOutline => match self.outline.take() {
+                Some(_) => Command::none(),
+                None => {
+                    self.outline = Some(String::new());
+
+                    text_input::focus(outline::input_id())
+                }
+            },
+            Messages::OutlineFilterChanged(query) => {
+                self.outline = Some(query);
+
+                Command::none()
+            }
+            Messages::JumpToSymbol(line) => {
+                self.clear_filter();
+                self.filter_pattern = None;
+                self.remember_jump();
+                self.reveal_line(line);
+
+                Command::none()
+            }
+            Messages::GoBack => self.navigate(false),
+            Messages::GoForward => self.navigate(true),
+            Messages::OpenLocation(path, (line, units)) => {
+                let column = self
+                    .position(&path)
+                    .and_then(|index| self.buffers[index].content.line(line))
+                    .map_or(units, |text| lsp::column(&text, units));
+
+                self.update(Messages::OpenSearchHit(path, line, column))
+            }
+            Messages::LanguageServer(lsp::Event::Ready(id, client)) => {
+                tracing::info!(server = id.0, root = %id.1.display(), "language server ready");
+
+                self.language_servers.insert(id, client);
+                self.sync_documents();
+
+                Command::none()
+            }
+            Messages::LanguageServer(lsp::Event::Diagnostics(path, diagnostics)) => {
+                if diagnostics.is_empty() {
+                    self.problems.remove(&path);
+                } else {
+                    self.problems.insert(path, diagnostics);
+                }
+
+                Command::none()
+            }
+            Messages::LanguageServer(lsp::Event::Failed(id, err)) => {
+                self.toasts
+                    .push(Severity::Warning, format!("Could not run {}: {err}", id.0));
+                self.release_language_server(&id);
+
+                Command::none()
+            }
+            Messages::SyncDocuments => {
+                self.sync_documents();
+
+                Command::none()
+            }
+            Messages::LanguageServer(lsp::Event::Responded(request, response)) => {
+                self.responded(request, response)
+            }
+            Messages::Complete => {
+                if !self.request_completion() {
+                    let (start, word) = self.word_before_cursor();
+
+                    self.complete_words(start, &word);
+
+                    if self.completion.is_none() {
+                        self.toasts.push(Severity::Info, "No completions");
+                    }
+                }
+
+                Command::none()
+            }
+            Messages::AcceptCompletion(index) => {
+                self.accept_completion(Some(index));
+
+                Command::none()
+            }
+            Messages::GoToDefinition => {
+                self.request_at_cursor(lsp::Request::Definition);
+
+                Command::none()
+            }
+            Messages::FindReferences => {
+                self.request_at_cursor(lsp::Request::References);
+
+                Command::none()
+            }
+            Messages::ReferencesFound(references) => {
+                self.references = Some(references);
+
+                Command::none()
+            }
+            Messages::DismissReferences => {
+                self.references = None;
+
+                Command::none()
+            }
+            Messages::ShowRename => {
+                let (line, column) = self.cursor();
+                let text = self.document().line(line);
+                let start = completion::word_start(&text, column);
+
+                self.rename = Some(
+                    text.chars()
+                        .skip(start)
+                        .take_while(|&c| completion::is_word(c))
+                        .collect(),
+                );
+
+                Command::batch([
+                    text_input::focus(rename_id()),
+                    text_input::select_all(rename_id()),
+                ])
+            }
+            Messages::RenameChanged(name) => {
+                self.rename = Some(name);
+
+                Command::none()
+            }
+            Messages::SubmitRename => {
+                let Some(name) = self.rename.clone() else {
+                    return Command::none();
+                };
+
+                if name.trim().is_empty() {
+                    return Command::none();
+                }
+
+                self.rename = None;
+                self.request_at_cursor(|path, position| lsp::Request::Rename(path, position, name));
+
+                Command::none()
+            }
+            Messages::CloseRename => {
+                self.rename = None;
+
+                Command::none()
+            }
+            Messages::RenamePreviewed(Ok(rename)) => {
+                self.rename_preview = Some(rename);
+
+                Command::none()
+            }
+            Messages::RenamePreviewed(Err(err)) => {
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::ApplyRename => {
+                let Some(rename) = self.rename_preview.take() else {
+                    return Command::none();
+                };
+
+                self.clear_filter();
+                self.filter_pattern = None;
+
+                let (open, saved): (Vec<_>, Vec<_>) =
+                    rename.files.into_iter().partition(|file| file.is_open);
+
+                // Nothing is renamed unless every open file still reads as
+                // it did in the preview.
+                let changed = open.iter().find(|file| {
+                    self.position(&file.path)
+                        .is_none_or(|index| self.buffers[index].content.text() != *file.original)
+                });
+
+                if let Some(file) = changed {
+                    self.toasts.push(
+                        Severity::Warning,
+                        format!(
+                            "{} changed since the preview, rename again",
+                            file.path.display()
+                        ),
+                    );
+
+                    return Command::none();
+                }
+
+                for file in &open {
+                    let Some(index) = self.position(&file.path) else {
+                        continue;
+                    };
+
+                    let buffer = &mut self.buffers[index];
+                    let (line, column) = buffer.content.cursor_position();
+
+                    buffer.record(Change::Other);
+                    buffer.replace_content(&file.renamed);
+                    move_cursor(&mut buffer.content, line, column);
+                }
+
+                self.count_matches();
+
+                let renamed = open.len();
+
+                Command::perform(rename::write(saved), move |result| {
+                    Messages::RenameApplied(result.map(|written| written + renamed))
+                })
+            }
+            Messages::CancelRename => {
+                self.rename_preview = None;
+
+                Command::none()
+            }
+            Messages::RenameApplied(Ok(files)) => {
+                self.toasts
+                    .push(Severity::Success, format!("Renamed in {files} file(s)"));
+
+                Command::none()
+            }
+            Messages::RenameApplied(Err(err)) => {
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::ToggleTerminal => {
+                if self.terminal.take().is_none() {
+                    self.terminals_started += 1;
+                    self.terminal = Some(Terminal::new(
+                        self.terminals_started,
+                        self.terminal_directory(),
+                    ));
+                }
+
+                Command::none()
+            }
+            Messages::FocusTerminal => {
+                if let Some(terminal) = self.terminal.as_mut() {
+                    terminal.is_focused = true;
+                }
+
+                Command::none()
+            }
+            Messages::Terminal(event) => {
+                let Some(terminal) = self.terminal.as_mut() else {
+                    return Command::none();
+                };
+
+                match event {
+                    terminal::Event::Ready(id, input) if id == terminal.id => {
+                        terminal.input = Some(input);
+                    }
+                    terminal::Event::Output(id, bytes) if id == terminal.id => {
+                        terminal.screen.feed(&bytes);
+
+                        return scrollable::snap_to(
+                            terminal::scroll_id(),
+                            scrollable::RelativeOffset::END,
+                        );
+                    }
+                    terminal::Event::Exited(id) if id == terminal.id => {
+                        self.terminal = None;
+                        self.toasts.push(Severity::Info, "The terminal exited");
+                    }
+                    terminal::Event::Failed(id, err) if id == terminal.id => {
+                        self.terminal = None;
+                        self.toasts
+                            .push(Severity::Error, format!("Could not run a terminal: {err}"));
+                    }
+                    _ => {}
+                }
+
+                Command::none()
+            }
+            // Control characters come through the keys that make them.
+            Messages::TerminalTyped(c) => {
+                if let Some(terminal) = self
+                    .terminal
+                    .as_ref()
+                    .filter(|terminal| terminal.is_focused)
+                {
+                    if !c.is_control() && !self.modifiers.command() {
+                        terminal.send(c.to_string().into_bytes());
+                    }
+                }
+
+                Command::none()
+            }
+            Messages::WindowFocused => self.read_repository(),
+            Messages::HeadRead(path, head) => {
+                if let Some(index) = self.position(&path) {
+                    let buffer = &mut self.buffers[index];
+
+                    if head.is_none() {
+                        buffer.blame = None;
+                    }
+
+                    buffer.gutter.set_head(head);
+                    buffer.blamed = None;
+                }
+
+                Command::none()
+            }
+            Messages::ToggleInlineBlame => {
+                self.is_blame_inline = !self.is_blame_inline;
+                self.warn_untracked(self.is_blame_inline);
+
+                Command::none()
+            }
+            Messages::ToggleBlameGutter => {
+                self.is_blame_gutter = !self.is_blame_gutter;
+                self.warn_untracked(self.is_blame_gutter);
+
+                Command::none()
+            }
+            Messages::Blamed(path, result) => {
+                let revision = self
+                    .blame_pending
+                    .take()
+                    .filter(|(pending, _)| *pending == path)
+                    .map(|(_, revision)| revision);
+
+                if let Some(index) = self.position(&path) {
+                    let buffer = &mut self.buffers[index];
+
+                    buffer.blamed = revision;
+                    buffer.blame = match result {
+                        Ok(blame) => Some(blame),
+                        Err(err) => {
+                            tracing::debug!(%err, "blame failed");
+
+                            None
+                        }
+                    };
+                }
+
+                Command::none()
+            }
+            Messages::StatusRead(status) => {
+                self.git_status = status;
+
+                Command::none()
+            }
+            Messages::ToggleSourceControl => {
+                if self.commit_message.take().is_some() {
+                    return Command::none();
+                }
+
+                self.commit_message = Some(String::new());
+
+                Command::batch([self.read_status(), text_input::focus(commit_message_id())])
+            }
+            Messages::CommitMessageChanged(message) => {
+                self.commit_message = Some(message);
+
+                Command::none()
+            }
+            Messages::StageFile => {
+                let Some(path) = self.buffer().path.clone() else {
+                    return Command::none();
+                };
+
+                if self.buffer().is_dirty() {
+                    self.toasts.push(
+                        Severity::Warning,
+                        "Only what is saved is staged, not the unsaved changes",
+                    );
+                }
+
+                Command::perform(git::stage(path), Messages::Staged)
+            }
+            Messages::Staged(Ok(())) => self.read_status(),
+            Messages::Staged(Err(err)) => {
+                self.notify_error(err, Some(Messages::StageFile));
+
+                Command::none()
+            }
+            Messages::Commit => {
+                let message = self.commit_message.clone().unwrap_or_default();
+
+                let Some(directory) = self.repository() else {
+                    return Command::none();
+                };
+
+                if message.trim().is_empty() {
+                    self.toasts
+                        .push(Severity::Warning, "Write a commit message first");
+
+                    return Command::none();
+                }
+
+                if self.git_status.as_ref().map_or(0, |status| status.staged) == 0 {
+                    self.toasts
+                        .push(Severity::Warning, "Nothing is staged to commit");
+
+                    return Command::none();
+                }
+
+                Command::perform(git::commit(directory, message), Messages::Committed)
+            }
+            Messages::Committed(Ok(summary)) => {
+                self.commit_message = Some(String::new());
+                self.toasts
+                    .push(Severity::Success, format!("Committed {summary}"));
+
+                self.read_repository()
+            }
+            Messages::Committed(Err(err)) => {
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::ShowUnsavedChanges => {
+                let buffer = self.buffer();
+
+                let Some(path) = buffer.path.clone() else {
+                    self.toasts
+                        .push(Severity::Info, "The file has not been saved yet");
+
+                    return Command::none();
+                };
+
+                if !buffer.is_dirty() {
+                    self.toasts.push(Severity::Info, "No unsaved changes");
+
+                    return Command::none();
+                }
+
+                Command::perform(
+                    reopen_file(path, buffer.format.encoding),
+                    Messages::SavedTextRead,
+                )
+            }
+            Messages::SavedTextRead(Ok((path, content, _, _))) => {
+                let Some(index) = self.position(&path) else {
+                    return Command::none();
+                };
+
+                let text = if index == self.active {
+                    self.text()
+                } else {
+                    self.buffers[index].content.text()
+                };
+                let hunks = diff::hunks(&content.replace("\r\n", "\n"), &text, diff::CONTEXT);
+
+                // Changing only the encoding or line endings leaves the lines
+                // as they were.
+                if hunks.is_empty() {
+                    self.toasts
+                        .push(Severity::Info, "The text is the same as the saved file");
+
+                    return Command::none();
+                }
+
+                let name = path
+                    .file_name()
+                    .map_or(String::new(), |name| name.to_string_lossy().into_owned());
+
+                self.comparison =
+                    Some(Comparison::new(format!("Unsaved changes to {name}"), hunks));
+
+                Command::none()
+            }
+            Messages::SavedTextRead(Err(err)) => {
+                self.notify_error(err, Some(Messages::ShowUnsavedChanges));
+
+                Command::none()
+            }
+            Messages::ToggleDiffLayout => {
+                if let Some(comparison) = self.comparison.as_mut() {
+                    comparison.toggle_layout();
+                }
+
+                Command::none()
+            }
+            Messages::ToggleCompare => {
+                self.is_compare_open = !self.is_compare_open;
+
+                Command::none()
+            }
+            Messages::CompareWithBuffer(CompareChoice(index, name)) => {
+                if let Some(buffer) = self.buffers.get(index) {
+                    let text = buffer.content.text();
+
+                    self.compare_with(name, &text);
+                }
+
+                Command::none()
+            }
+            Messages::CompareWithFile => Command::perform(
+                pick_file(DialogDefaults {
+                    file_name: None,
+                    ..self.dialog_defaults()
+                }),
+                Messages::CompareFilePicked,
+            ),
+            Messages::CompareFilePicked(Ok((path, content, _, _))) => {
+                self.compare_with(path.display().to_string(), &content);
+
+                Command::none()
+            }
+            Messages::CompareFilePicked(Err(err)) => {
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::NextChange => self.step_change(true),
+            Messages::PreviousChange => self.step_change(false),
+            Messages::NextConflict => {
+                self.step_conflict(true);
+
+                Command::none()
+            }
+            Messages::PreviousConflict => {
+                self.step_conflict(false);
+
+                Command::none()
+            }
+            Messages::ResolveConflict(resolution) => {
+                self.resolve_conflict(resolution);
+
+                Command::none()
+            }
+            Messages::CloseDiff => {
+                self.comparison = None;
+
+                Command::none()
+            }
+            Messages::Format => match self.format(None) {
+                Some(command) => command,
+                None => {
+                    self.toasts.push(
+                        Severity::Info,
+                        format!(
+                            "No formatter for {} files",
+                            language::name(&self.extension())
+                        ),
+                    );
+
+                    Command::none()
+                }
+            },
+            Messages::Formatted(path, revision, save, result) => {
+                let index = match &path {
+                    Some(path) => self.position(path),
+                    None => self.buffer().path.is_none().then_some(self.active),
+                };
+
+                let Some(index) = index else {
+                    return Command::none();
+                };
+
+                match result {
+                    Ok(_) if self.buffers[index].history.revision() != revision => {
+                        self.toasts.push(
+                            Severity::Warning,
+                            "The file changed while it was being formatted and was left as is",
+                        );
+                    }
+                    Ok(formatted) => self.apply_formatted(index, formatted),
+                    Err(err) => self.notify_error(err, None),
+                }
+
+                match save {
+                    Some(target) => {
+                        self.activate(index);
+                        self.write_checked(target)
+                    }
+                    None => Command::none(),
+                }
+            }
+            Messages::ToggleSpellCheck => {
+                self.is_spell_checking = !self.is_spell_checking;
+
+                if self.is_spell_checking {
+                    self.start_spell_check()
+                } else {
+                    self.spelling = None;
+
+                    Command::none()
+                }
+            }
+            Messages::DictionariesListed(languages) => {
+                self.dictionaries = languages;
+
+                Command::none()
+            }
+            Messages::DictionarySelected(language) => load_dictionary(language),
+            Messages::DictionaryLoaded(Ok(dictionary)) => {
+                self.dictionary = Some(dictionary);
+
+                Command::none()
+            }
+            Messages::DictionaryLoaded(Err(err)) => {
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::ShowSpelling => {
+                let Some(dictionary) = self.dictionary.clone().filter(|_| self.is_spell_checking)
+                else {
+                    self.toasts.push(Severity::Info, "Spell checking is off");
+
+                    return Command::none();
+                };
+
+                // Misspellings are found in the text as shown, filtered or
+                // not, and so is the cursor looked for among them.
+                let (line, column) = self.buffer().content.cursor_position();
+                let Some(&range) =
+                    self.buffer()
+                        .misspelled
+                        .iter()
+                        .find(|((at, start), (_, end))| {
+                            *at == line && (*start..=*end).contains(&column)
+                        })
+                else {
+                    self.toasts
+                        .push(Severity::Info, "No misspelled word at the cursor");
+
+                    return Command::none();
+                };
+
+                let ((_, start), (_, end)) = range;
+                let word = self
+                    .buffer()
+                    .content
+                    .line(line)
+                    .map_or(String::new(), |text| {
+                        text.chars().skip(start).take(end - start).collect()
+                    });
+
+                self.hover = None;
+                self.completion = None;
+                self.spelling = Some(spelling::Suggestions::new(
+                    self.focus,
+                    range,
+                    word,
+                    &dictionary,
+                ));
+
+                Command::none()
+            }
+            Messages::ReplaceMisspelled(replacement) => {
+                let Some(spelling) = self.spelling.take() else {
+                    return Command::none();
+                };
+
+                let before = self.snapshot();
+                let mut document = self.document();
+
+                document.select((spelling.start, spelling.end));
+                document.insert(&replacement);
+                self.buffer_mut().history.record(Change::Other, || before);
+
+                Command::none()
+            }
+            Messages::AddToDictionary => {
+                let Some(spelling) = self.spelling.take() else {
+                    return Command::none();
+                };
+
+                if let Some(dictionary) = self.dictionary.as_mut() {
+                    Arc::make_mut(dictionary).add(spelling.word.clone());
+                }
+
+                Command::perform(
+                    spelling::remember(spelling.word),
+                    Messages::AddedToDictionary,
+                )
+            }
+            Messages::AddedToDictionary(Ok(())) => Command::none(),
+            Messages::AddedToDictionary(Err(err)) => {
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::Copied => match self.copy_block() {
+                Some(copied) => copied,
+                None => clipboard::read(Messages::ClipboardRead),
+            },
+            Messages::ClipboardRead(text) => {
+                if let Some(text) = text {
+                    self.clips.push(text);
+                }
+
+                Command::none()
+            }
+            Messages::ShowClipboardHistory => {
+                if self.clips.is_empty() {
+                    self.toasts
+                        .push(Severity::Info, "Nothing has been cut or copied yet");
+
+                    return Command::none();
+                }
+
+                self.hover = None;
+                self.completion = None;
+                self.spelling = None;
+                self.clip_picker = Some((self.focus, self.buffer().content.cursor_position()));
+
+                Command::none()
+            }
+            Messages::PasteClip(index) => {
+                self.clip_picker = None;
+
+                match self.clips.take(index) {
+                    Some(text) => self.update(Messages::Edit(text_editor::Action::Edit(
+                        text_editor::Edit::Paste(Arc::new(text)),
+                    ))),
+                    None => Command::none(),
+                }
+            }
+            Messages::ShowHover => {
+                if !self.request_hover(self.focus, self.cursor(), true) {
+                    self.toasts.push(
+                        Severity::Info,
+                        "No language server is running for this file",
+                    );
+                }
+
+                Command::none()
+            }
+            // A hover stays open while the pointer is on its word or over
+            // the hover itself, and goes once it rests on another word.
+            Messages::Hovered(pane, cell) => {
+                self.hover_pending = None;
+
+                let Some((row, column)) = cell else {
+                    return Command::none();
+                };
+
+                let Some(&index) = self.panes.get(&pane) else {
+                    return Command::none();
+                };
+
+                if index == self.active && self.filter.is_some() {
+                    return Command::none();
+                }
+
+                let buffer = &self.buffers[index];
+                let position = (buffer.scroll + row, column);
+                let is_word = buffer
+                    .content
+                    .line(position.0)
+                    .is_some_and(|line| line.chars().nth(column).is_some_and(completion::is_word));
+
+                if self
+                    .hover
+                    .as_ref()
+                    .is_some_and(|hover| hover.contains(pane, position))
+                {
+                    return Command::none();
+                }
+
+                self.hover = None;
+
+                if is_word {
+                    self.hover_pending = Some((pane, position, Instant::now()));
+                }
+
+                Command::none()
+            }
+            Messages::PaneWrapped(pane, is_wrapped) => {
+                if is_wrapped {
+                    self.wrapped_panes.insert(pane);
+
+                    if self.hover.as_ref().is_some_and(|hover| hover.pane == pane) {
+                        self.hover = None;
+                    }
+
+                    if self
+                        .hover_pending
+                        .is_some_and(|(pending, ..)| pending == pane)
+                    {
+                        self.hover_pending = None;
+                    }
+                } else {
+                    self.wrapped_panes.remove(&pane);
+                }
+
+                Command::none()
+            }
+            Messages::CopyErrorLog => clipboard::write(self.error_log.export()),
+            Messages::ClearErrorLog => {
+                self.error_log.clear();
+
+                Command::none()
+            }
+            Messages::ToggleHud => {
+                self.hud.is_open = !self.hud.is_open;
+
+                Command::none()
+            }
+            Messages::ResetHud => {
+                self.hud.reset();
+
+                Command::none()
+            }
+            Messages::RecoveryFound(recoveries) => {
+                self.recoveries = recoveries;
+
+                Command::none()
+            }
+            Messages::RestoreRecovery => {
+                if self.recoveries.is_empty() {
+                    return Command::none();
+                }
+
+                let recovery = self.recoveries.remove(0);
+
+                let mut buffer = Buffer::new(self.buffer().theme);
+
+                buffer.replace_content(&recovery.rescued.text);
+                buffer.path = recovery.rescued.path;
+                self.push_buffer(buffer);
+
+                Command::perform(crash::discard(recovery.file), Messages::RecoveryDiscarded)
+            }
+            Messages::DiscardRecovery => {
+                if self.recoveries.is_empty() {
+                    return Command::none();
+                }
+
+                let recovery = self.recoveries.remove(0);
+
+                Command::perform(crash::discard(recovery.file), Messages::RecoveryDiscarded)
+            }
+            Messages::WriteSwaps => self.write_swaps(),
+            Messages::SwapWritten(Ok(())) => Command::none(),
+            // Snapshots are taken every few seconds, so a failing one is
+            // only logged rather than shown each time.
+            Messages::SwapWritten(Err(err)) => {
+                self.error_log.record(&err);
+
+                Command::none()
+            }
+            Messages::RecoveryDiscarded(Ok(())) => Command::none(),
+            Messages::RecoveryDiscarded(Err(err)) => {
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::JobFinished(id, message) => {
+                if self.jobs.finish(id) {
+                    self.update(*message)
+                } else {
+                    Command::none()
+                }
+            }
+            Messages::CancelJob(id) => {
+                self.jobs.cancel(id);
+
+                Command::none()
+            }
+            Messages::ToggleJobs => {
+                self.jobs.is_open = !self.jobs.is_open;
+
+                Command::none()
+            }
+            Messages::OpenLogFile => Command::perform(logging::latest(), Messages::LogFileFound),
+            Messages::ShowOpenUrl => {
+                self.open_url = Some(String::new());
+
+                text_input::focus(fetch::input_id())
+            }
+            Messages::OpenUrlChanged(input) => {
+                self.open_url = Some(input);
+
+                Command::none()
+            }
+            // An address without a scheme is taken to be on https.
+            Messages::SubmitOpenUrl => {
+                let Some(input) = self.open_url.take() else {
+                    return Command::none();
+                };
+
+                let input = input.trim();
+
+                if input.is_empty() {
+                    return Command::none();
+                }
+
+                let url = if input.contains("://") {
+                    input.to_string()
+                } else {
+                    format!("https://{input}")
+                };
+
+                self.update(Messages::FetchUrl(url))
+            }
+            Messages::CloseOpenUrl => {
+                self.open_url = None;
+
+                Command::none()
+            }
+            Messages::FetchUrl(url) => self.spawn(
+                format!("Fetching {url}"),
+                |_| fetch::fetch(url),
+                Messages::UrlFetched,
+            ),
+            // What comes from the web is there to be read, so it cannot be
+            // changed by accident until it is made editable.
+            Messages::UrlFetched(Ok(fetched)) => {
+                let mut buffer = Buffer::new(self.buffer().theme);
+
+                buffer.reinterpret(&fetched.text, fetched.encoding);
+                buffer.language = fetched.language;
+                buffer.url = Some(fetched.url);
+                buffer.is_read_only = true;
+                buffer.mark_saved();
+
+                self.push_buffer(buffer);
+
+                self.save_state()
+            }
+            Messages::UrlFetched(Err(err)) => {
+                let retry = match &err {
+                    Error::FetchFailed(url, _) => Some(Messages::FetchUrl(url.clone())),
+                    _ => None,
+                };
+
+                self.notify_error(err, retry);
+
+                Command::none()
+            }
+            Messages::ToggleReadOnly => {
+                let buffer = self.buffer_mut();
+
+                buffer.is_read_only = !buffer.is_read_only;
+
+                Command::none()
+            }
+            Messages::ShowOpenRemote => {
+                let profiles = self.config().remotes;
+
+                if profiles.is_empty() {
+                    self.toasts.push(
+                        Severity::Info,
+                        "Add a host under [remotes] in the settings to open files from it",
+                    );
+
+                    return Command::none();
+                }
+
+                self.remote = Some(remote::Browser::default());
+
+                match profiles.keys().next().filter(|_| profiles.len() == 1) {
+                    Some(name) => self.update(Messages::RemoteProfileSelected(name.clone())),
+                    None => Command::none(),
+                }
+            }
+            Messages::CloseRemote => {
+                self.remote = None;
+
+                Command::none()
+            }
+            Messages::RemoteProfileSelected(name) => {
+                let Some(profile) = self.config().remotes.get(&name).cloned() else {
+                    return Command::none();
+                };
+
+                let directory = profile.start();
+
+                self.list_remote(name, profile, directory)
+            }
+            Messages::RemoteListed(name, result) => {
+                let is_current = self
+                    .remote
+                    .as_ref()
+                    .is_some_and(|browser| browser.profile.as_ref() == Some(&name));
+
+                if !is_current {
+                    return Command::none();
+                }
+
+                match result {
+                    Ok((directory, entries)) => {
+                        if let Some(browser) = self.remote.as_mut() {
+                            browser.directory = directory;
+                            browser.entries = entries;
+                            browser.is_loading = false;
+                        }
+                    }
+                    Err(err) => {
+                        if let Some(browser) = self.remote.as_mut() {
+                            browser.is_loading = false;
+                        }
+
+                        self.notify_error(err, None);
+                    }
+                }
+
+                Command::none()
+            }
+            Messages::OpenRemoteEntry(entry) => {
+                let Some(browser) = self.remote.as_ref() else {
+                    return Command::none();
+                };
+
+                let path = browser.path(&entry);
+
+                let Some((name, profile)) = browser.profile.clone().and_then(|name| {
+                    let profile = self.config().remotes.get(&name).cloned()?;
+
+                    Some((name, profile))
+                }) else {
+                    return Command::none();
+                };
+
+                if entry.is_dir {
+                    return self.list_remote(name, profile, path);
+                }
+
+                if entry.is_link {
+                    if let Some(browser) = self.remote.as_mut() {
+                        browser.is_loading = true;
+                    }
+
+                    return Command::perform(remote::list(profile, path), move |result| {
+                        Messages::RemoteLinkFollowed(name, entry, result)
+                    });
+                }
+
+                let location = remote::Location {
+                    profile: name,
+                    path: path.clone(),
+                };
+
+                let Some(local) = location.mirror() else {
+                    self.toasts.push(
+                        Severity::Warning,
+                        "There is no cache directory to keep a copy of the file in",
+                    );
+
+                    return Command::none();
+                };
+
+                self.spawn(
+                    format!("Downloading {}", entry.name),
+                    |job| remote::download(profile, path, local, entry.size, job),
+                    move |result| Messages::RemoteDownloaded(location, result),
+                )
+            }
+            Messages::RemoteLinkFollowed(name, _, Ok(listed)) => {
+                self.update(Messages::RemoteListed(name, Ok(listed)))
+            }
+            // A link that cannot be listed leads to a file, and the size the
+            // listing gave was the link's own.
+            Messages::RemoteLinkFollowed(name, entry, Err(_)) => {
+                let Some(browser) = self
+                    .remote
+                    .as_mut()
+                    .filter(|browser| browser.profile.as_ref() == Some(&name))
+                else {
+                    return Command::none();
+                };
+
+                browser.is_loading = false;
+
+                self.update(Messages::OpenRemoteEntry(remote::Entry {
+                    is_link: false,
+                    size: None,
+                    ..entry
+                }))
+            }
+            Messages::RemoteDownloaded(location, Ok(local)) => {
+                self.remote = None;
+                self.remote_files.insert(local.clone(), location);
+
+                self.load(local)
+            }
+            Messages::RemoteDownloaded(_, Err(err)) => {
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::UploadRemote(local) => {
+                let Some(location) = self.remote_files.get(&local).cloned() else {
+                    return Command::none();
+                };
+
+                let Some(profile) = self.config().remotes.get(&location.profile).cloned() else {
+                    self.toasts.push(
+                        Severity::Warning,
+                        format!("{} is no longer among the remotes", location.profile),
+                    );
+
+                    return Command::none();
+                };
+
+                self.spawn(
+                    format!("Uploading to {}, progress not shown", profile.host),
+                    {
+                        let local = local.clone();
+
+                        |_| remote::upload(profile, local, location.path)
+                    },
+                    move |result| Messages::RemoteUploaded(local, result),
+                )
+            }
+            Messages::RemoteUploaded(_, Ok(target)) => {
+                self.toasts
+                    .push(Severity::Success, format!("Uploaded to {target}"));
+
+                Command::none()
+            }
+            Messages::RemoteUploaded(local, Err(err)) => {
+                self.notify_error(err, Some(Messages::UploadRemote(local)));
+
+                Command::none()
+            }
+            Messages::LogFileFound(Some(path)) => self.load(path),
+            Messages::LogFileFound(None) => {
+                self.toasts
+                    .push(Severity::Info, "Nothing has been logged yet");
+
+                Command::none()
+            }
+            Messages::FileOpened(Ok((path, content, encoding, stamp))) => {
+                self.is_welcome_open = false;
+
+                if let Some(index) = self.position(&path) {
+                    self.activate(index);
+
+                    if let Some((line, column)) = self.pending_jump.take() {
+                        move_cursor(&mut self.buffer_mut().content, line, column);
+                    }
+
+                    self.arrive(&path);
+
+                    return Command::none();
+                }
+
+                let buffer = self.open_buffer(path.clone(), &content, encoding, stamp);
+                let conflicts = conflict::find(&content).len();
+
+                if encoding == encoding::HEX {
+                    self.toasts.push(
+                        Severity::Info,
+                        format!("{} is not text and opens as hex", buffer.title()),
+                    );
+                }
+
+                if conflicts > 0 {
+                    self.toasts.push_with_action(
+                        Severity::Warning,
+                        format!("{conflicts} merge conflict(s) in {}", buffer.title()),
+                        Some((String::from("Go to first"), Messages::NextConflict)),
+                    );
+                }
+
+                self.recent
+                    .touch(path.clone(), buffer.content.cursor_position());
+                self.push_buffer(buffer);
+                self.arrive(&path);
+
+                Command::batch([
+                    self.save_state(),
+                    self.read_head(self.active),
+                    self.read_status(),
+                ])
+            }
+            Messages::SessionRestored(files) => {
+                let mut missing = 0;
+                let mut heads = Vec::new();
+
+                for file in files {
+                    match file {
+                        Ok((path, content, encoding, stamp)) if self.position(&path).is_none() => {
+                            let buffer = self.open_buffer(path, &content, encoding, stamp);
+
+                            self.push_buffer(buffer);
+                            heads.push(self.read_head(self.active));
+                        }
+                        Ok(_) => {}
+                        Err(err) => {
+                            self.error_log.record(&err);
+                            missing += 1;
+                        }
+                    }
+                }
+
+                if missing > 0 {
+                    self.toasts.push(
+                        Severity::Warning,
+                        format!("{missing} file(s) from the last session could not be reopened"),
+                    );
+                }
+
+                if let Some(index) = self
+                    .session
+                    .active_file()
+                    .and_then(|path| self.position(path))
+                {
+                    self.activate(index);
+                }
+
+                heads.push(self.save_state());
+
+                Command::batch(heads)
+            }
+            Messages::FileReloaded(Ok((path, content, encoding, stamp))) => {
+                let Some(index) = self.position(&path) else {
+                    return Command::none();
+                };
+
+                if index == self.active {
+                    self.clear_filter();
+                }
+
+                let buffer = &mut self.buffers[index];
+
+                if !buffer.is_dirty() {
+                    buffer.reload(&content, encoding, stamp);
+                }
+
+                self.read_head(index)
+            }
+            // Checking out another branch changes the file and what it is
+            // compared with alike.
+            Messages::FileTouched(path) => match self.position(&path) {
+                Some(index) => Command::batch([
+                    Command::perform(
+                        reopen_file(path, self.buffers[index].format.encoding),
+                        Messages::DiskChecked,
+                    ),
+                    self.read_head(index),
+                ]),
+                None => Command::none(),
+            },
+            Messages::DiskChecked(Ok((path, content, encoding, stamp))) => {
+                let Some(index) = self.position(&path) else {
+                    return Command::none();
+                };
+
+                if self.buffers[index].stamp.map(|known| known.hash) == Some(stamp.hash) {
+                    return Command::none();
+                }
+
+                let text = if index == self.active {
+                    self.text()
+                } else {
+                    self.buffers[index].content.text()
+                };
+
+                // Our own saves come back this way too, and leave the text
+                // just as it is in the buffer.
+                if content.replace("\r\n", "\n") == text {
+                    self.buffers[index].stamp = Some(stamp);
+
+                    return Command::none();
+                }
+
+                self.disk_changes.retain(|change| change.path != path);
+
+                if self.buffers[index].is_dirty() {
+                    self.disk_changes.push(DiskChange {
+                        path,
+                        content,
+                        encoding,
+                        stamp,
+                    });
+
+                    return Command::none();
+                }
+
+                if index == self.active {
+                    self.clear_filter();
+                    self.filter_pattern = None;
+                }
+
+                self.buffers[index].reload(&content, encoding, stamp);
+                self.toasts.push(
+                    Severity::Info,
+                    format!("Reloaded {} after it changed on disk", path.display()),
+                );
+
+                Command::none()
+            }
+            Messages::DiskChecked(Err(Error::FileFailed(path, err)))
+                if err.kind() == io::ErrorKind::NotFound =>
+            {
+                self.disk_changes.retain(|change| change.path != path);
+                self.toasts.push(
+                    Severity::Warning,
+                    format!(
+                        "{} was deleted or moved. Saving will write it again.",
+                        path.display()
+                    ),
+                );
+
+                Command::none()
+            }
+            Messages::DiskChecked(Err(err)) => {
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::ReloadFromDisk => {
+                if self.disk_changes.is_empty() {
+                    return Command::none();
+                }
+
+                let change = self.disk_changes.remove(0);
+
+                if let Some(index) = self.position(&change.path) {
+                    if index == self.active {
+                        self.clear_filter();
+                        self.filter_pattern = None;
+                    }
+
+                    self.buffers[index].reload(&change.content, change.encoding, change.stamp);
+                }
+
+                Command::none()
+            }
+            // Saving after this goes ahead without asking, since the change
+            // on disk has already been seen.
+            Messages::KeepMine => {
+                if self.disk_changes.is_empty() {
+                    return Command::none();
+                }
+
+                let change = self.disk_changes.remove(0);
+
+                if let Some(index) = self.position(&change.path) {
+                    self.buffers[index].stamp = Some(change.stamp);
+                }
+
+                Command::none()
+            }
+            // The version on disk opens beside the buffer as a scratch copy,
+            // so the two can be read side by side.
+            Messages::CompareWithDisk => {
+                if self.disk_changes.is_empty() {
+                    return Command::none();
+                }
+
+                let change = self.disk_changes.remove(0);
+
+                let Some(index) = self.position(&change.path) else {
+                    return Command::none();
+                };
+
+                self.activate(index);
+
+                let mut copy = Buffer::new(self.buffer().theme);
+
+                copy.replace_content(&change.content);
+                copy.language =
+                    language::Language::of(&self.extension()).map(|language| language.0);
+                copy.mark_saved();
+
+                if let Some((pane, _)) =
+                    self.panes
+                        .split(pane_grid::Axis::Vertical, &self.focus, self.active)
+                {
+                    self.focus = pane;
+                }
+
+                self.buffers.push(copy);
+                self.activate(self.buffers.len() - 1);
+
+                Command::none()
+            }
+            Messages::ConvertEncoding(encoding::Choice(encoding)) => {
+                let from = self.buffer().format.encoding;
+
+                if (from == encoding::HEX) != (encoding == encoding::HEX) {
+                    self.convert_hex(encoding);
+                } else {
+                    self.buffer_mut().format.encoding = encoding;
+                }
+
+                Command::none()
+            }
+            Messages::LanguageSelected(language) => {
+                self.buffer_mut().language = Some(language.0);
+
+                Command::none()
+            }
+            Messages::ConvertLineEnding(line_ending) => {
+                self.buffer_mut().format.line_ending = line_ending;
+
+                Command::none()
+            }
+            Messages::ReopenWithEncoding(encoding::Choice(encoding)) => {
+                let Some(path) = self.buffer().path.clone() else {
+                    return Command::none();
+                };
+
+                if self.buffer().is_dirty() {
+                    self.toasts.push(
+                        Severity::Warning,
+                        "Save or discard your changes before reopening the file",
+                    );
+
+                    return Command::none();
+                }
+
+                Command::perform(reopen_file(path, encoding), Messages::FileReinterpreted)
+            }
+            Messages::FileReinterpreted(Ok((path, content, encoding, stamp))) => {
+                let Some(index) = self.position(&path) else {
+                    return Command::none();
+                };
+
+                if index == self.active {
+                    self.clear_filter();
+                    self.filter_pattern = None;
+                }
+
+                self.buffers[index].reload(&content, encoding, stamp);
+
+                Command::none()
+            }
+            Messages::FileReloaded(Err(err)) | Messages::FileReinterpreted(Err(err)) => {
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::SplitPane(axis) => {
+                if let Some((pane, _)) = self.panes.split(axis, &self.focus, self.active) {
+                    self.focus = pane;
+                }
+
+                Command::none()
+            }
+            Messages::ClosePane => {
+                if let Some((_, sibling)) = self.panes.close(&self.focus) {
+                    self.focus_pane(sibling);
+                }
+
+                Command::none()
+            }
+            Messages::FocusPane(pane) => {
+                self.focus_pane(pane);
+
+                Command::none()
+            }
+            Messages::ResizePane(pane_grid::ResizeEvent { split, ratio }) => {
+                self.panes.resize(&split, ratio);
+
+                Command::none()
+            }
+            Messages::EditPane(pane, action) => {
+                // Keys meant for the shell reach the editor as well, and only
+                // a click takes the focus back from it.
+                if let Some(terminal) = self
+                    .terminal
+                    .as_mut()
+                    .filter(|terminal| terminal.is_focused)
+                {
+                    match action {
+                        text_editor::Action::Click(_) | text_editor::Action::Drag(_) => {
+                            terminal.is_focused = false;
+                        }
+                        text_editor::Action::Scroll { .. } => {}
+                        _ => return Command::none(),
+                    }
+                }
+
+                self.focus_pane(pane);
+
+                self.update(Messages::Edit(action))
+            }
+            Messages::MinimapScrolled(pane, line) => {
+                self.focus_pane(pane);
+
+                let buffer = self.buffer();
+                let top = line.min(buffer.content.line_count().saturating_sub(1));
+                let lines = top as i32 - buffer.scroll as i32;
+
+                self.update(Messages::Edit(text_editor::Action::Scroll { lines }))
+            }
+            Messages::ShowFind(is_replacing) => {
+                let content = &self.buffer().content;
+                let selected = content.selection();
+
+                // Several lines selected are taken as the place to replace in
+                // rather than as something to look for.
+                let scope = editing::selection(content)
+                    .filter(|_| selected.as_ref().is_some_and(|text| text.contains('\n')))
+                    .filter(|_| self.filter.is_none());
+                let selected = selected.filter(|selected| !selected.contains('\n'));
+                let find = self.find.get_or_insert_with(Find::default);
+
+                find.is_replacing = is_replacing;
+
+                if let Some(selected) = selected {
+                    find.query = selected;
+                }
+
+                if scope.is_some() {
+                    find.scope = scope;
+                }
+
+                self.count_matches();
+
+                Command::batch([
+                    text_input::focus(find::input_id()),
+                    text_input::move_cursor_to_end(find::input_id()),
+                ])
+            }
+            Messages::CloseFind => {
+                self.find = None;
+
+                Command::none()
+            }
+            Messages::AddNextOccurrence => {
+                if self.filter.is_some() {
+                    return Command::none();
+                }
+
+                let mut document = self.document();
+                let cursor = document.cursor();
+                let text = document.text();
+
+                // With nothing selected the word under the cursor is picked
+                // first, so pressing it again goes on to the next one.
+                let Some(selection) = document.selection().filter(|(start, end)| start != end)
+                else {
+                    if let Some(word) = editing::expanded(&text, (cursor, cursor)) {
+                        document.select(word);
+                    }
+
+                    return Command::none();
+                };
+
+                let buffer = self.buffer_mut();
+
+                if let Some(next) = buffer.cursors.next_occurrence(&text, selection) {
+                    buffer.cursors.add(selection);
+                    self.document().select(next);
+                }
+
+                Command::none()
+            }
+            Messages::SelectBlock(motion) => {
+                if self.filter.is_some() {
+                    return Command::none();
+                }
+
+                let buffer = self.buffer_mut();
+                let text = buffer.content.text();
+                let cursor = buffer.content.cursor_position();
+                let (anchor, corner) = buffer.cursors.block().unwrap_or((cursor, cursor));
+                let last = text.split('\n').count() - 1;
+                let longest = text
+                    .split('\n')
+                    .map(|line| line.chars().count())
+                    .max()
+                    .unwrap_or_default();
+
+                // The corner may go past the end of its own line, as long
+                // as some line reaches that far.
+                let corner = match motion {
+                    text_editor::Motion::Up => (corner.0.saturating_sub(1), corner.1),
+                    text_editor::Motion::Down => ((corner.0 + 1).min(last), corner.1),
+                    text_editor::Motion::Left => (corner.0, corner.1.saturating_sub(1)),
+                    text_editor::Motion::Right => (corner.0, (corner.1 + 1).min(longest)),
+                    _ => corner,
+                };
+
+                buffer.history.seal();
+
+                let selection = buffer.cursors.select_block(&text, anchor, corner);
+
+                self.document().select(selection);
+
+                Command::none()
+            }
+            Messages::ToggleComment => {
+                let Some(comment) = language::comment(&self.extension()) else {
+                    return Command::none();
+                };
+
+                let before = self.snapshot();
+                let mut document = self.document();
+                let (line, column) = document.cursor();
+                let selected = document
+                    .selected_lines()
+                    .filter(|(start, end)| start != end);
+                let (start, end) = selected.unwrap_or((line, line));
+                let width = document.line(line).chars().count();
+
+                if !editing::toggle_comment(&mut document, start, end, comment) {
+                    return Command::none();
+                }
+
+                match selected {
+                    Some((start, end)) => document.select_lines(start, end),
+                    None => {
+                        let shift = document.line(line).chars().count() as isize - width as isize;
+
+                        document.move_to((line, column.saturating_add_signed(shift)));
+                    }
+                }
+
+                self.buffer_mut().history.record(Change::Other, || before);
+
+                Command::none()
+            }
+            Messages::JumpToBracket => {
+                let extension = self.extension();
+                let mut document = self.document();
+                let cursor = document.cursor();
+
+                if let Some((_, partner)) =
+                    editing::matching_bracket(&document.text(), cursor, &extension)
+                {
+                    document.move_to(partner);
+                }
+
+                Command::none()
+            }
+            Messages::CycleWrap => {
+                let buffer = self.buffer_mut();
+
+                buffer.wrap = buffer.wrap.next();
+                self.remember_view();
+
+                self.save_state()
+            }
+            Messages::IndentWithSpaces => {
+                let tab_width = self.config().tab_width;
+
+                self.convert_indentation(Indentation::Spaces(tab_width));
+
+                Command::none()
+            }
+            Messages::IndentWithTabs => {
+                self.convert_indentation(Indentation::Tabs);
+
+                Command::none()
+            }
+            Messages::ConvertCase(case) => {
+                self.convert_case(case);
+
+                Command::none()
+            }
+            Messages::TransformLines(transform) => {
+                self.transform_lines(transform);
+
+                Command::none()
+            }
+            Messages::CycleWhitespace => {
+                self.whitespace = self.whitespace.next();
+
+                Command::none()
+            }
+            Messages::WhitespaceSelected(whitespace) => {
+                self.whitespace = whitespace;
+
+                Command::none()
+            }
+            Messages::Undo => {
+                self.step_history(false);
+
+                Command::none()
+            }
+            Messages::Redo => {
+                self.step_history(true);
+
+                Command::none()
+            }
+            Messages::ShowGoToLine => {
+                let (line, column) = self.cursor();
+
+                self.go_to_line = Some(format!("{}:{}", line + 1, column + 1));
+
+                Command::batch([
+                    text_input::focus(go_to_line_id()),
+                    text_input::select_all(go_to_line_id()),
+                ])
+            }
+            Messages::GoToLineChanged(input) => {
+                self.go_to_line = Some(input);
+
+                Command::none()
+            }
+            Messages::SubmitGoToLine => {
+                let Some(input) = self.go_to_line.clone() else {
+                    return Command::none();
+                };
+
+                let Some(position) = editing::parse_position(&input) else {
+                    self.toasts.push(
+                        Severity::Warning,
+                        format!("\"{input}\" is not a line or line:column"),
+                    );
+
+                    return Command::none();
+                };
+
+                self.jump_to(position);
+                self.go_to_line = None;
+
+                Command::none()
+            }
+            Messages::CloseGoToLine => {
+                self.go_to_line = None;
+
+                Command::none()
+            }
+            Messages::ShowSurround | Messages::ShowChangeSurrounding => {
+                self.surround = Some(Surround {
+                    input: String::new(),
+                    is_changing: matches!(message, Messages::ShowChangeSurrounding),
+                });
+
+                text_input::focus(surround::input_id())
+            }
+            Messages::SurroundChanged(input) => {
+                if let Some(surround) = self.surround.as_mut() {
+                    surround.input = input;
+                }
+
+                Command::none()
+            }
+            Messages::SubmitSurround => match self.surround.as_ref() {
+                Some(surround) => self.update(Messages::SurroundWith(surround.input.clone())),
+                None => Command::none(),
+            },
+            Messages::SurroundWith(opening) => {
+                let Some(surround) = self.surround.take() else {
+                    return Command::none();
+                };
+
+                let Some(pair) = surround::pair(&opening) else {
+                    return Command::none();
+                };
+
+                if surround.is_changing {
+                    self.change_surrounding(Some(pair));
+                } else {
+                    self.surround_selection(pair);
+                }
+
+                Command::none()
+            }
+            Messages::CloseSurround => {
+                self.surround = None;
+
+                Command::none()
+            }
+            Messages::RemoveSurrounding => {
+                self.change_surrounding(None);
+
+                Command::none()
+            }
+            Messages::FindChanged(query) => {
+                let Some(find) = self.find.as_mut() else {
+                    return Command::none();
+                };
+
+                find.query = query.clone();
+
+                let scope = find.scope;
+
+                if !query.is_empty() {
+                    find::select_nearest(&mut self.document(), &query, scope);
+                }
+
+                self.count_matches();
+
+                Command::none()
+            }
+            Messages::FindReplacementChanged(replacement) => {
+                if let Some(find) = self.find.as_mut() {
+                    find.replacement = replacement;
+                }
+
+                Command::none()
+            }
+            Messages::FindNext | Messages::FindPrevious => {
+                let Some(query) = self.find.as_ref().map(|find| find.query.clone()) else {
+                    return Command::none();
+                };
+                let direction = match message {
+                    Messages::FindPrevious => Direction::Backward,
+                    _ => Direction::Forward,
+                };
+
+                self.remember_jump();
+                find::select_match(&mut self.document(), &query, direction);
+                self.count_matches();
+
+                Command::none()
+            }
+            Messages::ReplaceOne => {
+                let Some(find) = self.find.clone() else {
+                    return Command::none();
+                };
+
+                let is_selected = {
+                    let document = self.document();
+
+                    document.selection().is_some_and(|range| {
+                        find::matches(&document.text(), &find.query).contains(&range)
+                    })
+                };
+
+                if is_selected {
+                    self.record(Change::Other);
+                    self.document().insert(&find.replacement);
+                }
+
+                find::select_match(&mut self.document(), &find.query, Direction::Forward);
+
+                if is_selected {
+                    self.count_matches();
+                }
+
+                Command::none()
+            }
+            Messages::ReplaceAll => {
+                let Some(find) = self.find.clone().filter(|find| !find.query.is_empty()) else {
+                    return Command::none();
+                };
+
+                self.clear_filter();
+                self.filter_pattern = None;
+
+                let text = self.buffer().content.text();
+                let (replaced, count, scope) =
+                    find::replace_all(&text, &find.query, &find.replacement, find.scope);
+
+                if count == 0 {
+                    let place = if find.scope.is_some() {
+                        " in the selection"
+                    } else {
+                        ""
+                    };
+
+                    self.toasts.push(
+                        Severity::Info,
+                        format!("No matches for \"{}\"{place}", find.query),
+                    );
+
+                    return Command::none();
+                }
+
+                let (line, column) = self.cursor();
+                let buffer = self.buffer_mut();
+
+                buffer.record(Change::Other);
+                buffer.replace_content(&replaced);
+                move_cursor(&mut buffer.content, line, column);
+
+                if let Some(find) = self.find.as_mut() {
+                    find.scope = scope;
+                }
+
+                self.toasts
+                    .push(Severity::Success, format!("Replaced {count} occurrence(s)"));
+                self.count_matches();
+
+                Command::none()
+            }
+            Messages::ToggleFindInSelection(is_scoped) => {
+                let scope = editing::selection(&self.buffer().content)
+                    .filter(|(start, end)| is_scoped && start != end && self.filter.is_none());
+
+                if is_scoped && scope.is_none() {
+                    self.toasts
+                        .push(Severity::Info, "Select the text to replace in first");
+                }
+
+                if let Some(find) = self.find.as_mut() {
+                    find.scope = scope;
+                }
+
+                self.count_matches();
+
+                Command::none()
+            }
+            Messages::ToggleSidebar => {
+                self.is_sidebar_open = !self.is_sidebar_open;
+
+                Command::none()
+            }
+            Messages::RefreshTree => match self.tree.as_ref() {
+                Some(tree) => Command::batch(
+                    tree.visible_directories()
+                        .into_iter()
+                        .map(|directory| self.list_directory(directory)),
+                ),
+                None => Command::none(),
+            },
+            Messages::TreeListed(directory, result) => {
+                let Some(tree) = self.tree.as_mut() else {
+                    return Command::none();
+                };
+
+                match result {
+                    Ok(entries) => tree.set_children(directory, entries),
+                    Err(_) if directory != tree.root() => tree.forget(&directory),
+                    Err(err) => self.notify_error(err, None),
+                }
+
+                Command::none()
+            }
+            Messages::ToggleTreeDirectory(directory) => {
+                let Some(tree) = self.tree.as_mut() else {
+                    return Command::none();
+                };
+
+                if tree.toggle(directory.clone()) {
+                    self.list_directory(directory)
+                } else {
+                    Command::none()
+                }
+            }
+            Messages::OpenTreeFile(path) => self.load(path),
+            Messages::ShowQuickOpen => {
+                let Some(root) = self.workspace.clone() else {
+                    self.toasts
+                        .push(Severity::Info, "Open a folder to search its files");
+
+                    return Command::none();
+                };
+
+                let mut quick_open = QuickOpen::default();
+                let excluded = self.config().excluded;
+
+                quick_open.update(&root, &self.file_index);
+                self.quick_open = Some(quick_open);
+                self.is_indexing = true;
+
+                let index = self.spawn(
+                    "Indexing files",
+                    |_| async move {
+                        let files = search::walk(root.clone(), &excluded).await;
+
+                        (root, files)
+                    },
+                    |(root, files)| Messages::FilesIndexed(root, files),
+                );
+
+                Command::batch([text_input::focus(quick_open::input_id()), index])
+            }
+            Messages::CloseQuickOpen => {
+                self.quick_open = None;
+
+                Command::none()
+            }
+            Messages::QuickOpenChanged(query) => {
+                if let (Some(quick_open), Some(root)) =
+                    (self.quick_open.as_mut(), self.workspace.as_ref())
+                {
+                    quick_open.query = query;
+                    quick_open.update(root, &self.file_index);
+                }
+
+                Command::none()
+            }
+            Messages::QuickOpenKey(key_code) => {
+                let Some(quick_open) = self.quick_open.as_mut() else {
+                    return Command::none();
+                };
+
+                match key_code {
+                    keyboard::KeyCode::Up => quick_open.select_previous(),
+                    keyboard::KeyCode::Down => quick_open.select_next(),
+                    keyboard::KeyCode::Escape => self.quick_open = None,
+                    _ => {}
+                }
+
+                Command::none()
+            }
+            Messages::OpenQuickMatch(index) => {
+                if let Some(quick_open) = self.quick_open.as_mut() {
+                    quick_open.select(index);
+                }
+
+                self.update(Messages::SubmitQuickOpen)
+            }
+            Messages::SubmitQuickOpen => {
+                match self.quick_open.as_ref().and_then(QuickOpen::target) {
+                    Some(path) => {
+                        self.quick_open = None;
+
+                        self.load(path)
+                    }
+                    None => Command::none(),
+                }
+            }
+            Messages::FilesIndexed(root, Ok(files)) => {
+                self.is_indexing = false;
+
+                if self.workspace.as_ref() == Some(&root) {
+                    self.file_index = files;
+
+                    if let Some(quick_open) = self.quick_open.as_mut() {
+                        quick_open.update(&root, &self.file_index);
+                    }
+                }
+
+                Command::none()
+            }
+            Messages::FilesIndexed(_, Err(err)) => {
+                self.is_indexing = false;
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::SelectTab(index) => {
+                self.is_welcome_open = false;
+                self.activate(index);
+
+                Command::none()
+            }
+            Messages::CloseTab(index) => {
+                self.activate(index);
+
+                self.guard(Pending::Close)
+            }
+            Messages::FileOpened(Err(err)) => {
+                self.pending_jump = None;
+
+                if let Error::FileFailed(path, _) = &err {
+                    self.startup_files.retain(|target| target.path != *path);
+                }
+
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::FileSaved(Ok((path, stamp))) => {
+                let reload_config = match self.workspace.clone() {
+                    Some(root) if config::project_file(&root) == path => {
+                        self.load_project_config(root)
+                    }
+                    _ => Command::none(),
+                };
+
+                let extension = self.extension();
+
+                self.toasts
+                    .push(Severity::Success, format!("Saved {}", path.display()));
+
+                // Saved under a new name, the file is opened afresh with
+                // whichever server handles it now.
+                if self.buffer().path.as_ref() != Some(&path) {
+                    let buffer = self.buffer();
+
+                    if let (Some(client), Some(previous)) = (self.client(buffer), &buffer.path) {
+                        client.close(previous);
+                    }
+
+                    self.buffer_mut().synced = None;
+                }
+
+                // A copy of a remote file goes back up to its host.
+                let upload = if self.remote_files.contains_key(&path) {
+                    self.update(Messages::UploadRemote(path.clone()))
+                } else {
+                    Command::none()
+                };
+
+                self.buffer_mut().path = Some(path);
+                self.buffer_mut().stamp = Some(stamp);
+                self.buffer_mut().mark_saved();
+
+                if self.extension() != extension {
+                    self.toasts.push(
+                        Severity::Info,
+                        format!("Language set to {}", language::name(&self.extension())),
+                    );
+                }
+                self.remember_view();
+
+                let resume = match self.after_save.take() {
+                    Some(pending) => self.guard(pending),
+                    None => Command::none(),
+                };
+
+                Command::batch([
+                    self.save_state(),
+                    self.read_head(self.active),
+                    self.read_status(),
+                    reload_config,
+                    resume,
+                    upload,
+                ])
+            }
+            // A save that is held back keeps whatever was waiting on it, so
+            // overwriting still carries on from there.
+            Messages::FileSaved(Err(Error::ChangedOnDisk(path))) => {
+                self.conflict = Some(path);
+
+                Command::none()
+            }
+            Messages::OverwriteConflict => match self.take_conflict() {
+                Some(path) => self.write_over(path, None),
+                None => Command::none(),
+            },
+            Messages::ReloadConflict => {
+                self.after_save = None;
+
+                match self.take_conflict() {
+                    Some(path) => {
+                        self.disk_changes.retain(|change| change.path != path);
+
+                        Command::perform(
+                            reopen_file(path, self.buffer().format.encoding),
+                            Messages::FileReinterpreted,
+                        )
+                    }
+                    None => Command::none(),
+                }
+            }
+            Messages::SaveConflictElsewhere => match self.take_conflict() {
+                Some(_) => self.update(Messages::SaveAs),
+                None => Command::none(),
+            },
+            Messages::CancelConflict => {
+                self.conflict = None;
+                self.after_save = None;
+
+                Command::none()
+            }
+            Messages::FileSaved(Err(err)) => {
+                self.after_save = None;
+                self.notify_error(err, Some(Messages::Save));
+
+                Command::none()
+            }
+            Messages::ThemeSelected(themes::Choice::Builtin(theme)) => {
+                self.buffer_mut().theme = theme;
+                self.custom_theme = None;
+
+                Command::none()
+            }
+            Messages::ThemeSelected(themes::Choice::Custom(name)) => {
+                self.custom_theme = Some(name);
+
+                Command::none()
+            }
+            Messages::CheckSettings => {
+                Command::perform(config::user_modified(), Messages::SettingsChecked)
+            }
+            Messages::SettingsChecked(modified) => {
+                if modified.is_none() || modified == self.settings_modified {
+                    return Command::none();
+                }
+
+                self.settings_modified = modified;
+
+                Command::perform(config::load_user(), Messages::SettingsReloaded)
+            }
+            Messages::SettingsReloaded(Ok(settings)) => {
+                if settings != self.settings {
+                    self.settings = settings;
+                    self.apply_settings();
+                    self.toasts.push(Severity::Info, "Settings reloaded");
+                }
+
+                Command::none()
+            }
+            Messages::SettingsReloaded(Err(err)) => {
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::ThemesLoaded(themes, errors) => {
+                self.themes = themes;
+
+                for err in errors {
+                    self.notify_error(err, None);
+                }
+
+                Command::none()
+            }
+            Messages::ToggleProjectSearch => {
+                self.project_search = match self.project_search {
+                    Some(_) => None,
+                    None => Some(ProjectSearch::default()),
+                };
+
+                Command::none()
+            }
+            Messages::ProjectSearchChanged(search) => {
+                if let Some(project) = self.project_search.as_mut() {
+                    project.search = search;
+                }
+
+                Command::none()
+            }
+            Messages::ProjectReplacementChanged(replacement) => {
+                if let Some(project) = self.project_search.as_mut() {
+                    project.replacement = replacement;
+                }
+
+                Command::none()
+            }
+            Messages::ToggleProjectRegex(is_regex) => {
+                if let Some(project) = self.project_search.as_mut() {
+                    project.is_regex = is_regex;
+                }
+
+                Command::none()
+            }
+            Messages::ProjectSearch => {
+                let Some(project) = self.project_search.clone() else {
+                    return Command::none();
+                };
+
+                if project.search.is_empty() {
+                    return Command::none();
+                }
+
+                let root = self.workspace.clone();
+                let excluded = self.config().excluded;
+
+                self.spawn(
+                    format!("Searching for \"{}\"", project.search),
+                    |job| search_in_folder(root, project.search, project.is_regex, excluded, job),
+                    Messages::ProjectSearched,
+                )
+            }
+            Messages::ProjectSearched(Ok(results)) => {
+                let command = self.adopt_workspace(results.root.clone());
+
+                if results.files.is_empty() {
+                    self.toasts.push(
+                        Severity::Info,
+                        format!("No matches for \"{}\"", results.search),
+                    );
+                }
+
+                self.search_results = Some(results);
+
+                command
+            }
+            Messages::ProjectSearched(Err(err)) => {
+                self.notify_error(err, Some(Messages::ProjectSearch));
+
+                Command::none()
+            }
+            Messages::ToggleSearchFile(index) => {
+                if let Some(file) = self
+                    .search_results
+                    .as_mut()
+                    .and_then(|results| results.files.get_mut(index))
+                {
+                    file.is_collapsed = !file.is_collapsed;
+                }
+
+                Command::none()
+            }
+            Messages::OpenSearchHit(path, line, column) => {
+                self.remember_jump();
+                self.open_at(path, line, column)
+            }
+            Messages::CopySearchResults => match self.search_results.as_ref() {
+                Some(results) => clipboard::write(results.export()),
+                None => Command::none(),
+            },
+            Messages::DismissSearchResults => {
+                self.search_results = None;
+
+                Command::none()
+            }
+            Messages::ProjectReplace => {
+                let Some(project) = self.project_search.clone() else {
+                    return Command::none();
+                };
+
+                if project.search.is_empty() {
+                    return Command::none();
+                }
+
+                let skip = self
+                    .buffers
+                    .iter()
+                    .filter(|buffer| buffer.is_dirty())
+                    .filter_map(|buffer| buffer.path.clone())
+                    .collect();
+
+                let root = self.workspace.clone();
+                let excluded = self.config().excluded;
+
+                self.spawn(
+                    format!("Replacing \"{}\"", project.search),
+                    |job| {
+                        replace_in_folder(
+                            root,
+                            project.search,
+                            project.replacement,
+                            project.is_regex,
+                            skip,
+                            excluded,
+                            job,
+                        )
+                    },
+                    Messages::ProjectReplaced,
+                )
+            }
+            Messages::ProjectReplaced(Ok(report)) => {
+                let command = Command::batch([
+                    self.reload_if_touched(&report),
+                    self.adopt_workspace(report.root.clone()),
+                ]);
+
+                self.search_results = None;
+                self.replace_report = Some(report);
+
+                command
+            }
+            Messages::ProjectReplaced(Err(err)) => {
+                self.notify_error(err, Some(Messages::ProjectReplace));
+
+                Command::none()
+            }
+            // The report stays until the revert has gone through, so a
+            // failed one can be tried again.
+            Messages::RevertReplace => match self.replace_report.clone() {
+                Some(report) => Command::perform(search::revert(report), Messages::ReplaceReverted),
+                None => Command::none(),
+            },
+            Messages::ReplaceReverted(Ok((report, skipped))) => {
+                self.replace_report = None;
+                self.toasts.push(
+                    Severity::Success,
+                    format!("Reverted replace in {} file(s)", report.files.len()),
+                );
+
+                if !skipped.is_empty() {
+                    self.toasts.push(
+                        Severity::Warning,
+                        format!(
+                            "{} file(s) changed since the replace and were left alone",
+                            skipped.len()
+                        ),
+                    );
+                }
+
+                self.reload_if_touched(&report)
+            }
+            Messages::ReplaceReverted(Err(err)) => {
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::DismissReplaceReport => {
+                self.replace_report = None;
+
+                Command::none()
+            }
+            Messages::ToggleFilter => {
+                if self.filter_pattern.take().is_none() {
+                    self.filter_pattern = Some(String::new());
+                } else {
+                    self.clear_filter();
+                }
+
+                Command::none()
+            }
+            Messages::FilterChanged(pattern) => {
+                self.filter_pattern = Some(pattern.clone());
+                self.apply_filter(pattern);
+
+                Command::none()
+            }
+            Messages::StateLoaded(session, recent, settings) => {
+                self.session = session;
+                self.recent = recent;
+
+                match settings {
+                    Ok(settings) => self.settings = settings,
+                    Err(err) => self.notify_error(err, None),
+                }
+
+                self.apply_settings();
+
+                let opened = if self.startup_files.is_empty() {
+                    self.start()
+                } else {
+                    let paths = self
+                        .startup_files
+                        .iter()
+                        .map(|target| target.path.clone())
+                        .collect::<Vec<_>>();
+
+                    Command::batch(paths.into_iter().map(|path| self.load(path)))
+                };
+
+                if !self.config().spell_check {
+                    return opened;
+                }
+
+                self.is_spell_checking = true;
+
+                Command::batch([opened, self.start_spell_check()])
+            }
+            Messages::OpenRecent(path) => self.load(path),
+            Messages::InstanceRequested(targets) => {
+                let paths = targets
+                    .iter()
+                    .map(|target| target.path.clone())
+                    .collect::<Vec<_>>();
+
+                self.startup_files.extend(targets);
+
+                Command::batch(
+                    paths
+                        .into_iter()
+                        .map(|path| self.load(path))
+                        .chain([window::gain_focus()]),
+                )
+            }
+            // Every dropped file arrives as an event of its own.
+            Messages::FileDropped(path) if path.is_dir() => {
+                self.update(Messages::SwitchProject(path))
+            }
+            Messages::FileDropped(path) => self.load(path),
+            Messages::ClearRecent => {
+                self.recent.clear_files();
+
+                self.save_state()
+            }
+            Messages::StateSaved(Ok(())) => Command::none(),
+            Messages::StateSaved(Err(err)) => {
+                self.error_log.record(&err);
+                self.toasts.push(
+                    Severity::Warning,
+                    format!("Could not save the session: {err}"),
+                );
+
+                Command::none()
+            }
+            Messages::OpenFolder => Command::perform(pick_folder(None), Messages::FolderPicked),
+            Messages::FolderPicked(Ok(root)) => self.update(Messages::SwitchProject(root)),
+            Messages::FolderPicked(Err(err)) => {
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::SwitchProject(root) => {
+                if self.workspace.as_ref() == Some(&root) {
+                    return Command::none();
+                }
+
+                self.guard(Pending::SwitchProject(root))
+            }
+            Messages::PromptSave => {
+                self.after_save = self.prompt.take();
+
+                self.update(Messages::Save)
+            }
+            Messages::PromptDiscard => match self.prompt.take() {
+                Some(pending) => {
+                    self.buffer_mut().mark_saved();
+
+                    self.guard(pending)
+                }
+                None => Command::none(),
+            },
+            Messages::PromptCancel => {
+                self.prompt = None;
+
+                Command::none()
+            }
+            Messages::ProjectConfigLoaded(Ok(overrides)) => {
+                self.project_config = overrides;
+
+                Command::none()
+            }
+            Messages::ProjectConfigLoaded(Err(err)) => {
+                self.project_config = Overrides::default();
+                self.notify_error(err, None);
+
+                Command::none()
+            }
+            Messages::ShowOpenBar => {
+                let base = self
+                    .workspace
+                    .clone()
+                    .or_else(|| {
+                        self.buffer()
+                            .path
+                            .as_deref()?
+                            .parent()
+                            .map(Path::to_path_buf)
+                    })
+                    .or_else(|| std::env::current_dir().ok())
+                    .unwrap_or_default();
+
+                self.open_bar = Some(OpenBar::new(base));
+
+                Command::batch([
+                    text_input::focus(open_bar_id()),
+                    text_input::move_cursor_to_end(open_bar_id()),
+                    self.refresh_open_bar(),
+                ])
+            }
+            Messages::CloseOpenBar => {
+                self.open_bar = None;
+
+                Command::none()
+            }
+            Messages::OpenPathChanged(input) => {
+                if let Some(bar) = self.open_bar.as_mut() {
+                    bar.input = input;
+                }
+
+                self.refresh_open_bar()
+            }
+            Messages::OpenCandidates(input, candidates) => {
+                if let Some(bar) = self.open_bar.as_mut().filter(|bar| bar.input == input) {
+                    bar.set_candidates(candidates);
+                }
+
+                Command::none()
+            }
+            Messages::OpenBarKey(key_code) => {
+                let Some(bar) = self.open_bar.as_mut() else {
+                    return Command::none();
+                };
+
+                match key_code {
+                    keyboard::KeyCode::Tab => {
+                        bar.complete();
+
+                        Command::batch([
+                            text_input::move_cursor_to_end(open_bar_id()),
+                            self.refresh_open_bar(),
+                        ])
+                    }
+                    keyboard::KeyCode::Up => {
+                        bar.select_previous();
+
+                        Command::none()
+                    }
+                    keyboard::KeyCode::Down => {
+                        bar.select_next();
+
+                        Command::none()
+                    }
+                    keyboard::KeyCode::Escape => self.update(Messages::CloseOpenBar),
+                    _ => Command::none(),
+                }
+            }
+            Messages::ToggleHiddenFiles(show_hidden) => {
+                if let Some(bar) = self.open_bar.as_mut() {
+                    bar.show_hidden = show_hidden;
+                }
+
+                self.refresh_open_bar()
+            }
+            Messages::OpenCandidate(index) => {
+                if let Some(bar) = self.open_bar.as_mut() {
+                    bar.selected = index;
+                }
+
+                self.update(Messages::SubmitOpenPath)
+            }
+            Messages::SubmitOpenPath => {
+                let Some(bar) = self.open_bar.as_mut() else {
+                    return Command::none();
+                };
+
+                match bar.target() {
+                    Some((directory, true)) => {
+                        bar.enter(&directory);
+
+                        Command::batch([
+                            text_input::move_cursor_to_end(open_bar_id()),
+                            self.refresh_open_bar(),
+                        ])
+                    }
+                    Some((path, false)) => {
+                        self.open_bar = None;
+
+                        self.load(path)
+                    }
+                    None => Command::none(),
+                }
+            }
+        }
+    }
+
+    fn layout(&self) -> Element<'_, Messages> {
+        let controls = row![
+            action(new_icon(), "Create a new file", Some(Messages::New)),
+            action(open_icon(), "Open file", Some(Messages::Open)),
+            pick_list(
+                self.recent
+                    .files()
+                    .iter()
+                    .take(RecentChoice::SHOWN)
+                    .map(|file| RecentChoice::File(file.path.clone()))
+                    .chain((!self.recent.files().is_empty()).then_some(RecentChoice::Clear))
+                    .collect::<Vec<_>>(),
+                None,
+                RecentChoice::message
+            )
+            .placeholder("Open recent…"),
+            action(
+                text("Path").size(14).into(),
+                "Open path…",
+                Some(Messages::ShowOpenBar)
+            ),
+            action(
+                save_icon(),
+                "Save file",
+                self.buffer().is_dirty().then_some(Messages::Save)
+            ),
+            action(
+                text("A→B").size(14).into(),
+                "Search and replace in folder",
+                Some(Messages::ToggleProjectSearch)
+            ),
+            action(
+                text("Filter").size(14).into(),
+                "Filter lines",
+                Some(Messages::ToggleFilter)
+            ),
+            action(
+                text("Folder").size(14).into(),
+                "Open folder",
+                Some(Messages::OpenFolder)
+            ),
+            action(
+                text("Files").size(14).into(),
+                "Toggle file tree",
+                self.tree.is_some().then_some(Messages::ToggleSidebar)
+            ),
+            horizontal_space(Length::Fill),
+            pick_list(
+                self.recent
+                    .folders()
+                    .iter()
+                    .cloned()
+                    .map(Project)
+                    .collect::<Vec<_>>(),
+                self.workspace.clone().map(Project),
+                |project| Messages::SwitchProject(project.0)
+            )
+            .placeholder("Switch project…"),
+            pick_list(
+                highlighter::Theme::ALL
+                    .iter()
+                    .copied()
+                    .map(themes::Choice::Builtin)
+                    .chain(
+                        self.themes
+                            .iter()
+                            .map(|theme| themes::Choice::Custom(theme.name.clone()))
+                    )
+                    .collect::<Vec<_>>(),
+                Some(match self.custom_theme.clone() {
+                    Some(name) => themes::Choice::Custom(name),
+                    None => themes::Choice::Builtin(self.buffer().theme),
+                }),
+                Messages::ThemeSelected
+            )
+        ]
+        .spacing(10);
+
+        let panes = PaneGrid::new(&self.panes, |pane, &index, _is_maximized| {
+            self.pane(pane, index)
+        })
+        .on_click(Messages::FocusPane)
+        .on_resize(10, Messages::ResizePane)
+        .spacing(5);
+        let status_bar = {
+            let status = match (
+                self.buffer().path.as_deref().and_then(Path::to_str),
+                &self.buffer().url,
+            ) {
+                (Some(path), _) => text(path).size(14),
+                (None, Some(url)) => text(url).size(14),
+                (None, None) => text("New file"),
+            };
+
+            let position = {
+                let (line, column) = self.cursor();
+
+                button(text(format!("{}:{}", line + 1, column + 1)).size(14))
+                    .style(theme::Button::Text)
+                    .padding(0)
+                    .on_press(Messages::ShowGoToLine)
+            };
+
+            let indentation = {
+                let config = self.config();
+                let kind = if config.soft_tabs { "Spaces" } else { "Tabs" };
+
+                text(format!("{kind}: {}", config.tab_width))
+            };
+
+            let cursors = self.buffer().cursors.count();
+
+            let wrap = match self.buffer().wrap {
+                Wrap::Window => String::from("Wrap: window"),
+                Wrap::Column => format!("Wrap: {}", self.config().wrap_column),
+                Wrap::Off => String::from("Wrap: off"),
+            };
+            let wrap = button(text(wrap).size(14))
+                .style(theme::Button::Text)
+                .padding(0)
+                .on_press(Messages::CycleWrap);
+
+            let mut status_bar = row![status, horizontal_space(Length::Fill)].spacing(10);
+
+            if self.is_safe_mode {
+                status_bar = status_bar.push(text("Safe mode").size(14));
+            }
+
+            if self.buffer().is_read_only {
+                status_bar = status_bar.push(
+                    button(text("Read-only").size(14))
+                        .style(theme::Button::Text)
+                        .padding(0)
+                        .on_press(Messages::ToggleReadOnly),
+                );
+            }
+
+            if let Some(cleanup) = self.cleanup {
+                status_bar = status_bar.push(text(cleanup.to_string()).size(14));
+            }
+
+            if cursors > 1 {
+                status_bar = status_bar.push(text(format!("{cursors} cursors")).size(14));
+            }
+
+            let stats = &self.buffer().stats;
+
+            if let Some(selection) = stats.selection {
+                status_bar = status_bar.push(text(format!("Selected: {selection}")).size(14));
+            }
+
+            status_bar = status_bar.push(text(stats.total().to_string()).size(14));
+
+            if self.key_prefix.is_pending() {
+                status_bar =
+                    status_bar.push(text(format!("{} …", self.key_prefix.describe())).size(14));
+            }
+
+            if let Some(status) = self.jobs.status() {
+                status_bar = status_bar.push(
+                    button(text(status).size(14))
+                        .style(theme::Button::Text)
+                        .padding(0)
+                        .on_press(Messages::ToggleJobs),
+                );
+            }
+
+            if let Some(status) = self.git_status.as_ref() {
+                let branch = match status.changed {
+                    0 => status.branch.clone(),
+                    changed => format!("{} ({changed} changed)", status.branch),
+                };
+
+                status_bar = status_bar.push(
+                    button(text(branch).size(14))
+                        .style(theme::Button::Text)
+                        .padding(0)
+                        .on_press(Messages::ToggleSourceControl),
+                );
+            }
+
+            if !self.problems.is_empty() {
+                let count = self.problems.values().map(Vec::len).sum::<usize>();
+
+                status_bar = status_bar.push(
+                    button(text(format!("Problems: {count}")).size(14))
+                        .style(theme::Button::Text)
+                        .padding(0)
+                        .on_press(Messages::ToggleProblems),
+                );
+            }
+
+            let log = if self.error_log.is_empty() {
+                String::from("Log")
+            } else {
+                format!("Errors: {}", self.error_log.len())
+            };
+
+            status_bar = status_bar.push(
+                button(text(log).size(14))
+                    .style(theme::Button::Text)
+                    .padding(0)
+                    .on_press(Messages::ToggleErrorLog),
+            );
+
+            let Format {
+                encoding,
+                line_ending,
+            } = self.buffer().format;
+
+            status_bar = status_bar.push(
+                pick_list(
+                    &encoding::ALL[..],
+                    Some(encoding::Choice(encoding)),
+                    Messages::ConvertEncoding,
+                )
+                .text_size(14)
+                .padding([0, 5]),
+            );
+
+            if self.buffer().path.is_some() {
+                status_bar = status_bar.push(
+                    pick_list(&encoding::ALL[..], None, Messages::ReopenWithEncoding)
+                        .placeholder("Reopen with…")
+                        .text_size(14)
+                        .padding([0, 5]),
+                );
+            }
+
+            status_bar = status_bar.push(
+                pick_list(
+                    &LineEnding::ALL[..],
+                    Some(line_ending),
+                    Messages::ConvertLineEnding,
+                )
+                .text_size(14)
+                .padding([0, 5]),
+            );
+
+            if self.is_spell_checking {
+                status_bar = status_bar.push(
+                    pick_list(
+                        &self.dictionaries[..],
+                        self.dictionary
+                            .as_ref()
+                            .map(|dictionary| dictionary.language.clone()),
+                        Messages::DictionarySelected,
+                    )
+                    .placeholder("Dictionary")
+                    .text_size(14)
+                    .padding([0, 5]),
+                );
+            }
+
+            let extension = self.extension();
+
+            status_bar = status_bar.push(
+                pick_list(
+                    &language::ALL[..],
+                    language::Language::of(&extension),
+                    Messages::LanguageSelected,
+                )
+                .placeholder(language::name(&extension))
+                .text_size(14)
+                .padding([0, 5]),
+            );
+
+            status_bar = status_bar.push(
+                pick_list(
+                    &Whitespace::ALL[..],
+                    Some(self.whitespace),
+                    Messages::WhitespaceSelected,
+                )
+                .text_size(14)
+                .padding([0, 5]),
+            );
+
+            status_bar.push(wrap).push(indentation).push(position)
+        };
+
+        let mut content = column![controls].spacing(10);
+
+        if let Some(pending) = self.prompt.as_ref() {
+            content = content.push(
+                row![
+                    text(pending.describe()),
+                    horizontal_space(Length::Fill),
+                    button("Save").on_press(Messages::PromptSave),
+                    button("Discard").on_press(Messages::PromptDiscard),
+                    button("Cancel")
+                        .style(theme::Button::Secondary)
+                        .on_press(Messages::PromptCancel),
+                ]
+                .spacing(10),
+            );
+        }
+
+        if let Some(recovery) = self.recoveries.first() {
+            let name = recovery
+                .rescued
+                .path
+                .as_ref()
+                .map_or(String::from("an unsaved file"), |path| {
+                    path.display().to_string()
+                });
+
+            content = content.push(
+                row![
+                    text(format!(
+                        "Recovered unsaved changes to {name} after a crash."
+                    )),
+                    horizontal_space(Length::Fill),
+                    button("Restore").on_press(Messages::RestoreRecovery),
+                    button("Discard")
+                        .style(theme::Button::Secondary)
+                        .on_press(Messages::DiscardRecovery),
+                ]
+                .spacing(10),
+            );
+        }
+
+        if let Some(change) = self.disk_changes.first() {
+            content = content.push(
+                row![
+                    text(format!("{} changed on disk.", change.path.display())),
+                    horizontal_space(Length::Fill),
+                    button("Reload").on_press(Messages::ReloadFromDisk),
+                    button("Compare").on_press(Messages::CompareWithDisk),
+                    button("Keep mine")
+                        .style(theme::Button::Secondary)
+                        .on_press(Messages::KeepMine),
+                ]
+                .spacing(10),
+            );
+        }
+
+        if let Some(path) = self.conflict.as_ref() {
+            content = content.push(
+                row![
+                    text(format!(
+                        "{} was changed by another program since it was opened.",
+                        path.display()
+                    )),
+                    horizontal_space(Length::Fill),
+                    button("Overwrite").on_press(Messages::OverwriteConflict),
+                    button("Reload").on_press(Messages::ReloadConflict),
+                    button("Save as…").on_press(Messages::SaveConflictElsewhere),
+                    button("Cancel")
+                        .style(theme::Button::Secondary)
+                        .on_press(Messages::CancelConflict),
+                ]
+                .spacing(10),
+            );
+        }
+
+        if let Some(target) = self.overwrite.as_ref() {
+            let suggestion = target
+                .suggestion
+                .file_name()
+                .map_or(String::new(), |name| name.to_string_lossy().into_owned());
+
+            content = content.push(
+                row![
+                    text(format!("{} already exists.", target.path.display())),
+                    horizontal_space(Length::Fill),
+                    button("Overwrite").on_press(Messages::ConfirmOverwrite),
+                    button(text(format!("Save as {suggestion}")))
+                        .on_press(Messages::SaveAsSuggestion),
+                    button("Cancel")
+                        .style(theme::Button::Secondary)
+                        .on_press(Messages::CancelOverwrite),
+                ]
+                .spacing(10),
+            );
+        }
+
+        if let (Some(quick_open), Some(root)) = (self.quick_open.as_ref(), self.workspace.as_ref())
+        {
+            content = content.push(quick_open.view(root, self.is_indexing));
+        }
+
+        if let Some(bar) = self.open_bar.as_ref() {
+            content = content.push(open_bar(bar));
+        }
+
+        if let Some(find) = self.find.as_ref() {
+            content = content.push(find.view());
+        }
+
+        if let Some(input) = self.open_url.as_deref() {
+            content = content.push(fetch::view(input));
+        }
+
+        if let Some(input) = self.go_to_line.as_deref() {
+            content = content.push(go_to_line(input));
+        }
+
+        if let Some(name) = self.rename.as_deref() {
+            content = content.push(rename_bar(name));
+        }
+
+        if let Some(surround) = self.surround.as_ref() {
+            content = content.push(surround.view());
+        }
+
+        if self.is_compare_open {
+            let choices = self
+                .buffers
+                .iter()
+                .enumerate()
+                .filter(|&(index, _)| index != self.active)
+                .map(|(index, buffer)| CompareChoice(index, buffer.title()))
+                .collect::<Vec<_>>();
+
+            content = content.push(compare_bar(&self.buffer().title(), choices));
+        }
+
+        if let Some(comparison) = self.comparison.as_ref() {
+            content = content.push(comparison.view(self.theme().palette()));
+        }
+
+        if !self.buffer().conflicts.is_empty() && self.filter.is_none() {
+            content = content.push(conflict::view(&self.buffer().conflicts, self.cursor().0));
+        }
+
+        if let Some(message) = self.commit_message.as_deref() {
+            content = content.push(source_control(
+                self.git_status.as_ref(),
+                message,
+                self.buffer().path.is_some(),
+            ));
+        }
+
+        if let Some(project) = self.project_search.as_ref() {
+            let folder = button(
+                text(
+                    self.workspace
+                        .as_ref()
+                        .map_or(String::from("No folder chosen"), |root| {
+                            root.display().to_string()
+                        }),
+                )
+                .size(14),
+            )
+            .style(theme::Button::Text)
+            .on_press(Messages::OpenFolder);
+
+            content = content.push(
+                row![
+                    folder,
+                    text_input("Find in folder", &project.search)
+                        .on_input(Messages::ProjectSearchChanged)
+                        .on_submit(Messages::ProjectSearch),
+                    checkbox("Regex", project.is_regex, Messages::ToggleProjectRegex),
+                    button("Search").on_press(Messages::ProjectSearch),
+                    text_input("Replace with", &project.replacement)
+                        .on_input(Messages::ProjectReplacementChanged)
+                        .on_submit(Messages::ProjectReplace),
+                    button("Replace all").on_press(Messages::ProjectReplace),
+                ]
+                .spacing(10),
+            );
+        }
+
+        if let Some(results) = self.search_results.as_ref() {
+            content = content.push(search_results(results));
+        }
+
+        if let Some(references) = self.references.as_ref() {
+            content = content.push(references.view());
+        }
+
+        if let Some(rename) = self.rename_preview.as_ref() {
+            content = content.push(rename.view());
+        }
+
+        if let Some(pattern) = self.filter_pattern.as_ref() {
+            let lines = match self.filter.as_ref() {
+                Some(filter) => format!(
+                    "{} of {} lines",
+                    filter.visible_lines(),
+                    filter.total_lines()
+                ),
+                None => String::from("All lines"),
+            };
+
+            content = content.push(
+                row![
+                    text_input("Show only lines containing…", pattern)
+                        .on_input(Messages::FilterChanged),
+                    text(lines),
+                    button("Close").on_press(Messages::ToggleFilter),
+                ]
+                .spacing(10),
+            );
+        }
+
+        if let Some(report) = self.replace_report.as_ref() {
+            let files = report
+                .files
+                .iter()
+                .fold(column![].spacing(2), |files, file| {
+                    files.push(
+                        text(format!(
+                            "{} — line(s) {}",
+                            file.path.display(),
+                            file.describe_lines()
+                        ))
+                        .size(14),
+                    )
+                });
+
+            content = content.push(column![
+                row![
+                    text(report.summary()),
+                    horizontal_space(Length::Fill),
+                    button("Revert this replace").on_press(Messages::RevertReplace),
+                    button("Dismiss").on_press(Messages::DismissReplaceReport),
+                ]
+                .spacing(10),
+                scrollable(files).height(120),
+            ]);
+        }
+
+        if self.is_shortcuts_open {
+            content = content.push(self.keymap.cheat_sheet());
+        }
+
+        if self.jobs.is_open {
+            content = content.push(self.jobs.view());
+        }
+
+        if self.is_error_log_open {
+            content = content.push(self.error_log.view());
+        }
+
+        if self.is_problems_open {
+            content = content.push(problems(&self.problems));
+        }
+
+        if self.is_bookmarks_open {
+            content = content.push(bookmarks(&self.buffers));
+        }
+
+        if let Some(browser) = self.remote.as_ref() {
+            content = content.push(browser.view(self.config().remotes.into_keys().collect()));
+        }
+
+        if self.hud.is_open {
+            content = content.push(self.hud.view());
+        }
+
+        let content = content.push(tabs(&self.buffers, self.active)).push({
+            let editor = if self.is_welcome_open {
+                welcome(&self.recent)
+            } else {
+                panes.into()
+            };
+
+            let editor = match self.outline.as_deref() {
+                Some(query) => {
+                    let symbols = outline::view(&self.buffer().symbols, query, self.cursor().0);
+
+                    row![editor, symbols].spacing(10).into()
+                }
+                None => editor,
+            };
+
+            match self.tree.as_ref().filter(|_| self.is_sidebar_open) {
+                Some(tree) => row![tree.view(self.buffer().path.as_deref()), editor]
+                    .spacing(10)
+                    .into(),
+                None => editor,
+            }
+        });
+
+        let content = match self.terminal.as_ref() {
+            Some(terminal) => content.push(terminal.view()),
+            None => content,
+        };
+
+        container(content.push(self.toasts.view()).push(status_bar))
+            .padding(10)
+            .into()
+    }
+
+    fn cursor(&self) -> (usize, usize) {
+        let (line, column) = self.buffer().content.cursor_position();
+
+        match self.filter.as_ref() {
+            Some(filter) => (filter.real_line(line), column),
+            None => (line, column),
+        }
+    }
+
+    fn remember_view(&mut self) {
+        let Some(path) = self.buffer().path.clone() else {
+            return;
+        };
+
+        let cursor = self.cursor();
+
+        if let Some(root) = self
+            .workspace
+            .as_ref()
+            .filter(|root| path.starts_with(root))
+        {
+            self.session.remember_project(root.clone(), path.clone());
+        }
+
+        self.recent.touch(path.clone(), cursor);
+        self.session.remember(
+            path,
+            ViewState {
+                cursor,
+                scroll: self.buffer().scroll,
+                wrap: self.buffer().wrap,
+            },
+        );
+    }
+
+    fn open_buffer(
+        &mut self,
+        path: PathBuf,
+        content: &str,
+        encoding: &'static Encoding,
+        stamp: Stamp,
+    ) -> Buffer {
+        let mut buffer = Buffer::open(path.clone(), content, encoding, stamp, self.buffer().theme);
+
+        if let Some(view) = self.session.view(&path) {
+            buffer.wrap = view.wrap;
+        }
+
+        if let Some((line, column)) = self.pending_jump.take() {
+            move_cursor(&mut buffer.content, line, column);
+        } else if let Some(view) = self.session.view(&path) {
+            restore_view(&mut buffer.content, view);
+            buffer.scroll = view.scroll;
+        } else if let Some((line, column)) = self.recent.cursor(&path) {
+            move_cursor(&mut buffer.content, line, column);
+        }
+
+        buffer
+    }
+
+    // Starting with --no-session leaves the previous session untouched, so
+    // it is still there the next time.
+    // Most settings are read through `config()` whenever they are needed,
+    // the rest has to be pushed out when the settings change.
+    fn apply_settings(&mut self) {
+        let config = self.config();
+        let (keymap, problems) = Keymap::configured(&config.keys);
+
+        self.keymap = keymap;
+        self.key_prefix.clear();
+
+        for problem in problems {
+            self.toasts.push(Severity::Warning, problem);
+        }
+
+        if !logging::set_level(&config.log_level) {
+            self.toasts.push(
+                Severity::Warning,
+                format!("Unknown log level \"{}\"", config.log_level),
+            );
+        }
+
+        let Some(name) = config.theme else {
+            return;
+        };
+
+        match highlighter::Theme::ALL
+            .iter()
+            .find(|theme| theme.to_string() == name)
+        {
+            Some(&theme) => {
+                for buffer in &mut self.buffers {
+                    buffer.theme = theme;
+                }
+
+                self.custom_theme = None;
+            }
+            None => self.custom_theme = Some(name),
+        }
+    }
+
+    fn remember_open_files(&mut self) {
+        if self.is_session_disabled {
+            return;
+        }
+
+        let files = self
+            .buffers
+            .iter()
+            .filter_map(|buffer| buffer.path.clone())
+            .collect();
+
+        self.session
+            .remember_open(files, self.buffer().path.clone());
+    }
+
+    fn save_state(&mut self) -> Command<Messages> {
+        self.remember_open_files();
+
+        Command::batch([
+            Command::perform(self.session.clone().save(), Messages::StateSaved),
+            Command::perform(self.recent.clone().save(), Messages::StateSaved),
+        ])
+    }
+
+    fn buffer(&self) -> &Buffer {
+        &self.buffers[self.active]
+    }
+
+    fn buffer_mut(&mut self) -> &mut Buffer {
+        &mut self.buffers[self.active]
+    }
+
+    fn document(&mut self) -> Document<'_> {
+        let buffer = &mut self.buffers[self.active];
+
+        Document::new(&mut buffer.content, &mut buffer.text, self.filter.as_mut())
+    }
+
+    // Drawn over the text, the guides first and the whitespace on top, with
+    // bookmarks along the left edge.
+    fn rules(
+        &self,
+        pane: pane_grid::Pane,
+        index: usize,
+        is_filtered: bool,
+    ) -> Vec<(Rectangle, Color)> {
+        let buffer = &self.buffers[index];
+        let palette = self.theme().palette();
+        let mut rules = self.indent_guides(pane, index, is_filtered);
+
+        if !is_filtered {
+            rules.extend(
+                buffer
+                    .bookmarks
+                    .lines()
+                    .iter()
+                    .filter(|&&line| line >= buffer.scroll)
+                    .map(|&line| {
+                        let top =
+                            EDITOR_PADDING / 2.0 + (line - buffer.scroll) as f32 * LINE_HEIGHT;
+
+                        (
+                            Rectangle::new(Point::new(0.0, top), Size::new(3.0, LINE_HEIGHT)),
+                            palette.primary,
+                        )
+                    }),
+            );
+        }
+
+        rules.extend(whitespace::marks(
+            &buffer.content,
+            self.whitespace,
+            editing::selection(&buffer.content),
+            buffer.scroll,
+            Vector::new(EDITOR_PADDING / 2.0, EDITOR_PADDING / 2.0),
+            Size::new(CHAR_WIDTH, LINE_HEIGHT),
+            (
+                Color {
+                    a: 0.35,
+                    ..palette.text
+                },
+                Color {
+                    a: 0.2,
+                    ..palette.danger
+                },
+            ),
+        ));
+
+        rules
+    }
+
+    // Faint at every level, and a little stronger for the block the cursor
+    // is in when the pane has the focus.
+    fn indent_guides(
+        &self,
+        pane: pane_grid::Pane,
+        index: usize,
+        is_filtered: bool,
+    ) -> Vec<(Rectangle, Color)> {
+        if !self.config().indent_guides || is_filtered {
+            return Vec::new();
+        }
+
+        let buffer = &self.buffers[index];
+        let text = self.theme().palette().text;
+        let active = buffer.active_guide.filter(|_| pane == self.focus);
+
+        buffer
+            .guides
+            .iter()
+            .enumerate()
+            .map(|(at, guide)| {
+                let top = guide.start as f32 - buffer.scroll as f32;
+                let rule = Rectangle::new(
+                    Point::new(
+                        EDITOR_PADDING / 2.0 + guide.column as f32 * CHAR_WIDTH,
+                        EDITOR_PADDING / 2.0 + top * LINE_HEIGHT,
+                    ),
+                    Size::new(1.0, (guide.end - guide.start) as f32 * LINE_HEIGHT),
+                );
+                let alpha = if Some(at) == active { 0.45 } else { 0.15 };
+
+                (rule, Color { a: alpha, ..text })
+            })
+            .collect()
+    }
+
+    fn pane(&self, pane: pane_grid::Pane, index: usize) -> pane_grid::Content<'_, Messages> {
+        let buffer = &self.buffers[index];
+
+        // A filter leaves only some of the lines, which no longer line up
+        // with the markers or the conflicts.
+        let is_filtered = index == self.active && self.filter.is_some();
+
+        let editor = text_editor(&buffer.content)
+            .on_edit(move |action| Messages::EditPane(pane, action))
+            .highlight::<Timed<Matches<Highlighter>>>(
+                find::Settings {
+                    inner: highlighter::Settings {
+                        theme: buffer.theme,
+                        extension: buffer.extension(),
+                    },
+                    query: self
+                        .find
+                        .as_ref()
+                        .map_or(String::new(), |find| find.query.clone()),
+                    brackets: buffer
+                        .brackets
+                        .map_or(Vec::new(), |(bracket, partner)| vec![bracket, partner]),
+                    problems: self.problem_spans(index),
+                    conflicts: if is_filtered {
+                        Vec::new()
+                    } else {
+                        buffer.conflicts.clone()
+                    },
+                    misspelled: buffer.misspelled.clone(),
+                },
+                |highlight, _theme| highlight.to_format(|syntax| syntax.to_format()),
+            );
+
+        // Lines only wrap at the width the editor is given, so a column limit
+        // narrows it and turning wrapping off widens it past the longest line.
+        let line_width = buffer.longest_line as f32 * CHAR_WIDTH + EDITOR_PADDING;
+        let editor: Element<'_, Messages> = match buffer.wrap {
+            Wrap::Window => editor.into(),
+            Wrap::Column => container(editor)
+                .max_width(self.config().wrap_column as f32 * CHAR_WIDTH + EDITOR_PADDING)
+                .into(),
+            Wrap::Off => scrollable(container(editor).width(line_width))
+                .direction(scrollable::Direction::Horizontal(
+                    scrollable::Properties::default(),
+                ))
+                .into(),
+        };
+
+        // Popups hang below the start of the word they are about, the
+        // completions taking the place of a hover.
+        let below = |(line, column): Position| {
+            Vector::new(
+                EDITOR_PADDING / 2.0 + column as f32 * CHAR_WIDTH,
+                EDITOR_PADDING / 2.0
+                    + (line + 1).saturating_sub(buffer.scroll) as f32 * LINE_HEIGHT,
+            )
+        };
+
+        // What is drawn over the text goes by one row to a line, which no
+        // longer holds once a line wraps, so it is left out until none do.
+        let is_wrapped = buffer.wrap != Wrap::Off && self.wrapped_panes.contains(&pane);
+
+        let anchored = Anchored::new(editor);
+        let anchored = if buffer.wrap == Wrap::Off {
+            anchored
+        } else {
+            anchored.on_wrap(line_width, move |is_wrapped| {
+                Messages::PaneWrapped(pane, is_wrapped)
+            })
+        };
+        let anchored = if is_wrapped {
+            anchored
+        } else {
+            anchored
+                .on_hover(
+                    Vector::new(EDITOR_PADDING / 2.0, EDITOR_PADDING / 2.0),
+                    Size::new(CHAR_WIDTH, LINE_HEIGHT),
+                    move |cell| Messages::Hovered(pane, cell),
+                )
+                .rules(self.rules(pane, index, is_filtered))
+        };
+
+        // Blame for the current line follows a few spaces after its end.
+        let cursor = buffer.content.cursor_position();
+        let inline_blame = buffer
+            .blame
+            .as_ref()
+            .filter(|_| self.is_blame_inline && pane == self.focus && !is_filtered && !is_wrapped)
+            .and_then(|blame| blame.line(cursor.0))
+            .map(|commit| {
+                let end = buffer
+                    .content
+                    .line(cursor.0)
+                    .map_or(0, |line| line.chars().count());
+                let faded = Color {
+                    a: 0.5,
+                    ..self.theme().palette().text
+                };
+
+                (
+                    text(commit.describe())
+                        .size(14)
+                        .style(theme::Text::Color(faded)),
+                    Vector::new(
+                        EDITOR_PADDING / 2.0 + (end + 4) as f32 * CHAR_WIDTH,
+                        EDITOR_PADDING / 2.0
+                            + cursor.0.saturating_sub(buffer.scroll) as f32 * LINE_HEIGHT,
+                    ),
+                )
+            });
+
+        let spelling = self
+            .spelling
+            .as_ref()
+            .filter(|spelling| spelling.pane == pane);
+
+        let clip_picker = self.clip_picker.filter(|(at, _)| *at == pane);
+
+        let editor: Element<'_, Messages> = match (
+            self.completion.as_ref(),
+            self.hover.as_ref(),
+            spelling,
+            clip_picker,
+        ) {
+            (Some(popup), ..) if pane == self.focus => anchored
+                .floating(popup.view(), below(popup.start), LINE_HEIGHT)
+                .into(),
+            (_, Some(hover), ..) if hover.pane == pane => anchored
+                .floating(hover.view(), below(hover.start), LINE_HEIGHT)
+                .into(),
+            (_, _, Some(spelling), _) => anchored
+                .floating(spelling.view(), below(spelling.start), LINE_HEIGHT)
+                .into(),
+            (.., Some((_, cursor))) => anchored
+                .floating(self.clips.view(), below(cursor), LINE_HEIGHT)
+                .into(),
+            _ => match inline_blame {
+                Some((annotation, offset)) => anchored.floating(annotation, offset, 0.0).into(),
+                None => anchored.into(),
+            },
+        };
+
+        let editor: Element<'_, Messages> = if buffer.gutter.is_tracked() && !is_filtered {
+            row![
+                git::view(
+                    &buffer.gutter,
+                    buffer.scroll,
+                    LINE_HEIGHT,
+                    EDITOR_PADDING / 2.0
+                ),
+                editor
+            ]
+            .into()
+        } else {
+            editor
+        };
+
+        let editor: Element<'_, Messages> = match buffer.blame.as_ref() {
+            Some(blame) if self.is_blame_gutter && !is_filtered => row![
+                git::blame_view(blame, buffer.scroll, LINE_HEIGHT, EDITOR_PADDING / 2.0),
+                editor
+            ]
+            .spacing(5)
+            .into(),
+            _ => editor,
+        };
+
+        let editor: Element<'_, Messages> = if self.config().minimap {
+            row![
+                editor,
+                minimap::view(&buffer.minimap, pane, buffer.scroll, LINE_HEIGHT)
+            ]
+            .into()
+        } else {
+            editor
+        };
+
+        let controls = row![
+            button(text("Split right").size(12))
+                .style(theme::Button::Text)
+                .on_press(Messages::SplitPane(pane_grid::Axis::Vertical)),
+            button(text("Split down").size(12))
+                .style(theme::Button::Text)
+                .on_press(Messages::SplitPane(pane_grid::Axis::Horizontal)),
+        ]
+        .spacing(5);
+        let controls = if self.panes.len() > 1 {
+            controls.push(
+                button(text("×").size(12))
+                    .style(theme::Button::Text)
+                    .on_press(Messages::ClosePane),
+            )
+        } else {
+            controls
+        };
+
+        let content = pane_grid::Content::new(editor).title_bar(
+            pane_grid::TitleBar::new(text(buffer.title()).size(14))
+                .controls(controls)
+                .padding(5),
+        );
+
+        if pane == self.focus {
+            content.style(theme::Container::Box)
+        } else {
+            content
+        }
+    }
+
+    fn count_matches(&mut self) {
+        if self.find.is_none() {
+            return;
+        }
+
+        let document = self.document();
+        let text = document.text();
+        let selection = document.selection();
+
+        // The match that is selected is counted from the top.
+        if let Some(find) = self.find.as_mut() {
+            let matches = find::matches(&text, &find.query)
+                .into_iter()
+                .filter(|&range| find::is_within(range, find.scope))
+                .collect::<Vec<_>>();
+
+            find.count = matches.len();
+            find.current = selection
+                .and_then(|selection| matches.iter().position(|&found| found == selection))
+                .map(|index| index + 1);
+        }
+    }
+
+    // Refreshes are throttled, so a visible minimap can still be behind the
+    // last edit.
+    fn is_minimap_stale(&self) -> bool {
+        self.config().minimap
+            && self
+                .panes
+                .iter()
+                .any(|(_, &index)| self.buffers[index].is_minimap_stale())
+    }
+
+    fn focus_pane(&mut self, pane: pane_grid::Pane) {
+        if let Some(&index) = self.panes.get(&pane) {
+            self.focus = pane;
+            self.activate(index);
+        }
+    }
+
+    fn position(&self, path: &Path) -> Option<usize> {
+        self.buffers
+            .iter()
+            .position(|buffer| buffer.path.as_deref() == Some(path))
+    }
+
+    // The filter rewrites the active content, so it is folded back in before
+    // another buffer takes its place.
+    // Switching files counts as a jump, unless it is going back or forward
+    // through them that does it.
+    fn activate(&mut self, index: usize) {
+        let is_navigating = std::mem::take(&mut self.is_navigating);
+
+        if index == self.active || index >= self.buffers.len() {
+            return;
+        }
+
+        if !is_navigating {
+            self.remember_jump();
+        }
+
+        self.remember_view();
+        self.clear_filter();
+        self.filter_pattern = None;
+        self.selection_history.clear();
+        self.cleanup = None;
+        self.active = index;
+
+        if let Some(shown) = self.panes.get_mut(&self.focus) {
+            *shown = index;
+        }
+    }
+
+    // An empty untitled buffer is taken over rather than left behind as a
+    // stray tab.
+    fn push_buffer(&mut self, buffer: Buffer) {
+        self.is_welcome_open = false;
+
+        if self.buffer().is_untouched() {
+            self.clear_filter();
+            self.filter_pattern = None;
+            self.buffers[self.active] = buffer;
+        } else {
+            self.buffers.push(buffer);
+            self.activate(self.buffers.len() - 1);
+        }
+    }
+
+    fn close_buffer(&mut self) {
+        let theme = self.buffer().theme;
+
+        self.is_welcome_open = false;
+        self.filter = None;
+        self.filter_pattern = None;
+        self.selection_history.clear();
+
+        let closed = self.active;
+
+        let removed = self.buffers.remove(closed);
+
+        if let Some((file, _)) = removed.swap {
+            crash::forget(&file);
+        }
+
+        self.close_document(&removed);
+
+        if let Some(path) = removed.path {
+            self.disk_changes.retain(|change| change.path != path);
+        }
+
+        if self.buffers.is_empty() {
+            self.buffers.push(Buffer::new(theme));
+        }
+
+        self.active = closed.min(self.buffers.len() - 1);
+
+        for (_, shown) in self.panes.iter_mut() {
+            if *shown > closed {
+                *shown -= 1;
+            } else if *shown == closed {
+                *shown = self.active;
+            }
+        }
+    }
+
+    fn close_all(&mut self) {
+        for buffer in self.buffers.drain(1..).collect::<Vec<_>>() {
+            if let Some((file, _)) = buffer.swap {
+                crash::forget(&file);
+            }
+
+            self.close_document(&buffer);
+        }
+
+        self.active = 0;
+        self.close_buffer();
+    }
+
+    // Each server is started for the project the file belongs to, or for
+    // the folder it is in when it lies outside the project.
+    fn language_server(&self, buffer: &Buffer) -> Option<(lsp::Id, &'static lsp::Server)> {
+        let path = buffer.path.as_deref()?;
+        let server = lsp::server(&buffer.extension())?;
+        let root = match self.tree.as_ref() {
+            Some(tree) if path.starts_with(tree.root()) => tree.root().to_owned(),
+            _ => path.parent()?.to_owned(),
+        };
+
+        Some(((server.name, root), server))
+    }
+
+    // Line numbers shift while a filter is applied, so its problems are left
+    // unmarked until it is cleared.
+    fn problem_spans(&self, index: usize) -> Vec<(Position, Position, lsp::Severity)> {
+        let buffer = &self.buffers[index];
+
+        let Some(diagnostics) = buffer
+            .path
+            .as_ref()
+            .and_then(|path| self.problems.get(path))
+        else {
+            return Vec::new();
+        };
+
+        if index == self.active && self.filter.is_some() {
+            return Vec::new();
+        }
+
+        let column = |(line, units): Position| {
+            let column = buffer
+                .content
+                .line(line)
+                .map_or(units, |text| lsp::column(&text, units));
+
+            (line, column)
+        };
+
+        diagnostics
+            .iter()
+            .map(|diagnostic| {
+                (
+                    column(diagnostic.start),
+                    column(diagnostic.end),
+                    diagnostic.severity,
+                )
+            })
+            .collect()
+    }
+
+    fn responded(&mut self, request: lsp::Request, response: lsp::Response) -> Command<Messages> {
+        match (request, response) {
+            (lsp::Request::Completion(path, (line, _)), lsp::Response::Completion(items)) => {
+                let (cursor_line, _) = self.cursor();
+
+                // The answer is dropped once the cursor has left the line
+                // it was asked for.
+                if self.buffer().path.as_ref() != Some(&path)
+                    || cursor_line != line
+                    || self.filter.is_some()
+                {
+                    return Command::none();
+                }
+
+                let (start, typed) = self.word_before_cursor();
+
+                self.completion = Popup::new(start, items, &typed);
+
+                Command::none()
+            }
+            (lsp::Request::Definition(..), lsp::Response::Locations(locations))
+                if locations.len() == 1 =>
+            {
+                let location = &locations[0];
+
+                self.update(Messages::OpenLocation(
+                    location.path.clone(),
+                    location.start,
+                ))
+            }
+            (lsp::Request::Definition(..), lsp::Response::Locations(locations))
+                if locations.is_empty() =>
+            {
+                self.toasts.push(Severity::Info, "No definition found");
+
+                Command::none()
+            }
+            (lsp::Request::Rename(.., name), lsp::Response::WorkspaceEdit(files)) => {
+                if files.is_empty() {
+                    self.toasts.push(Severity::Info, "Nothing to rename here");
+
+                    return Command::none();
+                }
+
+                self.clear_filter();
+                self.filter_pattern = None;
+
+                let open = files
+                    .iter()
+                    .map(|(path, _)| Some(self.buffers[self.position(path)?].content.text()))
+                    .collect();
+
+                Command::perform(
+                    rename::preview(name, files, open),
+                    Messages::RenamePreviewed,
+                )
+            }
+            (lsp::Request::Hover(path, sent), lsp::Response::Hover(markdown)) => {
+                let Some(target) = self.hover_target.filter(|target| target.sent == sent) else {
+                    return Command::none();
+                };
+
+                self.hover_target = None;
+
+                let Some(&index) = self.panes.get(&target.pane) else {
+                    return Command::none();
+                };
+
+                let buffer = &self.buffers[index];
+
+                if buffer.path.as_ref() != Some(&path) {
+                    return Command::none();
+                }
+
+                match markdown.filter(|markdown| !markdown.trim().is_empty()) {
+                    Some(markdown) => {
+                        let text = buffer
+                            .content
+                            .line(sent.0)
+                            .map(|line| String::from(&*line))
+                            .unwrap_or_default();
+                        let column = lsp::column(&text, sent.1);
+                        let start = completion::word_start(&text, column);
+                        let end = start
+                            + text
+                                .chars()
+                                .skip(start)
+                                .take_while(|&c| completion::is_word(c))
+                                .count();
+
+                        self.hover = Some(Hover::new(target.pane, (sent.0, start), end, &markdown));
+                    }
+                    None if target.is_explicit => {
+                        self.toasts.push(Severity::Info, "No information here");
+                    }
+                    None => {}
+                }
+
+                Command::none()
+            }
+            (lsp::Request::Rename(..), lsp::Response::Refused(message)) => {
+                self.toasts
+                    .push(Severity::Warning, format!("Cannot rename: {message}"));
+
+                Command::none()
+            }
+            // Others are refused mostly because the document changed while
+            // the server was still working on them.
+            (request, lsp::Response::Refused(message)) => {
+                tracing::debug!(
+                    ?request,
+                    reason = message,
+                    "language server refused a request"
+                );
+
+                Command::none()
+            }
+            (lsp::Request::References(..), lsp::Response::Locations(locations))
+                if locations.is_empty() =>
+            {
+                self.toasts.push(Severity::Info, "No references found");
+
+                Command::none()
+            }
+            (request, lsp::Response::Locations(locations)) => {
+                let noun = match request {
+                    lsp::Request::Definition(..) => "definition",
+                    _ => "reference",
+                };
+                let title = match locations.len() {
+                    1 => format!("1 {noun}"),
+                    count => format!("{count} {noun}s"),
+                };
+
+                let previews = locations
+                    .iter()
+                    .map(|location| {
+                        let index = self.position(&location.path)?;
+                        let line = self.buffers[index].content.line(location.start.0)?;
+
+                        Some(String::from(&*line))
+                    })
+                    .collect();
+
+                Command::perform(
+                    references::collect(title, locations, previews),
+                    Messages::ReferencesFound,
+                )
+            }
+            (request, response) => {
+                tracing::warn!(?request, ?response, "unexpected language server response");
+
+                Command::none()
+            }
+        }
+    }
+
+    // Completions asked for while typing fail quietly, since most files
+    // have no server at all.
+    fn request_completion(&mut self) -> bool {
+        self.send_request(lsp::Request::Completion)
+    }
+
+    fn request_at_cursor(&mut self, request: impl FnOnce(PathBuf, Position) -> lsp::Request) {
+        if !self.send_request(request) {
+            self.toasts.push(
+                Severity::Info,
+                "No language server is running for this file",
+            );
+        }
+    }
+
+    fn send_request(&mut self, request: impl FnOnce(PathBuf, Position) -> lsp::Request) -> bool {
+        let cursor = self.buffer().content.cursor_position();
+
+        self.send_request_at(self.active, cursor, request).is_some()
+    }
+
+    // The hover is only shown for the pane it was asked for, and only if no
+    // later request replaced it.
+    fn request_hover(
+        &mut self,
+        pane: pane_grid::Pane,
+        position: Position,
+        is_explicit: bool,
+    ) -> bool {
+        let Some(&index) = self.panes.get(&pane) else {
+            return false;
+        };
+
+        let sent = self.send_request_at(index, position, lsp::Request::Hover);
+
+        self.hover_target = sent.map(|sent| hover::Target {
+            pane,
+            sent,
+            is_explicit,
+        });
+
+        sent.is_some()
+    }
+
+    // Positions go out in UTF-16 columns, after any changes the server has
+    // not seen yet. Gives back the position that was sent.
+    fn send_request_at(
+        &mut self,
+        index: usize,
+        (line, column): Position,
+        request: impl FnOnce(PathBuf, Position) -> lsp::Request,
+    ) -> Option<Position> {
+        if index == self.active && self.filter.is_some() {
+            return None;
+        }
+
+        self.sync_documents();
+
+        let buffer = &self.buffers[index];
+        let client = self.client(buffer)?;
+        let path = buffer.path.clone()?;
+
+        let units = buffer
+            .content
+            .line(line)
+            .map_or(column, |text| lsp::units(&text, column));
+
+        client.request(request(path, (line, units)));
+
+        Some((line, units))
+    }
+
+    // The popup takes over the keys that move through it while it is open.
+    fn navigate_completion(&mut self, action: &text_editor::Action) -> bool {
+        let Some(popup) = self.completion.as_mut() else {
+            return false;
+        };
+
+        match action {
+            text_editor::Action::Move(text_editor::Motion::Up) => popup.select_previous(),
+            text_editor::Action::Move(text_editor::Motion::Down) => popup.select_next(),
+            text_editor::Action::Edit(text_editor::Edit::Enter) => self.accept_completion(None),
+            _ => return false,
+        }
+
+        true
+    }
+
+    // Typing a word narrows the open popup, or asks for completions once a
+    // word or member access begins. Anything else closes it.
+    fn follow_completion(&mut self, action: &text_editor::Action) {
+        let typed = match action {
+            text_editor::Action::Edit(text_editor::Edit::Insert(c)) => Some(*c),
+            text_editor::Action::Edit(text_editor::Edit::Backspace) => None,
+            text_editor::Action::Scroll { .. } => return,
+            _ => {
+                self.completion = None;
+                return;
+            }
+        };
+
+        let (start, word) = self.word_before_cursor();
+
+        match self.completion.as_mut() {
+            Some(popup) if popup.start == start && !word.is_empty() => {
+                if !popup.narrow(&word) {
+                    self.completion = None;
+                }
+            }
+            _ => {
+                self.completion = None;
+
+                let is_started = typed
+                    .is_some_and(|c| completion::is_word(c) || COMPLETION_TRIGGERS.contains(&c));
+                let after = self.config().word_completion_after;
+
+                // Without a server, words are offered once enough of one is
+                // typed. A setting of zero never offers them unasked.
+                if is_started
+                    && !self.request_completion()
+                    && after > 0
+                    && word.chars().count() >= after
+                {
+                    self.complete_words(start, &word);
+                }
+            }
+        }
+    }
+
+    // The position the word before the cursor starts at, and what of it is
+    // typed so far.
+    fn word_before_cursor(&self) -> (Position, String) {
+        let (line, column) = self.cursor();
+        let text = self.document().line(line);
+        let start = completion::word_start(&text, column);
+        let word = text
+            .chars()
+            .skip(start)
+            .take(column - start)
+            .collect::<String>();
+
+        ((line, start), word)
+    }
+
+    // Words from every open buffer, ranked by how near and how common they
+    // are in the one being edited.
+    fn complete_words(&mut self, start: Position, word: &str) {
+        let buffer = self.buffer();
+        let others = self
+            .buffers
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| index != self.active)
+            .map(|(_, buffer)| &buffer.content);
+        let items = words::completions(
+            &buffer.content,
+            buffer.content.cursor_position(),
+            others,
+            word,
+        );
+
+        self.completion = Popup::new(start, items, word);
+    }
+
+    // Additional edits, such as an import the completion needs, are applied
+    // from the bottom up so that none of them moves another.
+    fn accept_completion(&mut self, index: Option<usize>) {
+        let Some(popup) = self.completion.take() else {
+            return;
+        };
+
+        let Some(item) = index
+            .map_or(popup.selected(), |index| popup.get(index))
+            .cloned()
+        else {
+            return;
+        };
+
+        self.record(Change::Other);
+
+        let cursor = self.cursor();
+        let mut document = self.document();
+        let chars = |document: &Document<'_>, (line, units): Position| {
+            (line, lsp::column(&document.line(line), units))
+        };
+
+        let (start, end) = match item.range {
+            Some((start, end)) => (chars(&document, start), chars(&document, end).max(cursor)),
+            None => (popup.start, cursor),
+        };
+
+        let (text, stop) = if item.is_snippet {
+            completion::expand(&item.text)
+        } else {
+            (item.text.clone(), None)
+        };
+
+        let mut edits = item
+            .additional
+            .iter()
+            .map(|edit| {
+                (
+                    chars(&document, edit.start),
+                    chars(&document, edit.end),
+                    edit.text.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let shift = edits
+            .iter()
+            .filter(|(from, ..)| *from < start)
+            .map(|(from, to, text)| text.matches('\n').count() as isize - (to.0 - from.0) as isize)
+            .sum::<isize>();
+
+        edits.push((start, end, text.clone()));
+        edits.sort_by(|a, b| b.0.cmp(&a.0));
+
+        for (from, to, replacement) in &edits {
+            document.select((*from, *to));
+
+            if replacement.is_empty() {
+                if from != to {
+                    document.apply(text_editor::Action::Edit(text_editor::Edit::Delete));
+                }
+            } else {
+                document.insert(replacement);
+            }
+        }
+
+        if let Some((from, to)) = stop {
+            let line = start.0.saturating_add_signed(shift);
+            let at = |offset: usize| {
+                let before = text.chars().take(offset).collect::<String>();
+
+                match before.rfind('\n') {
+                    Some(index) => (
+                        line + before.matches('\n').count(),
+                        before[index + 1..].chars().count(),
+                    ),
+                    None => (line, start.1 + before.chars().count()),
+                }
+            };
+
+            document.select((at(from), at(to)));
+        }
+
+        self.count_matches();
+    }
+
+    fn client(&self, buffer: &Buffer) -> Option<&lsp::Client> {
+        self.language_servers.get(&self.language_server(buffer)?.0)
+    }
+
+    fn has_unsynced_documents(&self) -> bool {
+        self.buffers.iter().any(|buffer| {
+            self.client(buffer).is_some() && buffer.synced != Some(buffer.history.revision())
+        })
+    }
+
+    // Sent on a timer rather than on every keystroke. The active buffer
+    // waits while a filter hides some of its lines.
+    fn sync_documents(&mut self) {
+        for index in 0..self.buffers.len() {
+            if index == self.active && self.filter.is_some() {
+                continue;
+            }
+
+            let buffer = &self.buffers[index];
+            let revision = buffer.history.revision();
+
+            let (Some(client), Some(path)) = (self.client(buffer), buffer.path.as_deref()) else {
+                continue;
+            };
+
+            match buffer.synced {
+                None => client.open(path, buffer.content.text()),
+                Some(synced) if synced != revision => client.change(path, buffer.content.text()),
+                Some(_) => continue,
+            }
+
+            self.buffers[index].synced = Some(revision);
+        }
+    }
+
+    fn close_document(&mut self, buffer: &Buffer) {
+        let Some((id, _)) = self.language_server(buffer) else {
+            return;
+        };
+
+        if let (Some(client), Some(path)) = (self.language_servers.get(&id), &buffer.path) {
+            client.close(path);
+        }
+
+        let is_needed = self.buffers.iter().any(|other| {
+            self.language_server(other)
+                .is_some_and(|(other, _)| other == id)
+        });
+
+        if !is_needed {
+            self.release_language_server(&id);
+        }
+    }
+
+    // Problems the server reported stay listed only while it still runs.
+    fn release_language_server(&mut self, id: &lsp::Id) {
+        self.language_servers.remove(id);
+
+        let (name, root) = id;
+
+        for index in 0..self.buffers.len() {
+            if self
+                .language_server(&self.buffers[index])
+                .as_ref()
+                .map(|(id, _)| id)
+                == Some(id)
+            {
+                self.buffers[index].synced = None;
+            }
+        }
+
+        self.problems.retain(|path, _| {
+            let extension = path.extension().and_then(|extension| extension.to_str());
+            let is_served = extension
+                .and_then(lsp::server)
+                .is_some_and(|server| server.name == *name);
+
+            !(is_served && path.starts_with(root))
+        });
+    }
+
+    // Closing one buffer only asks about that buffer, while quitting or
+    // switching projects walks every unsaved buffer in turn.
+    fn guard(&mut self, pending: Pending) -> Command<Messages> {
+        let unsaved = match pending {
+            Pending::Close => self.buffer().has_unsaved_changes().then_some(self.active),
+            Pending::SwitchProject(_) | Pending::Quit => {
+                self.buffers.iter().position(Buffer::has_unsaved_changes)
+            }
+        };
+
+        match unsaved {
+            Some(index) => {
+                self.activate(index);
+                self.prompt = Some(pending);
+
+                Command::none()
+            }
+            None => self.resume(pending),
+        }
+    }
+
+    fn resume(&mut self, pending: Pending) -> Command<Messages> {
+        match pending {
+            Pending::SwitchProject(root) => self.switch_project(root),
+            Pending::Close => {
+                self.remember_view();
+                self.close_buffer();
+
+                self.save_state()
+            }
+            Pending::Quit => {
+                self.remember_view();
+                self.remember_open_files();
+
+                for buffer in &mut self.buffers {
+                    if let Some((file, _)) = buffer.swap.take() {
+                        crash::forget(&file);
+                    }
+                }
+
+                Command::perform(save_all(self.session.clone(), self.recent.clone()), |_| {
+                    Messages::Exit
+                })
+            }
+        }
+    }
+
+    fn start(&mut self) -> Command<Messages> {
+        match self.config().startup {
+            Startup::Empty => Command::none(),
+            Startup::LastSession if self.is_session_disabled => Command::none(),
+            Startup::LastSession => {
+                let workspace = match self.recent.folders().first().cloned() {
+                    Some(root) => self.adopt_workspace(root),
+                    None => Command::none(),
+                };
+                let open = self.session.open_files().to_vec();
+                let files = if !open.is_empty() {
+                    Command::perform(load_files(open), Messages::SessionRestored)
+                } else {
+                    match self.recent.files().first().map(|file| file.path.clone()) {
+                        Some(path) => self.load(path),
+                        None => Command::none(),
+                    }
+                };
+
+                Command::batch([workspace, files])
+            }
+            Startup::File(path) => self.load(paths::expand(&path)),
+            Startup::Folder(root) => self.switch_project(paths::expand(&root)),
+            Startup::Welcome => {
+                self.is_welcome_open = true;
+
+                Command::none()
+            }
+        }
+    }
+
+    fn switch_project(&mut self, root: PathBuf) -> Command<Messages> {
+        self.remember_view();
+        self.close_all();
+        self.search_results = None;
+        self.replace_report = None;
+        self.workspace = Some(root.clone());
+        self.tree = Some(Tree::new(root.clone()));
+        self.file_index.clear();
+        self.quick_open = None;
+        self.recent.touch_folder(root.clone());
+        self.project_config = Overrides::default();
+
+        let reopen = match self.session.project_file(&root) {
+            Some(path) => self.load(path),
+            None => Command::none(),
+        };
+
+        Command::batch([
+            self.save_state(),
+            self.list_directory(root.clone()),
+            self.load_project_config(root),
+            self.read_status(),
+            reopen,
+        ])
+    }
+
+    fn adopt_workspace(&mut self, root: PathBuf) -> Command<Messages> {
+        if self.workspace.is_some() {
+            return Command::none();
+        }
+
+        self.workspace = Some(root.clone());
+        self.tree = Some(Tree::new(root.clone()));
+        self.file_index.clear();
+        self.quick_open = None;
+        self.recent.touch_folder(root.clone());
+
+        Command::batch([
+            self.save_state(),
+            self.list_directory(root.clone()),
+            self.load_project_config(root),
+        ])
+    }
+
+    fn list_directory(&self, directory: PathBuf) -> Command<Messages> {
+        Command::perform(
+            tree::list(directory, self.config().excluded),
+            |(directory, entries)| Messages::TreeListed(directory, entries),
+        )
+    }
+
+    fn load_project_config(&self, root: PathBuf) -> Command<Messages> {
+        if self.is_safe_mode {
+            return Command::none();
+        }
+
+        Command::perform(config::load_project(root), Messages::ProjectConfigLoaded)
+    }
+
+    fn refresh_open_bar(&self) -> Command<Messages> {
+        let Some(bar) = self.open_bar.as_ref() else {
+            return Command::none();
+        };
+
+        let input = bar.input.clone();
+        let (directory, prefix) = bar.split();
+
+        Command::perform(
+            open_bar::candidates(directory, prefix, bar.show_hidden),
+            move |candidates| Messages::OpenCandidates(input, candidates),
+        )
+    }
+
+    fn spawn<T, F>(
+        &mut self,
+        label: impl Into<String>,
+        task: impl FnOnce(Handle) -> F,
+        map: impl FnOnce(T) -> Messages + Send + 'static,
+    ) -> Command<Messages>
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        let (id, job) = self.jobs.start(label);
+
+        Command::perform(task(job), move |output| {
+            Messages::JobFinished(id, Box::new(map(output)))
+        })
+    }
+
+    fn load(&mut self, path: PathBuf) -> Command<Messages> {
+        let label = format!(
+            "Opening {}",
+            path.file_name()
+                .map_or(String::new(), |name| name.to_string_lossy().into_owned())
+        );
+
+        self.spawn(label, |_| load_file(path), Messages::FileOpened)
+    }
+
+    // The file is taken to be indented this way from then on, whether or
+    // not anything had to change.
+    fn convert_indentation(&mut self, to: Indentation) {
+        let text = self.text();
+        let converted = indentation::convert(&text, to, self.config().tab_width);
+
+        self.buffer_mut().indentation = Some(to);
+
+        if converted == text {
+            return;
+        }
+
+        let (line, column) = self.cursor();
+
+        self.clear_filter();
+        self.filter_pattern = None;
+
+        let buffer = self.buffer_mut();
+
+        buffer.record(Change::Other);
+        buffer.replace_content(&converted);
+        move_cursor(&mut buffer.content, line, column);
+
+        self.toasts.push(
+            Severity::Success,
+            format!("Converted the indentation to {to}"),
+        );
+    }
+
+    // The selection, or with nothing selected the word under the cursor,
+    // along with its text.
+    fn selection_or_word(&mut self) -> Option<((Position, Position), String)> {
+        let document = self.document();
+        let cursor = document.cursor();
+        let text = document.text();
+
+        let (start, end) = document
+            .selection()
+            .filter(|(start, end)| start != end)
+            .or_else(|| editing::expanded(&text, (cursor, cursor)))?;
+
+        let chars = text.chars().collect::<Vec<_>>();
+        let starts = editing::line_starts(&chars);
+        let offset = |(line, column): Position| starts[line] + column;
+
+        Some((
+            (start, end),
+            chars[offset(start)..offset(end)].iter().collect(),
+        ))
+    }
+
+    // What was converted stays selected, so one case can follow another.
+    fn convert_case(&mut self, case: Case) {
+        let Some(((start, end), selected)) = self.selection_or_word() else {
+            return;
+        };
+
+        let converted = case::convert(&selected, case);
+
+        if converted == selected {
+            return;
+        }
+
+        let before = self.snapshot();
+        let mut document = self.document();
+
+        document.select((start, end));
+        document.insert(&converted);
+
+        let last = converted.rsplit('\n').next().unwrap_or_default();
+        let end = match converted.matches('\n').count() {
+            0 => (start.0, start.1 + last.chars().count()),
+            lines => (start.0 + lines, last.chars().count()),
+        };
+
+        document.select((start, end));
+        self.buffer_mut().history.record(Change::Other, || before);
+    }
+
+    // What was surrounded stays selected, without the pair.
+    fn surround_selection(&mut self, (opening, closing): (String, String)) {
+        let Some(((start, end), selected)) = self.selection_or_word() else {
+            self.toasts.push(Severity::Info, "Nothing to surround");
+
+            return;
+        };
+
+        let width = opening.chars().count();
+
+        self.record(Change::Other);
+
+        let mut document = self.document();
+
+        document.select((start, end));
+        document.insert(&format!("{opening}{selected}{closing}"));
+        document.select((
+            (start.0, start.1 + width),
+            (end.0, end.1 + if end.0 == start.0 { width } else { 0 }),
+        ));
+    }
+
+    // The closing side goes first, so where the opening one is still holds.
+    // Without a pair to take their place, both are removed.
+    fn change_surrounding(&mut self, pair: Option<(String, String)>) {
+        let text = self.document().text();
+        let cursor = self.document().cursor();
+        let range = self.document().selection().unwrap_or((cursor, cursor));
+
+        let Some((opened, closed)) = editing::surrounding(&text, range) else {
+            self.toasts
+                .push(Severity::Info, "No brackets or quotes around the cursor");
+
+            return;
+        };
+
+        let (opening, closing) = pair.unwrap_or_default();
+        let width = opening.chars().count();
+
+        self.record(Change::Other);
+
+        let mut document = self.document();
+
+        for (at, replacement) in [(closed, closing), (opened, opening)] {
+            document.select((at, (at.0, at.1 + 1)));
+
+            if replacement.is_empty() {
+                document.apply(text_editor::Action::Edit(text_editor::Edit::Delete));
+            } else {
+                document.insert(&replacement);
+            }
+        }
+
+        let end = if closed.0 == opened.0 {
+            (closed.0, closed.1 - 1 + width)
+        } else {
+            closed
+        };
+
+        document.select(((opened.0, opened.1 + width), end));
+    }
+
+    // The selected lines are rewritten as one edit and stay selected, while
+    // with nothing selected the whole buffer is, short of the empty line
+    // after a final newline.
+    fn transform_lines(&mut self, transform: Transform) {
+        let before = self.snapshot();
+        let mut document = self.document();
+        let last = document.line_count().saturating_sub(1);
+        let (start, end) = document
+            .selected_lines()
+            .filter(|(start, end)| start != end)
+            .unwrap_or((
+                0,
+                last - usize::from(last > 0 && document.line(last).is_empty()),
+            ));
+
+        let lines = (start..=end)
+            .map(|line| document.line(line))
+            .collect::<Vec<_>>();
+        let transformed = lines::transform(lines.clone(), transform);
+
+        if transformed == lines {
+            return;
+        }
+
+        document.select_lines(start, end);
+        document.insert(&transformed.join("\n"));
+        document.select_lines(start, start + transformed.len() - 1);
+        self.buffer_mut().history.record(Change::Other, || before);
+
+        if transform == Transform::Unique {
+            self.toasts.push(
+                Severity::Info,
+                format!(
+                    "Removed {} duplicate lines",
+                    lines.len() - transformed.len()
+                ),
+            );
+        }
+    }
+
+    // How many closers the editor put in itself on the line is counted, so
+    // only those are typed over.
+    fn auto_close(&mut self, c: char) -> bool {
+        if !self.config().auto_close || self.filter.is_some() {
+            return false;
+        }
+
+        let buffer = self.buffer();
+        let (line, column) = buffer.content.cursor_position();
+        let pending = match buffer.auto_closed {
+            Some((at, count)) if at == line => count,
+            _ => 0,
+        };
+        let text = buffer
+            .content
+            .line(line)
+            .map_or(Vec::new(), |text| text.chars().collect());
+        let selection = editing::selection(&buffer.content).filter(|(start, end)| start != end);
+        let selected = buffer.content.selection().unwrap_or_default();
+
+        match editing::pairing(c, &text, column, selection.is_some(), pending > 0) {
+            None => return false,
+            Some(Pairing::Overtype) => {
+                self.buffer_mut().history.seal();
+                self.document()
+                    .apply(text_editor::Action::Move(text_editor::Motion::Right));
+                self.buffer_mut().auto_closed = Some((line, pending - 1));
+            }
+            Some(Pairing::Wrap(closing)) => {
+                let Some((start, end)) = selection else {
+                    return false;
+                };
+
+                self.record(Change::Other);
+
+                let mut document = self.document();
+
+                document.select((start, end));
+                document.insert(&format!("{c}{selected}{closing}"));
+                document.select((
+                    (start.0, start.1 + 1),
+                    (end.0, end.1 + usize::from(end.0 == start.0)),
+                ));
+            }
+            Some(Pairing::Close(closing)) => {
+                self.record(Change::Insert);
+
+                let mut document = self.document();
+
+                document.insert(&format!("{c}{closing}"));
+                document.apply(text_editor::Action::Move(text_editor::Motion::Left));
+                self.buffer_mut().auto_closed = Some((line, pending + 1));
+            }
+        }
+
+        true
+    }
+
+    // Going to hex dumps the bytes the text is saved as, and coming back
+    // reads the dump as the bytes of the text.
+    fn convert_hex(&mut self, to: &'static Encoding) {
+        let format = self.buffer().format;
+        let text = format.line_ending.apply(self.text());
+
+        let Some(bytes) = encoding::encode(&text, format.encoding) else {
+            let problem = if format.encoding == encoding::HEX {
+                String::from("Every byte of the dump has to be two hex digits")
+            } else {
+                format!(
+                    "The text cannot be written as {}",
+                    encoding::name(format.encoding)
+                )
+            };
+
+            self.toasts.push(Severity::Warning, problem);
+
+            return;
+        };
+
+        self.clear_filter();
+        self.filter_pattern = None;
+        self.buffer_mut()
+            .reinterpret(&encoding::decode(bytes, to), to);
+    }
+
+    fn jump_to(&mut self, position: Position) {
+        self.remember_jump();
+
+        let (line, _) = editing::go_to(&mut self.document(), position);
+        let buffer = self.buffer_mut();
+        let top = line.saturating_sub(GO_TO_CONTEXT);
+
+        buffer.content.edit(text_editor::Action::Scroll {
+            lines: top as i32 - buffer.scroll as i32,
+        });
+        buffer.scroll = top;
+    }
+
+    // A file named on the command line goes to the spot it was asked for
+    // once it is open, whether it was already or not.
+    fn arrive(&mut self, path: &Path) {
+        let Some(index) = self
+            .startup_files
+            .iter()
+            .position(|target| target.path == path)
+        else {
+            return;
+        };
+
+        let target = self.startup_files.swap_remove(index);
+
+        self.buffer_mut().is_read_only |= target.is_read_only;
+
+        if let Some(position) = target.position {
+            self.clear_filter();
+            self.jump_to(position);
+        }
+    }
+
+    fn list_remote(
+        &mut self,
+        name: String,
+        profile: remote::Profile,
+        directory: String,
+    ) -> Command<Messages> {
+        let browser = self.remote.get_or_insert_with(remote::Browser::default);
+
+        browser.profile = Some(name.clone());
+        browser.is_loading = true;
+
+        Command::perform(remote::list(profile, directory), move |result| {
+            Messages::RemoteListed(name, result)
+        })
+    }
+
+    // The unit a level of indentation takes in the current buffer.
+    fn indent_unit(&self) -> String {
+        let config = self.config();
+
+        if config.soft_tabs {
+            " ".repeat(config.tab_width)
+        } else {
+            String::from("\t")
+        }
+    }
+
+    // With soft tabs and nothing selected, Tab goes on to the next stop
+    // with spaces. Extra cursors take the tab as it is.
+    fn indent_lines(&mut self, is_dedent: bool) -> bool {
+        let config = self.config();
+        let unit = config.tab_width;
+        let (line, column) = self.document().cursor();
+        let lines = self
+            .document()
+            .selected_lines()
+            .filter(|(start, end)| start != end);
+
+        if lines.is_none() && !is_dedent {
+            if !config.soft_tabs || !self.buffer().cursors.is_empty() {
+                return false;
+            }
+
+            let spaces = unit.max(1) - column % unit.max(1);
+
+            self.record(Change::Insert);
+            self.document().insert(&" ".repeat(spaces));
+
+            return true;
+        }
+
+        let indentation = self.indent_unit();
+
+        self.record(Change::Other);
+
+        let mut document = self.document();
+
+        match lines {
+            Some((start, end)) => {
+                if is_dedent {
+                    editing::dedent(&mut document, start, end, unit);
+                } else {
+                    editing::indent(&mut document, start, end, &indentation);
+                }
+
+                document.select_lines(start, end);
+            }
+            None => {
+                let width = editing::dedent_width(&document.line(line), unit);
+
+                editing::dedent(&mut document, line, line, unit);
+                document.move_to((line, column.saturating_sub(width)));
+            }
+        }
+
+        true
+    }
+
+    // Only buffers with unsaved changes keep a swap file, and it is only
+    // written again once they have changed since the last snapshot.
+    fn write_swaps(&mut self) -> Command<Messages> {
+        let active = (self.active, self.text());
+        let mut commands = Vec::new();
+
+        for (index, buffer) in self.buffers.iter_mut().enumerate() {
+            let revision = buffer.history.revision();
+
+            if !buffer.has_unsaved_changes() {
+                if let Some((file, _)) = buffer.swap.take() {
+                    crash::forget(&file);
+                }
+
+                continue;
+            }
+
+            if buffer.swap.as_ref().map(|(_, at)| *at) == Some(revision) {
+                continue;
+            }
+
+            let Some(file) = buffer
+                .swap
+                .take()
+                .map(|(file, _)| file)
+                .or_else(crash::swap_file)
+            else {
+                continue;
+            };
+
+            let rescued = Rescued {
+                path: buffer.path.clone(),
+                text: if index == active.0 {
+                    active.1.clone()
+                } else {
+                    buffer.content.text()
+                },
+            };
+
+            buffer.swap = Some((file.clone(), revision));
+            commands.push(Command::perform(
+                crash::write_swap(file, rescued),
+                Messages::SwapWritten,
+            ));
+        }
+
+        Command::batch(commands)
+    }
+
+    fn rescue(&self) {
+        for (index, buffer) in self.buffers.iter().enumerate() {
+            if !buffer.has_unsaved_changes() {
+                continue;
+            }
+
+            let rescued = Rescued {
+                path: buffer.path.clone(),
+                text: if index == self.active {
+                    self.text()
+                } else {
+                    buffer.content.text()
+                },
+            };
+
+            // The rescued copy is newer than any snapshot, which would only
+            // be offered a second time otherwise.
+            if let Some(file) = crash::rescue(&rescued) {
+                tracing::error!(file = %file.display(), "rescued unsaved changes");
+
+                if let Some((swap, _)) = &buffer.swap {
+                    crash::forget(swap);
+                }
+            }
+        }
+    }
+
+    fn notify_error(&mut self, err: Error, retry: Option<Messages>) {
+        self.error_log.record(&err);
+
+        if let Error::DialogClosed | Error::Cancelled = err {
+            return;
+        }
+
+        self.toasts.push_with_action(
+            Severity::Error,
+            err.to_string(),
+            retry.map(|message| (String::from("Retry"), message)),
+        );
+    }
+
+    // Untitled buffers are left alone rather than asking where to put them.
+    fn autosave(&mut self) -> Command<Messages> {
+        let dirty = (0..self.buffers.len())
+            .filter(|&index| self.buffers[index].is_dirty())
+            .collect::<Vec<_>>();
+
+        let saves = dirty
+            .into_iter()
+            .filter_map(|index| {
+                let path = self.buffers[index].path.clone()?;
+                let text = if index == self.active {
+                    self.rope()
+                } else {
+                    self.buffers[index].rope()
+                };
+                let buffer = &self.buffers[index];
+                let revision = buffer.history.revision();
+                let format = buffer.format;
+
+                Some(Command::perform(
+                    save_file(path, text, format, buffer.stamp, backup::Policy::default()),
+                    move |result| Messages::Autosaved(revision, format, result),
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        Command::batch(saves)
+    }
+
+    // Formatting on save runs first, and the file is written once it is
+    // done, whether or not it worked.
+    fn write_to(&mut self, path: PathBuf) -> Command<Messages> {
+        if self.config().format_on_save {
+            if let Some(command) = self.format(Some(path.clone())) {
+                return command;
+            }
+        }
+
+        self.write_checked(path)
+    }
+
+    // Saving over the file that was opened first checks nobody else
+    // has written to it since.
+    fn write_checked(&mut self, path: PathBuf) -> Command<Messages> {
+        let expected = self
+            .buffer()
+            .stamp
+            .filter(|_| self.buffer().path.as_ref() == Some(&path));
+
+        self.write_over(path, expected)
+    }
+
+    // Autosaves leave the text alone and take no backups, since they happen
+    // while typing.
+    fn write_over(&mut self, path: PathBuf, expected: Option<Stamp>) -> Command<Messages> {
+        self.clean_up();
+
+        let format = self.buffer().format;
+        let config = self.config();
+        let backups = backup::Policy {
+            backups: config.backups,
+            limit: config.backup_limit,
+        };
+
+        Command::perform(
+            save_file(path, self.rope(), format, expected, backups),
+            Messages::FileSaved,
+        )
+    }
+
+    // The conflict may be about a file autosaved in the background, so its
+    // buffer is brought forward first.
+    fn take_conflict(&mut self) -> Option<PathBuf> {
+        let path = self.conflict.take()?;
+
+        self.activate(self.position(&path)?);
+
+        Some(path)
+    }
+
+    fn warn_untracked(&mut self, is_shown: bool) {
+        if is_shown && !self.buffer().gutter.is_tracked() {
+            self.toasts.push(
+                Severity::Info,
+                "Blame shows once the file has been committed to git",
+            );
+        }
+    }
+
+    // Only one file is blamed at a time, and the next waits for its answer,
+    // so typing does not start git again on every key.
+    fn request_blame(&mut self) -> Command<Messages> {
+        if !(self.is_blame_inline || self.is_blame_gutter) || self.blame_pending.is_some() {
+            return Command::none();
+        }
+
+        let candidates: Vec<usize> = if self.is_blame_gutter {
+            self.panes.iter().map(|(_, &index)| index).collect()
+        } else {
+            vec![self.active]
+        };
+
+        let Some(index) = candidates.into_iter().find(|&index| {
+            let buffer = &self.buffers[index];
+
+            buffer.gutter.is_tracked()
+                && buffer.blamed != Some(buffer.history.revision())
+                && !(index == self.active && self.filter.is_some())
+        }) else {
+            return Command::none();
+        };
+
+        let buffer = &self.buffers[index];
+
+        let Some(path) = buffer.path.clone() else {
+            return Command::none();
+        };
+
+        let revision = buffer.history.revision();
+        let text = buffer.content.text();
+
+        self.blame_pending = Some((path.clone(), revision));
+
+        Command::perform(git::blame(path.clone(), revision, text), move |result| {
+            Messages::Blamed(path, result)
+        })
+    }
+
+    // The open folder, or else the folder of the file being edited.
+    fn repository(&self) -> Option<PathBuf> {
+        self.workspace.clone().or_else(|| {
+            self.buffer()
+                .path
+                .as_deref()
+                .and_then(Path::parent)
+                .map(Path::to_path_buf)
+        })
+    }
+
+    // Folders outside of a repository have no status, which hides it.
+    fn read_status(&self) -> Command<Messages> {
+        let Some(directory) = self.repository() else {
+            return Command::none();
+        };
+
+        Command::perform(git::status(directory), |status| {
+            Messages::StatusRead(status.ok())
+        })
+    }
+
+    // Commits and checkouts made elsewhere may leave the files alone, so
+    // everything is compared again on coming back to the window.
+    fn read_repository(&self) -> Command<Messages> {
+        Command::batch(
+            (0..self.buffers.len())
+                .map(|index| self.read_head(index))
+                .chain(Some(self.read_status())),
+        )
+    }
+
+    // The dictionary is only loaded the first time it is needed, while the
+    // ones to choose from are looked for again each time.
+    fn start_spell_check(&mut self) -> Command<Messages> {
+        let listed = Command::perform(spelling::available(), Messages::DictionariesListed);
+
+        if self.dictionary.is_some() {
+            return listed;
+        }
+
+        Command::batch([listed, load_dictionary(self.config().dictionary)])
+    }
+
+    fn step_change(&mut self, is_forward: bool) -> Command<Messages> {
+        match self
+            .comparison
+            .as_mut()
+            .and_then(|comparison| comparison.step(is_forward))
+        {
+            Some(offset) => scrollable::snap_to(diff::scroll_id(), offset),
+            None => Command::none(),
+        }
+    }
+
+    // Wraps around at either end. The conflicts are found again first, as
+    // they are otherwise only brought up to date after each message.
+    fn step_conflict(&mut self, is_forward: bool) {
+        self.clear_filter();
+        self.filter_pattern = None;
+
+        let conflicts = conflict::find(&self.text());
+        let (line, _) = self.cursor();
+
+        let target = if is_forward {
+            conflicts
+                .iter()
+                .find(|conflict| conflict.start > line)
+                .or(conflicts.first())
+        } else {
+            conflicts
+                .iter()
+                .rev()
+                .find(|conflict| conflict.start < line)
+                .or(conflicts.last())
+        };
+
+        let Some(&conflict) = target else {
+            self.toasts.push(Severity::Info, "No merge conflicts");
+
+            return;
+        };
+
+        self.remember_jump();
+        self.reveal_line(conflict.start);
+    }
+
+    // Only buffers with a file can be found again once closed, so those are
+    // the ones remembered.
+    fn location(&self) -> Option<Location> {
+        Some((self.buffer().path.clone()?, self.cursor()))
+    }
+
+    fn remember_jump(&mut self) {
+        if let Some(location) = self.location() {
+            self.jumps.push(location);
+        }
+    }
+
+    fn navigate(&mut self, is_forward: bool) -> Command<Messages> {
+        let current = self.location();
+        let target = if is_forward {
+            self.jumps.forward(current)
+        } else {
+            self.jumps.back(current)
+        };
+
+        let Some((path, (line, column))) = target else {
+            return Command::none();
+        };
+
+        self.is_navigating = true;
+        self.open_at(path, line, column)
+    }
+
+    // A file that is no longer open is loaded again first.
+    fn open_at(&mut self, path: PathBuf, line: usize, column: usize) -> Command<Messages> {
+        if let Some(index) = self.position(&path) {
+            self.activate(index);
+            self.clear_filter();
+            move_cursor(&mut self.buffer_mut().content, line, column);
+
+            Command::none()
+        } else {
+            self.pending_jump = Some((line, column));
+
+            self.load(path)
+        }
+    }
+
+    fn step_bookmark(&mut self, is_forward: bool) {
+        self.clear_filter();
+        self.filter_pattern = None;
+
+        let (line, _) = self.cursor();
+
+        match self.buffer().bookmarks.next(line, is_forward) {
+            Some(line) => {
+                self.remember_jump();
+                self.reveal_line(line);
+            }
+            None => self
+                .toasts
+                .push(Severity::Info, "No bookmarks in this buffer"),
+        }
+    }
+
+    // Puts the cursor at the start of the line, with a few lines above it.
+    fn reveal_line(&mut self, line: usize) {
+        let buffer = self.buffer_mut();
+        let top = line.saturating_sub(GO_TO_CONTEXT);
+
+        move_cursor(&mut buffer.content, line, 0);
+        buffer.content.edit(text_editor::Action::Scroll {
+            lines: top as i32 - buffer.scroll as i32,
+        });
+        buffer.scroll = top;
+    }
+
+    // The whole region goes, markers and all, in favour of the side or
+    // sides kept.
+    fn resolve_conflict(&mut self, resolution: conflict::Resolution) {
+        self.clear_filter();
+        self.filter_pattern = None;
+
+        let text = self.text();
+        let (line, _) = self.cursor();
+
+        let Some(conflict) = conflict::find(&text)
+            .into_iter()
+            .find(|conflict| conflict.contains(line))
+        else {
+            self.toasts
+                .push(Severity::Info, "The cursor is not in a merge conflict");
+
+            return;
+        };
+
+        let lines = text.split('\n').collect::<Vec<_>>();
+        let resolved = lines[..conflict.start]
+            .iter()
+            .copied()
+            .chain(conflict.resolved(&lines, resolution))
+            .chain(lines[conflict.end + 1..].iter().copied())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let buffer = self.buffer_mut();
+
+        buffer.record(Change::Other);
+        buffer.replace_content(&resolved);
+        move_cursor(&mut buffer.content, conflict.start, 0);
+
+        if conflict::find(&resolved).is_empty() {
+            self.toasts
+                .push(Severity::Success, "All merge conflicts resolved");
+        }
+    }
+
+    // The buffer being edited goes on the left, whatever it is compared
+    // with on the right.
+    fn compare_with(&mut self, name: String, text: &str) {
+        let title = format!("{} ↔ {name}", self.buffer().title());
+        let hunks = diff::whole(&self.text(), &text.replace("\r\n", "\n"));
+
+        self.is_compare_open = false;
+
+        if hunks.is_empty() {
+            self.toasts
+                .push(Severity::Info, format!("{title}: no differences"));
+
+            return;
+        }
+
+        self.comparison = Some(Comparison::side_by_side(title, hunks));
+    }
+
+    fn read_head(&self, index: usize) -> Command<Messages> {
+        let buffer = &self.buffers[index];
+
+        let Some(path) = buffer.path.clone() else {
+            return Command::none();
+        };
+
+        Command::perform(
+            git::head(path.clone(), buffer.format.encoding),
+            move |head| Messages::HeadRead(path, head),
+        )
+    }
+
+    fn terminal_directory(&self) -> PathBuf {
+        self.repository()
+            .or_else(dirs::home_dir)
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    // The formatter is told the file it formats, or where it is about to be
+    // saved, since some pick their rules by its name.
+    fn format(&self, save: Option<PathBuf>) -> Option<Command<Messages>> {
+        let extension = self.extension();
+        let formatter = format::formatter(&extension, &self.config().formatters)?;
+        let path = self.buffer().path.clone();
+        let revision = self.buffer().history.revision();
+        let file = save
+            .clone()
+            .or_else(|| path.clone())
+            .unwrap_or_else(|| PathBuf::from(format!("untitled.{extension}")));
+
+        Some(Command::perform(
+            format::run(formatter, file, self.text()),
+            move |result| Messages::Formatted(path, revision, save, result),
+        ))
+    }
+
+    // Like any other edit, formatting can be undone.
+    fn apply_formatted(&mut self, index: usize, formatted: String) {
+        if index == self.active {
+            self.clear_filter();
+            self.filter_pattern = None;
+        }
+
+        let buffer = &mut self.buffers[index];
+        let text = buffer.content.text();
+
+        if text == formatted {
+            return;
+        }
+
+        let cursor = format::follow(&text, &formatted, buffer.content.cursor_position());
+
+        buffer.record(Change::Other);
+        buffer.restore(Snapshot::new(formatted, cursor));
+
+        if index == self.active {
+            self.count_matches();
+        }
+    }
+
+    // Cleanups go into the buffer rather than only the file, so what is
+    // saved is what is shown and the cleanup can be undone.
+    fn clean_up(&mut self) {
+        let (line, column) = self.cursor();
+        let Some((text, cleanup)) = cleanup::apply(&self.text(), line, &self.config()) else {
+            return;
+        };
+
+        self.clear_filter();
+        self.filter_pattern = None;
+        self.selection_history.clear();
+
+        let width = text
+            .split('\n')
+            .nth(line)
+            .map_or(0, |line| line.chars().count());
+
+        self.record(Change::Other);
+        self.buffer_mut()
+            .restore(Snapshot::new(text, (line, column.min(width))));
+        self.cleanup = Some(cleanup);
+    }
+
+    fn extension(&self) -> String {
+        self.buffer().extension()
+    }
+
+    fn dialog_defaults(&self) -> DialogDefaults {
+        DialogDefaults {
+            directory: self
+                .buffer()
+                .path
+                .as_deref()
+                .and_then(Path::parent)
+                .map(Path::to_path_buf)
+                .or_else(|| self.workspace.clone()),
+            file_name: self
+                .buffer()
+                .path
+                .as_deref()
+                .and_then(Path::file_name)
+                .and_then(|name| name.to_str())
+                .map(str::to_owned),
+            extension: self.extension(),
+        }
+    }
+
+    // Settings for the buffer's language go over the general ones, and the
+    // indentation found in the file over all of them unless turned off.
+    fn config(&self) -> Config {
+        let extension = self.extension();
+        let config = Config::default()
+            .overridden(&language::defaults(&extension))
+            .overridden(&self.settings)
+            .overridden(&self.settings.language(&extension))
+            .overridden(&self.project_config)
+            .overridden(&self.project_config.language(&extension))
+            .overridden(&self.flag_overrides);
+
+        match self.buffer().indentation {
+            Some(indentation) if config.detect_indentation => {
+                config.overridden(&indentation.overrides())
+            }
+            _ => config,
+        }
+    }
+
+    // The filtered lines are only put back together in full, so a filter
+    // makes a rope of its own.
+    fn rope(&mut self) -> Rope {
+        match self.filter.as_ref() {
+            Some(filter) => Rope::from_str(&filter.text()),
+            None => self.buffers[self.active].rope(),
+        }
+    }
+
+    fn text(&self) -> String {
+        match self.filter.as_ref() {
+            Some(filter) => filter.text(),
+            None => self.buffer().content.text(),
+        }
+    }
+
+    // The filtered view only holds some of the lines, so snapshots are taken
+    // from the full text the filter keeps aside.
+    fn snapshot(&self) -> Snapshot {
+        match self.filter.as_ref() {
+            Some(filter) => Snapshot::new(filter.text(), self.cursor()),
+            None => self.buffer().snapshot(),
+        }
+    }
+
+    fn record(&mut self, change: Change) {
+        self.buffer_mut().cursors.clear();
+
+        if self.filter.is_some() {
+            let snapshot = self.snapshot();
+
+            self.buffer_mut().history.record(change, || snapshot);
+        } else {
+            self.buffer_mut().record(change);
+        }
+    }
+
+    // Extra cursors follow typing and plain caret moves. Ctrl+Click adds
+    // one where the editor's own cursor was, Alt+Drag selects a block, and
+    // anything else brings the buffer back to a single cursor.
+    fn edit_at_cursors(&mut self, action: &text_editor::Action) -> bool {
+        let is_adding = matches!(action, text_editor::Action::Click(_)) && self.modifiers.command();
+        let is_block = matches!(
+            action,
+            text_editor::Action::Click(_) | text_editor::Action::Drag(_)
+        ) && self.modifiers.alt();
+
+        if self.filter.is_some() || (self.buffer().cursors.is_empty() && !is_adding && !is_block) {
+            self.buffer_mut().cursors.clear();
+
+            return false;
+        }
+
+        let primary = {
+            let document = self.document();
+            let cursor = document.cursor();
+
+            document.selection().unwrap_or((cursor, cursor))
+        };
+
+        let buffer = self.buffer_mut();
+
+        match action {
+            text_editor::Action::Click(_) if is_adding => {
+                buffer.cursors.add(primary);
+                buffer.history.seal();
+
+                false
+            }
+            text_editor::Action::Click(_) if is_block => {
+                buffer.cursors.clear();
+                buffer.history.seal();
+                buffer.content.edit(action.clone());
+
+                let cursor = buffer.content.cursor_position();
+
+                buffer
+                    .cursors
+                    .select_block(&buffer.content.text(), cursor, cursor);
+
+                true
+            }
+            text_editor::Action::Drag(_) if is_block && buffer.cursors.block().is_some() => {
+                let (anchor, _) = buffer.cursors.block().unwrap_or_default();
+
+                buffer.content.edit(action.clone());
+
+                let corner = buffer.content.cursor_position();
+                let selection = buffer
+                    .cursors
+                    .select_block(&buffer.content.text(), anchor, corner);
+
+                self.document().select(selection);
+
+                true
+            }
+            text_editor::Action::Edit(edit) => {
+                buffer.record(match edit {
+                    text_editor::Edit::Insert(_) => Change::Insert,
+                    text_editor::Edit::Backspace | text_editor::Edit::Delete => Change::Delete,
+                    _ => Change::Other,
+                });
+
+                let (text, cursor) = buffer.cursors.edit(&buffer.content.text(), primary, edit);
+
+                buffer.restore(Snapshot::new(text, cursor));
+                self.count_matches();
+
+                true
+            }
+            text_editor::Action::Move(motion) => {
+                buffer.history.seal();
+                buffer.content.edit(action.clone());
+
+                let cursor = buffer.content.cursor_position();
+
+                if !buffer
+                    .cursors
+                    .shift(&buffer.content.text(), *motion, cursor)
+                {
+                    buffer.cursors.clear();
+                }
+
+                true
+            }
+            text_editor::Action::Scroll { .. } => false,
+            _ => {
+                buffer.cursors.clear();
+
+                false
+            }
+        }
+    }
+
+    // Puts the columns of a block selection on the clipboard, one line of
+    // text for every line of the block.
+    fn copy_block(&mut self) -> Option<Command<Messages>> {
+        let primary = {
+            let document = self.document();
+            let cursor = document.cursor();
+
+            document.selection().unwrap_or((cursor, cursor))
+        };
+
+        let buffer = self.buffer();
+        let text = buffer.cursors.block_text(&buffer.content.text(), primary)?;
+
+        self.clips.push(text.clone());
+
+        Some(clipboard::write(text))
+    }
+
+    // Whatever is selected goes away with the newline, so only the text
+    // before the selection counts.
+    fn new_line_indentation(&mut self) -> String {
+        let unit = self.indent_unit();
+        let document = self.document();
+        let cursor = document.cursor();
+        let (line, column) = document.selection().map_or(cursor, |(start, _)| start);
+        let before = document.line(line).chars().take(column).collect::<String>();
+
+        editing::indentation(&before, &unit)
+    }
+
+    fn step_history(&mut self, is_redo: bool) {
+        let history = &self.buffer().history;
+
+        let can_step = if is_redo {
+            history.can_redo()
+        } else {
+            history.can_undo()
+        };
+
+        if !can_step {
+            return;
+        }
+
+        self.clear_filter();
+        self.filter_pattern = None;
+        self.selection_history.clear();
+        self.buffer_mut().cursors.clear();
+
+        let current = self.buffer().snapshot();
+        let buffer = self.buffer_mut();
+        let restored = if is_redo {
+            buffer.history.redo(current)
+        } else {
+            buffer.history.undo(current)
+        };
+
+        if let Some(snapshot) = restored {
+            buffer.restore(snapshot);
+            self.count_matches();
+        }
+    }
+
+    fn clear_filter(&mut self) {
+        let Some(filter) = self.filter.take() else {
+            return;
+        };
+
+        let buffer = &mut self.buffers[self.active];
+        let line = filter.real_line(buffer.content.cursor_position().0);
+
+        buffer.replace_content(&filter.text());
+        move_cursor(&mut buffer.content, line, 0);
+    }
+
+    fn apply_filter(&mut self, pattern: String) {
+        self.clear_filter();
+
+        if pattern.is_empty() {
+            return;
+        }
+
+        let (filter, content) = Filter::new(&self.buffer().content.text(), pattern);
+
+        self.filter = Some(filter);
+        self.buffer_mut().show(content);
+    }
+
+    fn reload_if_touched(&self, report: &ReplaceReport) -> Command<Messages> {
+        Command::batch(
+            self.buffers
+                .iter()
+                .filter(|buffer| !buffer.is_dirty())
+                .filter_map(|buffer| buffer.path.clone())
+                .filter(|path| report.touches(path))
+                .map(|path| Command::perform(load_file(path), Messages::FileReloaded)),
+        )
+    }
+}
+
+fn action<'a>(
+    content: Element<'a, Messages>,
+    label: &str,
+    on_press: Option<Messages>,
+) -> Element<'a, Messages> {
+    let is_disabled = on_press.is_none();
+
+    tooltip(
+        button(container(content).width(30).center_x())
+            .on_press_maybe(on_press)
+            .padding([5, 10])
+            .style(if is_disabled {
+                theme::Button::Secondary
+            } else {
+                theme::Button::Primary
+            }),
+        label,
+        tooltip::Position::FollowCursor,
+    )
+    .style(theme::Container::Box)
+    .into()
+}
+
+fn new_icon<'a>() -> Element<'a, Messages> {
+    icon('\u{E800}')
+}
+
+fn open_icon<'a>() -> Element<'a, Messages> {
+    icon('\u{E802}')
+}
+
+fn save_icon<'a>() -> Element<'a, Messages> {
+    icon('\u{E801}')
+}
+
+fn icon<'a>(codepoint: char) -> Element<'a, Messages> {
+    const ICON_FONT: Font = Font::with_name("editor-icons");
+
+    text(codepoint).font(ICON_FONT).into()
+}
+
+async fn save_all(session: Session, recent: RecentFiles) -> Result<(), Error> {
+    session.save().await?;
+    recent.save().await
+}
+
+async fn load_files(paths: Vec<PathBuf>) -> Vec<Result<Loaded, Error>> {
+    let mut files = Vec::with_capacity(paths.len());
+
+    for path in paths {
+        files.push(load_file(path).await);
+    }
+
+    files
+}
+
+async fn load_state(is_safe_mode: bool) -> (Session, RecentFiles, Result<Overrides, Error>) {
+    let settings = if is_safe_mode {
+        Ok(Overrides::default())
+    } else {
+        config::load_user().await
+    };
+
+    (Session::load().await, RecentFiles::load().await, settings)
+}
+
+async fn pick_file(defaults: DialogDefaults) -> Result<Loaded, Error> {
+    let handle = defaults
+        .apply(rfd::AsyncFileDialog::new())
+        .set_title("Choose a text file")
+        .pick_file()
+        .await
+        .ok_or(Error::DialogClosed)?;
+
+    load_file(handle.path().to_path_buf()).await
+}
+
+#[tracing::instrument(err)]
+async fn load_file(path: PathBuf) -> Result<Loaded, Error> {
+    match retry_transient(|| tokio::fs::read(&path)).await {
+        Ok(bytes) => {
+            let encoding = encoding::detect(&bytes);
+            let stamp = stamp(&path, &bytes).await;
+
+            Ok((
+                path,
+                Arc::new(encoding::decode(bytes, encoding)),
+                encoding,
+                stamp,
+            ))
+        }
+        Err(err) => Err(Error::FileFailed(path, Arc::new(err))),
+    }
+}
+
+async fn reopen_file(path: PathBuf, encoding: &'static Encoding) -> Result<Loaded, Error> {
+    match retry_transient(|| tokio::fs::read(&path)).await {
+        Ok(bytes) => {
+            let stamp = stamp(&path, &bytes).await;
+
+            Ok((
+                path,
+                Arc::new(encoding::decode(bytes, encoding)),
+                encoding,
+                stamp,
+            ))
+        }
+        Err(err) => Err(Error::FileFailed(path, Arc::new(err))),
+    }
+}
+
+async fn stamp(path: &Path, bytes: &[u8]) -> Stamp {
+    let modified = tokio::fs::metadata(path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok();
+
+    Stamp::of(bytes, modified)
+}
+
+// A file that has gone missing is no conflict, saving simply writes it again.
+async fn is_changed_since(path: &Path, expected: Stamp) -> bool {
+    let modified = tokio::fs::metadata(path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok();
+
+    if modified.is_none() || modified == expected.modified {
+        return false;
+    }
+
+    match tokio::fs::read(path).await {
+        Ok(bytes) => Stamp::of(&bytes, modified).hash != expected.hash,
+        Err(_) => false,
+    }
+}
+
+// The dialog starts out on a name that is still free, so saving a copy
+// next to the file does not default to writing over it.
+async fn pick_save_path(mut defaults: DialogDefaults) -> Result<PathBuf, Error> {
+    if let (Some(directory), Some(file_name)) = (&defaults.directory, &defaults.file_name) {
+        let path = directory.join(file_name);
+
+        if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            defaults.file_name = unique_name(&path)
+                .await
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned());
+        }
+    }
+
+    defaults
+        .apply(rfd::AsyncFileDialog::new())
+        .set_title("Choose a file name.")
+        .save_file()
+        .await
+        .ok_or(Error::DialogClosed)
+        .map(|handle| handle.path().to_path_buf())
+}
+
+// Whether the picked file is there right after the dialog tells whether the
+// dialog asked to write over it.
+async fn pick_save_target(defaults: DialogDefaults) -> Result<(PathBuf, bool), Error> {
+    let path = pick_save_path(defaults).await?;
+    let is_asked = tokio::fs::try_exists(&path).await.unwrap_or(false);
+
+    Ok((path, is_asked))
+}
+
+fn load_dictionary(language: String) -> Command<Messages> {
+    Command::perform(
+        async move { spelling::load(language).await.map(Arc::new) },
+        Messages::DictionaryLoaded,
+    )
+}
+
+async fn write_export(path: PathBuf, html: String) -> Result<PathBuf, Error> {
+    match tokio::fs::write(&path, html).await {
+        Ok(()) => Ok(path),
+        Err(err) => Err(Error::FileFailed(path, Arc::new(err))),
+    }
+}
+
+// Looks again whether the file exists, as it may have been made since the
+// path was picked, and finds a free name to offer in its place.
+async fn check_save_target(path: PathBuf, is_asked: bool) -> Result<SaveTarget, Error> {
+    let directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    ensure_writable(&directory).await?;
+
+    let exists = tokio::fs::try_exists(&path).await.unwrap_or(false);
+    let suggestion = if exists && !is_asked {
+        unique_name(&path).await
+    } else {
+        path.clone()
+    };
+
+    Ok(SaveTarget {
+        path,
+        is_asked,
+        exists,
+        suggestion,
+    })
+}
+
+async fn ensure_writable(directory: &Path) -> Result<(), Error> {
+    let probe = directory.join(format!(".editor-write-check-{}", std::process::id()));
+
+    match tokio::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .await
+    {
+        Ok(file) => {
+            drop(file);
+            let _ = tokio::fs::remove_file(&probe).await;
+
+            Ok(())
+        }
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => Ok(()),
+        Err(_) => Err(Error::NotWritable(directory.to_path_buf())),
+    }
+}
+
+async fn unique_name(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("file");
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    let mut index = 1;
+
+    loop {
+        let name = match extension {
+            Some(extension) => format!("{stem}({index}).{extension}"),
+            None => format!("{stem}({index})"),
+        };
+        let candidate = path.with_file_name(name);
+
+        if !tokio::fs::try_exists(&candidate).await.unwrap_or(false) {
+            return candidate;
+        }
+
+        index += 1;
+    }
+}
+
+#[tracing::instrument(skip(text), fields(bytes = text.len_bytes()), err)]
+async fn save_file(
+    path: PathBuf,
+    text: Rope,
+    format: Format,
+    expected: Option<Stamp>,
+    backups: backup::Policy,
+) -> Result<(PathBuf, Stamp), Error> {
+    let Some(bytes) = text::bytes(&text, format) else {
+        return Err(Error::Unencodable(path, encoding::name(format.encoding)));
+    };
+
+    if let Some(expected) = expected {
+        if is_changed_since(&path, expected).await {
+            return Err(Error::ChangedOnDisk(path));
+        }
+    }
+
+    if let Err(err) = backup::write(&path, &backups).await {
+        return Err(Error::BackupFailed(path, Arc::new(err)));
+    }
+
+    match retry_transient(|| tokio::fs::write(&path, &bytes)).await {
+        Ok(()) => {
+            let stamp = stamp(&path, &bytes).await;
+
+            Ok((path, stamp))
+        }
+        Err(err) => Err(Error::FileFailed(path, Arc::new(err))),
+    }
+}
+
+async fn retry_transient<T, F>(mut operation: impl FnMut() -> F) -> io::Result<T>
+where
+    F: Future<Output = io::Result<T>>,
+{
+    const ATTEMPTS: u32 = 4;
+
+    let mut delay = Duration::from_millis(100);
+
+    for _ in 1..ATTEMPTS {
+        match operation().await {
+            Err(err) if is_transient(&err) => {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+
+    operation().await
+}
+
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::Interrupted | io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+    )
+}
+
+async fn pick_folder(root: Option<PathBuf>) -> Result<PathBuf, Error> {
+    if let Some(root) = root {
+        return Ok(root);
+    }
+
+    rfd::AsyncFileDialog::new()
+        .set_title("Choose a folder")
+        .pick_folder()
+        .await
+        .ok_or(Error::DialogClosed)
+        .map(|handle| handle.path().to_path_buf())
+}
+
+async fn search_in_folder(
+    root: Option<PathBuf>,
+    search: String,
+    is_regex: bool,
+    excluded: Vec<String>,
+    job: Handle,
+) -> Result<SearchResults, Error> {
+    search::search_folder(pick_folder(root).await?, search, is_regex, excluded, job).await
+}
+
+async fn replace_in_folder(
+    root: Option<PathBuf>,
+    search: String,
+    replacement: String,
+    is_regex: bool,
+    skip: Vec<PathBuf>,
+    excluded: Vec<String>,
+    job: Handle,
+) -> Result<Arc<ReplaceReport>, Error> {
+    search::replace_in_folder(
+        pick_folder(root).await?,
+        search,
+        replacement,
+        is_regex,
+        skip,
+        excluded,
+        job,
+    )
+    .await
+    .map(Arc::new)
 }
@@ -0,0 +1,247 @@
+use std::{
+    ops::Range,
+    time::{Duration, Instant},
+};
+
+use iced::{
+    advanced::text::Highlighter as _,
+    highlighter::{self, Highlighter},
+    mouse,
+    widget::{
+        canvas::{self, event, Cache, Canvas, Frame, Geometry},
+        pane_grid, text_editor,
+    },
+    Color, Element, Length, Point, Rectangle, Renderer, Size, Theme,
+};
+
+use crate::Messages;
+
+const WIDTH: f32 = 80.0;
+const ROW_HEIGHT: f32 = 2.0;
+const MIN_DELAY: Duration = Duration::from_millis(300);
+
+// A run of non-blank columns on one line drawn in a single color. No color
+// means the theme's text color.
+type Run = (usize, usize, Option<Color>);
+
+type Key = (u64, usize, highlighter::Settings);
+
+pub struct Minimap {
+    rows: Vec<Vec<Run>>,
+    key: Option<Key>,
+    refreshed: Option<Instant>,
+    delay: Duration,
+    cache: Cache,
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Self {
+            rows: Vec::new(),
+            key: None,
+            refreshed: None,
+            delay: MIN_DELAY,
+            cache: Cache::new(),
+        }
+    }
+}
+
+impl Minimap {
+    pub fn is_stale(&self, revision: u64, content: &text_editor::Content) -> bool {
+        self.key
+            .as_ref()
+            .is_none_or(|(at, lines, _)| *at != revision || *lines != content.line_count())
+    }
+
+    // Highlighting the whole buffer again takes a while on long files, so
+    // the next refresh waits a few times as long as the last one took.
+    pub fn refresh(
+        &mut self,
+        revision: u64,
+        content: &text_editor::Content,
+        settings: highlighter::Settings,
+    ) {
+        let key = (revision, content.line_count(), settings);
+
+        if self.key.as_ref() == Some(&key) {
+            return;
+        }
+
+        let started = Instant::now();
+
+        if self.refreshed.is_some_and(|at| started - at < self.delay) {
+            return;
+        }
+
+        let mut highlighter = Highlighter::new(&key.2);
+
+        self.rows = (0..content.line_count())
+            .map(|line| {
+                let text = content
+                    .line(line)
+                    .map_or(String::new(), |text| String::from(&*text));
+                let spans: Vec<_> = highlighter
+                    .highlight_line(&text)
+                    .map(|(range, highlight)| (range, highlight.to_format().color))
+                    .collect();
+
+                runs(&text, &spans)
+            })
+            .collect();
+
+        self.key = Some(key);
+        self.delay = (started.elapsed() * 4).max(MIN_DELAY);
+        self.refreshed = Some(Instant::now());
+        self.cache.clear();
+    }
+}
+
+fn runs(line: &str, spans: &[(Range<usize>, Option<Color>)]) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+
+    for (column, (index, c)) in line.char_indices().enumerate() {
+        if column as f32 >= WIDTH {
+            break;
+        }
+
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let color = spans
+            .iter()
+            .rev()
+            .find(|(range, _)| range.contains(&index))
+            .and_then(|(_, color)| *color);
+
+        match runs.last_mut() {
+            Some(run) if run.1 == column && run.2 == color => run.1 += 1,
+            _ => runs.push((column, column + 1, color)),
+        }
+    }
+
+    runs
+}
+
+pub fn view(
+    minimap: &Minimap,
+    pane: pane_grid::Pane,
+    scroll: usize,
+    line_height: f32,
+) -> Element<'_, Messages> {
+    Canvas::new(Overview {
+        minimap,
+        pane,
+        scroll,
+        line_height,
+    })
+    .width(Length::Fixed(WIDTH))
+    .height(Length::Fill)
+    .into()
+}
+
+struct Overview<'a> {
+    minimap: &'a Minimap,
+    pane: pane_grid::Pane,
+    scroll: usize,
+    line_height: f32,
+}
+
+impl Overview<'_> {
+    // Rows shrink below the usual height once the whole buffer would no
+    // longer fit.
+    fn row_height(&self, bounds: Rectangle) -> f32 {
+        (bounds.height / self.minimap.rows.len().max(1) as f32).min(ROW_HEIGHT)
+    }
+
+    fn visible_lines(&self, bounds: Rectangle) -> f32 {
+        bounds.height / self.line_height
+    }
+}
+
+// The dragging state lets the pointer leave the minimap while scrolling.
+impl canvas::Program<Messages> for Overview<'_> {
+    type State = bool;
+
+    fn update(
+        &self,
+        is_dragging: &mut bool,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Messages>) {
+        let canvas::Event::Mouse(event) = event else {
+            return (event::Status::Ignored, None);
+        };
+
+        match event {
+            mouse::Event::ButtonPressed(mouse::Button::Left) if cursor.is_over(bounds) => {
+                *is_dragging = true;
+            }
+            mouse::Event::ButtonReleased(mouse::Button::Left) if *is_dragging => {
+                *is_dragging = false;
+
+                return (event::Status::Captured, None);
+            }
+            mouse::Event::CursorMoved { .. } if *is_dragging => {}
+            _ => return (event::Status::Ignored, None),
+        }
+
+        let Some(position) = cursor.position() else {
+            return (event::Status::Ignored, None);
+        };
+
+        // The clicked line ends up in the middle of the viewport.
+        let y = (position.y - bounds.y).clamp(0.0, bounds.height);
+        let line = (y / self.row_height(bounds)) as usize;
+        let top = line.saturating_sub(self.visible_lines(bounds) as usize / 2);
+
+        (
+            event::Status::Captured,
+            Some(Messages::MinimapScrolled(self.pane, top)),
+        )
+    }
+
+    fn draw(
+        &self,
+        _is_dragging: &bool,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let text = theme.palette().text;
+        let row = self.row_height(bounds);
+
+        let lines = self.minimap.cache.draw(renderer, bounds.size(), |frame| {
+            for (line, runs) in self.minimap.rows.iter().enumerate() {
+                let y = line as f32 * row;
+
+                if y > bounds.height {
+                    break;
+                }
+
+                for &(start, end, color) in runs {
+                    frame.fill_rectangle(
+                        Point::new(start as f32, y),
+                        Size::new((end - start) as f32, (row * 0.75).max(1.0)),
+                        Color {
+                            a: 0.7,
+                            ..color.unwrap_or(text)
+                        },
+                    );
+                }
+            }
+        });
+
+        let mut viewport = Frame::new(renderer, bounds.size());
+
+        viewport.fill_rectangle(
+            Point::new(0.0, self.scroll as f32 * row),
+            Size::new(bounds.width, self.visible_lines(bounds) * row),
+            Color { a: 0.15, ..text },
+        );
+
+        vec![lines, viewport.into_geometry()]
+    }
+}
@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+impl Candidate {
+    pub fn label(&self) -> String {
+        if self.is_dir {
+            format!("{}/", self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenBar {
+    pub input: String,
+    pub show_hidden: bool,
+    pub candidates: Vec<Candidate>,
+    pub selected: usize,
+    base: PathBuf,
+}
+
+impl OpenBar {
+    pub fn new(base: PathBuf) -> Self {
+        Self {
+            input: format!("{}/", base.display()),
+            show_hidden: false,
+            candidates: Vec::new(),
+            selected: 0,
+            base,
+        }
+    }
+
+    pub fn split(&self) -> (PathBuf, String) {
+        let expanded = self.base.join(paths::expand(&self.input));
+
+        if self.input.is_empty() || self.input.ends_with(['/', '\\']) {
+            return (expanded, String::new());
+        }
+
+        let directory = expanded.parent().map(Path::to_path_buf).unwrap_or_default();
+        let prefix = expanded
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_owned();
+
+        (directory, prefix)
+    }
+
+    pub fn set_candidates(&mut self, candidates: Vec<Candidate>) {
+        self.candidates = candidates;
+        self.selected = 0;
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.candidates.is_empty() {
+            self.selected = (self.selected + 1) % self.candidates.len();
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if !self.candidates.is_empty() {
+            self.selected = self
+                .selected
+                .checked_sub(1)
+                .unwrap_or(self.candidates.len() - 1);
+        }
+    }
+
+    pub fn complete(&mut self) {
+        let (_, prefix) = self.split();
+
+        if self.candidates.is_empty() || !self.input.ends_with(&prefix) {
+            return;
+        }
+
+        let common = common_prefix(
+            self.candidates
+                .iter()
+                .map(|candidate| candidate.name.as_str()),
+        );
+
+        let completion = match self.candidates.as_slice() {
+            [candidate] => candidate.label(),
+            _ if common.len() > prefix.len() => common.to_owned(),
+            candidates => candidates[self.selected].label(),
+        };
+
+        self.input.truncate(self.input.len() - prefix.len());
+        self.input.push_str(&completion);
+    }
+
+    pub fn target(&self) -> Option<(PathBuf, bool)> {
+        let (directory, _) = self.split();
+
+        match self.candidates.get(self.selected) {
+            Some(candidate) => Some((directory.join(&candidate.name), candidate.is_dir)),
+            None if !self.input.is_empty() => {
+                Some((self.base.join(paths::expand(&self.input)), false))
+            }
+            None => None,
+        }
+    }
+
+    pub fn enter(&mut self, directory: &Path) {
+        self.input = format!("{}/", directory.display());
+    }
+}
+
+pub async fn candidates(directory: PathBuf, prefix: String, show_hidden: bool) -> Vec<Candidate> {
+    let Ok(mut entries) = tokio::fs::read_dir(&directory).await else {
+        return Vec::new();
+    };
+
+    let mut candidates = Vec::new();
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+
+        if !name.starts_with(&prefix) || (!show_hidden && name.starts_with('.')) {
+            continue;
+        }
+
+        let is_dir = entry
+            .file_type()
+            .await
+            .is_some_and(|file_type| file_type.is_dir());
+
+        candidates.push(Candidate { name, is_dir });
+    }
+
+    candidates.sort_by(|a, b| a.name.cmp(&b.name));
+    candidates
+}
+
+fn common_prefix<'a>(mut names: impl Iterator<Item = &'a str>) -> &'a str {
+    let Some(first) = names.next() else {
+        return "";
+    };
+
+    names.fold(first, |common, name| {
+        let length = common
+            .char_indices()
+            .zip(name.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((index, c), _)| index + c.len_utf8());
+
+        &common[..length]
+    })
+}
@@ -0,0 +1,230 @@
+use iced::{
+    theme,
+    widget::{button, column, horizontal_space, row, scrollable, text, text_input},
+    Element, Length,
+};
+use regex::Regex;
+
+use crate::Messages;
+
+// Words that read like a call in C-like code, but never name a function.
+const KEYWORDS: &[&str] = &[
+    "if", "for", "while", "switch", "return", "else", "sizeof", "catch", "new", "delete",
+];
+
+pub fn input_id() -> text_input::Id {
+    text_input::Id::new("outline-filter")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Function,
+    Type,
+    Module,
+    Heading,
+}
+
+impl Kind {
+    fn icon(self) -> char {
+        match self {
+            Kind::Function => 'ƒ',
+            Kind::Type => 'T',
+            Kind::Module => '◫',
+            Kind::Heading => '#',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub kind: Kind,
+    pub name: String,
+    pub line: usize,
+    pub depth: usize,
+}
+
+// Patterns in the manner of ctags: every one looks at a single line, and
+// names what it finds with the `name` group. Headings say how deep they go
+// with `level`, everything else with its indentation.
+fn patterns(extension: &str) -> Vec<(Kind, &'static str)> {
+    match extension {
+        "rs" => vec![
+            (
+                Kind::Function,
+                concat!(
+                    r"^\s*(?:pub(?:\([^)]*\))?\s+)?",
+                    r#"(?:(?:const|async|unsafe|extern\s+"[^"]*")\s+)*fn\s+(?P<name>\w+)"#,
+                ),
+            ),
+            (
+                Kind::Type,
+                r"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:struct|enum|trait|type|union)\s+(?P<name>\w+)",
+            ),
+            (
+                Kind::Type,
+                r"^\s*(?:unsafe\s+)?impl(?:<[^>]*>)?\s+(?P<name>[^{]+?)\s*(?:where\b.*|\{.*)?$",
+            ),
+            (
+                Kind::Module,
+                r"^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+(?P<name>\w+)\s*\{",
+            ),
+        ],
+        "py" => vec![
+            (Kind::Function, r"^\s*(?:async\s+)?def\s+(?P<name>\w+)"),
+            (Kind::Type, r"^\s*class\s+(?P<name>\w+)"),
+        ],
+        "js" | "mjs" | "ts" => vec![
+            (
+                Kind::Function,
+                r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\*?\s+(?P<name>\w+)",
+            ),
+            (
+                Kind::Function,
+                concat!(
+                    r"^\s*(?:export\s+)?(?:const|let)\s+(?P<name>\w+)\s*=",
+                    r"\s*(?:async\s*)?(?:\([^)]*\)|\w+)\s*=>",
+                ),
+            ),
+            (
+                Kind::Type,
+                concat!(
+                    r"^\s*(?:export\s+)?(?:default\s+)?(?:abstract\s+)?",
+                    r"(?:class|interface|enum|type)\s+(?P<name>\w+)",
+                ),
+            ),
+        ],
+        "go" => vec![
+            (Kind::Function, r"^func\s+(?:\([^)]*\)\s*)?(?P<name>\w+)"),
+            (Kind::Type, r"^type\s+(?P<name>\w+)"),
+        ],
+        "c" | "h" | "cpp" | "cc" | "hpp" | "java" => vec![
+            (
+                Kind::Type,
+                concat!(
+                    r"^\s*(?:(?:public|private|protected|static|final|abstract|typedef)\s+)*",
+                    r"(?:struct|class|enum|union|interface)\s+(?P<name>\w+)\s*[^;]*$",
+                ),
+            ),
+            (
+                Kind::Function,
+                r"^\s*(?:[\w\*&:<>\[\],]+\s+)+[\*&]*(?P<name>[A-Za-z_][\w:~]*)\s*\([^;]*$",
+            ),
+        ],
+        "sh" | "bash" => vec![(
+            Kind::Function,
+            r"^\s*(?:function\s+)?(?P<name>[\w-]+)\s*\(\)",
+        )],
+        "md" => vec![(
+            Kind::Heading,
+            r"^(?P<level>#{1,6})\s+(?P<name>.+?)\s*#*\s*$",
+        )],
+        "toml" => vec![(Kind::Module, r"^\s*\[+(?P<name>[^\]]+)\]+")],
+        _ => Vec::new(),
+    }
+}
+
+// A symbol sits under the last one before it that is less deep, so the
+// list can be indented like the code it comes from.
+pub fn symbols(text: &str, extension: &str) -> Vec<Symbol> {
+    let patterns = patterns(extension)
+        .into_iter()
+        .filter_map(|(kind, pattern)| Some((kind, Regex::new(pattern).ok()?)))
+        .collect::<Vec<_>>();
+
+    if patterns.is_empty() {
+        return Vec::new();
+    }
+
+    let mut symbols = Vec::new();
+    let mut levels = Vec::<usize>::new();
+    let mut is_fenced = false;
+
+    for (line, content) in text.lines().enumerate() {
+        // Headings in a fenced block of Markdown are only code.
+        if extension == "md" && content.trim_start().starts_with("```") {
+            is_fenced = !is_fenced;
+        }
+
+        if is_fenced {
+            continue;
+        }
+
+        let Some((kind, captures)) = patterns
+            .iter()
+            .find_map(|(kind, regex)| Some((*kind, regex.captures(content)?)))
+        else {
+            continue;
+        };
+
+        let name = captures["name"].trim().to_string();
+
+        if KEYWORDS.contains(&name.as_str()) {
+            continue;
+        }
+
+        let level = captures.name("level").map_or_else(
+            || content.len() - content.trim_start().len(),
+            |level| level.len(),
+        );
+
+        while levels.last().is_some_and(|&last| last >= level) {
+            levels.pop();
+        }
+
+        symbols.push(Symbol {
+            kind,
+            name,
+            line,
+            depth: levels.len(),
+        });
+        levels.push(level);
+    }
+
+    symbols
+}
+
+// The symbol the cursor is in is the last one to start above it.
+pub fn view<'a>(symbols: &'a [Symbol], query: &str, line: usize) -> Element<'a, Messages> {
+    let needle = query.to_lowercase();
+    let current = symbols.iter().rposition(|symbol| symbol.line <= line);
+
+    let entries = symbols
+        .iter()
+        .enumerate()
+        .filter(|(_, symbol)| symbol.name.to_lowercase().contains(&needle))
+        .fold(column![].spacing(2), |entries, (index, symbol)| {
+            let marker = if Some(index) == current { '›' } else { ' ' };
+
+            entries.push(
+                button(
+                    text(format!(
+                        "{marker}{}{} {}",
+                        "  ".repeat(symbol.depth),
+                        symbol.kind.icon(),
+                        symbol.name
+                    ))
+                    .size(13),
+                )
+                .style(theme::Button::Text)
+                .padding(0)
+                .on_press(Messages::JumpToSymbol(symbol.line)),
+            )
+        });
+
+    column![
+        row![
+            text("Outline").size(16),
+            horizontal_space(Length::Fill),
+            button("Close").on_press(Messages::ToggleOutline),
+        ]
+        .spacing(10),
+        text_input("Filter symbols", query)
+            .id(input_id())
+            .on_input(Messages::OutlineFilterChanged)
+            .size(13),
+        scrollable(entries).height(Length::Fill),
+    ]
+    .spacing(5)
+    .width(220)
+    .into()
+}
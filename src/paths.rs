@@ -0,0 +1,67 @@
+use std::{env, path::PathBuf};
+
+pub fn expand(path: &str) -> PathBuf {
+    let expanded = expand_variables(path);
+
+    match expanded.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => match dirs::home_dir() {
+            Some(home) => home.join(rest.trim_start_matches(['/', '\\'])),
+            None => expanded.into(),
+        },
+        _ => expanded.into(),
+    }
+}
+
+fn expand_variables(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find(|c| c == '$' || c == '%') {
+        output.push_str(&rest[..start]);
+
+        let marker = &rest[start..];
+
+        match variable(marker).and_then(|(name, length)| Some((lookup(name)?, length))) {
+            Some((value, length)) => {
+                output.push_str(&value);
+                rest = &marker[length..];
+            }
+            None => {
+                output.push_str(&marker[..1]);
+                rest = &marker[1..];
+            }
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
+
+// Recognizes `${NAME}`, `$NAME` and `%NAME%`, returning the name and the
+// length of the whole reference.
+fn variable(marker: &str) -> Option<(&str, usize)> {
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    if let Some(braced) = marker.strip_prefix("${") {
+        let end = braced.find('}')?;
+
+        Some((&braced[..end], end + 3))
+    } else if let Some(name) = marker.strip_prefix('$') {
+        let end = name.find(|c| !is_name(c)).unwrap_or(name.len());
+
+        (end > 0).then(|| (&name[..end], end + 1))
+    } else {
+        let name = marker.strip_prefix('%')?;
+        let end = name.find('%')?;
+
+        (end > 0 && name[..end].chars().all(is_name)).then(|| (&name[..end], end + 2))
+    }
+}
+
+fn lookup(name: &str) -> Option<String> {
+    env::var(name).ok().or_else(|| match name {
+        "HOME" | "USERPROFILE" => dirs::home_dir().map(|home| home.display().to_string()),
+        "APPDATA" => dirs::config_dir().map(|config| config.display().to_string()),
+        _ => None,
+    })
+}
@@ -0,0 +1,196 @@
+use std::{
+    cell::Cell,
+    collections::BTreeMap,
+    fmt::{self, Write},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use iced::{
+    advanced::text,
+    theme,
+    widget::{column, container, text as label},
+    Element, Length,
+};
+
+use crate::Messages;
+
+const SLOW_HIGHLIGHT: Duration = Duration::from_millis(5);
+
+static HIGHLIGHT_NANOS: AtomicU64 = AtomicU64::new(0);
+static HIGHLIGHTED_LINES: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Timing {
+    count: u64,
+    total: Duration,
+    max: Duration,
+}
+
+impl Timing {
+    fn record(&mut self, elapsed: Duration) {
+        self.count += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+
+    fn average(&self) -> Duration {
+        self.total / self.count.max(1) as u32
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Hud {
+    pub is_open: bool,
+    updates: BTreeMap<String, Timing>,
+    last_frame: Cell<Option<Instant>>,
+    frame_interval: Cell<Duration>,
+    view_time: Cell<Duration>,
+    document: (usize, usize),
+}
+
+impl Hud {
+    pub fn record_update(&mut self, kind: String, elapsed: Duration) {
+        if self.is_open {
+            self.updates.entry(kind).or_default().record(elapsed);
+        }
+    }
+
+    // Taken once an update is done rather than on every frame, since the
+    // size of the text is not for free.
+    pub fn measure(&mut self, lines: usize, bytes: usize) {
+        self.document = (lines, bytes);
+    }
+
+    pub fn frame(&self) -> Instant {
+        let now = Instant::now();
+
+        if let Some(last) = self.last_frame.replace(Some(now)) {
+            self.frame_interval.set(now - last);
+        }
+
+        now
+    }
+
+    pub fn finish_frame(&self, started: Instant) {
+        self.view_time.set(started.elapsed());
+    }
+
+    pub fn reset(&mut self) {
+        self.updates.clear();
+        HIGHLIGHT_NANOS.store(0, Ordering::Relaxed);
+        HIGHLIGHTED_LINES.store(0, Ordering::Relaxed);
+    }
+
+    pub fn view(&self) -> Element<'_, Messages> {
+        let (lines, bytes) = self.document;
+        let highlighted = HIGHLIGHTED_LINES.load(Ordering::Relaxed);
+        let highlighting = Duration::from_nanos(HIGHLIGHT_NANOS.load(Ordering::Relaxed));
+
+        let updates = self.updates.iter().fold(
+            column![label("update()  count  avg  max").size(12)],
+            |updates, (kind, timing)| {
+                updates.push(
+                    label(format!(
+                        "{kind}  {}  {:.2?}  {:.2?}",
+                        timing.count,
+                        timing.average(),
+                        timing.max
+                    ))
+                    .size(12),
+                )
+            },
+        );
+
+        container(
+            column![
+                label(format!(
+                    "Frame interval: {:.2?}  view(): {:.2?}",
+                    self.frame_interval.get(),
+                    self.view_time.get()
+                ))
+                .size(12),
+                label(format!(
+                    "Highlighting: {highlighted} line(s) in {highlighting:.2?}"
+                ))
+                .size(12),
+                label(format!("Document: {lines} line(s), {bytes} byte(s)")).size(12),
+                updates,
+            ]
+            .spacing(5),
+        )
+        .padding(10)
+        .width(Length::Fill)
+        .style(theme::Container::Box)
+        .into()
+    }
+}
+
+pub struct Timed<H>(H);
+
+impl<H: text::Highlighter> text::Highlighter for Timed<H> {
+    type Settings = H::Settings;
+    type Highlight = H::Highlight;
+    type Iterator<'a>
+        = H::Iterator<'a>
+    where
+        Self: 'a;
+
+    fn new(settings: &Self::Settings) -> Self {
+        Timed(H::new(settings))
+    }
+
+    fn update(&mut self, new_settings: &Self::Settings) {
+        self.0.update(new_settings);
+    }
+
+    fn change_line(&mut self, line: usize) {
+        self.0.change_line(line);
+    }
+
+    fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+        let started = Instant::now();
+        let highlights = self.0.highlight_line(line);
+
+        let elapsed = started.elapsed();
+
+        if elapsed > SLOW_HIGHLIGHT {
+            tracing::debug!(?elapsed, length = line.len(), "slow line highlight");
+        }
+
+        HIGHLIGHT_NANOS.fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+        HIGHLIGHTED_LINES.fetch_add(1, Ordering::Relaxed);
+
+        highlights
+    }
+
+    fn current_line(&self) -> usize {
+        self.0.current_line()
+    }
+}
+
+// Only the variant name is needed, so formatting stops at the first
+// delimiter instead of rendering payloads such as whole file contents.
+pub fn kind(message: &Messages) -> String {
+    struct VariantName(String);
+
+    impl Write for VariantName {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            match s.find(|c: char| !c.is_alphanumeric() && c != '_') {
+                Some(end) => {
+                    self.0.push_str(&s[..end]);
+                    Err(fmt::Error)
+                }
+                None => {
+                    self.0.push_str(s);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    let mut name = VariantName(String::new());
+    let _ = write!(name, "{message:?}");
+
+    name.0
+}
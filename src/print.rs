@@ -0,0 +1,285 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use tokio::process::Command;
+
+use crate::Error;
+
+// A4, in points.
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const FONT_SIZE: f32 = 9.0;
+const SMALL_FONT_SIZE: f32 = 8.0;
+const LINE_HEIGHT: f32 = 11.0;
+
+// Every character of Courier takes up this much of the font size.
+const ADVANCE: f32 = 0.6;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    pub line_numbers: bool,
+    pub header: bool,
+    pub tab_width: usize,
+}
+
+// One printed line. Lines too long for the page go on over several rows,
+// and only the first of them is numbered.
+struct Row {
+    number: Option<usize>,
+    text: String,
+}
+
+fn rows(text: &str, options: Options, gutter: usize) -> Vec<Row> {
+    let columns = ((PAGE_WIDTH - 2.0 * MARGIN) / (FONT_SIZE * ADVANCE)) as usize;
+    let columns = columns.saturating_sub(gutter).max(1);
+    let mut rows = Vec::new();
+
+    for (index, line) in text.split('\n').enumerate() {
+        let chars = expand_tabs(line, options.tab_width)
+            .chars()
+            .collect::<Vec<_>>();
+
+        if chars.is_empty() {
+            rows.push(Row {
+                number: Some(index + 1),
+                text: String::new(),
+            });
+        }
+
+        for (part, chunk) in chars.chunks(columns).enumerate() {
+            rows.push(Row {
+                number: (part == 0).then_some(index + 1),
+                text: chunk.iter().collect(),
+            });
+        }
+    }
+
+    rows
+}
+
+fn expand_tabs(line: &str, tab_width: usize) -> String {
+    let tab_width = tab_width.max(1);
+    let mut expanded = String::with_capacity(line.len());
+    let mut column = 0;
+
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = tab_width - column % tab_width;
+
+            expanded.extend(std::iter::repeat(' ').take(spaces));
+            column += spaces;
+        } else {
+            expanded.push(c);
+            column += 1;
+        }
+    }
+
+    expanded
+}
+
+// Pages laid out in the standard Courier font, which every PDF reader has,
+// so nothing needs embedding. Characters it cannot show print as "?".
+pub fn pdf(title: &str, text: &str, options: Options) -> Vec<u8> {
+    let text = text.strip_suffix('\n').unwrap_or(text);
+    let width = text.split('\n').count().to_string().len();
+    let gutter = if options.line_numbers { width + 2 } else { 0 };
+    let rows = rows(text, options, gutter);
+
+    let space = if options.header {
+        2.0 * LINE_HEIGHT
+    } else {
+        0.0
+    };
+    let top = PAGE_HEIGHT - MARGIN - space;
+    let per_page = (((top - MARGIN - space) / LINE_HEIGHT) as usize).max(1);
+    let pages = rows.chunks(per_page).collect::<Vec<_>>();
+    let count = pages.len();
+
+    let mut writer = Writer::default();
+
+    writer.object(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+
+    let kids = (0..count)
+        .map(|index| format!("{} 0 R", 4 + 2 * index))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    writer.object(format!("<< /Type /Pages /Kids [{kids}] /Count {count} >>").into_bytes());
+    writer.object(
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_vec(),
+    );
+
+    for (index, rows) in pages.iter().enumerate() {
+        let mut content = Vec::new();
+
+        if options.header {
+            let footer = format!("Page {} of {count}", index + 1);
+            let footer_width = footer.chars().count() as f32 * SMALL_FONT_SIZE * ADVANCE;
+
+            text_at(&mut content, MARGIN, PAGE_HEIGHT - MARGIN, title);
+            text_at(
+                &mut content,
+                (PAGE_WIDTH - footer_width) / 2.0,
+                MARGIN,
+                &footer,
+            );
+        }
+
+        content.extend(
+            format!(
+                "BT\n/F1 {FONT_SIZE} Tf\n{LINE_HEIGHT} TL\n{MARGIN} {} Td\n",
+                top - LINE_HEIGHT
+            )
+            .into_bytes(),
+        );
+
+        for row in rows.iter() {
+            if options.line_numbers {
+                let number = row
+                    .number
+                    .map_or(String::new(), |number| number.to_string());
+
+                content.extend(b"0.55 g\n");
+                push_string(&mut content, &format!("{number:>width$}  "));
+                content.extend(b" Tj\n0 g\n");
+            }
+
+            push_string(&mut content, &row.text);
+            content.extend(b" Tj\nT*\n");
+        }
+
+        content.extend(b"ET");
+
+        writer.object(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+                 /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                5 + 2 * index
+            )
+            .into_bytes(),
+        );
+
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+
+        stream.extend(content);
+        stream.extend(b"\nendstream");
+        writer.object(stream);
+    }
+
+    writer.finish()
+}
+
+fn text_at(content: &mut Vec<u8>, x: f32, y: f32, text: &str) {
+    content.extend(format!("BT\n/F1 {SMALL_FONT_SIZE} Tf\n{x} {y} Td\n").into_bytes());
+    push_string(content, text);
+    content.extend(b" Tj\nET\n");
+}
+
+// A literal string in the font's encoding, which matches Latin-1 for the
+// characters kept.
+fn push_string(content: &mut Vec<u8>, text: &str) {
+    content.push(b'(');
+
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => content.extend([b'\\', c as u8]),
+            ' '..='~' | '\u{a0}'..='\u{ff}' => content.push(c as u32 as u8),
+            _ => content.push(b'?'),
+        }
+    }
+
+    content.push(b')');
+}
+
+// Objects are numbered in the order they are written, starting at one.
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl Writer {
+    fn object(&mut self, body: Vec<u8>) {
+        if self.bytes.is_empty() {
+            self.bytes.extend(b"%PDF-1.4\n");
+        }
+
+        self.offsets.push(self.bytes.len());
+        self.bytes
+            .extend(format!("{} 0 obj\n", self.offsets.len()).into_bytes());
+        self.bytes.extend(body);
+        self.bytes.extend(b"\nendobj\n");
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let start = self.bytes.len();
+        let size = self.offsets.len() + 1;
+
+        self.bytes
+            .extend(format!("xref\n0 {size}\n0000000000 65535 f \n").into_bytes());
+
+        for offset in &self.offsets {
+            self.bytes
+                .extend(format!("{offset:010} 00000 n \n").into_bytes());
+        }
+
+        self.bytes.extend(
+            format!("trailer\n<< /Size {size} /Root 1 0 R >>\nstartxref\n{start}\n%%EOF\n")
+                .into_bytes(),
+        );
+
+        self.bytes
+    }
+}
+
+// The document goes to the temporary directory and opens in the system's
+// PDF viewer, whose print dialog takes it from there.
+pub async fn open(name: String, pdf: Vec<u8>) -> Result<PathBuf, Error> {
+    let name = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let path = std::env::temp_dir().join(format!("{name}.pdf"));
+
+    if let Err(err) = tokio::fs::write(&path, pdf).await {
+        return Err(Error::FileFailed(path, Arc::new(err)));
+    }
+
+    match viewer(&path).status().await {
+        Ok(status) if status.success() => Ok(path),
+        Ok(status) => Err(Error::PrintFailed(format!(
+            "the PDF viewer exited with {status}"
+        ))),
+        Err(err) => Err(Error::PrintFailed(err.to_string())),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn viewer(path: &Path) -> Command {
+    let mut command = Command::new("open");
+    command.arg(path);
+    command
+}
+
+#[cfg(windows)]
+fn viewer(path: &Path) -> Command {
+    let mut command = Command::new("cmd");
+    command.args(["/C", "start", ""]).arg(path);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn viewer(path: &Path) -> Command {
+    let mut command = Command::new("xdg-open");
+    command.arg(path);
+    command
+}
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+
+use iced::{
+    theme,
+    widget::{button, column, container, row, text, text_input},
+    Element, Length,
+};
+
+use crate::Messages;
+
+const MAX_MATCHES: usize = 50;
+const SHOWN: usize = 10;
+
+pub fn input_id() -> text_input::Id {
+    text_input::Id::new("quick-open")
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct QuickOpen {
+    pub query: String,
+    pub selected: usize,
+    matches: Vec<PathBuf>,
+}
+
+impl QuickOpen {
+    pub fn update(&mut self, root: &Path, files: &[PathBuf]) {
+        let mut scored = files
+            .iter()
+            .filter_map(|path| Some((score(&relative(root, path), &self.query)?, path)))
+            .collect::<Vec<_>>();
+
+        scored.sort_by(|(a, a_path), (b, b_path)| b.cmp(a).then_with(|| a_path.cmp(b_path)));
+
+        self.matches = scored
+            .into_iter()
+            .take(MAX_MATCHES)
+            .map(|(_, path)| path.clone())
+            .collect();
+        self.selected = 0;
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = (self.selected + 1) % self.matches.len().min(SHOWN);
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = self
+                .selected
+                .checked_sub(1)
+                .unwrap_or(self.matches.len().min(SHOWN) - 1);
+        }
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.matches.len().saturating_sub(1));
+    }
+
+    pub fn target(&self) -> Option<PathBuf> {
+        self.matches.get(self.selected).cloned()
+    }
+
+    pub fn view(&self, root: &Path, is_indexing: bool) -> Element<'_, Messages> {
+        let matches = self.matches.iter().take(SHOWN).enumerate().fold(
+            column![].spacing(2),
+            |matches, (index, path)| {
+                matches.push(
+                    button(text(relative(root, path)).size(14))
+                        .width(Length::Fill)
+                        .style(if index == self.selected {
+                            theme::Button::Primary
+                        } else {
+                            theme::Button::Text
+                        })
+                        .on_press(Messages::OpenQuickMatch(index)),
+                )
+            },
+        );
+
+        let status = match self.matches.len() {
+            _ if is_indexing => String::from("Indexing…"),
+            0 => String::from("No matching files"),
+            count => format!("{count} file(s)"),
+        };
+
+        container(
+            column![
+                row![
+                    text_input("Go to file", &self.query)
+                        .id(input_id())
+                        .on_input(Messages::QuickOpenChanged)
+                        .on_submit(Messages::SubmitQuickOpen),
+                    text(status).size(14),
+                    button("Close").on_press(Messages::CloseQuickOpen),
+                ]
+                .spacing(10),
+                matches,
+            ]
+            .spacing(5),
+        )
+        .padding(10)
+        .width(Length::Fill)
+        .style(theme::Container::Box)
+        .into()
+    }
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+// Every query character has to appear in order. Runs of consecutive
+// characters, characters that start a word and hits inside the file name
+// all rank a candidate higher, and shorter paths win ties.
+pub fn score(candidate: &str, query: &str) -> Option<i64> {
+    let chars = candidate.chars().collect::<Vec<_>>();
+    let name_start = candidate
+        .rfind(['/', '\\'])
+        .map_or(0, |index| candidate[..=index].chars().count());
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous = None;
+
+    for wanted in query.chars().filter(|c| !c.is_whitespace()) {
+        let index = (position..chars.len())
+            .find(|&index| chars[index].to_lowercase().eq(wanted.to_lowercase()))?;
+
+        let is_boundary = index == 0
+            || matches!(chars[index - 1], '/' | '\\' | '_' | '-' | '.' | ' ')
+            || (chars[index].is_uppercase() && chars[index - 1].is_lowercase());
+
+        score += 1;
+
+        if previous == Some(index.wrapping_sub(1)) {
+            score += 5;
+        }
+
+        if is_boundary {
+            score += 8;
+        }
+
+        if index >= name_start {
+            score += 3;
+        }
+
+        previous = Some(index);
+        position = index + 1;
+    }
+
+    Some(score * 100 - chars.len() as i64)
+}
@@ -0,0 +1,63 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{store, Error};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecentFiles {
+    #[serde(default)]
+    files: Vec<RecentFile>,
+    #[serde(default)]
+    folders: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentFile {
+    pub path: PathBuf,
+    pub cursor: (usize, usize),
+}
+
+impl RecentFiles {
+    const FILE: &'static str = "recent.json";
+    const LIMIT: usize = 50;
+
+    pub async fn load() -> RecentFiles {
+        store::load(Self::FILE).await
+    }
+
+    pub async fn save(self) -> Result<(), Error> {
+        store::save(Self::FILE, &self).await
+    }
+
+    pub fn cursor(&self, path: &Path) -> Option<(usize, usize)> {
+        self.files
+            .iter()
+            .find(|file| file.path == path)
+            .map(|file| file.cursor)
+    }
+
+    pub fn touch(&mut self, path: PathBuf, cursor: (usize, usize)) {
+        self.files.retain(|file| file.path != path);
+        self.files.insert(0, RecentFile { path, cursor });
+        self.files.truncate(Self::LIMIT);
+    }
+
+    pub fn files(&self) -> &[RecentFile] {
+        &self.files
+    }
+
+    pub fn clear_files(&mut self) {
+        self.files.clear();
+    }
+
+    pub fn folders(&self) -> &[PathBuf] {
+        &self.folders
+    }
+
+    pub fn touch_folder(&mut self, path: PathBuf) {
+        self.folders.retain(|folder| *folder != path);
+        self.folders.insert(0, path);
+        self.folders.truncate(Self::LIMIT);
+    }
+}
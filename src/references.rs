@@ -0,0 +1,102 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use iced::{
+    theme,
+    widget::{button, column, horizontal_space, row, scrollable, text},
+    Element, Length,
+};
+
+use crate::{editing::Position, lsp::Location, Messages};
+
+// Columns are left in UTF-16 code units, as the server sent them.
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub path: PathBuf,
+    pub start: Position,
+    pub preview: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct References {
+    pub title: String,
+    pub entries: Vec<Reference>,
+}
+
+impl References {
+    pub fn view(&self) -> Element<'_, Messages> {
+        let entries = self
+            .entries
+            .iter()
+            .fold(column![].spacing(2), |entries, reference| {
+                entries.push(
+                    button(
+                        text(format!(
+                            "{}:{}: {}",
+                            reference.path.display(),
+                            reference.start.0 + 1,
+                            reference.preview
+                        ))
+                        .size(13),
+                    )
+                    .style(theme::Button::Text)
+                    .on_press(Messages::OpenLocation(
+                        reference.path.clone(),
+                        reference.start,
+                    )),
+                )
+            });
+
+        column![
+            row![
+                text(&self.title),
+                horizontal_space(Length::Fill),
+                button("Dismiss").on_press(Messages::DismissReferences),
+            ]
+            .spacing(10),
+            scrollable(entries).height(160),
+        ]
+        .spacing(5)
+        .into()
+    }
+}
+
+// Lines of open files are passed in, since those may differ from what is
+// saved. The rest are read from disk, each file once.
+pub async fn collect(
+    title: String,
+    locations: Vec<Location>,
+    mut previews: Vec<Option<String>>,
+) -> References {
+    let mut files: HashMap<PathBuf, Option<Vec<String>>> = HashMap::new();
+
+    for (location, preview) in locations.iter().zip(&mut previews) {
+        if preview.is_some() {
+            continue;
+        }
+
+        if !files.contains_key(&location.path) {
+            let lines = tokio::fs::read_to_string(&location.path)
+                .await
+                .ok()
+                .map(|content| content.lines().map(String::from).collect());
+
+            files.insert(location.path.clone(), lines);
+        }
+
+        *preview = files[&location.path]
+            .as_ref()
+            .and_then(|lines| lines.get(location.start.0).cloned());
+    }
+
+    let entries = locations
+        .into_iter()
+        .zip(previews)
+        .map(|(location, preview)| Reference {
+            path: location.path,
+            start: location.start,
+            preview: preview.unwrap_or_default().trim().to_owned(),
+        })
+        .collect();
+
+    References { title, entries }
+}
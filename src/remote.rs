@@ -0,0 +1,305 @@
+use std::{path::PathBuf, pin::pin, process::Stdio, time::Duration};
+
+use iced::{
+    futures::future,
+    theme,
+    widget::{button, column, horizontal_space, pick_list, row, scrollable, text},
+    Element, Length,
+};
+use serde::Deserialize;
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::{jobs::Handle, paths, Error, Messages};
+
+// How often a download looks at how much of the file has come in so far.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+// A host to edit files on, as given in the settings under `[remotes.name]`.
+// Signing in is left to ssh, through its agent or the identity file, since
+// nothing can be typed into a transfer running in the background.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Profile {
+    pub host: String,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity: Option<String>,
+    pub directory: Option<String>,
+}
+
+impl Profile {
+    fn destination(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{user}@{}", self.host),
+            None => self.host.clone(),
+        }
+    }
+
+    pub fn start(&self) -> String {
+        self.directory.clone().unwrap_or_else(|| String::from("."))
+    }
+}
+
+// Where a file opened from a host lives there. The editor works on a copy
+// of it, which goes back up whenever it is saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub profile: String,
+    pub path: String,
+}
+
+impl Location {
+    pub fn mirror(&self) -> Option<PathBuf> {
+        let relative = self.path.trim_start_matches('/');
+
+        dirs::cache_dir().map(|dir| {
+            dir.join("editor")
+                .join("remote")
+                .join(&self.profile)
+                .join(relative)
+        })
+    }
+}
+
+// A link could lead to a file or a directory, which the listing does not
+// tell, so opening one tries it as a directory first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub is_dir: bool,
+    pub is_link: bool,
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Browser {
+    pub profile: Option<String>,
+    pub directory: String,
+    pub entries: Vec<Entry>,
+    pub is_loading: bool,
+}
+
+impl Browser {
+    pub fn path(&self, entry: &Entry) -> String {
+        format!("{}/{}", self.directory.trim_end_matches('/'), entry.name)
+    }
+
+    pub fn view(&self, profiles: Vec<String>) -> Element<'_, Messages> {
+        let entries = self
+            .entries
+            .iter()
+            .fold(column![].spacing(2), |entries, entry| {
+                let marker = if entry.is_dir { '▸' } else { ' ' };
+                let link = if entry.is_link { "@" } else { "" };
+
+                entries.push(
+                    button(text(format!("{marker} {}{link}", entry.name)).size(13))
+                        .style(theme::Button::Text)
+                        .padding(0)
+                        .on_press(Messages::OpenRemoteEntry(entry.clone())),
+                )
+            });
+
+        let status = if self.is_loading {
+            String::from("Connecting…")
+        } else {
+            self.directory.clone()
+        };
+
+        column![
+            row![
+                pick_list(
+                    profiles,
+                    self.profile.clone(),
+                    Messages::RemoteProfileSelected
+                )
+                .placeholder("Host"),
+                text(status),
+                horizontal_space(Length::Fill),
+                button("Close").on_press(Messages::CloseRemote),
+            ]
+            .spacing(10),
+            scrollable(entries).height(160),
+        ]
+        .spacing(5)
+        .into()
+    }
+}
+
+// The directory is looked up through the host itself, so ".." comes back
+// as the path it leads to. Directories are listed first.
+pub async fn list(profile: Profile, directory: String) -> Result<(String, Vec<Entry>), Error> {
+    let output = sftp(
+        &profile,
+        format!("cd {}\npwd\nls -la\n", quoted(&directory)),
+    )
+    .await?;
+
+    let mut resolved = directory;
+    let mut entries = Vec::new();
+
+    for line in output.lines() {
+        if let Some(directory) = line.strip_prefix("Remote working directory: ") {
+            resolved = directory.trim().to_string();
+        } else if !line.starts_with("sftp>") {
+            entries.extend(entry(line));
+        }
+    }
+
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+    Ok((resolved, entries))
+}
+
+// sftp says nothing of its progress when it is not run in a terminal, so
+// the copy is measured as it grows against the size the listing gave.
+pub async fn download(
+    profile: Profile,
+    remote: String,
+    local: PathBuf,
+    size: Option<u64>,
+    job: Handle,
+) -> Result<PathBuf, Error> {
+    if let Some(parent) = local.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let batch = format!(
+        "get {} {}\n",
+        quoted(&remote),
+        quoted(&local.to_string_lossy())
+    );
+
+    let mut transfer = pin!(sftp(&profile, batch));
+
+    let Some(size) = size.filter(|&size| size > 0) else {
+        transfer.await?;
+
+        return Ok(local);
+    };
+
+    job.set_total(100);
+
+    loop {
+        match tokio::time::timeout(PROGRESS_INTERVAL, &mut transfer).await {
+            Ok(result) => {
+                result?;
+
+                return Ok(local);
+            }
+            Err(_) => {
+                if let Ok(metadata) = tokio::fs::metadata(&local).await {
+                    job.set_done((metadata.len().min(size) * 100 / size) as usize);
+                }
+            }
+        }
+    }
+}
+
+pub async fn upload(profile: Profile, local: PathBuf, remote: String) -> Result<String, Error> {
+    let batch = format!(
+        "put {} {}\n",
+        quoted(&local.to_string_lossy()),
+        quoted(&remote)
+    );
+
+    sftp(&profile, batch).await?;
+
+    Ok(format!("{}:{remote}", profile.host))
+}
+
+// The listing goes like `ls -l`: the size is the fifth column and the name
+// comes after eight, and may have spaces of its own. A link is named with
+// where it points, which is left off.
+fn entry(line: &str) -> Option<Entry> {
+    let mut rest = line;
+    let mut size = None;
+
+    for column in 0..8 {
+        rest = rest.trim_start();
+
+        let end = rest.find(char::is_whitespace)?;
+
+        if column == 4 {
+            size = rest[..end].parse().ok();
+        }
+
+        rest = &rest[end..];
+    }
+
+    let is_link = line.starts_with('l');
+    let name = rest.trim_start();
+    let name = match name.split_once(" -> ") {
+        Some((name, _)) if is_link => name,
+        _ => name,
+    };
+
+    if name.is_empty() || name == "." {
+        return None;
+    }
+
+    Some(Entry {
+        name: name.to_string(),
+        is_dir: line.starts_with('d'),
+        is_link,
+        size,
+    })
+}
+
+fn quoted(path: &str) -> String {
+    format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// Runs sftp in batch mode, which stops at the first command that fails, and
+// returns what it printed.
+async fn sftp(profile: &Profile, batch: String) -> Result<String, Error> {
+    let failed = |reason: String| Error::RemoteFailed(profile.host.clone(), reason);
+
+    let mut command = Command::new("sftp");
+
+    command.args(["-q", "-b", "-"]);
+
+    if let Some(port) = profile.port {
+        command.arg("-P").arg(port.to_string());
+    }
+
+    if let Some(identity) = &profile.identity {
+        command.arg("-i").arg(paths::expand(identity));
+    }
+
+    let mut child = command
+        .arg(profile.destination())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| failed(err.to_string()))?;
+
+    let stdin = child.stdin.take();
+    let write = async move {
+        if let Some(mut stdin) = stdin {
+            stdin.write_all(batch.as_bytes()).await?;
+        }
+
+        Ok::<_, std::io::Error>(())
+    };
+
+    let (written, output) = future::join(write, child.wait_with_output()).await;
+    let output = output.map_err(|err| failed(err.to_string()))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map_or_else(|| output.status.to_string(), str::to_owned);
+
+        return Err(failed(reason));
+    }
+
+    written.map_err(|err| failed(err.to_string()))?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,229 @@
+use std::{path::PathBuf, sync::Arc};
+
+use iced::{
+    widget::{button, column, horizontal_space, row, scrollable, text},
+    Element, Length,
+};
+
+use crate::{editing::Position, lsp::TextEdit, Error, Messages};
+
+#[derive(Debug, Clone)]
+pub struct LineChange {
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+// Open files are renamed in their buffer and the rest on disk, in both
+// cases only while they still read as they did when the preview was made.
+#[derive(Debug, Clone)]
+pub struct FileRename {
+    pub path: PathBuf,
+    pub is_open: bool,
+    pub original: Arc<String>,
+    pub renamed: Arc<String>,
+    pub changes: Vec<LineChange>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Rename {
+    pub name: String,
+    pub files: Vec<FileRename>,
+}
+
+impl Rename {
+    pub fn occurrences(&self) -> usize {
+        self.files.iter().map(|file| file.changes.len()).sum()
+    }
+
+    pub fn view(&self) -> Element<'_, Messages> {
+        let files = self.files.iter().fold(column![].spacing(2), |files, file| {
+            let files = files.push(text(file.path.display()).size(14));
+
+            file.changes.iter().fold(files, |files, change| {
+                files.push(
+                    text(format!(
+                        "    {}: {}  →  {}",
+                        change.line + 1,
+                        change.before.trim(),
+                        change.after.trim()
+                    ))
+                    .size(13),
+                )
+            })
+        });
+
+        column![
+            row![
+                text(format!(
+                    "Rename to \"{}\": {} change(s) in {} file(s)",
+                    self.name,
+                    self.occurrences(),
+                    self.files.len()
+                )),
+                horizontal_space(Length::Fill),
+                button("Apply").on_press(Messages::ApplyRename),
+                button("Cancel").on_press(Messages::CancelRename),
+            ]
+            .spacing(10),
+            scrollable(files).height(160),
+        ]
+        .spacing(5)
+        .into()
+    }
+}
+
+// Texts of open files are passed in, since those may differ from what is
+// saved. The rest are read from disk.
+pub async fn preview(
+    name: String,
+    edits: Vec<(PathBuf, Vec<TextEdit>)>,
+    open: Vec<Option<String>>,
+) -> Result<Rename, Error> {
+    let mut files = Vec::new();
+
+    for ((path, edits), text) in edits.into_iter().zip(open) {
+        let is_open = text.is_some();
+        let original = match text {
+            Some(text) => text,
+            None => tokio::fs::read_to_string(&path)
+                .await
+                .map_err(|err| Error::FileFailed(path.clone(), Arc::new(err)))?,
+        };
+
+        let renamed = apply(&original, &edits);
+        let changes = changes(&original, &edits);
+
+        files.push(FileRename {
+            path,
+            is_open,
+            original: Arc::new(original),
+            renamed: Arc::new(renamed),
+            changes,
+        });
+    }
+
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(Rename { name, files })
+}
+
+// Every file is checked before any is written, so that a file changed since
+// the preview leaves all of them alone.
+#[tracing::instrument(skip_all, fields(files = files.len()), err)]
+pub async fn write(files: Vec<FileRename>) -> Result<usize, Error> {
+    for file in &files {
+        let current = tokio::fs::read_to_string(&file.path)
+            .await
+            .map_err(|err| Error::FileFailed(file.path.clone(), Arc::new(err)))?;
+
+        if current != *file.original {
+            return Err(Error::ChangedOnDisk(file.path.clone()));
+        }
+    }
+
+    for file in &files {
+        tokio::fs::write(&file.path, file.renamed.as_bytes())
+            .await
+            .map_err(|err| Error::FileFailed(file.path.clone(), Arc::new(err)))?;
+    }
+
+    Ok(files.len())
+}
+
+// Edits are applied from the end of the text back, so that none of them
+// moves another.
+pub fn apply(text: &str, edits: &[TextEdit]) -> String {
+    let starts = line_starts(text);
+    let mut ranges = edits
+        .iter()
+        .map(|edit| {
+            (
+                offset(text, &starts, edit.start),
+                offset(text, &starts, edit.end),
+                edit.text.as_str(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    ranges.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let mut result = text.to_owned();
+
+    for (start, end, replacement) in ranges {
+        result.replace_range(start..end.max(start), replacement);
+    }
+
+    result
+}
+
+// Edits on the same lines are shown together, as those lines read before
+// and after all of them.
+fn changes(text: &str, edits: &[TextEdit]) -> Vec<LineChange> {
+    let lines = text.split('\n').collect::<Vec<_>>();
+    let mut sorted = edits.to_vec();
+
+    sorted.sort_by(|a, b| a.start.cmp(&b.start));
+
+    let mut groups: Vec<(usize, usize, Vec<TextEdit>)> = Vec::new();
+
+    for edit in sorted {
+        match groups.last_mut() {
+            Some((_, last, group)) if edit.start.0 <= *last => {
+                *last = (*last).max(edit.end.0);
+                group.push(edit);
+            }
+            _ => groups.push((edit.start.0, edit.end.0, vec![edit])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(first, last, group)| {
+            let before = lines
+                .get(first..=last.min(lines.len().saturating_sub(1)))
+                .unwrap_or_default()
+                .join("\n");
+            let shifted = group
+                .into_iter()
+                .map(|edit| TextEdit {
+                    start: (edit.start.0 - first, edit.start.1),
+                    end: (edit.end.0 - first, edit.end.1),
+                    text: edit.text,
+                })
+                .collect::<Vec<_>>();
+
+            LineChange {
+                line: first,
+                after: apply(&before, &shifted),
+                before,
+            }
+        })
+        .collect()
+}
+
+fn line_starts(text: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+        .collect()
+}
+
+// The byte offset of a position in UTF-16 columns. Columns past the end of
+// a line stop at its end.
+fn offset(text: &str, starts: &[usize], (line, units): Position) -> usize {
+    let Some(&start) = starts.get(line) else {
+        return text.len();
+    };
+
+    let line = text[start..].split('\n').next().unwrap_or_default();
+    let mut counted = 0;
+
+    line.char_indices()
+        .find(|(_, c)| {
+            let before = counted;
+            counted += c.len_utf16();
+
+            before >= units
+        })
+        .map_or(start + line.len(), |(index, _)| start + index)
+}
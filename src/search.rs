@@ -0,0 +1,434 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt::Write,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use regex::Regex;
+
+use crate::{jobs::Handle, Error};
+
+pub const CONTEXT_LINES: usize = 2;
+
+#[derive(Debug, Clone)]
+enum Pattern {
+    Literal(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn new(search: &str, is_regex: bool) -> Result<Self, Error> {
+        if !is_regex {
+            return Ok(Pattern::Literal(search.to_owned()));
+        }
+
+        Regex::new(search)
+            .map(Pattern::Regex)
+            .map_err(|err| Error::InvalidPattern(search.to_owned(), err.to_string()))
+    }
+
+    fn find(&self, text: &str) -> Option<usize> {
+        match self {
+            Pattern::Literal(search) => text.find(search.as_str()),
+            Pattern::Regex(regex) => regex.find(text).map(|found| found.start()),
+        }
+    }
+
+    fn count(&self, text: &str) -> usize {
+        match self {
+            Pattern::Literal(search) => text.matches(search.as_str()).count(),
+            Pattern::Regex(regex) => regex.find_iter(text).count(),
+        }
+    }
+
+    // Regex replacements may refer to capture groups as `$1` or `${name}`.
+    fn replace(&self, text: &str, replacement: &str) -> String {
+        match self {
+            Pattern::Literal(search) => text.replace(search.as_str(), replacement),
+            Pattern::Regex(regex) => regex.replace_all(text, replacement).into_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResults {
+    pub root: PathBuf,
+    pub search: String,
+    pub files: Vec<FileMatches>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileMatches {
+    pub path: PathBuf,
+    pub hits: Vec<Hit>,
+    pub is_collapsed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub line: usize,
+    pub column: usize,
+    pub context: Vec<(usize, String)>,
+}
+
+impl SearchResults {
+    pub fn hit_count(&self) -> usize {
+        self.files.iter().map(|file| file.hits.len()).sum()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} match(es) for \"{}\" in {} file(s)",
+            self.hit_count(),
+            self.search,
+            self.files.len()
+        )
+    }
+
+    pub fn export(&self) -> String {
+        let mut output = format!("{} under {}\n", self.summary(), self.root.display());
+
+        for file in &self.files {
+            let _ = writeln!(output, "\n{}", file.path.display());
+
+            for hit in &file.hits {
+                for (line, text) in &hit.context {
+                    let marker = if *line == hit.line { '>' } else { ' ' };
+                    let _ = writeln!(output, "{marker}{:>6}: {text}", line + 1);
+                }
+
+                output.push_str("--\n");
+            }
+        }
+
+        output
+    }
+}
+
+#[tracing::instrument(skip(excluded, job), err)]
+pub async fn search_folder(
+    root: PathBuf,
+    search: String,
+    is_regex: bool,
+    excluded: Vec<String>,
+    job: Handle,
+) -> Result<SearchResults, Error> {
+    let pattern = Pattern::new(&search, is_regex)?;
+    let mut files = Vec::new();
+    let paths = walk(root.clone(), &excluded).await?;
+
+    job.set_total(paths.len());
+
+    for path in paths {
+        if job.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+
+        job.advance();
+
+        let Ok(content) = tokio::fs::read_to_string(&path).await else {
+            continue;
+        };
+
+        let hits = find_hits(&content, &pattern);
+
+        if !hits.is_empty() {
+            files.push(FileMatches {
+                path,
+                hits,
+                is_collapsed: false,
+            });
+        }
+    }
+
+    Ok(SearchResults {
+        root,
+        search,
+        files,
+    })
+}
+
+fn find_hits(content: &str, pattern: &Pattern) -> Vec<Hit> {
+    let lines = content.lines().collect::<Vec<_>>();
+
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let offset = pattern.find(line)?;
+            let start = index.saturating_sub(CONTEXT_LINES);
+            let end = (index + CONTEXT_LINES + 1).min(lines.len());
+
+            Some(Hit {
+                line: index,
+                column: line[..offset].chars().count(),
+                context: (start..end)
+                    .map(|line| (line, lines[line].to_owned()))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplaceReport {
+    pub root: PathBuf,
+    pub search: String,
+    pub replacement: String,
+    pub files: Vec<FileChange>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub lines: Vec<usize>,
+    pub replacements: usize,
+    original: Arc<String>,
+    replaced: u64,
+}
+
+impl ReplaceReport {
+    pub fn lines_changed(&self) -> usize {
+        self.files.iter().map(|file| file.lines.len()).sum()
+    }
+
+    pub fn replacements(&self) -> usize {
+        self.files.iter().map(|file| file.replacements).sum()
+    }
+
+    pub fn touches(&self, path: &Path) -> bool {
+        self.files.iter().any(|file| file.path == path)
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "Replaced {} occurrence(s) of \"{}\" with \"{}\" on {} line(s) in {} file(s)",
+            self.replacements(),
+            self.search,
+            self.replacement,
+            self.lines_changed(),
+            self.files.len()
+        )
+    }
+}
+
+impl FileChange {
+    pub fn describe_lines(&self) -> String {
+        const MAX_LINES: usize = 10;
+
+        let mut lines = self
+            .lines
+            .iter()
+            .take(MAX_LINES)
+            .map(|line| (line + 1).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        if self.lines.len() > MAX_LINES {
+            lines.push_str(&format!(" and {} more", self.lines.len() - MAX_LINES));
+        }
+
+        lines
+    }
+}
+
+#[tracing::instrument(skip(excluded, job), err)]
+pub async fn replace_in_folder(
+    root: PathBuf,
+    search: String,
+    replacement: String,
+    is_regex: bool,
+    skip: Vec<PathBuf>,
+    excluded: Vec<String>,
+    job: Handle,
+) -> Result<ReplaceReport, Error> {
+    // Every line is rewritten on its own, which a query over several lines
+    // would never match the way it reads.
+    if search.contains('\n') {
+        return Err(Error::InvalidPattern(
+            search,
+            String::from("a replace cannot span more than one line"),
+        ));
+    }
+
+    let pattern = Pattern::new(&search, is_regex)?;
+    let mut files = Vec::new();
+    let mut replaced = Vec::new();
+    let paths = walk(root.clone(), &excluded).await?;
+
+    job.set_total(paths.len());
+
+    // Nothing is written until every file has been read, so this loop is the
+    // only place a cancel cannot leave the folder half-replaced.
+    for path in paths {
+        if job.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+
+        job.advance();
+
+        if skip.contains(&path) {
+            continue;
+        }
+
+        let Ok(content) = tokio::fs::read_to_string(&path).await else {
+            continue;
+        };
+
+        let Some((text, lines, replacements)) = replace_lines(&content, &pattern, &replacement)
+        else {
+            continue;
+        };
+
+        files.push(FileChange {
+            path,
+            lines,
+            replacements,
+            original: Arc::new(content),
+            replaced: digest(&text),
+        });
+        replaced.push(text);
+    }
+
+    if !job.commit() {
+        return Err(Error::Cancelled);
+    }
+
+    for (index, (file, text)) in files.iter().zip(replaced).enumerate() {
+        if let Err(err) = tokio::fs::write(&file.path, text).await {
+            let _ = restore(&files[..index]).await;
+
+            return Err(Error::from(err));
+        }
+    }
+
+    Ok(ReplaceReport {
+        root,
+        search,
+        replacement,
+        files,
+    })
+}
+
+// A file edited since the replace is left alone, since putting back what
+// it held before would throw the edit away. What comes back is the report
+// of the files reverted, and the paths of those that were left.
+#[tracing::instrument(skip_all, fields(files = report.files.len()), err)]
+pub async fn revert(
+    report: Arc<ReplaceReport>,
+) -> Result<(Arc<ReplaceReport>, Vec<PathBuf>), Error> {
+    let mut reverted = Vec::new();
+    let mut skipped = Vec::new();
+
+    for file in &report.files {
+        let current = tokio::fs::read_to_string(&file.path).await.ok();
+
+        match current {
+            Some(current) if digest(&current) == file.replaced => reverted.push(file.clone()),
+            // Reverted already, by an earlier attempt that stopped short.
+            Some(current) if current == *file.original => {}
+            _ => skipped.push(file.path.clone()),
+        }
+    }
+
+    restore(&reverted).await.map_err(Error::from)?;
+
+    Ok((
+        Arc::new(ReplaceReport {
+            files: reverted,
+            ..ReplaceReport::clone(&report)
+        }),
+        skipped,
+    ))
+}
+
+fn digest(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+async fn restore(files: &[FileChange]) -> io::Result<()> {
+    for file in files {
+        tokio::fs::write(&file.path, file.original.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+// Lines are matched without their line break, so `$` holds at the end of
+// each and `\s` cannot join one to the next. A file whose only matches run
+// across lines is left out, since none of its lines gets replaced.
+fn replace_lines(
+    content: &str,
+    pattern: &Pattern,
+    replacement: &str,
+) -> Option<(String, Vec<usize>, usize)> {
+    pattern.find(content)?;
+
+    let mut lines = Vec::new();
+    let mut replacements = 0;
+
+    let text = content
+        .split_inclusive('\n')
+        .enumerate()
+        .map(|(index, line)| {
+            let body = line
+                .strip_suffix('\n')
+                .map_or(line, |body| body.strip_suffix('\r').unwrap_or(body));
+            let count = pattern.count(body);
+
+            if count == 0 {
+                return line.to_owned();
+            }
+
+            lines.push(index);
+            replacements += count;
+
+            pattern.replace(body, replacement) + &line[body.len()..]
+        })
+        .collect();
+
+    (replacements > 0).then_some((text, lines, replacements))
+}
+
+pub async fn walk(root: PathBuf, excluded: &[String]) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    let mut directories = vec![root];
+
+    while let Some(directory) = directories.pop() {
+        let mut entries = tokio::fs::read_dir(&directory).await.map_err(Error::from)?;
+
+        while let Some(entry) = entries.next_entry().await.map_err(Error::from)? {
+            let path = entry.path();
+
+            if is_ignored(&path, excluded) {
+                continue;
+            }
+
+            let file_type = entry.file_type().await.map_err(Error::from)?;
+
+            if file_type.is_dir() {
+                directories.push(path);
+            } else if file_type.is_file() {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+pub fn is_ignored(path: &Path, excluded: &[String]) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| {
+            name.starts_with('.') || excluded.iter().any(|excluded| excluded == name)
+        })
+}
@@ -0,0 +1,70 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{buffer::Wrap, store, Error};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewState {
+    pub cursor: (usize, usize),
+    #[serde(default)]
+    pub scroll: usize,
+    #[serde(default)]
+    pub wrap: Wrap,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Session {
+    #[serde(default)]
+    views: HashMap<PathBuf, ViewState>,
+    #[serde(default)]
+    projects: HashMap<PathBuf, PathBuf>,
+    #[serde(default)]
+    open: Vec<PathBuf>,
+    #[serde(default)]
+    active: Option<PathBuf>,
+}
+
+impl Session {
+    const FILE: &'static str = "session.json";
+
+    pub async fn load() -> Session {
+        store::load(Self::FILE).await
+    }
+
+    pub async fn save(self) -> Result<(), Error> {
+        store::save(Self::FILE, &self).await
+    }
+
+    pub fn view(&self, path: &Path) -> Option<ViewState> {
+        self.views.get(path).copied()
+    }
+
+    pub fn remember(&mut self, path: PathBuf, view: ViewState) {
+        self.views.insert(path, view);
+    }
+
+    pub fn open_files(&self) -> &[PathBuf] {
+        &self.open
+    }
+
+    pub fn active_file(&self) -> Option<&Path> {
+        self.active.as_deref()
+    }
+
+    pub fn remember_open(&mut self, files: Vec<PathBuf>, active: Option<PathBuf>) {
+        self.open = files;
+        self.active = active;
+    }
+
+    pub fn project_file(&self, root: &Path) -> Option<PathBuf> {
+        self.projects.get(root).cloned()
+    }
+
+    pub fn remember_project(&mut self, root: PathBuf, path: PathBuf) {
+        self.projects.insert(root, path);
+    }
+}
@@ -0,0 +1,750 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::PathBuf,
+    sync::Arc,
+};
+
+use encoding_rs::{Encoding, UTF_8};
+use iced::{
+    theme,
+    widget::{button, column, container, horizontal_rule, pane_grid, text},
+    Element, Length,
+};
+use tokio::io::AsyncWriteExt;
+
+use crate::{editing::Position, language, Error, Messages};
+
+const SUGGESTIONS: usize = 8;
+
+// Hunspell dictionaries come as a word list and a file of affixes, found
+// under the name of their language. The user's own folder is searched
+// before the ones the system installs them in.
+fn folders() -> Vec<PathBuf> {
+    let mut folders = Vec::new();
+
+    if let Some(config) = dirs::config_dir() {
+        folders.push(config.join("editor").join("dictionaries"));
+    }
+
+    folders.extend(
+        [
+            "/usr/share/hunspell",
+            "/usr/share/myspell",
+            "/usr/share/myspell/dicts",
+            "/Library/Spelling",
+        ]
+        .map(PathBuf::from),
+    );
+
+    folders
+}
+
+fn personal_file() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("editor").join("dictionary.txt"))
+}
+
+pub async fn available() -> Vec<String> {
+    let mut languages = Vec::new();
+
+    for folder in folders() {
+        let Ok(mut entries) = tokio::fs::read_dir(&folder).await else {
+            continue;
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+
+            if path.extension().is_none_or(|extension| extension != "dic") {
+                continue;
+            }
+
+            if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                if !languages.iter().any(|language| language == name) {
+                    languages.push(name.to_owned());
+                }
+            }
+        }
+    }
+
+    languages.sort();
+    languages
+}
+
+#[derive(Clone)]
+pub struct Dictionary {
+    pub language: String,
+    words: HashSet<String>,
+    personal: HashSet<String>,
+    alphabet: Vec<char>,
+}
+
+impl fmt::Debug for Dictionary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dictionary")
+            .field("language", &self.language)
+            .field("words", &self.words.len())
+            .finish()
+    }
+}
+
+pub async fn load(language: String) -> Result<Dictionary, Error> {
+    let mut found = None;
+
+    for folder in folders() {
+        let path = folder.join(format!("{language}.dic"));
+
+        if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            found = Some((path, folder.join(format!("{language}.aff"))));
+            break;
+        }
+    }
+
+    let Some((words, affixes)) = found else {
+        return Err(Error::NoDictionary(language));
+    };
+
+    let words = match tokio::fs::read(&words).await {
+        Ok(bytes) => bytes,
+        Err(err) => return Err(Error::FileFailed(words, Arc::new(err))),
+    };
+
+    // A word list without affixes still works, only without the forms they
+    // would have added.
+    let affixes = tokio::fs::read(&affixes).await.unwrap_or_default();
+    let encoding = declared_encoding(&affixes);
+
+    let personal = match personal_file() {
+        Some(path) => tokio::fs::read_to_string(path).await.unwrap_or_default(),
+        None => String::new(),
+    };
+
+    Ok(Dictionary::new(
+        language,
+        &encoding.decode(&affixes).0,
+        &encoding.decode(&words).0,
+        &personal,
+    ))
+}
+
+// Both files are in the encoding the affixes name on their SET line.
+fn declared_encoding(affixes: &[u8]) -> &'static Encoding {
+    String::from_utf8_lossy(affixes)
+        .lines()
+        .find_map(|line| line.strip_prefix("SET "))
+        .and_then(|label| Encoding::for_label(label.trim().as_bytes()))
+        .unwrap_or(UTF_8)
+}
+
+pub async fn remember(word: String) -> Result<(), Error> {
+    let Some(path) = personal_file() else {
+        return Ok(());
+    };
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    // Writes to a file only reach it once flushed.
+    let written = async {
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+
+        file.write_all(format!("{word}\n").as_bytes()).await?;
+        file.flush().await
+    };
+
+    match written.await {
+        Ok(()) => Ok(()),
+        Err(err) => Err(Error::FileFailed(path, Arc::new(err))),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flags {
+    Single,
+    Long,
+    Numbers,
+}
+
+impl Flags {
+    fn split(self, flags: &str) -> Vec<String> {
+        match self {
+            Flags::Single => flags.chars().map(String::from).collect(),
+            Flags::Long => flags
+                .chars()
+                .collect::<Vec<_>>()
+                .chunks(2)
+                .map(|pair| pair.iter().collect())
+                .collect(),
+            Flags::Numbers => flags.split(',').map(str::to_owned).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Class {
+    Any,
+    OneOf(Vec<char>),
+    NoneOf(Vec<char>),
+}
+
+impl Class {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Class::Any => true,
+            Class::OneOf(chars) => chars.contains(&c),
+            Class::NoneOf(chars) => !chars.contains(&c),
+        }
+    }
+}
+
+fn condition(pattern: &str) -> Vec<Class> {
+    let mut classes = Vec::new();
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        classes.push(match c {
+            '.' => Class::Any,
+            '[' => {
+                let set = chars.by_ref().take_while(|&c| c != ']').collect::<Vec<_>>();
+
+                match set.split_first() {
+                    Some(('^', rest)) => Class::NoneOf(rest.to_vec()),
+                    _ => Class::OneOf(set),
+                }
+            }
+            c => Class::OneOf(vec![c]),
+        });
+    }
+
+    classes
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    strip: String,
+    add: String,
+    condition: Vec<Class>,
+}
+
+impl Rule {
+    fn suffixed(&self, word: &str) -> Option<String> {
+        let chars = word.chars().collect::<Vec<_>>();
+        let tail = chars.len().checked_sub(self.condition.len())?;
+
+        if !self
+            .condition
+            .iter()
+            .zip(&chars[tail..])
+            .all(|(class, &c)| class.matches(c))
+        {
+            return None;
+        }
+
+        let stem = word.strip_suffix(self.strip.as_str())?;
+
+        Some(format!("{stem}{}", self.add))
+    }
+
+    fn prefixed(&self, word: &str) -> Option<String> {
+        let mut chars = word.chars();
+
+        if !self
+            .condition
+            .iter()
+            .all(|class| chars.next().is_some_and(|c| class.matches(c)))
+        {
+            return None;
+        }
+
+        let stem = word.strip_prefix(self.strip.as_str())?;
+
+        Some(format!("{}{stem}", self.add))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Affix {
+    is_cross: bool,
+    rules: Vec<Rule>,
+}
+
+// Only what it takes to spell out every form of a word is read: the flag
+// format, the prefixes and the suffixes. Compounds and the rules for
+// suggesting are left out.
+#[derive(Debug, Default)]
+struct Affixes {
+    flags: Option<Flags>,
+    prefixes: HashMap<String, Affix>,
+    suffixes: HashMap<String, Affix>,
+    alphabet: Vec<char>,
+}
+
+impl Affixes {
+    fn parse(text: &str) -> Self {
+        let mut affixes = Affixes::default();
+
+        for line in text.lines() {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+
+            match fields.as_slice() {
+                ["FLAG", "long", ..] => affixes.flags = Some(Flags::Long),
+                ["FLAG", "num", ..] => affixes.flags = Some(Flags::Numbers),
+                ["TRY", letters, ..] => affixes.alphabet = letters.chars().collect(),
+                [kind @ ("PFX" | "SFX"), flag, rest @ ..] => {
+                    let table = if *kind == "PFX" {
+                        &mut affixes.prefixes
+                    } else {
+                        &mut affixes.suffixes
+                    };
+
+                    // The first line for a flag says whether it combines
+                    // with the other kind, and the ones after it are rules.
+                    match (table.get_mut(*flag), rest) {
+                        (None, [cross, ..]) => {
+                            table.insert(
+                                flag.to_string(),
+                                Affix {
+                                    is_cross: *cross == "Y",
+                                    rules: Vec::new(),
+                                },
+                            );
+                        }
+                        (Some(affix), [strip, add, rest @ ..]) => {
+                            let add = add.split('/').next().unwrap_or_default();
+
+                            affix.rules.push(Rule {
+                                strip: none_if_zero(strip),
+                                add: none_if_zero(add),
+                                condition: condition(rest.first().copied().unwrap_or(".")),
+                            });
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        affixes
+    }
+}
+
+fn none_if_zero(field: &str) -> String {
+    if field == "0" {
+        String::new()
+    } else {
+        field.to_owned()
+    }
+}
+
+impl Dictionary {
+    // Every form the affixes allow is worked out up front, which takes a
+    // moment when loading but keeps looking a word up to a single check.
+    fn new(language: String, affixes: &str, words: &str, personal: &str) -> Self {
+        let affixes = Affixes::parse(affixes);
+        let format = affixes.flags.unwrap_or(Flags::Single);
+        let mut known = HashSet::new();
+
+        // The first line only tells how many words there are.
+        for line in words.lines().skip(1) {
+            let Some(entry) = line.split_whitespace().next() else {
+                continue;
+            };
+
+            let (word, flags) = entry
+                .split_once('/')
+                .map_or((entry, Vec::new()), |(word, flags)| {
+                    (word, format.split(flags))
+                });
+
+            let prefixes = flags
+                .iter()
+                .filter_map(|flag| affixes.prefixes.get(flag))
+                .collect::<Vec<_>>();
+
+            for suffix in flags.iter().filter_map(|flag| affixes.suffixes.get(flag)) {
+                for form in suffix.rules.iter().filter_map(|rule| rule.suffixed(word)) {
+                    if suffix.is_cross {
+                        known.extend(
+                            prefixes
+                                .iter()
+                                .filter(|prefix| prefix.is_cross)
+                                .flat_map(|prefix| &prefix.rules)
+                                .filter_map(|rule| rule.prefixed(&form)),
+                        );
+                    }
+
+                    known.insert(form);
+                }
+            }
+
+            for prefix in &prefixes {
+                known.extend(prefix.rules.iter().filter_map(|rule| rule.prefixed(word)));
+            }
+
+            known.insert(word.to_owned());
+        }
+
+        let alphabet = if affixes.alphabet.is_empty() {
+            ('a'..='z').collect()
+        } else {
+            affixes.alphabet
+        };
+
+        Self {
+            language,
+            words: known,
+            personal: personal
+                .lines()
+                .map(str::trim)
+                .filter(|word| !word.is_empty())
+                .map(str::to_owned)
+                .collect(),
+            alphabet,
+        }
+    }
+
+    // Words added since loading, which only ever grow in number.
+    pub fn added(&self) -> usize {
+        self.personal.len()
+    }
+
+    pub fn add(&mut self, word: String) {
+        self.personal.insert(word);
+    }
+
+    fn is_known(&self, word: &str) -> bool {
+        self.words.contains(word) || self.personal.contains(word)
+    }
+
+    // A word may also start a sentence, or be written in capitals, when
+    // the dictionary has it in lowercase.
+    pub fn is_correct(&self, word: &str) -> bool {
+        if self.is_known(word) {
+            return true;
+        }
+
+        let lowercase = word.to_lowercase();
+
+        match case(word) {
+            Case::Capitalized => self.is_known(&lowercase),
+            Case::Upper => self.is_known(&lowercase) || self.is_known(&capitalized(&lowercase)),
+            Case::Lower => false,
+        }
+    }
+
+    // Known words one edit away, the likeliest kinds of slip first, written
+    // in the case of the word they replace.
+    pub fn suggestions(&self, word: &str) -> Vec<String> {
+        let chars = word.to_lowercase().chars().collect::<Vec<_>>();
+        let mut candidates = Vec::new();
+
+        for index in 1..chars.len() {
+            let mut swapped = chars.clone();
+
+            swapped.swap(index - 1, index);
+            candidates.push(swapped.into_iter().collect::<String>());
+        }
+
+        for index in 0..chars.len() {
+            for &c in &self.alphabet {
+                let mut replaced = chars.clone();
+
+                replaced[index] = c;
+                candidates.push(replaced.into_iter().collect());
+            }
+        }
+
+        for index in 0..chars.len() {
+            let mut removed = chars.clone();
+
+            removed.remove(index);
+            candidates.push(removed.into_iter().collect());
+        }
+
+        for index in 0..=chars.len() {
+            for &c in &self.alphabet {
+                let mut inserted = chars.clone();
+
+                inserted.insert(index, c);
+                candidates.push(inserted.into_iter().collect());
+            }
+        }
+
+        for index in 1..chars.len() {
+            let (first, second) = chars.split_at(index);
+            let first = first.iter().collect::<String>();
+            let second = second.iter().collect::<String>();
+
+            if self.is_correct(&first) && self.is_correct(&second) {
+                candidates.push(format!("{first} {second}"));
+            }
+        }
+
+        let mut seen = HashSet::new();
+
+        candidates
+            .into_iter()
+            .filter(|candidate| {
+                candidate != &word.to_lowercase()
+                    && (candidate.contains(' ') || self.is_correct(candidate))
+            })
+            .filter(|candidate| seen.insert(candidate.clone()))
+            .take(SUGGESTIONS)
+            .map(|candidate| match case(word) {
+                Case::Lower => candidate,
+                Case::Capitalized => capitalized(&candidate),
+                Case::Upper => candidate.to_uppercase(),
+            })
+            .collect()
+    }
+}
+
+enum Case {
+    Lower,
+    Capitalized,
+    Upper,
+}
+
+fn case(word: &str) -> Case {
+    let mut chars = word.chars();
+    let is_first_upper = chars.next().is_some_and(char::is_uppercase);
+
+    match (is_first_upper, chars.any(char::is_uppercase)) {
+        (true, true) => Case::Upper,
+        (true, false) => Case::Capitalized,
+        _ => Case::Lower,
+    }
+}
+
+fn capitalized(word: &str) -> String {
+    let mut chars = word.chars();
+
+    chars.next().map_or(String::new(), |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+enum Region {
+    Code,
+    String(char),
+    Comment(&'static str),
+}
+
+// Code is only checked in its comments and strings, prose all through
+// apart from the code it quotes. A string ends with its line, which keeps
+// a stray quote from running on.
+pub fn check(dictionary: &Dictionary, text: &str, extension: &str) -> Vec<(Position, Position)> {
+    let is_prose = matches!(extension, "" | "txt" | "md" | "markdown" | "rst");
+    let line_comment = language::line_comment(extension);
+    let block_comment = language::block_comment(extension);
+    let quotes: &[char] = match extension {
+        "js" | "mjs" | "ts" => &['"', '\'', '`'],
+        "py" | "sh" | "bash" | "toml" | "yaml" | "yml" => &['"', '\''],
+        _ => &['"'],
+    };
+
+    let mut misspelled = Vec::new();
+    let mut region = Region::Code;
+    let mut is_fenced = false;
+
+    for (index, line) in text.split('\n').enumerate() {
+        let chars = line.chars().collect::<Vec<_>>();
+        let mut checked = vec![is_prose; chars.len()];
+
+        if is_prose {
+            if line.trim_start().starts_with("```") {
+                is_fenced = !is_fenced;
+            }
+
+            let mut is_quoted = is_fenced;
+
+            for (column, &c) in chars.iter().enumerate() {
+                if c == '`' {
+                    is_quoted = !is_quoted || is_fenced;
+                }
+
+                checked[column] = !is_quoted && c != '`';
+            }
+        } else {
+            let mut column = 0;
+
+            while column < chars.len() {
+                let rest = &chars[column..];
+
+                match region {
+                    Region::Comment(close) if starts_with(rest, close) => {
+                        region = Region::Code;
+                        column += close.chars().count();
+                    }
+                    Region::Comment(_) => {
+                        checked[column] = true;
+                        column += 1;
+                    }
+                    Region::String(_) if rest[0] == '\\' => column += 2,
+                    Region::String(quote) if rest[0] == quote => {
+                        region = Region::Code;
+                        column += 1;
+                    }
+                    Region::String(_) => {
+                        checked[column] = true;
+                        column += 1;
+                    }
+                    Region::Code => {
+                        if line_comment.is_some_and(|comment| starts_with(rest, comment)) {
+                            checked[column..].fill(true);
+                            break;
+                        }
+
+                        match block_comment {
+                            Some((open, close)) if starts_with(rest, open) => {
+                                region = Region::Comment(close);
+                                column += open.chars().count();
+                            }
+                            _ if quotes.contains(&rest[0]) => {
+                                region = Region::String(rest[0]);
+                                column += 1;
+                            }
+                            _ => column += 1,
+                        }
+                    }
+                }
+            }
+
+            if let Region::String(_) = region {
+                region = Region::Code;
+            }
+        }
+
+        for (start, end) in words(&chars, &checked) {
+            let word = chars[start..end].iter().collect::<String>();
+
+            if !dictionary.is_correct(&word) {
+                misspelled.push(((index, start), (index, end)));
+            }
+        }
+    }
+
+    misspelled
+}
+
+fn starts_with(chars: &[char], pattern: &str) -> bool {
+    let mut chars = chars.iter();
+
+    pattern.chars().all(|c| chars.next() == Some(&c))
+}
+
+// Runs of letters worth looking up. Anything that looks like a name from
+// code, a path or an abbreviation is passed over.
+fn words(chars: &[char], checked: &[bool]) -> Vec<(usize, usize)> {
+    let is_part = |c: char| c.is_alphanumeric() || c == '_' || c == '\'';
+    let at = |column: Option<usize>| column.and_then(|column| chars.get(column)).copied();
+    let mut words = Vec::new();
+    let mut column = 0;
+
+    while column < chars.len() {
+        if !checked[column] || !is_part(chars[column]) {
+            column += 1;
+            continue;
+        }
+
+        let mut start = column;
+
+        while column < chars.len() && checked[column] && is_part(chars[column]) {
+            column += 1;
+        }
+
+        let mut end = column;
+
+        while start < end && chars[start] == '\'' {
+            start += 1;
+        }
+
+        while end > start && chars[end - 1] == '\'' {
+            end -= 1;
+        }
+
+        let word = &chars[start..end];
+        let before = at(start.checked_sub(1));
+        let after = at(Some(end));
+        let beyond = at(Some(end + 1));
+
+        let is_word = word.len() >= 2
+            && word.iter().all(|&c| c.is_alphabetic() || c == '\'')
+            && !word[1..].iter().any(|c| c.is_uppercase())
+            && !matches!(before, Some('.' | ':' | '/' | '\\' | '@' | '#' | '$' | '&'))
+            && !matches!(after, Some('(' | '/' | '\\' | '@'))
+            && !(matches!(after, Some('.' | ':'))
+                && beyond.is_some_and(|c| c.is_alphanumeric() || c == '/'));
+
+        if is_word {
+            words.push((start, end));
+        }
+    }
+
+    words
+}
+
+// Offered for a misspelled word from start up to end, on the same line.
+#[derive(Debug, Clone)]
+pub struct Suggestions {
+    pub pane: pane_grid::Pane,
+    pub start: Position,
+    pub end: Position,
+    pub word: String,
+    replacements: Vec<String>,
+}
+
+impl Suggestions {
+    pub fn new(
+        pane: pane_grid::Pane,
+        (start, end): (Position, Position),
+        word: String,
+        dictionary: &Dictionary,
+    ) -> Self {
+        Self {
+            pane,
+            start,
+            end,
+            replacements: dictionary.suggestions(&word),
+            word,
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Messages> {
+        let item = |label: String, message: Messages| {
+            button(text(label).size(14))
+                .style(theme::Button::Text)
+                .width(Length::Fill)
+                .padding([2, 5])
+                .on_press(message)
+        };
+
+        let items = if self.replacements.is_empty() {
+            column![text("No suggestions").size(14)]
+        } else {
+            self.replacements
+                .iter()
+                .fold(column![], |items, replacement| {
+                    items.push(item(
+                        replacement.clone(),
+                        Messages::ReplaceMisspelled(replacement.clone()),
+                    ))
+                })
+        };
+
+        container(items.push(horizontal_rule(1)).push(item(
+            format!("Add \"{}\" to dictionary", self.word),
+            Messages::AddToDictionary,
+        )))
+        .width(240)
+        .padding(5)
+        .style(theme::Container::Box)
+        .into()
+    }
+}
@@ -0,0 +1,164 @@
+use std::fmt;
+
+use iced::widget::text_editor;
+
+// Line breaks count toward neither words nor characters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub lines: usize,
+    pub words: usize,
+    pub characters: usize,
+}
+
+impl Counts {
+    fn of(text: &str) -> Counts {
+        Counts {
+            lines: text.lines().count().max(1),
+            words: text.split_whitespace().count(),
+            characters: text.chars().filter(|&c| c != '\n' && c != '\r').count(),
+        }
+    }
+}
+
+impl fmt::Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |count: usize, noun: &str| match count {
+            1 => format!("1 {noun}"),
+            count => format!("{count} {noun}s"),
+        };
+
+        write!(
+            f,
+            "{}, {}, {}",
+            plural(self.lines, "line"),
+            plural(self.words, "word"),
+            plural(self.characters, "char")
+        )
+    }
+}
+
+// The lines an edit may touch, taken just before it is applied. Everything
+// outside them keeps its counts.
+#[derive(Debug, Clone, Copy)]
+pub struct Span {
+    key: (u64, usize),
+    first: usize,
+    last: usize,
+}
+
+impl Span {
+    // One line of margin either way covers backspacing into the line above
+    // and deleting the line break at the end.
+    pub fn before(content: &text_editor::Content, revision: u64) -> Span {
+        let (line, _) = content.cursor_position();
+        let selected = content
+            .selection()
+            .map_or(0, |selection| selection.matches('\n').count());
+
+        Span {
+            key: (revision, content.line_count()),
+            first: line.saturating_sub(selected + 1),
+            last: (line + selected + 1).min(content.line_count().saturating_sub(1)),
+        }
+    }
+
+    pub fn key(&self) -> (u64, usize) {
+        self.key
+    }
+
+    // The first line and the last one it covered before the edit, and the
+    // end of what took their place. Nothing if the edit reached past them.
+    pub fn replaced(&self, content: &text_editor::Content) -> Option<(usize, usize, usize)> {
+        let (line, _) = content.cursor_position();
+        let line_count = content.line_count();
+        let first = self.first.min(line.saturating_sub(1));
+        let end = (self.last + 1 + line_count).saturating_sub(self.key.1);
+
+        (end >= first && end <= line_count).then_some((first, self.last, end))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    lines: Vec<Counts>,
+    total: Counts,
+    key: Option<(u64, usize)>,
+    pub selection: Option<Counts>,
+}
+
+impl Stats {
+    pub fn total(&self) -> Counts {
+        self.total
+    }
+
+    pub fn refresh(&mut self, content: &text_editor::Content, revision: u64) {
+        self.selection = content
+            .selection()
+            .filter(|selection| !selection.is_empty())
+            .map(|selection| Counts::of(&selection));
+
+        let key = (revision, content.line_count());
+
+        if self.key == Some(key) {
+            return;
+        }
+
+        self.lines = (0..content.line_count())
+            .map(|line| line_counts(content, line))
+            .collect();
+        self.total = Counts {
+            lines: self.lines.len(),
+            ..self.lines.iter().fold(Counts::default(), add)
+        };
+        self.key = Some(key);
+    }
+
+    // Falls back to a full recount on the next refresh whenever the counts
+    // were already behind before the edit.
+    pub fn splice(&mut self, content: &text_editor::Content, span: Span, revision: u64) {
+        if self.key != Some(span.key) {
+            return;
+        }
+
+        let line_count = content.line_count();
+
+        let Some((first, _, end)) = span
+            .replaced(content)
+            .filter(|_| span.last < self.lines.len())
+        else {
+            self.key = None;
+
+            return;
+        };
+
+        let counted: Vec<_> = (first..end)
+            .map(|line| line_counts(content, line))
+            .collect();
+        let removed = self.lines[first..=span.last]
+            .iter()
+            .fold(Counts::default(), add);
+        let added = counted.iter().fold(Counts::default(), add);
+
+        self.lines.splice(first..=span.last, counted);
+        self.total = Counts {
+            lines: line_count,
+            words: self.total.words - removed.words + added.words,
+            characters: self.total.characters - removed.characters + added.characters,
+        };
+        self.key = Some((revision, line_count));
+    }
+}
+
+fn line_counts(content: &text_editor::Content, line: usize) -> Counts {
+    content
+        .line(line)
+        .map_or(Counts::default(), |text| Counts::of(&text))
+}
+
+fn add(sum: Counts, counts: &Counts) -> Counts {
+    Counts {
+        lines: sum.lines + counts.lines,
+        words: sum.words + counts.words,
+        characters: sum.characters + counts.characters,
+    }
+}
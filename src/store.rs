@@ -0,0 +1,67 @@
+use std::{
+    io,
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::Error;
+
+static WRITES: AtomicU64 = AtomicU64::new(0);
+
+fn path(name: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("editor").join(name))
+}
+
+pub async fn load<T>(name: &str) -> T
+where
+    T: DeserializeOwned + Default,
+{
+    let Some(path) = path(name) else {
+        return T::default();
+    };
+
+    tokio::fs::read_to_string(path)
+        .await
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// The new state goes to a file beside the old one and is renamed over it,
+// so a crash while writing keeps what was saved before. Every write gets its
+// own file, as saves can overlap and would otherwise write into each other.
+pub async fn save<T>(name: &str, value: &T) -> Result<(), Error>
+where
+    T: Serialize,
+{
+    let path = path(name)
+        .ok_or_else(|| Error::from(io::Error::new(io::ErrorKind::NotFound, "no data directory")))?;
+
+    if let Some(directory) = path.parent() {
+        tokio::fs::create_dir_all(directory)
+            .await
+            .map_err(Error::from)?;
+    }
+
+    let content =
+        serde_json::to_string_pretty(value).map_err(|err| Error::from(io::Error::from(err)))?;
+
+    let partial = path.with_extension(format!(
+        "{}-{}.tmp",
+        std::process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let written = match tokio::fs::write(&partial, content).await {
+        Ok(()) => tokio::fs::rename(&partial, &path).await,
+        Err(err) => Err(err),
+    };
+
+    if written.is_err() {
+        let _ = tokio::fs::remove_file(&partial).await;
+    }
+
+    written.map_err(Error::from)
+}
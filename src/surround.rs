@@ -0,0 +1,81 @@
+use iced::{
+    widget::{button, row, text, text_input},
+    Element,
+};
+
+use crate::Messages;
+
+const PRESETS: [&str; 9] = ["\"", "'", "`", "(", "[", "{", "*", "**", "~~"];
+
+pub fn input_id() -> text_input::Id {
+    text_input::Id::new("surround")
+}
+
+// Changing asks for the pair that takes the place of the one around the
+// cursor, surrounding for the one that goes around the selection.
+#[derive(Debug, Clone, Default)]
+pub struct Surround {
+    pub input: String,
+    pub is_changing: bool,
+}
+
+impl Surround {
+    pub fn view(&self) -> Element<'_, Messages> {
+        let label = if self.is_changing {
+            "Change surrounding pair to"
+        } else {
+            "Surround with"
+        };
+
+        PRESETS
+            .iter()
+            .fold(row![text(label)].spacing(5), |bar, &preset| {
+                bar.push(button(text(preset)).on_press(Messages::SurroundWith(preset.to_string())))
+            })
+            .push(
+                text_input("Custom, like <em> or /*", &self.input)
+                    .id(input_id())
+                    .on_input(Messages::SurroundChanged)
+                    .on_submit(Messages::SubmitSurround),
+            )
+            .push(button("Close").on_press(Messages::CloseSurround))
+            .into()
+    }
+}
+
+// Brackets close the other way round, so "/*" closes with "*/", and a tag
+// closes with its name alone.
+pub fn pair(opening: &str) -> Option<(String, String)> {
+    if opening.is_empty() {
+        return None;
+    }
+
+    if let Some(tag) = opening.strip_prefix('<').filter(|_| opening.ends_with('>')) {
+        let name = tag
+            .chars()
+            .take_while(|&c| c.is_alphanumeric() || c == '-')
+            .collect::<String>();
+
+        if !name.is_empty() {
+            return Some((opening.to_string(), format!("</{name}>")));
+        }
+    }
+
+    let closing = opening
+        .chars()
+        .rev()
+        .map(|c| match c {
+            '(' => ')',
+            ')' => '(',
+            '[' => ']',
+            ']' => '[',
+            '{' => '}',
+            '}' => '{',
+            '<' => '>',
+            '>' => '<',
+            c => c,
+        })
+        .collect();
+
+    Some((opening.to_string(), closing))
+}
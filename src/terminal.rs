@@ -0,0 +1,438 @@
+use std::{
+    io::{Read, Write},
+    path::PathBuf,
+};
+
+use iced::{
+    futures::{channel::mpsc, stream, SinkExt, StreamExt},
+    keyboard::{KeyCode, Modifiers},
+    subscription, theme,
+    widget::{button, column, container, horizontal_space, mouse_area, row, scrollable, text},
+    Element, Length, Subscription,
+};
+use portable_pty::{native_pty_system, Child, ChildKiller, CommandBuilder, PtySize};
+
+use crate::Messages;
+
+const SCROLLBACK: usize = 1000;
+
+// The panel does not know its width in characters, so the shell is told a
+// fixed size and long lines wrap past it.
+const SIZE: PtySize = PtySize {
+    rows: 24,
+    cols: 120,
+    pixel_width: 0,
+    pixel_height: 0,
+};
+
+// Keys go to the shell while the panel is focused, until the editor is
+// clicked again.
+#[derive(Debug, Clone)]
+pub struct Terminal {
+    pub id: u64,
+    pub directory: PathBuf,
+    pub input: Option<Input>,
+    pub screen: Screen,
+    pub is_focused: bool,
+}
+
+impl Terminal {
+    pub fn new(id: u64, directory: PathBuf) -> Self {
+        Self {
+            id,
+            directory,
+            input: None,
+            screen: Screen::default(),
+            is_focused: true,
+        }
+    }
+
+    pub fn send(&self, bytes: Vec<u8>) {
+        if let Some(input) = self.input.as_ref() {
+            input.send(bytes);
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Messages> {
+        let screen = scrollable(text(self.screen.text_with_cursor(self.is_focused)).size(13))
+            .id(scroll_id())
+            .width(Length::Fill)
+            .height(200);
+
+        column![
+            row![
+                text(format!("Terminal — {}", self.directory.display())),
+                horizontal_space(Length::Fill),
+                button("Close").on_press(Messages::ToggleTerminal),
+            ]
+            .spacing(10),
+            mouse_area(container(screen).padding(5).style(theme::Container::Box))
+                .on_press(Messages::FocusTerminal),
+        ]
+        .spacing(5)
+        .into()
+    }
+}
+
+pub fn scroll_id() -> scrollable::Id {
+    scrollable::Id::new("terminal")
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Ready(u64, Input),
+    Output(u64, Vec<u8>),
+    Exited(u64),
+    Failed(u64, String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Input(mpsc::UnboundedSender<Vec<u8>>);
+
+impl Input {
+    pub fn send(&self, bytes: Vec<u8>) {
+        let _ = self.0.unbounded_send(bytes);
+    }
+}
+
+// One shell runs per session, and it is killed once the subscription goes
+// away with the panel.
+pub fn connect(id: u64, directory: PathBuf) -> Subscription<Event> {
+    subscription::channel(("terminal", id), 100, move |mut output| async move {
+        match run(id, directory, &mut output).await {
+            Ok(()) => {
+                let _ = output.send(Event::Exited(id)).await;
+            }
+            Err(err) => {
+                tracing::warn!(%err, "terminal failed");
+
+                let _ = output.send(Event::Failed(id, err)).await;
+            }
+        }
+
+        std::future::pending().await
+    })
+}
+
+struct Shell(Box<dyn Child + Send + Sync>);
+
+impl Drop for Shell {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+    }
+}
+
+enum Received {
+    Output(Vec<u8>),
+    Closed,
+    Input(Vec<u8>),
+}
+
+async fn run(id: u64, directory: PathBuf, output: &mut mpsc::Sender<Event>) -> Result<(), String> {
+    let pair = native_pty_system()
+        .openpty(SIZE)
+        .map_err(|err| err.to_string())?;
+
+    // Only a plain terminal is emulated, so programs are asked to keep
+    // their output simple.
+    let mut command = CommandBuilder::new_default_prog();
+    command.cwd(directory);
+    command.env("TERM", "dumb");
+
+    let _shell = Shell(
+        pair.slave
+            .spawn_command(command)
+            .map_err(|err| err.to_string())?,
+    );
+    drop(pair.slave);
+
+    let mut reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|err| err.to_string())?;
+    let mut writer = pair.master.take_writer().map_err(|err| err.to_string())?;
+
+    // Reading blocks, so it gets a thread of its own.
+    let (chunks, received) = mpsc::unbounded();
+
+    std::thread::spawn(move || {
+        let mut buffer = [0; 4096];
+
+        while let Ok(read @ 1..) = reader.read(&mut buffer) {
+            if chunks.unbounded_send(buffer[..read].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let (sender, inputs) = mpsc::unbounded();
+    let _ = output.send(Event::Ready(id, Input(sender))).await;
+
+    let mut events = stream::select(
+        received
+            .map(Received::Output)
+            .chain(stream::once(async { Received::Closed })),
+        inputs.map(Received::Input),
+    );
+
+    while let Some(event) = events.next().await {
+        match event {
+            Received::Output(bytes) => {
+                let _ = output.send(Event::Output(id, bytes)).await;
+            }
+            Received::Closed => return Ok(()),
+            Received::Input(bytes) => {
+                writer
+                    .write_all(&bytes)
+                    .and_then(|()| writer.flush())
+                    .map_err(|err| err.to_string())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// What a key sends to the shell. Letters with Ctrl become control codes,
+// and Ctrl+Shift chords are left to the keymap.
+pub fn key(key_code: KeyCode, modifiers: Modifiers) -> Option<Vec<u8>> {
+    if modifiers.command() {
+        let name = format!("{key_code:?}");
+
+        return match name.as_bytes() {
+            [letter @ b'A'..=b'Z'] if !modifiers.shift() => Some(vec![letter - b'A' + 1]),
+            _ => None,
+        };
+    }
+
+    let sequence: &[u8] = match key_code {
+        KeyCode::Enter => b"\r",
+        KeyCode::Backspace => b"\x7f",
+        KeyCode::Tab => b"\t",
+        KeyCode::Escape => b"\x1b",
+        KeyCode::Up => b"\x1b[A",
+        KeyCode::Down => b"\x1b[B",
+        KeyCode::Right => b"\x1b[C",
+        KeyCode::Left => b"\x1b[D",
+        KeyCode::Home => b"\x1b[H",
+        KeyCode::End => b"\x1b[F",
+        KeyCode::Delete => b"\x1b[3~",
+        KeyCode::PageUp => b"\x1b[5~",
+        KeyCode::PageDown => b"\x1b[6~",
+        _ => return None,
+    };
+
+    Some(sequence.to_vec())
+}
+
+#[derive(Debug, Clone, Default)]
+enum State {
+    #[default]
+    Text,
+    Escape,
+    Control(String),
+    Command,
+    CommandEscape,
+}
+
+// Keeps the lines a shell writes, following carriage returns, backspaces
+// and the escape sequences for moving along and clearing a line. Colors and
+// titles are dropped.
+#[derive(Debug, Clone)]
+pub struct Screen {
+    lines: Vec<Vec<char>>,
+    line: usize,
+    column: usize,
+    state: State,
+    pending: Vec<u8>,
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Self {
+            lines: vec![Vec::new()],
+            line: 0,
+            column: 0,
+            state: State::default(),
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl Screen {
+    // A character may be split between two reads, in which case its start
+    // waits for the rest.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+
+        let pending = std::mem::take(&mut self.pending);
+        let mut rest = &pending[..];
+        let mut text = String::new();
+
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    rest = &[];
+                    break;
+                }
+                Err(err) => {
+                    let (valid, after) = rest.split_at(err.valid_up_to());
+                    text.push_str(std::str::from_utf8(valid).unwrap_or_default());
+
+                    match err.error_len() {
+                        Some(length) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            rest = &after[length..];
+                        }
+                        None => {
+                            rest = after;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.pending = rest.to_vec();
+
+        for c in text.chars() {
+            self.state = match std::mem::take(&mut self.state) {
+                State::Text => self.text(c),
+                State::Escape => match c {
+                    '[' => State::Control(String::new()),
+                    ']' => State::Command,
+                    _ => State::Text,
+                },
+                State::Control(mut parameters) => {
+                    if ('@'..='~').contains(&c) {
+                        self.control(&parameters, c);
+
+                        State::Text
+                    } else {
+                        parameters.push(c);
+
+                        State::Control(parameters)
+                    }
+                }
+                State::Command => match c {
+                    '\x07' => State::Text,
+                    '\x1b' => State::CommandEscape,
+                    _ => State::Command,
+                },
+                State::CommandEscape => State::Text,
+            };
+        }
+    }
+
+    pub fn text_with_cursor(&self, is_focused: bool) -> String {
+        let mut text = String::new();
+
+        for (index, line) in self.lines.iter().enumerate() {
+            if index > 0 {
+                text.push('\n');
+            }
+
+            if is_focused && index == self.line {
+                let mut line = line.clone();
+
+                line.resize(line.len().max(self.column + 1), ' ');
+                line[self.column] = '█';
+                text.extend(line);
+            } else {
+                text.extend(line);
+            }
+        }
+
+        text
+    }
+
+    fn text(&mut self, c: char) -> State {
+        match c {
+            '\x1b' => return State::Escape,
+            '\n' => self.line_feed(),
+            '\r' => self.column = 0,
+            '\x08' => self.column = self.column.saturating_sub(1),
+            '\t' => self.column = (self.column / 8 + 1) * 8,
+            c if c.is_control() => {}
+            c => {
+                let line = &mut self.lines[self.line];
+
+                if line.len() <= self.column {
+                    line.resize(self.column, ' ');
+                    line.push(c);
+                } else {
+                    line[self.column] = c;
+                }
+
+                self.column += 1;
+            }
+        }
+
+        State::Text
+    }
+
+    fn line_feed(&mut self) {
+        self.line += 1;
+
+        if self.line == self.lines.len() {
+            self.lines.push(Vec::new());
+        }
+
+        if self.lines.len() > SCROLLBACK {
+            let excess = self.lines.len() - SCROLLBACK;
+
+            self.lines.drain(..excess);
+            self.line -= excess;
+        }
+    }
+
+    fn control(&mut self, parameters: &str, action: char) {
+        let count = |default: usize| {
+            parameters
+                .trim_start_matches('?')
+                .split(';')
+                .next()
+                .and_then(|count| count.parse().ok())
+                .unwrap_or(default)
+        };
+
+        let line = &mut self.lines[self.line];
+
+        match action {
+            'K' => match count(0) {
+                0 => line.truncate(self.column),
+                1 => line.iter_mut().take(self.column).for_each(|c| *c = ' '),
+                _ => line.clear(),
+            },
+            'J' if count(0) >= 2 => {
+                self.lines = vec![Vec::new()];
+                self.line = 0;
+                self.column = 0;
+            }
+            'J' => {
+                line.truncate(self.column);
+                self.lines.truncate(self.line + 1);
+            }
+            'P' => {
+                let end = (self.column + count(1)).min(line.len());
+
+                if self.column < end {
+                    line.drain(self.column..end);
+                }
+            }
+            '@' if self.column <= line.len() => {
+                line.splice(
+                    self.column..self.column,
+                    std::iter::repeat(' ').take(count(1)),
+                );
+            }
+            'C' => self.column += count(1),
+            'D' => self.column = self.column.saturating_sub(count(1)),
+            'G' => self.column = count(1).saturating_sub(1),
+            'A' => self.line = self.line.saturating_sub(count(1)),
+            'B' => self.line = (self.line + count(1)).min(self.lines.len() - 1),
+            _ => {}
+        }
+    }
+}
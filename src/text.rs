@@ -0,0 +1,123 @@
+use encoding_rs::UTF_8;
+use iced::widget::text_editor;
+use ropey::{Rope, RopeBuilder};
+
+use crate::{
+    buffer::{Format, LineEnding},
+    encoding,
+    stats::Span,
+};
+
+// A copy of the buffer's text as a rope, kept next to the lines the editor
+// widget holds. The widget stays where the text lives, so this takes as
+// much memory again rather than less. What it buys is saving: typing
+// splices in only the lines it touched, and since ropes share their nodes
+// a save takes a copy without putting the whole text together first. Any
+// other change has the rope read back in full the next time it is asked
+// for.
+#[derive(Debug, Clone, Default)]
+pub struct Text {
+    rope: Rope,
+    key: Option<(u64, usize)>,
+}
+
+impl Text {
+    pub fn new(text: &str, content: &text_editor::Content, revision: u64) -> Text {
+        Text {
+            rope: Rope::from_str(text),
+            key: Some((revision, content.line_count())),
+        }
+    }
+
+    pub fn rope(&mut self, content: &text_editor::Content, revision: u64) -> Rope {
+        let key = (revision, content.line_count());
+
+        if self.key != Some(key) {
+            let mut builder = RopeBuilder::new();
+
+            for line in 0..content.line_count() {
+                if line > 0 {
+                    builder.append("\n");
+                }
+
+                if let Some(text) = content.line(line) {
+                    builder.append(&text);
+                }
+            }
+
+            self.rope = builder.finish();
+            self.key = Some(key);
+        }
+
+        self.rope.clone()
+    }
+
+    // Only a rope that was in step before the edit can take it, and one
+    // that cannot falls back to being read in full.
+    pub fn splice(&mut self, content: &text_editor::Content, span: Span, revision: u64) {
+        if self.key != Some(span.key()) {
+            return;
+        }
+
+        let Some((first, last, end)) = span
+            .replaced(content)
+            .filter(|&(first, last, end)| end > first && last < self.rope.len_lines())
+        else {
+            self.key = None;
+
+            return;
+        };
+
+        let start = self.rope.line_to_char(first);
+        let stop = if last + 1 < self.rope.len_lines() {
+            self.rope.line_to_char(last + 1)
+        } else {
+            self.rope.len_chars()
+        };
+
+        let mut lines = (first..end)
+            .map(|line| {
+                content
+                    .line(line)
+                    .map_or(String::new(), |text| text.to_string())
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if end < content.line_count() {
+            lines.push('\n');
+        }
+
+        self.rope.remove(start..stop);
+        self.rope.insert(start, &lines);
+        self.key = Some((revision, content.line_count()));
+    }
+}
+
+// UTF-8 is written from the chunks of the rope as they are, which saves
+// putting the whole text together first. Nothing is returned when the text
+// has characters the encoding cannot represent.
+pub fn bytes(rope: &Rope, format: Format) -> Option<Vec<u8>> {
+    if format.encoding != UTF_8 {
+        return encoding::encode(&format.line_ending.apply(rope.to_string()), format.encoding);
+    }
+
+    let mut bytes = Vec::with_capacity(rope.len_bytes() + rope.len_lines());
+
+    for chunk in rope.chunks() {
+        match format.line_ending {
+            LineEnding::Lf => bytes.extend_from_slice(chunk.as_bytes()),
+            LineEnding::Crlf => {
+                for (index, line) in chunk.split('\n').enumerate() {
+                    if index > 0 {
+                        bytes.extend_from_slice(b"\r\n");
+                    }
+
+                    bytes.extend_from_slice(line.as_bytes());
+                }
+            }
+        }
+    }
+
+    Some(bytes)
+}
@@ -0,0 +1,119 @@
+use std::{fmt, path::PathBuf};
+
+use iced::{highlighter, theme::Palette, Color};
+use serde::Deserialize;
+
+use crate::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomTheme {
+    pub name: String,
+    pub palette: Palette,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Choice {
+    Builtin(highlighter::Theme),
+    Custom(String),
+}
+
+impl fmt::Display for Choice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Choice::Builtin(theme) => write!(f, "{theme}"),
+            Choice::Custom(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+// Selections and buttons are both drawn in the primary color.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ThemeFile {
+    name: Option<String>,
+    background: String,
+    text: String,
+    primary: String,
+    success: Option<String>,
+    danger: Option<String>,
+}
+
+pub fn directory() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("editor").join("themes"))
+}
+
+// A broken theme file is reported without keeping the others from loading.
+pub async fn load_all() -> (Vec<CustomTheme>, Vec<Error>) {
+    let mut themes = Vec::new();
+    let mut errors = Vec::new();
+
+    let Some(directory) = directory() else {
+        return (themes, errors);
+    };
+
+    let Ok(mut entries) = tokio::fs::read_dir(&directory).await else {
+        return (themes, errors);
+    };
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+
+        if path.extension().is_none_or(|extension| extension != "toml") {
+            continue;
+        }
+
+        match load(path).await {
+            Ok(theme) => themes.push(theme),
+            Err(err) => errors.push(err),
+        }
+    }
+
+    themes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    (themes, errors)
+}
+
+async fn load(path: PathBuf) -> Result<CustomTheme, Error> {
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(Error::from)?;
+    let invalid = |err: String| Error::InvalidConfig(path.clone(), err);
+
+    let file = toml::from_str::<ThemeFile>(&content).map_err(|err| invalid(err.to_string()))?;
+    let fallback = Palette::DARK;
+
+    let color =
+        |value: &str| parse_color(value).ok_or_else(|| invalid(format!("bad color {value:?}")));
+    let optional = |value: &Option<String>, default: Color| match value {
+        Some(value) => color(value),
+        None => Ok(default),
+    };
+
+    let palette = Palette {
+        background: color(&file.background)?,
+        text: color(&file.text)?,
+        primary: color(&file.primary)?,
+        success: optional(&file.success, fallback.success)?,
+        danger: optional(&file.danger, fallback.danger)?,
+    };
+
+    let name = file.name.unwrap_or_else(|| {
+        path.file_stem().map_or(String::from("Custom"), |stem| {
+            stem.to_string_lossy().into_owned()
+        })
+    });
+
+    Ok(CustomTheme { name, palette })
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+
+    if hex.len() != 6 {
+        return None;
+    }
+
+    let channel = |index: usize| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok();
+
+    Some(Color::from_rgb8(channel(0)?, channel(2)?, channel(4)?))
+}
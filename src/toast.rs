@@ -0,0 +1,148 @@
+use std::time::{Duration, Instant};
+
+use iced::{
+    theme,
+    widget::{button, column, container, horizontal_space, row, text},
+    Color, Element, Length, Theme,
+};
+
+use crate::Messages;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn timeout(self) -> Option<Duration> {
+        match self {
+            Severity::Info | Severity::Success => Some(Duration::from_secs(4)),
+            Severity::Warning => Some(Duration::from_secs(8)),
+            Severity::Error => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+    id: u64,
+    severity: Severity,
+    message: String,
+    action: Option<(String, Messages)>,
+    expires: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+    next_id: u64,
+}
+
+impl Toasts {
+    const LIMIT: usize = 5;
+
+    pub fn push(&mut self, severity: Severity, message: impl Into<String>) {
+        self.push_with_action(severity, message, None);
+    }
+
+    pub fn push_with_action(
+        &mut self,
+        severity: Severity,
+        message: impl Into<String>,
+        action: Option<(String, Messages)>,
+    ) {
+        let message = message.into();
+
+        self.toasts.retain(|toast| toast.message != message);
+        self.toasts.push(Toast {
+            id: self.next_id,
+            severity,
+            message,
+            action,
+            expires: severity.timeout().map(|timeout| Instant::now() + timeout),
+        });
+        self.next_id += 1;
+
+        if self.toasts.len() > Self::LIMIT {
+            self.toasts.remove(0);
+        }
+    }
+
+    pub fn dismiss(&mut self, id: u64) -> Option<Toast> {
+        let index = self.toasts.iter().position(|toast| toast.id == id)?;
+
+        Some(self.toasts.remove(index))
+    }
+
+    pub fn take_action(&mut self, id: u64) -> Option<Messages> {
+        self.dismiss(id)?.action.map(|(_, message)| message)
+    }
+
+    pub fn expire(&mut self, now: Instant) {
+        self.toasts
+            .retain(|toast| toast.expires.is_none_or(|expires| expires > now));
+    }
+
+    pub fn is_expiring(&self) -> bool {
+        self.toasts.iter().any(|toast| toast.expires.is_some())
+    }
+
+    pub fn view(&self) -> Element<'_, Messages> {
+        self.toasts
+            .iter()
+            .fold(column![].spacing(5), |toasts, toast| {
+                let mut content =
+                    row![text(&toast.message), horizontal_space(Length::Fill)].spacing(10);
+
+                if let Some((label, _)) = toast.action.as_ref() {
+                    content =
+                        content.push(button(text(label)).on_press(Messages::ToastAction(toast.id)));
+                }
+
+                toasts.push(
+                    container(
+                        content.push(
+                            button("×")
+                                .style(theme::Button::Text)
+                                .on_press(Messages::DismissToast(toast.id)),
+                        ),
+                    )
+                    .padding([5, 10])
+                    .width(Length::Fill)
+                    .style(theme::Container::Custom(Box::new(Style(toast.severity)))),
+                )
+            })
+            .into()
+    }
+}
+
+struct Style(Severity);
+
+impl container::StyleSheet for Style {
+    type Style = Theme;
+
+    fn appearance(&self, theme: &Self::Style) -> container::Appearance {
+        let palette = theme.extended_palette();
+
+        let (background, text_color) = match self.0 {
+            Severity::Info => (
+                palette.background.strong.color,
+                palette.background.strong.text,
+            ),
+            Severity::Success => (palette.success.base.color, palette.success.base.text),
+            Severity::Warning => (Color::from_rgb(0.85, 0.65, 0.13), Color::BLACK),
+            Severity::Error => (palette.danger.base.color, palette.danger.base.text),
+        };
+
+        container::Appearance {
+            text_color: Some(text_color),
+            background: Some(background.into()),
+            border_radius: 4.0.into(),
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+        }
+    }
+}
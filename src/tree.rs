@@ -0,0 +1,176 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use iced::{
+    theme,
+    widget::{button, column, scrollable, text, Column},
+    Element, Length,
+};
+
+use crate::{search, Error, Messages};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+impl Entry {
+    fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map_or(String::new(), |name| name.to_string_lossy().into_owned())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Tree {
+    root: PathBuf,
+    children: HashMap<PathBuf, Vec<Entry>>,
+    expanded: HashSet<PathBuf>,
+}
+
+impl Tree {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            children: HashMap::new(),
+            expanded: HashSet::new(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    // Children are only listed the first time a directory is expanded.
+    pub fn toggle(&mut self, directory: PathBuf) -> bool {
+        if self.expanded.remove(&directory) {
+            return false;
+        }
+
+        let is_loaded = self.children.contains_key(&directory);
+
+        self.expanded.insert(directory);
+
+        !is_loaded
+    }
+
+    pub fn set_children(&mut self, directory: PathBuf, entries: Vec<Entry>) {
+        self.children.insert(directory, entries);
+    }
+
+    pub fn forget(&mut self, directory: &Path) {
+        self.children.remove(directory);
+        self.expanded.remove(directory);
+    }
+
+    pub fn visible_directories(&self) -> Vec<PathBuf> {
+        std::iter::once(self.root.clone())
+            .chain(
+                self.expanded
+                    .iter()
+                    .filter(|directory| self.children.contains_key(*directory))
+                    .cloned(),
+            )
+            .collect()
+    }
+
+    pub fn view(&self, active: Option<&Path>) -> Element<'_, Messages> {
+        let name = self
+            .root
+            .file_name()
+            .map_or(self.root.display().to_string(), |name| {
+                name.to_string_lossy().into_owned()
+            });
+
+        let entries = self.entries(&self.root, 0, active, column![].spacing(2));
+
+        column![
+            text(name).size(16),
+            scrollable(entries).height(Length::Fill)
+        ]
+        .spacing(5)
+        .width(220)
+        .into()
+    }
+
+    fn entries<'a>(
+        &'a self,
+        directory: &Path,
+        depth: usize,
+        active: Option<&Path>,
+        mut entries: Column<'a, Messages>,
+    ) -> Column<'a, Messages> {
+        let Some(children) = self.children.get(directory) else {
+            return entries;
+        };
+
+        for entry in children {
+            let indent = "  ".repeat(depth);
+
+            if entry.is_dir {
+                let is_expanded = self.expanded.contains(&entry.path);
+                let marker = if is_expanded { '▾' } else { '▸' };
+
+                entries = entries.push(
+                    button(text(format!("{indent}{marker} {}", entry.name())).size(14))
+                        .style(theme::Button::Text)
+                        .padding(0)
+                        .on_press(Messages::ToggleTreeDirectory(entry.path.clone())),
+                );
+
+                if is_expanded {
+                    entries = self.entries(&entry.path, depth + 1, active, entries);
+                }
+            } else {
+                let is_active = active == Some(entry.path.as_path());
+
+                entries = entries.push(
+                    button(text(format!("{indent}  {}", entry.name())).size(14))
+                        .style(if is_active {
+                            theme::Button::Primary
+                        } else {
+                            theme::Button::Text
+                        })
+                        .padding(0)
+                        .on_press(Messages::OpenTreeFile(entry.path.clone())),
+                );
+            }
+        }
+
+        entries
+    }
+}
+
+pub async fn list(
+    directory: PathBuf,
+    excluded: Vec<String>,
+) -> (PathBuf, Result<Vec<Entry>, Error>) {
+    let entries = read(&directory, &excluded).await;
+
+    (directory, entries)
+}
+
+async fn read(directory: &Path, excluded: &[String]) -> Result<Vec<Entry>, Error> {
+    let mut reader = tokio::fs::read_dir(directory).await.map_err(Error::from)?;
+    let mut entries = Vec::new();
+
+    while let Some(entry) = reader.next_entry().await.map_err(Error::from)? {
+        let path = entry.path();
+
+        if search::is_ignored(&path, excluded) {
+            continue;
+        }
+
+        let is_dir = entry.file_type().await.map_err(Error::from)?.is_dir();
+
+        entries.push(Entry { path, is_dir });
+    }
+
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.path.cmp(&b.path)));
+
+    Ok(entries)
+}
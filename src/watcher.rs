@@ -0,0 +1,46 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+use iced::{
+    futures::{channel::mpsc, SinkExt, StreamExt},
+    subscription, Subscription,
+};
+use notify::{RecursiveMode, Watcher};
+
+// The folders are watched instead of the files, so a file that is replaced
+// on save or moved away is still noticed. Opening or closing a file starts
+// a new watcher, since the set of paths is what tells them apart.
+pub fn watch(paths: BTreeSet<PathBuf>) -> Subscription<PathBuf> {
+    subscription::channel(paths.clone(), 100, move |mut output| async move {
+        let (sender, mut events) = mpsc::unbounded();
+
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event {
+                let _ = sender.unbounded_send(event);
+            }
+        });
+
+        match watcher {
+            Ok(mut watcher) => {
+                let folders = paths
+                    .iter()
+                    .filter_map(|path| path.parent())
+                    .collect::<BTreeSet<_>>();
+
+                for folder in folders {
+                    if let Err(err) = watcher.watch(folder, RecursiveMode::NonRecursive) {
+                        tracing::warn!(folder = %folder.display(), %err, "cannot watch folder");
+                    }
+                }
+
+                while let Some(event) = events.next().await {
+                    for path in event.paths.into_iter().filter(|path| paths.contains(path)) {
+                        let _ = output.send(path).await;
+                    }
+                }
+            }
+            Err(err) => tracing::warn!(%err, "cannot watch open files"),
+        }
+
+        std::future::pending().await
+    })
+}
@@ -0,0 +1,104 @@
+use std::fmt;
+
+use iced::{widget::text_editor::Content, Color, Point, Rectangle, Size, Vector};
+
+use crate::editing::Position;
+
+// More lines than a window shows at once, so everything in view is marked
+// without going through the whole buffer.
+const ROWS: usize = 200;
+const DOT: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Whitespace {
+    #[default]
+    Hidden,
+    Shown,
+    Selection,
+}
+
+impl Whitespace {
+    pub const ALL: [Whitespace; 3] = [Whitespace::Hidden, Whitespace::Shown, Whitespace::Selection];
+
+    pub fn next(self) -> Self {
+        match self {
+            Whitespace::Hidden => Whitespace::Shown,
+            Whitespace::Shown => Whitespace::Selection,
+            Whitespace::Selection => Whitespace::Hidden,
+        }
+    }
+}
+
+impl fmt::Display for Whitespace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Whitespace::Hidden => write!(f, "Whitespace: hidden"),
+            Whitespace::Shown => write!(f, "Whitespace: shown"),
+            Whitespace::Selection => write!(f, "Whitespace: in selection"),
+        }
+    }
+}
+
+// A dot in the middle of every space and a dash along every tab, with the
+// whitespace at the end of a line tinted as well. Cells are laid out from
+// the origin like the editor's own characters.
+pub fn marks(
+    content: &Content,
+    whitespace: Whitespace,
+    selection: Option<(Position, Position)>,
+    scroll: usize,
+    origin: Vector,
+    cell: Size,
+    (color, trailing): (Color, Color),
+) -> Vec<(Rectangle, Color)> {
+    let range = match (whitespace, selection) {
+        (Whitespace::Hidden, _) | (Whitespace::Selection, None) => return Vec::new(),
+        (Whitespace::Shown, _) => None,
+        (Whitespace::Selection, Some(selection)) => Some(selection),
+    };
+
+    let is_shown =
+        |position: Position| range.is_none_or(|(start, end)| start <= position && position < end);
+
+    let mut marks = Vec::new();
+
+    for line in scroll..(scroll + ROWS).min(content.line_count()) {
+        let Some(text) = content.line(line) else {
+            continue;
+        };
+
+        let width = text.chars().count();
+        let trimmed = text.trim_end().chars().count();
+        let top = origin.y + (line - scroll) as f32 * cell.height;
+
+        for (column, c) in text.chars().enumerate() {
+            if !c.is_whitespace() || !is_shown((line, column)) {
+                continue;
+            }
+
+            let left = origin.x + column as f32 * cell.width;
+
+            if column >= trimmed && trimmed < width {
+                marks.push((Rectangle::new(Point::new(left, top), cell), trailing));
+            }
+
+            let mark = match c {
+                '\t' => Rectangle::new(
+                    Point::new(left + DOT, top + (cell.height - 1.0) / 2.0),
+                    Size::new(cell.width - 2.0 * DOT, 1.0),
+                ),
+                _ => Rectangle::new(
+                    Point::new(
+                        left + (cell.width - DOT) / 2.0,
+                        top + (cell.height - DOT) / 2.0,
+                    ),
+                    Size::new(DOT, DOT),
+                ),
+            };
+
+            marks.push((mark, color));
+        }
+    }
+
+    marks
+}
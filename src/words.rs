@@ -0,0 +1,107 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use iced::widget::text_editor;
+
+use crate::{completion, editing::Position, lsp::Completion};
+
+const MIN_LENGTH: usize = 2;
+const LIMIT: usize = 200;
+
+#[derive(Default)]
+struct Seen {
+    distance: Option<usize>,
+    count: usize,
+}
+
+// Words closest to the cursor come first, then those used most often.
+// Words only found in other buffers follow those in the one being edited.
+pub fn completions<'a>(
+    content: &text_editor::Content,
+    (line, column): Position,
+    others: impl IntoIterator<Item = &'a text_editor::Content>,
+    typed: &str,
+) -> Vec<Completion> {
+    let mut seen: HashMap<String, Seen> = HashMap::new();
+
+    for (index, start, word) in words(content) {
+        // The word being typed does not count.
+        if index == line && (start..=start + word.chars().count()).contains(&column) {
+            continue;
+        }
+
+        let distance = index.abs_diff(line);
+        let entry = seen.entry(word).or_default();
+
+        entry.distance = Some(entry.distance.map_or(distance, |seen| seen.min(distance)));
+        entry.count += 1;
+    }
+
+    for other in others {
+        for (_, _, word) in words(other) {
+            seen.entry(word).or_default().count += 1;
+        }
+    }
+
+    let lowercase = typed.to_lowercase();
+    let mut ranked = seen
+        .into_iter()
+        .filter(|(word, _)| word != typed && word.to_lowercase().contains(&lowercase))
+        .collect::<Vec<_>>();
+
+    ranked.sort_by(|(a, x), (b, y)| {
+        let key = |seen: &Seen| (seen.distance.unwrap_or(usize::MAX), Reverse(seen.count));
+
+        key(x).cmp(&key(y)).then_with(|| a.cmp(b))
+    });
+
+    ranked
+        .into_iter()
+        .take(LIMIT)
+        .enumerate()
+        .map(|(rank, (word, _))| Completion {
+            label: word.clone(),
+            detail: None,
+            documentation: None,
+            filter: word.clone(),
+            sort: format!("{rank:06}"),
+            text: word,
+            range: None,
+            is_snippet: false,
+            additional: Vec::new(),
+        })
+        .collect()
+}
+
+// Every word by line and the column it starts at. Numbers are left out.
+fn words(content: &text_editor::Content) -> Vec<(usize, usize, String)> {
+    let mut words = Vec::new();
+
+    for index in 0..content.line_count() {
+        let Some(line) = content.line(index) else {
+            continue;
+        };
+
+        let mut word = String::new();
+        let mut start = 0;
+
+        for (column, c) in line.chars().chain(Some(' ')).enumerate() {
+            if completion::is_word(c) {
+                if word.is_empty() {
+                    start = column;
+                }
+
+                word.push(c);
+            } else if !word.is_empty() {
+                let word = std::mem::take(&mut word);
+
+                if word.chars().count() >= MIN_LENGTH
+                    && !word.starts_with(|c: char| c.is_ascii_digit())
+                {
+                    words.push((index, start, word));
+                }
+            }
+        }
+    }
+
+    words
+}